@@ -0,0 +1,22 @@
+#[derive(Default)]
+struct Shared {
+    invocations: std::sync::atomic::AtomicU64,
+}
+
+pub fn main() -> anyhow::Result<()> {
+    lambda_runtime_types::exec_tokio_fn(
+        |_region| async {
+            simple_logger::SimpleLogger::new()
+                .with_level(log::LevelFilter::Info)
+                .init()
+                .expect("Unable to setup logging");
+            Ok(Shared::default())
+        },
+        |shared: &'static Shared, _event: (), _ctx| async move {
+            let invocations = shared
+                .invocations
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(invocations + 1)
+        },
+    )
+}
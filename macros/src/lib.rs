@@ -0,0 +1,184 @@
+//! Procedural macros for `lambda-runtime-types`. Use them through
+//! the `macros` feature of `lambda-runtime-types` instead of
+//! depending on this crate directly.
+
+#![deny(clippy::all, clippy::nursery)]
+#![deny(nonstandard_style, rust_2018_idioms, unused_crate_dependencies)]
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+
+/// Turns an async function into a `Runner` implementation and a
+/// `main` function executing it with `exec_tokio`.
+#[proc_macro_attribute]
+pub fn lambda_runner(
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = syn::parse_macro_input!(args as Args);
+    let func = syn::parse_macro_input!(item as syn::ItemFn);
+    expand(args, func)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Arguments of the `lambda_runner` attribute
+struct Args {
+    runner: syn::Ident,
+    setup: Option<syn::Path>,
+    main: bool,
+}
+
+impl syn::parse::Parse for Args {
+    fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
+        let mut args = Self {
+            runner: syn::Ident::new("Runner", proc_macro2::Span::call_site()),
+            setup: None,
+            main: true,
+        };
+        let metas =
+            syn::punctuated::Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated(
+                input,
+            )?;
+        for meta in metas {
+            let value = &meta.value;
+            if meta.path.is_ident("runner") {
+                args.runner = syn::parse2(quote!(#value))?;
+            } else if meta.path.is_ident("setup") {
+                args.setup = Some(syn::parse2(quote!(#value))?);
+            } else if meta.path.is_ident("main") {
+                let value: syn::LitBool = syn::parse2(quote!(#value))?;
+                args.main = value.value;
+            } else {
+                return Err(syn::Error::new(
+                    meta.path.span(),
+                    "Unknown argument. Expected one of: runner, setup, main",
+                ));
+            }
+        }
+        Ok(args)
+    }
+}
+
+fn expand(args: Args, func: syn::ItemFn) -> syn::Result<TokenStream> {
+    if func.sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            func.sig.fn_token.span(),
+            "lambda_runner requires an async function",
+        ));
+    }
+    let inputs: Vec<_> = func.sig.inputs.iter().collect();
+    let (shared, event) = match inputs.as_slice() {
+        [event] => (None, *event),
+        [shared, event] => (Some(shared_type(shared)?), *event),
+        _ => {
+            return Err(syn::Error::new(
+                func.sig.inputs.span(),
+                "Expected the parameters `(event: LambdaEvent<'_, Event>)` \
+                 or `(shared: &Shared, event: LambdaEvent<'_, Event>)`",
+            ))
+        }
+    };
+    let event = last_generic_type(arg_type(event)?, "LambdaEvent<'_, Event>")?;
+    let ret = match &func.sig.output {
+        syn::ReturnType::Type(_, ty) => last_generic_type(ty, "anyhow::Result<Return>")?,
+        syn::ReturnType::Default => {
+            return Err(syn::Error::new(
+                func.sig.span(),
+                "Expected the return type `anyhow::Result<Return>`",
+            ))
+        }
+    };
+
+    let name = &func.sig.ident;
+    let runner = &args.runner;
+    let krate = quote!(::lambda_runtime_types);
+    let shared_ty = shared.map_or_else(|| quote!(()), |shared| quote!(#shared));
+    let call = if inputs.len() == 1 {
+        quote!(#name(event).await)
+    } else {
+        quote!(#name(shared, event).await)
+    };
+    let setup = args.setup.map_or_else(
+        || {
+            quote!(::core::result::Result::Ok(
+                ::core::default::Default::default()
+            ))
+        },
+        |setup| quote!(#setup(region).await),
+    );
+    let main = args.main.then(|| {
+        quote! {
+            fn main() -> #krate::__private::anyhow::Result<()> {
+                #krate::exec_tokio::<_, _, #runner, _>()
+            }
+        }
+    });
+
+    Ok(quote! {
+        #func
+
+        struct #runner;
+
+        #[#krate::__private::async_trait]
+        impl<'a> #krate::Runner<'a, #shared_ty, #event, #ret> for #runner {
+            #[allow(unused_variables)]
+            async fn run(
+                shared: &'a #shared_ty,
+                event: #krate::LambdaEvent<'a, #event>,
+            ) -> #krate::__private::anyhow::Result<#ret> {
+                #call
+            }
+
+            async fn setup(region: &'a str) -> #krate::__private::anyhow::Result<#shared_ty> {
+                #setup
+            }
+        }
+
+        #main
+    })
+}
+
+fn arg_type(arg: &syn::FnArg) -> syn::Result<&syn::Type> {
+    match arg {
+        syn::FnArg::Typed(arg) => Ok(&arg.ty),
+        syn::FnArg::Receiver(receiver) => Err(syn::Error::new(
+            receiver.span(),
+            "lambda_runner does not support methods",
+        )),
+    }
+}
+
+fn shared_type(arg: &syn::FnArg) -> syn::Result<&syn::Type> {
+    match arg_type(arg)? {
+        syn::Type::Reference(reference) if reference.mutability.is_none() => Ok(&reference.elem),
+        ty => Err(syn::Error::new(
+            ty.span(),
+            "Expected shared data as reference `&Shared`",
+        )),
+    }
+}
+
+/// Returns the last generic type argument of a type like `LambdaEvent<'_, Event>`
+fn last_generic_type<'t>(ty: &'t syn::Type, expected: &str) -> syn::Result<&'t syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return Err(syn::Error::new(
+            ty.span(),
+            format!("Expected `{}`", expected),
+        ));
+    };
+    path.path
+        .segments
+        .last()
+        .and_then(|segment| match &segment.arguments {
+            syn::PathArguments::AngleBracketed(args) => {
+                args.args.iter().rev().find_map(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+            }
+            _ => None,
+        })
+        .ok_or_else(|| syn::Error::new(ty.span(), format!("Expected `{}`", expected)))
+}
@@ -0,0 +1,165 @@
+//! Provides credentials of an assumed role which can be stored in `Shared`.
+//!
+//! The role is assumed once during setup. Every access to the credentials checks
+//! whether they expire soon and assumes the role again if necessary. As lambda
+//! environments are frozen between invocations, checking on access (usually at
+//! the start of an invocation) makes sure that credentials never expire in the
+//! middle of an invocation.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::assume_role::AssumedRole;
+//!
+//! struct Shared {
+//!     role: AssumedRole,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), ()> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+//!         let config = shared.role.sdk_config().await?;
+//!         // Create clients for the other account with `config`
+//!         Ok(())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             role: AssumedRole::new("arn:aws:iam::123456789012:role/other", "lambda").await?,
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use std::time::{Duration, SystemTime};
+
+/// Credentials of an assumed role, which are refreshed before they expire
+#[derive(Debug)]
+pub struct AssumedRole {
+    client: aws_sdk_sts::Client,
+    role_arn: String,
+    session_name: String,
+    duration: Duration,
+    refresh_before: Duration,
+    credentials: tokio::sync::Mutex<aws_sdk_sts::Credentials>,
+}
+
+impl AssumedRole {
+    /// Assumes the given role with credentials from the environment
+    pub async fn new(
+        role_arn: impl Into<String>,
+        session_name: impl Into<String>,
+    ) -> anyhow::Result<Self> {
+        let config = aws_config::load_from_env().await;
+        let client = aws_sdk_sts::Client::new(&config);
+        Self::with_client(client, role_arn, session_name).await
+    }
+
+    /// Assumes the given role using an existing client
+    pub async fn with_client(
+        client: aws_sdk_sts::Client,
+        role_arn: impl Into<String>,
+        session_name: impl Into<String>,
+    ) -> anyhow::Result<Self> {
+        let role_arn = role_arn.into();
+        let session_name = session_name.into();
+        let duration = Duration::from_secs(3600);
+        let credentials = assume_role(&client, &role_arn, &session_name, duration).await?;
+        Ok(Self {
+            client,
+            role_arn,
+            session_name,
+            duration,
+            refresh_before: Duration::from_secs(300),
+            credentials: tokio::sync::Mutex::new(credentials),
+        })
+    }
+
+    /// Sets the duration of newly assumed credentials. Defaults to 1 hour.
+    /// Takes effect on the next refresh.
+    pub const fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets how long before their expiry credentials are refreshed.
+    /// Defaults to 5 minutes. Should be longer than the lambda timeout.
+    pub const fn with_refresh_before(mut self, refresh_before: Duration) -> Self {
+        self.refresh_before = refresh_before;
+        self
+    }
+
+    /// Returns the credentials, refreshing them if they expire soon
+    pub async fn credentials(&self) -> anyhow::Result<aws_sdk_sts::Credentials> {
+        let mut credentials = self.credentials.lock().await;
+        let refresh_at = credentials
+            .expiry()
+            .and_then(|expiry| expiry.checked_sub(self.refresh_before));
+        if refresh_at.is_some_and(|refresh_at| refresh_at <= SystemTime::now()) {
+            log::info!("Refreshing credentials of role: {}", self.role_arn);
+            *credentials = assume_role(
+                &self.client,
+                &self.role_arn,
+                &self.session_name,
+                self.duration,
+            )
+            .await?;
+        }
+        let res = credentials.clone();
+        drop(credentials);
+        Ok(res)
+    }
+
+    /// Returns a config with the credentials of the assumed role, which can
+    /// be used to create clients of the aws sdk
+    pub async fn sdk_config(&self) -> anyhow::Result<aws_config::SdkConfig> {
+        let credentials = self.credentials().await?;
+        Ok(aws_config::from_env()
+            .credentials_provider(credentials)
+            .load()
+            .await)
+    }
+}
+
+async fn assume_role(
+    client: &aws_sdk_sts::Client,
+    role_arn: &str,
+    session_name: &str,
+    duration: Duration,
+) -> anyhow::Result<aws_sdk_sts::Credentials> {
+    use anyhow::Context;
+
+    let output = client
+        .assume_role()
+        .role_arn(role_arn)
+        .role_session_name(session_name)
+        .duration_seconds(i32::try_from(duration.as_secs()).unwrap_or(i32::MAX))
+        .send()
+        .await
+        .with_context(|| format!("Unable to assume role: {}", role_arn))?;
+    let credentials = output
+        .credentials()
+        .with_context(|| format!("No credentials returned for role: {}", role_arn))?;
+    let expiry = credentials
+        .expiration()
+        .map(|expiration| SystemTime::try_from(*expiration))
+        .transpose()
+        .context("Invalid expiration of assumed credentials")?;
+    Ok(aws_sdk_sts::Credentials::new(
+        credentials
+            .access_key_id()
+            .context("Assumed credentials have no access key id")?,
+        credentials
+            .secret_access_key()
+            .context("Assumed credentials have no secret access key")?,
+        credentials.session_token().map(ToOwned::to_owned),
+        expiry,
+        "AssumedRole",
+    ))
+}
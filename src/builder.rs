@@ -0,0 +1,270 @@
+use crate::{Clock, Runner};
+use std::time::Duration;
+
+/// Env variable which disables the timeout handler if set to `false` or `0`.
+/// See [`Builder::timeout_handler`]
+pub const TIMEOUT_HANDLER_ENV: &str = "LAMBDA_RUNTIME_TYPES_TIMEOUT_HANDLER";
+
+/// Flavor of the tokio runtime created by [`Builder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// Multi-thread runtime. Allows the timeout handler to fail
+    /// invocations which block a thread
+    MultiThread,
+    /// Runtime which executes everything on the current thread
+    CurrentThread,
+}
+
+/// Settings which are used during lambda invocations
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    pub timeout_margin: Duration,
+    pub hard_deadline: bool,
+    pub timeout_handler: bool,
+    pub memory_watchdog: Option<f32>,
+    pub clock: &'static dyn Clock,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            timeout_margin: Duration::from_millis(100),
+            hard_deadline: false,
+            timeout_handler: !matches!(
+                std::env::var(TIMEOUT_HANDLER_ENV).as_deref(),
+                Ok("false" | "0")
+            ),
+            memory_watchdog: None,
+            clock: &crate::SystemClock,
+        }
+    }
+}
+
+/// Configures how the lambda runtime is executed. [`crate::exec_tokio`]
+/// uses the defaults of this builder.
+///
+/// ```no_run
+/// struct Runner;
+///
+/// #[async_trait::async_trait]
+/// impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+///     async fn run(shared: &'a (), event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+///         Ok(())
+///     }
+///
+///     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// pub fn main() -> anyhow::Result<()> {
+///     lambda_runtime_types::Builder::new()
+///         .worker_threads(2)
+///         .timeout_margin(std::time::Duration::from_millis(500))
+///         .exec::<_, _, Runner, _>()
+/// }
+/// ```
+pub struct Builder {
+    flavor: Flavor,
+    worker_threads: Option<usize>,
+    region_required: bool,
+    concurrent_setup: bool,
+    logger: Option<Box<dyn FnOnce() -> anyhow::Result<()>>>,
+    settings: Settings,
+}
+
+impl std::fmt::Debug for Builder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Builder")
+            .field("flavor", &self.flavor)
+            .field("worker_threads", &self.worker_threads)
+            .field("region_required", &self.region_required)
+            .field("concurrent_setup", &self.concurrent_setup)
+            .field("logger", &self.logger.as_ref().map(|_| "[...]"))
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Builder {
+    /// Creates a new builder with the default configuration
+    pub fn new() -> Self {
+        Self {
+            flavor: Flavor::MultiThread,
+            worker_threads: None,
+            region_required: true,
+            concurrent_setup: false,
+            logger: None,
+            settings: Settings::default(),
+        }
+    }
+
+    /// Flavor of the tokio runtime. Defaults to [`Flavor::MultiThread`]
+    #[must_use]
+    pub const fn flavor(mut self, flavor: Flavor) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    /// Amount of worker threads of the multi-thread runtime. Must be greater
+    /// than zero, otherwise [`Builder::exec`] fails. Defaults to the amount of cpu cores
+    #[must_use]
+    pub const fn worker_threads(mut self, worker_threads: usize) -> Self {
+        self.worker_threads = Some(worker_threads);
+        self
+    }
+
+    /// Time before the deadline at which the timeout handler fails the
+    /// invocation. Defaults to 100 milliseconds
+    #[must_use]
+    pub const fn timeout_margin(mut self, timeout_margin: Duration) -> Self {
+        self.settings.timeout_margin = timeout_margin;
+        self
+    }
+
+    /// Whether the timeout handler races invocations against their deadline. If
+    /// disabled, invocations run until they complete or lambda stops them, e.g. to
+    /// rely on an own watchdog. This overrides [`crate::Runner::TIMEOUT`] and also
+    /// disables [`Builder::hard_deadline`]. Defaults to `true`, unless the env
+    /// variable [`TIMEOUT_HANDLER_ENV`] is set to `false` or `0`
+    #[must_use]
+    pub const fn timeout_handler(mut self, timeout_handler: bool) -> Self {
+        self.settings.timeout_handler = timeout_handler;
+        self
+    }
+
+    /// Whether the timeout error is delivered even if the runner blocks the thread,
+    /// which keeps the timeout handler from running. If an invocation did not
+    /// complete a quarter of the timeout margin before its deadline, i.e. after
+    /// [`crate::Runner::on_timeout`] had its time, a watchdog thread reports the
+    /// timeout error and exits the process. Aborting the runner instead is not an
+    /// option, as a task is only aborted once it awaits, which a blocking runner
+    /// never does. Lambda then starts a new execution environment for the next invocation.
+    /// Only applies to runners using [`crate::TimeoutBehavior::Fail`]. Defaults to `false`
+    #[must_use]
+    pub const fn hard_deadline(mut self, hard_deadline: bool) -> Self {
+        self.settings.hard_deadline = hard_deadline;
+        self
+    }
+
+    /// Fails invocations once the memory usage of the process exceeds `threshold`
+    /// (greater than `0.0` and at most `1.0`) of the memory configured for the lambda,
+    /// e.g. `0.9`. Other thresholds let [`Builder::exec`] fail.
+    /// Lambda stops the execution environment without reporting an error when it runs
+    /// out of memory, so the watchdog reports a descriptive error before that happens.
+    /// The usage is read from `/proc/self/status` and therefore only available on linux.
+    /// Disabled by default
+    #[must_use]
+    pub const fn memory_watchdog(mut self, threshold: f32) -> Self {
+        self.settings.memory_watchdog = Some(threshold);
+        self
+    }
+
+    /// Clock which is used to compute the time left until the deadline of
+    /// an invocation, e.g. to simulate deadlines in tests. Defaults to
+    /// [`crate::SystemClock`]
+    #[must_use]
+    pub const fn clock(mut self, clock: &'static dyn Clock) -> Self {
+        self.settings.clock = clock;
+        self
+    }
+
+    /// Whether startup fails if the `AWS_REGION` env variable is missing.
+    /// Otherwise an empty region is passed to the runner. Defaults to `true`
+    #[must_use]
+    pub const fn region_required(mut self, region_required: bool) -> Self {
+        self.region_required = region_required;
+        self
+    }
+
+    /// Whether [`Runner::setup`] is executed while the first invocation is fetched
+    /// from the Runtime API, which shortens cold starts with slow setups, e.g. when
+    /// connection pools or SDK clients are created.
+    ///
+    /// Lambda considers the initialization complete once the first invocation is
+    /// fetched. The setup is then billed and counts towards the timeout of the first
+    /// invocation. If it fails, the error is reported as error of the first invocation
+    /// instead of an initialization error. Defaults to `false`
+    #[must_use]
+    pub const fn concurrent_setup(mut self, concurrent_setup: bool) -> Self {
+        self.concurrent_setup = concurrent_setup;
+        self
+    }
+
+    /// Function which sets up logging. It is called before the runtime
+    /// is started, so errors during setup are logged as well
+    #[must_use]
+    pub fn logger(mut self, logger: impl FnOnce() -> anyhow::Result<()> + 'static) -> Self {
+        self.logger = Some(Box::new(logger));
+        self
+    }
+
+    /// Lambda entrypoint. Builds the tokio runtime and executes
+    /// the lambda runtime with `Run`. See [`crate::exec`]
+    pub fn exec<Shared, Event, Run, Return>(self) -> anyhow::Result<()>
+    where
+        Shared: Send + Sync,
+        Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+        Run: for<'a> Runner<'a, Shared, Event, Return>,
+        Return: serde::Serialize,
+    {
+        use anyhow::Context;
+
+        if let Some(logger) = self.logger {
+            logger().context("Unable to setup logging")?;
+        }
+        anyhow::ensure!(
+            self.worker_threads != Some(0),
+            "Amount of worker threads must be greater than zero"
+        );
+        if let Some(threshold) = self.settings.memory_watchdog {
+            anyhow::ensure!(
+                threshold > 0.0 && threshold <= 1.0,
+                "Memory watchdog threshold must be greater than 0.0 and at most 1.0, got {}",
+                threshold
+            );
+        }
+        let mut builder = match self.flavor {
+            Flavor::MultiThread => tokio::runtime::Builder::new_multi_thread(),
+            Flavor::CurrentThread => tokio::runtime::Builder::new_current_thread(),
+        };
+        if let (Flavor::MultiThread, Some(worker_threads)) = (self.flavor, self.worker_threads) {
+            builder.worker_threads(worker_threads);
+        }
+        let region_required = self.region_required;
+        let concurrent_setup = self.concurrent_setup;
+        let settings = self.settings;
+        builder
+            .enable_all()
+            .build()
+            .context("Unable to build tokio runtime")?
+            .block_on(async move {
+                log::info!("Starting lambda runtime");
+                let setup = async {
+                    let region = crate::region(region_required)?;
+                    let shared = Run::setup(&region).await?;
+                    Ok((region, shared))
+                };
+                let ((region, shared), first) = if concurrent_setup {
+                    let (res, first) = crate::invocation_loop::prefetch(setup).await?;
+                    (res, Some(first))
+                } else {
+                    (crate::init_error::report(setup).await?, None)
+                };
+                crate::exec_runtime(
+                    &crate::StaticRunner::<Run>::default(),
+                    &shared,
+                    &region,
+                    &settings,
+                    first,
+                )
+                .await
+            })
+    }
+}
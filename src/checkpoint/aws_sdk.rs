@@ -0,0 +1,89 @@
+/// [`super::Store`] which persists checkpoints in a DynamoDB table.
+///
+/// The table requires a string partition key named `id`. Checkpoints are
+/// stored as json in the attribute `checkpoint` and the time of the last
+/// update in the attribute `updated_at`.
+#[derive(Clone, Debug)]
+pub struct DynamoDbStore {
+    client: aws_sdk_dynamodb::Client,
+    table: String,
+}
+
+impl DynamoDbStore {
+    /// Creates a new store using the given table
+    pub async fn new(table: impl Into<String>) -> Self {
+        let config = aws_config::load_from_env().await;
+        let client = aws_sdk_dynamodb::Client::new(&config);
+        Self::with_client(client, table)
+    }
+
+    /// Creates a new store using an existing client
+    pub fn with_client(client: aws_sdk_dynamodb::Client, table: impl Into<String>) -> Self {
+        Self {
+            client,
+            table: table.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl super::Store for DynamoDbStore {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        use anyhow::Context;
+        use aws_sdk_dynamodb::model::AttributeValue;
+
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .key("id", AttributeValue::S(key.to_owned()))
+            .consistent_read(true)
+            .send()
+            .await
+            .with_context(|| format!("Unable to fetch checkpoint with key: {}", key))?;
+        let Some(item) = output.item() else {
+            return Ok(None);
+        };
+        let checkpoint = item
+            .get("checkpoint")
+            .and_then(|v| v.as_s().ok())
+            .with_context(|| format!("Checkpoint with key {} has no value", key))?;
+        let value = serde_json::from_str(checkpoint)
+            .with_context(|| format!("Checkpoint with key {} is invalid json", key))?;
+        Ok(Some(value))
+    }
+
+    async fn put(&self, key: &str, value: &serde_json::Value) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use aws_sdk_dynamodb::model::AttributeValue;
+
+        let updated_at = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.client
+            .put_item()
+            .table_name(&self.table)
+            .item("id", AttributeValue::S(key.to_owned()))
+            .item("checkpoint", AttributeValue::S(value.to_string()))
+            .item("updated_at", AttributeValue::N(updated_at.to_string()))
+            .send()
+            .await
+            .with_context(|| format!("Unable to store checkpoint with key: {}", key))?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use aws_sdk_dynamodb::model::AttributeValue;
+
+        self.client
+            .delete_item()
+            .table_name(&self.table)
+            .key("id", AttributeValue::S(key.to_owned()))
+            .send()
+            .await
+            .with_context(|| format!("Unable to delete checkpoint with key: {}", key))?;
+        Ok(())
+    }
+}
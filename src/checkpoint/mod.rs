@@ -0,0 +1,329 @@
+//! Provides checkpointing for long running invocations which may hit the timeout.
+//!
+//! Runners periodically save a typed progress marker through [`Progress::save`].
+//! Markers are persisted in a [`Store`] at most once per configured interval. If
+//! the invocation gets close to its deadline or fails, the latest marker is
+//! persisted and the next invocation with the same key (e.g. a retry or a
+//! self-invocation) resumes from it. Once the operation completes, the checkpoint
+//! is removed. A DynamoDB store is available with the feature `checkpoint_aws_sdk`.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::checkpoint::{Checkpointer, MemoryStore, Status};
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! #[derive(serde::Deserialize, Debug)]
+//! struct Event {
+//!     job_id: String,
+//! }
+//!
+//! struct Shared {
+//!     checkpoints: Checkpointer<usize>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, Event, bool> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, Event>) -> anyhow::Result<bool> {
+//!         let deadline = UNIX_EPOCH + Duration::from_millis(event.ctx.deadline);
+//!         let status = shared
+//!             .checkpoints
+//!             .run(&event.event.job_id, deadline, |resumed, progress| async move {
+//!                 for item in resumed.unwrap_or(0)..10_000 {
+//!                     // Process item
+//!                     progress.save(item + 1).await?;
+//!                 }
+//!                 Ok(())
+//!             })
+//!             .await?;
+//!         // Return whether the job needs to be continued
+//!         Ok(matches!(status, Status::Suspended))
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             checkpoints: Checkpointer::new(MemoryStore::default()),
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+#[cfg(feature = "checkpoint_aws_sdk")]
+mod aws_sdk;
+
+#[cfg(feature = "checkpoint_aws_sdk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checkpoint_aws_sdk")))]
+pub use aws_sdk::DynamoDbStore;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Persistent storage for checkpoints
+#[async_trait::async_trait]
+pub trait Store: Send + Sync {
+    /// Returns the checkpoint stored for the given key
+    async fn get(&self, key: &str) -> anyhow::Result<Option<serde_json::Value>>;
+
+    /// Stores the checkpoint for the given key
+    async fn put(&self, key: &str, value: &serde_json::Value) -> anyhow::Result<()>;
+
+    /// Removes the checkpoint for the given key
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+}
+
+/// [`Store`] which keeps checkpoints in memory. Checkpoints are only
+/// available as long as the execution environment is alive, which makes
+/// it mostly useful for testing.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    checkpoints: std::sync::Mutex<HashMap<String, serde_json::Value>>,
+}
+
+impl MemoryStore {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, serde_json::Value>> {
+        self.checkpoints
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for MemoryStore {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        Ok(self.lock().get(key).cloned())
+    }
+
+    async fn put(&self, key: &str, value: &serde_json::Value) -> anyhow::Result<()> {
+        self.lock().insert(key.to_owned(), value.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.lock().remove(key);
+        Ok(())
+    }
+}
+
+/// Result of [`Checkpointer::run`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status<R> {
+    /// The operation completed and the checkpoint was removed
+    Completed(R),
+    /// The deadline was reached and the latest checkpoint was persisted
+    Suspended,
+}
+
+/// Runs resumable operations and persists their progress
+pub struct Checkpointer<T> {
+    store: Arc<dyn Store>,
+    interval: Duration,
+    margin: Duration,
+    _m: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T> std::fmt::Debug for Checkpointer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Checkpointer")
+            .field("interval", &self.interval)
+            .field("margin", &self.margin)
+            .finish()
+    }
+}
+
+impl<T> Checkpointer<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// Creates a new checkpointer which persists checkpoints at most every
+    /// 10 seconds and stops operations 1 second before the deadline.
+    pub fn new(store: impl Store + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            interval: Duration::from_secs(10),
+            margin: Duration::from_secs(1),
+            _m: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the minimum time between two persisted checkpoints
+    pub const fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the time before the deadline at which operations are stopped.
+    /// Needs to leave enough time to persist the final checkpoint.
+    pub const fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Returns the checkpoint stored for the given key
+    pub async fn load(&self, key: &str) -> anyhow::Result<Option<T>> {
+        use anyhow::Context;
+
+        self.store
+            .get(key)
+            .await?
+            .map(serde_json::from_value)
+            .transpose()
+            .with_context(|| format!("Unable to deserialize checkpoint with key: {}", key))
+    }
+
+    /// Removes the checkpoint stored for the given key
+    pub async fn clear(&self, key: &str) -> anyhow::Result<()> {
+        self.store.delete(key).await
+    }
+
+    /// Executes `operation` with the checkpoint stored for `key`, if there is one.
+    ///
+    /// The operation is stopped shortly before `deadline`, in which case the latest
+    /// progress is persisted and [`Status::Suspended`] is returned. The latest progress
+    /// is persisted as well if the operation fails. If the operation completes, the
+    /// checkpoint is removed.
+    pub async fn run<R, Op, Fut>(
+        &self,
+        key: &str,
+        deadline: SystemTime,
+        operation: Op,
+    ) -> anyhow::Result<Status<R>>
+    where
+        Op: FnOnce(Option<T>, Progress<T>) -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<R>>,
+    {
+        use futures::FutureExt;
+
+        let resumed = self.load(key).await?;
+        if resumed.is_some() {
+            log::info!("Resuming from checkpoint: {}", key);
+        }
+        let progress = Progress {
+            inner: Arc::new(ProgressInner {
+                store: Arc::clone(&self.store),
+                key: key.to_owned(),
+                interval: self.interval,
+                state: std::sync::Mutex::new(ProgressState {
+                    pending: None,
+                    persisted_at: SystemTime::now(),
+                }),
+            }),
+            _m: std::marker::PhantomData,
+        };
+        let remaining = deadline
+            .checked_sub(self.margin)
+            .and_then(|stop_at| stop_at.duration_since(SystemTime::now()).ok())
+            .unwrap_or(Duration::ZERO);
+
+        let operation = operation(resumed, progress.clone()).fuse();
+        let timeout = tokio::time::sleep(remaining).fuse();
+        futures::pin_mut!(operation, timeout);
+        futures::select! {
+            res = operation => match res {
+                Ok(res) => {
+                    self.clear(key).await?;
+                    Ok(Status::Completed(res))
+                }
+                Err(err) => {
+                    if let Err(flush_err) = progress.flush().await {
+                        log::error!("Unable to persist checkpoint {}: {:?}", key, flush_err);
+                    }
+                    Err(err)
+                }
+            },
+            _ = timeout => {
+                log::warn!("Deadline reached. Suspending operation with checkpoint: {}", key);
+                progress.flush().await?;
+                Ok(Status::Suspended)
+            }
+        }
+    }
+}
+
+/// Handle to save the progress of an operation executed by [`Checkpointer::run`]
+pub struct Progress<T> {
+    inner: Arc<ProgressInner>,
+    _m: std::marker::PhantomData<fn(T)>,
+}
+
+struct ProgressInner {
+    store: Arc<dyn Store>,
+    key: String,
+    interval: Duration,
+    state: std::sync::Mutex<ProgressState>,
+}
+
+struct ProgressState {
+    pending: Option<serde_json::Value>,
+    persisted_at: SystemTime,
+}
+
+impl<T> Clone for Progress<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            _m: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Progress<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("key", &self.inner.key)
+            .finish()
+    }
+}
+
+impl<T: serde::Serialize> Progress<T> {
+    /// Records the latest progress. It is persisted if the configured
+    /// interval passed since the last persisted checkpoint.
+    pub async fn save(&self, marker: T) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let value = serde_json::to_value(marker).with_context(|| {
+            format!(
+                "Unable to serialize checkpoint with key: {}",
+                self.inner.key
+            )
+        })?;
+        let persist = self.with_state(|state| {
+            state.pending = Some(value);
+            SystemTime::now()
+                .duration_since(state.persisted_at)
+                .is_ok_and(|elapsed| elapsed >= self.inner.interval)
+        });
+        if persist {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Persists the latest progress, if it was not persisted yet
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        let pending = self.with_state(|state| {
+            state.persisted_at = SystemTime::now();
+            state.pending.take()
+        });
+        if let Some(value) = pending {
+            log::info!("Persisting checkpoint: {}", self.inner.key);
+            self.inner.store.put(&self.inner.key, &value).await?;
+        }
+        Ok(())
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut ProgressState) -> R) -> R {
+        let mut state = self
+            .inner
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        f(&mut state)
+    }
+}
@@ -0,0 +1,262 @@
+//! Provides a circuit breaker which can be stored in `Shared` to protect flaky
+//! dependencies across invocations.
+//!
+//! Each named dependency gets its own circuit. The outcomes of the last calls are
+//! tracked and once the failure rate exceeds the configured threshold, the circuit
+//! opens and calls are short-circuited with [`Error::Open`] without calling the
+//! dependency. After the open duration passed, a single trial call is let through.
+//! If it succeeds the circuit closes again, otherwise it stays open. A trial call
+//! which is dropped before it completed, e.g. by a timeout, counts as failed.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # async fn call_downstream() -> anyhow::Result<()> { Ok(()) }
+//! use lambda_runtime_types::circuit_breaker::{CircuitBreaker, Config};
+//!
+//! struct Shared {
+//!     breaker: CircuitBreaker,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), ()> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+//!         shared.breaker.call("downstream", || call_downstream()).await?;
+//!         Ok(())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             breaker: CircuitBreaker::new(Config::default()),
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime};
+
+/// Configuration of a [`CircuitBreaker`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Config {
+    /// Failure rate (between 0 and 1) at which the circuit opens
+    pub failure_rate: f64,
+    /// Amount of recent calls used to calculate the failure rate
+    pub window: usize,
+    /// Minimum amount of calls in the window before the circuit may open
+    pub minimum_calls: usize,
+    /// Duration the circuit stays open before a trial call is allowed
+    pub open_duration: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.5,
+            window: 20,
+            minimum_calls: 5,
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of a single circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// Calls are passed through
+    Closed,
+    /// Calls are short-circuited
+    Open,
+    /// A single trial call is allowed to check whether the dependency recovered
+    HalfOpen,
+}
+
+/// Error returned by calls through a [`CircuitBreaker`]
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The circuit is open and the call was not executed
+    Open(CircuitOpen),
+    /// The call was executed and failed
+    Inner(E),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Open(open) => open.fmt(f),
+            Self::Inner(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: std::fmt::Display + std::fmt::Debug> std::error::Error for Error<E> {}
+
+/// Error returned if a call was short-circuited
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitOpen {
+    /// Name of the dependency
+    pub name: String,
+    /// Time until the next trial call is allowed
+    pub retry_in: Duration,
+}
+
+impl std::fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Circuit for {} is open. Retry in {:?}",
+            self.name, self.retry_in
+        )
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+/// Circuit breaker tracking multiple named dependencies
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: Config,
+    circuits: std::sync::Mutex<HashMap<String, Circuit>>,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    outcomes: VecDeque<bool>,
+    opened_at: Option<SystemTime>,
+    trial_running: bool,
+}
+
+impl CircuitBreaker {
+    /// Creates a new circuit breaker
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            circuits: std::sync::Mutex::default(),
+        }
+    }
+
+    /// Returns the current state of the circuit with the given name
+    pub fn state(&self, name: &str) -> State {
+        self.with_circuit(name, |circuit| self.circuit_state(circuit).0)
+    }
+
+    /// Executes `operation` if the circuit with the given name is not open
+    /// and records its outcome.
+    pub async fn call<T, E, Op, Fut>(&self, name: &str, operation: Op) -> Result<T, Error<E>>
+    where
+        Op: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+    {
+        let trial = self.acquire(name).map_err(Error::Open)?;
+        let mut guard = TrialGuard {
+            breaker: self,
+            name,
+            trial,
+        };
+        let res = operation().await;
+        guard.trial = false;
+        self.record(name, res.is_ok());
+        res.map_err(Error::Inner)
+    }
+
+    /// Checks whether a call may be done and marks trial calls as running.
+    /// Returns whether the call is a trial call
+    fn acquire(&self, name: &str) -> Result<bool, CircuitOpen> {
+        self.with_circuit(name, |circuit| match self.circuit_state(circuit) {
+            (State::Closed, _) => Ok(false),
+            (State::HalfOpen, _) if !circuit.trial_running => {
+                circuit.trial_running = true;
+                Ok(true)
+            }
+            (_, retry_in) => Err(CircuitOpen {
+                name: name.to_owned(),
+                retry_in,
+            }),
+        })
+    }
+
+    fn record(&self, name: &str, success: bool) {
+        self.with_circuit(name, |circuit| self.record_outcome(name, circuit, success));
+    }
+
+    fn record_outcome(&self, name: &str, circuit: &mut Circuit, success: bool) {
+        if circuit.trial_running {
+            circuit.trial_running = false;
+            if success {
+                log::info!("Circuit for {} closed", name);
+                *circuit = Circuit::default();
+            } else {
+                circuit.opened_at = Some(SystemTime::now());
+            }
+            return;
+        }
+        if circuit.opened_at.is_some() {
+            // Calls started before the circuit opened do not change its state
+            return;
+        }
+        circuit.outcomes.push_back(success);
+        while circuit.outcomes.len() > self.config.window {
+            circuit.outcomes.pop_front();
+        }
+        let failures = circuit.outcomes.iter().filter(|success| !**success).count();
+        let calls = circuit.outcomes.len();
+        if calls >= self.config.minimum_calls
+            && failures as f64 / calls as f64 >= self.config.failure_rate
+        {
+            log::warn!(
+                "Circuit for {} opened after {} of {} calls failed",
+                name,
+                failures,
+                calls
+            );
+            circuit.opened_at = Some(SystemTime::now());
+        }
+    }
+
+    /// Returns the state of the circuit and the time until it becomes half open
+    fn circuit_state(&self, circuit: &Circuit) -> (State, Duration) {
+        let Some(opened_at) = circuit.opened_at else {
+            return (State::Closed, Duration::ZERO);
+        };
+        let open_for = SystemTime::now()
+            .duration_since(opened_at)
+            .unwrap_or(Duration::ZERO);
+        if open_for >= self.config.open_duration {
+            (State::HalfOpen, Duration::ZERO)
+        } else {
+            (State::Open, self.config.open_duration - open_for)
+        }
+    }
+
+    fn with_circuit<T>(&self, name: &str, f: impl FnOnce(&mut Circuit) -> T) -> T {
+        let mut circuits = self
+            .circuits
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        f(circuits.entry(name.to_owned()).or_default())
+    }
+}
+
+/// Records a trial call as failed if it is dropped before its outcome is recorded,
+/// e.g. because the invocation timed out. Otherwise the circuit would never leave
+/// the half open state
+struct TrialGuard<'b> {
+    breaker: &'b CircuitBreaker,
+    name: &'b str,
+    trial: bool,
+}
+
+impl Drop for TrialGuard<'_> {
+    fn drop(&mut self) {
+        if self.trial {
+            log::warn!("Trial call for {} was dropped", self.name);
+            self.breaker.record(self.name, false);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::{Duration, SystemTime};
+
+/// Source of the wall clock time, which is used to compute the time left
+/// until the deadline of an invocation. See [`crate::Builder::clock`]
+///
+/// Lambda sends deadlines as wall clock time, while timers of the runtime
+/// use a monotonic clock. The conversion happens in [`Clock::until`], which
+/// never panics, even if the wall clock is skewed.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// Current wall clock time
+    fn now(&self) -> SystemTime;
+
+    /// Time left until `deadline`. Zero if the deadline already passed
+    fn until(&self, deadline: SystemTime) -> Duration {
+        deadline.duration_since(self.now()).unwrap_or_default()
+    }
+
+    /// Time left until a deadline in milliseconds since the unix epoch,
+    /// as it is sent by lambda. Zero if the deadline already passed
+    fn until_deadline_ms(&self, deadline_in_ms: u64) -> Duration {
+        SystemTime::UNIX_EPOCH
+            .checked_add(Duration::from_millis(deadline_in_ms))
+            .map_or(Duration::MAX, |deadline| self.until(deadline))
+    }
+}
+
+/// [`Clock`] using the time of the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
@@ -0,0 +1,180 @@
+//! Provides codecs for events and responses which are not plain JSON.
+//!
+//! Lambda always passes events as JSON, but direct invocations may embed
+//! other encodings, e.g. MessagePack encoded as base64 string or a custom
+//! envelope around the actual event. By using [`Coded`] as `Event` or
+//! `Return` type, the payload is decoded (or encoded) with a [`Codec`]
+//! before it reaches the runner, without changing how the lambda is executed.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::codec::{Codec, Coded};
+//! use serde_json::value::RawValue;
+//!
+//! /// Events are wrapped in `{"version": 1, "data": ...}`
+//! struct Envelope;
+//!
+//! impl Codec for Envelope {
+//!     fn decode<T>(payload: &RawValue) -> anyhow::Result<T>
+//!     where
+//!         T: serde::de::DeserializeOwned,
+//!     {
+//!         #[derive(serde::Deserialize)]
+//!         struct Wrapper<T> {
+//!             version: u32,
+//!             data: T,
+//!         }
+//!
+//!         let wrapper: Wrapper<T> = serde_json::from_str(payload.get())?;
+//!         anyhow::ensure!(wrapper.version == 1, "Unsupported version {}", wrapper.version);
+//!         Ok(wrapper.data)
+//!     }
+//!
+//!     fn encode<T>(value: &T) -> anyhow::Result<Box<RawValue>>
+//!     where
+//!         T: serde::Serialize,
+//!     {
+//!         Ok(serde_json::value::to_raw_value(
+//!             &serde_json::json!({ "version": 1, "data": value }),
+//!         )?)
+//!     }
+//! }
+//!
+//! #[derive(serde::Deserialize, Debug)]
+//! struct Order {
+//!     order_id: String,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, (), Coded<Envelope, Order>, Coded<Envelope, String>> for Runner {
+//!     async fn run(
+//!         _shared: &'a (),
+//!         event: lambda_runtime_types::LambdaEvent<'a, Coded<Envelope, Order>>,
+//!     ) -> anyhow::Result<Coded<Envelope, String>> {
+//!         Ok(Coded::new(event.event.into_inner().order_id))
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use serde_json::value::RawValue;
+use std::marker::PhantomData;
+
+/// Decodes events from and encodes responses into the JSON payload
+pub trait Codec {
+    /// Decodes a value from the payload of an invocation
+    fn decode<T>(payload: &RawValue) -> anyhow::Result<T>
+    where
+        T: serde::de::DeserializeOwned;
+
+    /// Encodes a value into the payload of a response
+    fn encode<T>(value: &T) -> anyhow::Result<Box<RawValue>>
+    where
+        T: serde::Serialize;
+}
+
+/// Codec for plain JSON, which is also used if no codec is specified
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+impl Codec for Json {
+    fn decode<T>(payload: &RawValue) -> anyhow::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        Ok(serde_json::from_str(payload.get())?)
+    }
+
+    fn encode<T>(value: &T) -> anyhow::Result<Box<RawValue>>
+    where
+        T: serde::Serialize,
+    {
+        Ok(serde_json::value::to_raw_value(value)?)
+    }
+}
+
+/// Event or return type which is decoded or encoded with the codec `C`
+pub struct Coded<C, T> {
+    value: T,
+    codec: PhantomData<fn() -> C>,
+}
+
+impl<C, T> Coded<C, T> {
+    /// Wraps `value`, e.g. to return it as response
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            codec: PhantomData,
+        }
+    }
+
+    /// Returns the decoded value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<C, T> std::ops::Deref for Coded<C, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<C, T: std::fmt::Debug> std::fmt::Debug for Coded<C, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Coded").field(&self.value).finish()
+    }
+}
+
+impl<C, T: Clone> Clone for Coded<C, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<'de, C, T> serde::Deserialize<'de> for Coded<C, T>
+where
+    C: Codec,
+    T: serde::de::DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let payload = Box::<RawValue>::deserialize(deserializer)?;
+        C::decode(&payload)
+            .map(Self::new)
+            .map_err(|err| D::Error::custom(format!("{:#}", err)))
+    }
+}
+
+impl<C, T> serde::Serialize for Coded<C, T>
+where
+    C: Codec,
+    T: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::Error;
+
+        C::encode(&self.value)
+            .map_err(|err| S::Error::custom(format!("{:#}", err)))?
+            .serialize(serializer)
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+/// Price per GB-second for `x86_64` functions in USD
+pub const PRICE_PER_GB_SECOND_X86: f64 = 0.000_016_666_7;
+/// Price per GB-second for `arm64` functions in USD
+pub const PRICE_PER_GB_SECOND_ARM: f64 = 0.000_013_333_4;
+/// Price per request in USD
+pub const PRICE_PER_REQUEST: f64 = 0.000_000_2;
+
+/// Estimated cost of a single invocation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// Billed duration, rounded up to the next millisecond
+    pub billed: Duration,
+    /// Consumed GB-seconds
+    pub gb_seconds: f64,
+    /// Estimated cost in USD including the request charge
+    pub cost: f64,
+}
+
+impl Estimate {
+    /// Estimates the cost of an invocation with the given duration and
+    /// memory size in MB based on the on-demand prices of `us-east-1`.
+    pub fn new(duration: Duration, memory: i32) -> Self {
+        let billed_ms = duration.as_nanos().div_ceil(1_000_000);
+        let billed = Duration::from_millis(u64::try_from(billed_ms).unwrap_or(u64::MAX));
+        let gb_seconds = billed.as_secs_f64() * f64::from(memory.max(0)) / 1024.0;
+        let price = if std::env::consts::ARCH == "aarch64" {
+            PRICE_PER_GB_SECOND_ARM
+        } else {
+            PRICE_PER_GB_SECOND_X86
+        };
+        Self {
+            billed,
+            gb_seconds,
+            cost: gb_seconds.mul_add(price, PRICE_PER_REQUEST),
+        }
+    }
+}
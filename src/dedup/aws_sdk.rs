@@ -0,0 +1,89 @@
+use std::time::{Duration, SystemTime};
+
+/// [`super::Store`] which persists results in a DynamoDB table.
+///
+/// The table requires a string partition key named `id`. Results are
+/// stored as json in the attribute `result` and the expiry time in the
+/// attribute `expires_at`, which can be configured as the tables ttl
+/// attribute.
+#[derive(Clone, Debug)]
+pub struct DynamoDbStore {
+    client: aws_sdk_dynamodb::Client,
+    table: String,
+}
+
+impl DynamoDbStore {
+    /// Creates a new store using the given table
+    pub async fn new(table: impl Into<String>) -> Self {
+        let config = aws_config::load_from_env().await;
+        let client = aws_sdk_dynamodb::Client::new(&config);
+        Self::with_client(client, table)
+    }
+
+    /// Creates a new store using an existing client
+    pub fn with_client(client: aws_sdk_dynamodb::Client, table: impl Into<String>) -> Self {
+        Self {
+            client,
+            table: table.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl super::Store for DynamoDbStore {
+    async fn get(&self, id: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        use anyhow::Context;
+        use aws_sdk_dynamodb::model::AttributeValue;
+
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .key("id", AttributeValue::S(id.to_owned()))
+            .consistent_read(true)
+            .send()
+            .await
+            .with_context(|| format!("Unable to fetch deduplication entry with id: {}", id))?;
+        let Some(item) = output.item() else {
+            return Ok(None);
+        };
+        // DynamoDB removes expired items with a delay
+        let expires_at = item
+            .get("expires_at")
+            .and_then(|v| v.as_n().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        if expires_at.is_some_and(|expires_at| expires_at < unix_time(SystemTime::now())) {
+            return Ok(None);
+        }
+        let result = item
+            .get("result")
+            .and_then(|v| v.as_s().ok())
+            .with_context(|| format!("Deduplication entry with id {} has no result", id))?;
+        let value = serde_json::from_str(result)
+            .with_context(|| format!("Deduplication entry with id {} is invalid json", id))?;
+        Ok(Some(value))
+    }
+
+    async fn put(&self, id: &str, value: &serde_json::Value, ttl: Duration) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use aws_sdk_dynamodb::model::AttributeValue;
+
+        let expires_at = unix_time(SystemTime::now() + ttl);
+        self.client
+            .put_item()
+            .table_name(&self.table)
+            .item("id", AttributeValue::S(id.to_owned()))
+            .item("result", AttributeValue::S(value.to_string()))
+            .item("expires_at", AttributeValue::N(expires_at.to_string()))
+            .send()
+            .await
+            .with_context(|| format!("Unable to store deduplication entry with id: {}", id))?;
+        Ok(())
+    }
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,246 @@
+//! Provides a helper to skip duplicate deliveries of the same event.
+//!
+//! Events are identified by an id extracted by the runner (e.g. SQS message id,
+//! EventBridge id or request id). Results of processed events are kept in an
+//! in-memory LRU cache, which survives as long as the execution environment does.
+//! Optionally, results can additionally be persisted in a [`Store`] to detect
+//! duplicates across execution environments. A DynamoDB store is available with
+//! the feature `dedup_aws_sdk`.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::dedup::Deduplicator;
+//!
+//! #[derive(serde::Deserialize, Debug)]
+//! struct Event {
+//!     id: String,
+//! }
+//!
+//! struct Shared {
+//!     dedup: Deduplicator<u64>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, Event, u64> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, Event>) -> anyhow::Result<u64> {
+//!         let res = shared
+//!             .dedup
+//!             .run(&event.event.id, || async {
+//!                 // Only executed once per id
+//!                 Ok(42)
+//!             })
+//!             .await?;
+//!         Ok(res.into_inner())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             dedup: Deduplicator::new(1000, std::time::Duration::from_secs(3600)),
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+#[cfg(feature = "dedup_aws_sdk")]
+mod aws_sdk;
+
+#[cfg(feature = "dedup_aws_sdk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dedup_aws_sdk")))]
+pub use aws_sdk::DynamoDbStore;
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime};
+
+/// Persistent storage for results of processed events
+#[async_trait::async_trait]
+pub trait Store: Send + Sync {
+    /// Returns the stored result for the given id, if there is one
+    /// which did not expire yet
+    async fn get(&self, id: &str) -> anyhow::Result<Option<serde_json::Value>>;
+
+    /// Stores the result for the given id. The entry may be removed
+    /// after `ttl` has passed
+    async fn put(&self, id: &str, value: &serde_json::Value, ttl: Duration) -> anyhow::Result<()>;
+}
+
+/// Result of [`Deduplicator::run`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deduplicated<R> {
+    /// The event was not seen before and got processed
+    New(R),
+    /// The event was already processed. Contains the previous result
+    Duplicate(R),
+}
+
+impl<R> Deduplicated<R> {
+    /// Returns the result, regardless of whether it is new or not
+    pub fn into_inner(self) -> R {
+        match self {
+            Self::New(res) | Self::Duplicate(res) => res,
+        }
+    }
+
+    /// Whether the event was already processed before
+    pub const fn is_duplicate(&self) -> bool {
+        matches!(self, Self::Duplicate(_))
+    }
+}
+
+/// Skips events which were already processed
+pub struct Deduplicator<R> {
+    ttl: Duration,
+    cache: std::sync::Mutex<Lru<R>>,
+    store: Option<Box<dyn Store>>,
+}
+
+impl<R> std::fmt::Debug for Deduplicator<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Deduplicator")
+            .field("ttl", &self.ttl)
+            .field("store", &self.store.as_ref().map(|_| "[...]"))
+            .finish()
+    }
+}
+
+impl<R> Deduplicator<R>
+where
+    R: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// Creates a new deduplicator which keeps up to `capacity` results in memory.
+    /// Results are considered for deduplication for the duration of `ttl`
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cache: std::sync::Mutex::new(Lru::new(capacity)),
+            store: None,
+        }
+    }
+
+    /// Additionally persists results in the given store
+    #[must_use]
+    pub fn with_store(mut self, store: impl Store + 'static) -> Self {
+        self.store = Some(Box::new(store));
+        self
+    }
+
+    /// Runs `operation` if no result for `id` is known. Otherwise the
+    /// previous result is returned. Results are only remembered if
+    /// `operation` succeeds, so failed events can be retried.
+    pub async fn run<Op, Fut>(&self, id: &str, operation: Op) -> anyhow::Result<Deduplicated<R>>
+    where
+        Op: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<R>>,
+    {
+        use anyhow::Context;
+
+        if let Some(res) = self.get(id).await? {
+            log::info!("Skipping duplicate event with id: {}", id);
+            return Ok(Deduplicated::Duplicate(res));
+        }
+        let res = operation().await?;
+        if let Some(store) = &self.store {
+            let value = serde_json::to_value(&res)
+                .with_context(|| format!("Unable to serialize result for id: {}", id))?;
+            store.put(id, &value, self.ttl).await?;
+        }
+        self.lock().insert(id, res.clone(), SystemTime::now());
+        Ok(Deduplicated::New(res))
+    }
+
+    /// Returns the previous result for the given id
+    pub async fn get(&self, id: &str) -> anyhow::Result<Option<R>> {
+        use anyhow::Context;
+
+        let cached = self.lock().get(id, self.ttl);
+        if cached.is_some() {
+            return Ok(cached);
+        }
+        let Some(store) = &self.store else {
+            return Ok(None);
+        };
+        let Some(value) = store.get(id).await? else {
+            return Ok(None);
+        };
+        let res: R = serde_json::from_value(value)
+            .with_context(|| format!("Unable to deserialize stored result for id: {}", id))?;
+        self.lock().insert(id, res.clone(), SystemTime::now());
+        Ok(Some(res))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru<R>> {
+        self.cache
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Least recently used cache
+struct Lru<R> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, Entry<R>>,
+    order: BTreeMap<u64, String>,
+}
+
+struct Entry<R> {
+    value: R,
+    inserted_at: SystemTime,
+    tick: u64,
+}
+
+impl<R: Clone> Lru<R> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, id: &str, ttl: Duration) -> Option<R> {
+        let entry = self.entries.get_mut(id)?;
+        let age = SystemTime::now()
+            .duration_since(entry.inserted_at)
+            .unwrap_or(Duration::ZERO);
+        if age > ttl {
+            self.order.remove(&entry.tick);
+            self.entries.remove(id);
+            return None;
+        }
+        self.tick += 1;
+        self.order.remove(&entry.tick);
+        self.order.insert(self.tick, id.to_owned());
+        entry.tick = self.tick;
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, id: &str, value: R, inserted_at: SystemTime) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        let entry = Entry {
+            value,
+            inserted_at,
+            tick: self.tick,
+        };
+        if let Some(previous) = self.entries.insert(id.to_owned(), entry) {
+            self.order.remove(&previous.tick);
+        }
+        self.order.insert(self.tick, id.to_owned());
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+}
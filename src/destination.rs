@@ -0,0 +1,144 @@
+//! Provides types for lambdas consuming Lambda Destinations events.
+//!
+//! When a lambda is configured as `on_success` or `on_failure` destination of
+//! another lambda, it receives an envelope containing the original request and
+//! the response (or error) of the invoking lambda.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::destination::{ErrorPayload, Event};
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Order {
+//!     id: String,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, (), Event<Order, ErrorPayload>, ()> for Runner {
+//!     async fn run(
+//!         shared: &'a (),
+//!         event: lambda_runtime_types::LambdaEvent<'a, Event<Order, ErrorPayload>>,
+//!     ) -> anyhow::Result<()> {
+//!         let failure = event.event;
+//!         log::error!(
+//!             "Order {} failed after {} attempts: {:?}",
+//!             failure.request_payload.id,
+//!             failure.request_context.approximate_invoke_count,
+//!             failure.response_payload,
+//!         );
+//!         Ok(())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+/// `Event` which is send to a destination lambda. `Request` is the
+/// event of the invoking lambda and `Response` its result, which is
+/// usually [`ErrorPayload`] for `on_failure` destinations.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Event<Request, Response> {
+    /// Version of the event format
+    #[serde(rename = "version")]
+    pub version: String,
+    /// Time of the invocation
+    #[serde(rename = "timestamp")]
+    pub timestamp: String,
+    /// Information about the invocation
+    #[serde(rename = "requestContext")]
+    pub request_context: RequestContext,
+    /// Event which was send to the invoking lambda
+    #[serde(rename = "requestPayload")]
+    pub request_payload: Request,
+    /// Information about the response. Not available if
+    /// the event was never processed
+    #[serde(rename = "responseContext")]
+    pub response_context: Option<ResponseContext>,
+    /// Result of the invoking lambda. Not available if
+    /// the event was never processed
+    #[serde(rename = "responsePayload")]
+    pub response_payload: Option<Response>,
+}
+
+/// Information about the invocation of a destination `Event`
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RequestContext {
+    /// Request id of the invocation
+    #[serde(rename = "requestId")]
+    pub request_id: String,
+    /// Arn of the invoked lambda
+    #[serde(rename = "functionArn")]
+    pub function_arn: String,
+    /// Reason why the event was send to the destination
+    #[serde(rename = "condition")]
+    pub condition: Condition,
+    /// Amount of times the lambda was invoked with the event
+    #[serde(rename = "approximateInvokeCount")]
+    pub approximate_invoke_count: u32,
+}
+
+/// Reason why a destination `Event` was send
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub enum Condition {
+    /// The invocation succeeded
+    #[serde(rename = "Success")]
+    Success,
+    /// All retries failed
+    #[serde(rename = "RetriesExhausted")]
+    RetriesExhausted,
+    /// The event was too old to be processed
+    #[serde(rename = "EventAgeExceeded")]
+    EventAgeExceeded,
+    /// Any other condition
+    #[serde(other)]
+    Other,
+}
+
+/// Information about the response of a destination `Event`
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ResponseContext {
+    /// Status code of the invocation
+    #[serde(rename = "statusCode")]
+    pub status_code: u16,
+    /// Version of the lambda which was executed
+    #[serde(rename = "executedVersion")]
+    pub executed_version: String,
+    /// Type of the error, if the invocation failed
+    #[serde(rename = "functionError")]
+    pub function_error: Option<String>,
+}
+
+/// Error returned by a failed lambda invocation
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ErrorPayload {
+    /// Error message
+    #[serde(rename = "errorMessage")]
+    pub error_message: String,
+    /// Type of the error
+    #[serde(rename = "errorType")]
+    pub error_type: Option<String>,
+    /// Stack trace of the error, if available
+    #[serde(rename = "stackTrace", default)]
+    pub stack_trace: Vec<String>,
+    /// Additional fields of the error. See [`crate::ErrorShape::fields`]
+    #[serde(flatten)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl std::fmt::Display for ErrorPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.error_type {
+            Some(error_type) => write!(f, "{}: {}", error_type, self.error_message),
+            None => f.write_str(&self.error_message),
+        }
+    }
+}
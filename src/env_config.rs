@@ -0,0 +1,225 @@
+//! Provides a loader which deserializes configuration from env variables.
+//!
+//! Every field of the configuration is read from the env variable with the
+//! uppercased field name, optionally with a prefix. Numbers and booleans are
+//! parsed, sequences are separated by commas and fields of type [`Option`] may
+//! be missing. If variables are missing, loading fails with an error listing
+//! all of them, so startup fails early instead of during an invocation.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! #[derive(serde::Deserialize)]
+//! struct Config {
+//!     // Read from `ORDERS_TABLE_NAME`
+//!     table_name: String,
+//!     // Read from `ORDERS_BATCH_SIZE`
+//!     batch_size: u32,
+//!     // Read from `ORDERS_ENDPOINT`, which may be missing
+//!     endpoint: Option<String>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Config, (), ()> for Runner {
+//!     async fn run(shared: &'a Config, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+//!         log::info!("Writing batches of {} to {}", shared.batch_size, shared.table_name);
+//!         Ok(())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Config> {
+//!         lambda_runtime_types::env_config::load_prefixed("ORDERS_")
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use serde::de::{self, IntoDeserializer};
+
+/// Deserializes `T` from env variables named like its fields
+pub fn load<T>() -> anyhow::Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    load_prefixed("")
+}
+
+/// Deserializes `T` from env variables named like its fields with `prefix`
+pub fn load_prefixed<T>(prefix: &str) -> anyhow::Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let mut vars: Vec<_> = std::env::vars_os()
+        .filter_map(|(key, value)| {
+            let key = key.into_string().ok()?.strip_prefix(prefix)?.to_lowercase();
+            let value = value.into_string().ok()?;
+            Some((key, Value(Some(value))))
+        })
+        .collect();
+    let mut missing = Vec::new();
+    loop {
+        match T::deserialize(Vars(vars.clone())) {
+            Ok(config) if missing.is_empty() => return Ok(config),
+            Err(Error::Custom(err)) if missing.is_empty() => {
+                anyhow::bail!("Invalid configuration: {}", err)
+            }
+            // Collect all missing variables by retrying with a placeholder
+            Err(Error::Missing(field)) if !missing.contains(&field) => {
+                missing.push(field);
+                vars.push((field.to_owned(), Value(None)));
+            }
+            _ => break,
+        }
+    }
+    let missing: Vec<_> = missing
+        .into_iter()
+        .map(|field| format!("{}{}", prefix, field.to_uppercase()))
+        .collect();
+    anyhow::bail!(
+        "Missing configuration env variables: {}",
+        missing.join(", ")
+    )
+}
+
+#[derive(Debug)]
+enum Error {
+    Missing(&'static str),
+    Custom(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(field) => write!(f, "missing field `{}`", field),
+            Self::Custom(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::Missing(field)
+    }
+}
+
+/// All env variables, keyed by the lowercased name without prefix
+struct Vars(Vec<(String, Value)>);
+
+impl<'de> de::Deserializer<'de> for Vars {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(de::value::MapDeserializer::new(self.0.into_iter()))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Value of an env variable. `None` is a placeholder for a missing
+/// variable, which deserializes into an empty value
+#[derive(Clone)]
+struct Value(Option<String>);
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0 {
+                    Some(value) => visitor.$visit(value.trim().parse().map_err(|err| {
+                        de::Error::custom(format!("invalid value {:?}: {}", value, err))
+                    })?),
+                    None => visitor.$visit(Default::default()),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0.unwrap_or_default())
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            Some(_) => visitor.visit_some(self),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let values: Vec<_> = match self.0 {
+            Some(value) if !value.is_empty() => value
+                .split(',')
+                .map(|value| Self(Some(value.trim().to_owned())))
+                .collect(),
+            _ => Vec::new(),
+        };
+        visitor.visit_seq(de::value::SeqDeserializer::new(values.into_iter()))
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let variant = self
+            .0
+            .unwrap_or_else(|| variants.first().copied().unwrap_or_default().to_owned());
+        visitor.visit_enum(variant.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
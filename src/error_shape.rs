@@ -0,0 +1,90 @@
+/// Error as it is reported to lambda for a failed invocation.
+///
+/// `error_type` is what Step Functions `Catch` and `Retry` clauses match
+/// against, while `error_message` is shown in the console and passed to
+/// `on_failure` destinations. See [`crate::Runner::classify`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ErrorShape {
+    /// Type of the error, e.g. `PaymentDeclined`
+    #[serde(rename = "errorType")]
+    pub error_type: String,
+    /// Human readable message of the error
+    #[serde(rename = "errorMessage")]
+    pub error_message: String,
+    /// Additional fields of the error, e.g. an error code. They are reported
+    /// next to `errorType` and `errorMessage`, which they can not replace
+    #[serde(flatten)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Fields which are always set by [`ErrorShape`]
+const RESERVED_FIELDS: [&str; 2] = ["errorType", "errorMessage"];
+
+impl ErrorShape {
+    /// Creates a new error shape
+    pub fn new(error_type: impl Into<String>, error_message: impl Into<String>) -> Self {
+        Self {
+            error_type: error_type.into(),
+            error_message: error_message.into(),
+            fields: serde_json::Map::new(),
+        }
+    }
+
+    /// Shape which is used if the runner does not classify errors. The message
+    /// is the outermost context of `error`, the type is derived from its root cause:
+    ///
+    /// * [`crate::ValidationError`] as `ValidationError`
+    /// * [`std::io::Error`] as `IoError`
+    /// * [`serde_json::Error`] as `JsonError`
+    /// * [`tokio::time::error::Elapsed`] as `TimeoutError`
+    /// * any other error as `Error`
+    ///
+    /// To report own error types, use [`crate::Runner::classify`]
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        if error.is::<crate::ValidationError>() {
+            return Self::new("ValidationError", error.to_string());
+        }
+        let root_cause = error.root_cause();
+        let error_type = if root_cause.is::<std::io::Error>() {
+            "IoError"
+        } else if root_cause.is::<serde_json::Error>() {
+            "JsonError"
+        } else if root_cause.is::<tokio::time::error::Elapsed>() {
+            "TimeoutError"
+        } else {
+            "Error"
+        };
+        Self::new(error_type, error.to_string())
+    }
+
+    /// Shape of a structured error. The message is the `Display` output of `error`,
+    /// and the fields of its serialized form are reported as additional fields.
+    /// Errors which do not serialize into an object are reported as `errorData`
+    pub fn from_serialize<E>(error_type: impl Into<String>, error: &E) -> Self
+    where
+        E: serde::Serialize + std::fmt::Display + ?Sized,
+    {
+        let shape = Self::new(error_type, error.to_string());
+        match serde_json::to_value(error) {
+            Ok(serde_json::Value::Object(fields)) => fields
+                .into_iter()
+                .fold(shape, |shape, (name, value)| shape.with_field(name, value)),
+            Ok(value) => shape.with_field("errorData", value),
+            Err(err) => {
+                log::warn!("Unable to serialize error {}: {}", shape.error_type, err);
+                shape
+            }
+        }
+    }
+
+    /// Adds an additional field to the error. `errorType` and `errorMessage`
+    /// are ignored, use `error_type` and `error_message` instead
+    #[must_use]
+    pub fn with_field(mut self, name: impl Into<String>, value: serde_json::Value) -> Self {
+        let name = name.into();
+        if !RESERVED_FIELDS.contains(&name.as_str()) {
+            self.fields.insert(name, value);
+        }
+        self
+    }
+}
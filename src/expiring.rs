@@ -0,0 +1,157 @@
+//! Provides state for `Shared` which expires and is refreshed transparently.
+//!
+//! Tokens, cached configuration or secrets in `Shared` often have a limited
+//! lifetime. An [`Expiring`] value is built with a user provided refresh function
+//! and rebuilt by [`Expiring::get`] once it expired, so `run` always receives a
+//! valid value. The lifetime is either a fixed ttl or returned by the refresh
+//! function together with the value, e.g. from the `expires_in` of a token.
+//!
+//! To refresh the value before `run` is invoked, instead of on first use, call
+//! [`Expiring::refresh_if_expired`] in [`crate::Runner::before_invoke`].
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # async fn fetch_token() -> anyhow::Result<(String, u64)> { Ok((String::new(), 3600)) }
+//! use lambda_runtime_types::expiring::Expiring;
+//! use std::time::{Duration, SystemTime};
+//!
+//! struct Shared {
+//!     token: Expiring<String>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), ()> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+//!         let token = shared.token.get().await?;
+//!         log::info!("Using token {}", token);
+//!         Ok(())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         let token = Expiring::with_expiry(|| async {
+//!             let (token, expires_in) = fetch_token().await?;
+//!             Ok((token, SystemTime::now() + Duration::from_secs(expires_in)))
+//!         })
+//!         .refresh_margin(Duration::from_secs(60));
+//!         Ok(Shared { token })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+type Refresh<T> =
+    Box<dyn Fn() -> BoxFuture<'static, anyhow::Result<(T, SystemTime)>> + Send + Sync>;
+
+/// Value which is refreshed once it expired
+pub struct Expiring<T> {
+    refresh: Refresh<T>,
+    margin: Duration,
+    entry: tokio::sync::Mutex<Option<Entry<T>>>,
+}
+
+struct Entry<T> {
+    value: Arc<T>,
+    expires_at: SystemTime,
+}
+
+impl<T> std::fmt::Debug for Expiring<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Expiring")
+            .field("margin", &self.margin)
+            .field("value", &"[...]")
+            .finish()
+    }
+}
+
+impl<T: Send + Sync + 'static> Expiring<T> {
+    /// Creates a new value, which is built with `refresh` and
+    /// refreshed once `ttl` passed
+    pub fn new<R, Fut>(ttl: Duration, refresh: R) -> Self
+    where
+        R: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        Self::with_expiry(move || {
+            let value = refresh();
+            async move { Ok((value.await?, SystemTime::now() + ttl)) }
+        })
+    }
+
+    /// Creates a new value, which is built with `refresh`. Besides the
+    /// value, `refresh` returns the time at which the value expires
+    pub fn with_expiry<R, Fut>(refresh: R) -> Self
+    where
+        R: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<(T, SystemTime)>> + Send + 'static,
+    {
+        Self {
+            refresh: Box::new(move || Box::pin(refresh())),
+            margin: Duration::ZERO,
+            entry: tokio::sync::Mutex::default(),
+        }
+    }
+
+    /// Time before the expiry at which the value is already refreshed,
+    /// so it does not expire while it is used. Defaults to zero
+    #[must_use]
+    pub const fn refresh_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Returns the value or refreshes it, if it is missing or expired.
+    /// If refreshing fails, the error is returned and the next access
+    /// tries again
+    pub async fn get(&self) -> anyhow::Result<Arc<T>> {
+        let mut entry = self.entry.lock().await;
+        if let Some(entry) = entry.as_ref() {
+            let expired = entry
+                .expires_at
+                .checked_sub(self.margin)
+                .is_none_or(|refresh_at| refresh_at <= SystemTime::now());
+            if !expired {
+                return Ok(Arc::clone(&entry.value));
+            }
+        }
+        log::info!("Refreshing expired shared value");
+        let (value, expires_at) = (self.refresh)().await?;
+        let value = Arc::new(value);
+        *entry = Some(Entry {
+            value: Arc::clone(&value),
+            expires_at,
+        });
+        drop(entry);
+        Ok(value)
+    }
+
+    /// Refreshes the value, if it is missing or expired. Can be called
+    /// in [`crate::Runner::before_invoke`] to refresh it before `run`
+    pub async fn refresh_if_expired(&self) -> anyhow::Result<()> {
+        self.get().await.map(drop)
+    }
+
+    /// Time at which the current value expires. `None` if
+    /// the value was not built yet
+    pub async fn expires_at(&self) -> Option<SystemTime> {
+        self.entry
+            .lock()
+            .await
+            .as_ref()
+            .map(|entry| entry.expires_at)
+    }
+
+    /// Removes the value, so it is refreshed on the next access
+    pub async fn invalidate(&self) {
+        *self.entry.lock().await = None;
+    }
+}
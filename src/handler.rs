@@ -0,0 +1,293 @@
+use crate::{
+    ErrorShape, InstanceRunner, InvocationInfo, LambdaEvent, LocalRunner, TimeoutBehavior,
+    ValidationError,
+};
+use std::future::Future;
+
+/// Runner as used by the invocation pipeline. Abstracts over runners
+/// with `Send` futures ([`InstanceRunner`]) and without ([`LocalRunner`]),
+/// so the pipeline is only `Send` if the runner futures are.
+pub trait Handler<Shared, Event, Return> {
+    fn run<'a>(
+        &'a self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+    ) -> impl Future<Output = anyhow::Result<Return>>
+    where
+        Event: 'a;
+
+    fn timeout(&self) -> TimeoutBehavior;
+
+    fn deny_unknown_fields(&self) -> bool;
+
+    fn early_warning(&self) -> Option<f32>;
+
+    fn heartbeat(&self) -> Option<std::time::Duration>;
+
+    fn validate(&self, event: &Event) -> Result<(), ValidationError>;
+
+    fn warmup(&self, event: &serde_json::value::RawValue) -> Option<serde_json::Value>;
+
+    fn fallback(&self, shared: &Shared, error: &anyhow::Error) -> Option<Return>;
+
+    fn on_error<'a>(
+        &'a self,
+        shared: &'a Shared,
+        error: anyhow::Error,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = anyhow::Result<Return>>;
+
+    fn on_timeout<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()>;
+
+    fn on_early_warning<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()>;
+
+    fn on_heartbeat<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()>;
+
+    fn before_invoke<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()>;
+
+    fn after_invoke<'a>(
+        &'a self,
+        shared: &'a Shared,
+        info: &'a InvocationInfo<'a>,
+    ) -> impl Future<Output = ()>;
+
+    fn classify(&self, error: &anyhow::Error) -> ErrorShape;
+
+    fn on_shutdown<'a>(&'a self, shared: &'a Shared) -> impl Future<Output = ()>;
+}
+
+/// [`Handler`] executing an [`InstanceRunner`]
+pub struct Instance<'r, Run>(pub &'r Run);
+
+impl<Shared, Event, Return, Run> Handler<Shared, Event, Return> for Instance<'_, Run>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize,
+    Run: InstanceRunner<Shared, Event, Return>,
+{
+    fn run<'a>(
+        &'a self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+    ) -> impl Future<Output = anyhow::Result<Return>>
+    where
+        Event: 'a,
+    {
+        self.0.run(shared, event)
+    }
+
+    fn timeout(&self) -> TimeoutBehavior {
+        self.0.timeout()
+    }
+
+    fn deny_unknown_fields(&self) -> bool {
+        self.0.deny_unknown_fields()
+    }
+
+    fn early_warning(&self) -> Option<f32> {
+        self.0.early_warning()
+    }
+
+    fn heartbeat(&self) -> Option<std::time::Duration> {
+        self.0.heartbeat()
+    }
+
+    fn validate(&self, event: &Event) -> Result<(), ValidationError> {
+        self.0.validate(event)
+    }
+
+    fn warmup(&self, event: &serde_json::value::RawValue) -> Option<serde_json::Value> {
+        self.0.warmup(event)
+    }
+
+    fn fallback(&self, shared: &Shared, error: &anyhow::Error) -> Option<Return> {
+        self.0.fallback(shared, error)
+    }
+
+    fn on_error<'a>(
+        &'a self,
+        shared: &'a Shared,
+        error: anyhow::Error,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = anyhow::Result<Return>> {
+        self.0.on_error(shared, error, ctx)
+    }
+
+    fn on_timeout<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()> {
+        self.0.on_timeout(shared, ctx)
+    }
+
+    fn on_early_warning<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()> {
+        self.0.on_early_warning(shared, ctx)
+    }
+
+    fn on_heartbeat<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()> {
+        self.0.on_heartbeat(shared, ctx)
+    }
+
+    fn before_invoke<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()> {
+        self.0.before_invoke(shared, ctx)
+    }
+
+    fn after_invoke<'a>(
+        &'a self,
+        shared: &'a Shared,
+        info: &'a InvocationInfo<'a>,
+    ) -> impl Future<Output = ()> {
+        self.0.after_invoke(shared, info)
+    }
+
+    fn classify(&self, error: &anyhow::Error) -> ErrorShape {
+        self.0.classify(error)
+    }
+
+    fn on_shutdown<'a>(&'a self, shared: &'a Shared) -> impl Future<Output = ()> {
+        self.0.on_shutdown(shared)
+    }
+}
+
+/// [`Handler`] executing a [`LocalRunner`]
+pub struct Local<Run>(std::marker::PhantomData<fn() -> Run>);
+
+impl<Run> Default for Local<Run> {
+    fn default() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+#[allow(clippy::future_not_send)]
+impl<Shared, Event, Return, Run> Handler<Shared, Event, Return> for Local<Run>
+where
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug,
+    Return: serde::Serialize,
+    Run: for<'a> LocalRunner<'a, Shared, Event, Return>,
+{
+    fn run<'a>(
+        &'a self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+    ) -> impl Future<Output = anyhow::Result<Return>>
+    where
+        Event: 'a,
+    {
+        Run::run(shared, event)
+    }
+
+    fn timeout(&self) -> TimeoutBehavior {
+        <Run as LocalRunner<'_, Shared, Event, Return>>::TIMEOUT
+    }
+
+    fn deny_unknown_fields(&self) -> bool {
+        <Run as LocalRunner<'_, Shared, Event, Return>>::DENY_UNKNOWN_FIELDS
+    }
+
+    fn early_warning(&self) -> Option<f32> {
+        <Run as LocalRunner<'_, Shared, Event, Return>>::EARLY_WARNING
+    }
+
+    fn heartbeat(&self) -> Option<std::time::Duration> {
+        <Run as LocalRunner<'_, Shared, Event, Return>>::HEARTBEAT
+    }
+
+    fn validate(&self, event: &Event) -> Result<(), ValidationError> {
+        <Run as LocalRunner<'_, Shared, Event, Return>>::validate(event)
+    }
+
+    fn warmup(&self, _event: &serde_json::value::RawValue) -> Option<serde_json::Value> {
+        None
+    }
+
+    fn fallback(&self, _shared: &Shared, _error: &anyhow::Error) -> Option<Return> {
+        None
+    }
+
+    async fn on_error<'a>(
+        &'a self,
+        _shared: &'a Shared,
+        error: anyhow::Error,
+        _ctx: &'a crate::Context,
+    ) -> anyhow::Result<Return> {
+        Err(error)
+    }
+
+    fn on_timeout<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()> {
+        Run::on_timeout(shared, ctx)
+    }
+
+    fn on_early_warning<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()> {
+        Run::on_early_warning(shared, ctx)
+    }
+
+    fn on_heartbeat<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()> {
+        Run::on_heartbeat(shared, ctx)
+    }
+
+    fn before_invoke<'a>(
+        &'a self,
+        shared: &'a Shared,
+        ctx: &'a crate::Context,
+    ) -> impl Future<Output = ()> {
+        Run::before_invoke(shared, ctx)
+    }
+
+    fn after_invoke<'a>(
+        &'a self,
+        shared: &'a Shared,
+        info: &'a InvocationInfo<'a>,
+    ) -> impl Future<Output = ()> {
+        Run::after_invoke(shared, info)
+    }
+
+    fn classify(&self, error: &anyhow::Error) -> ErrorShape {
+        <Run as LocalRunner<'_, Shared, Event, Return>>::classify(error)
+    }
+
+    fn on_shutdown<'a>(&'a self, shared: &'a Shared) -> impl Future<Output = ()> {
+        Run::on_shutdown(shared)
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{Context, Stats};
+use std::time::Duration;
+
+/// Information about a completed invocation. See [`crate::Runner::after_invoke`]
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct InvocationInfo<'a> {
+    /// Lambda Invocation Context of the invocation
+    pub ctx: &'a Context,
+    /// Time between the start of the invocation and
+    /// the response being produced
+    pub duration: Duration,
+    /// Whether the invocation succeeded
+    pub outcome: InvocationOutcome<'a>,
+    /// Statistics of the execution environment, taken
+    /// at the start of the invocation
+    pub stats: &'a Stats,
+}
+
+/// Outcome of a completed invocation
+#[derive(Debug, Clone, Copy)]
+pub enum InvocationOutcome<'a> {
+    /// The runner returned a response
+    Success,
+    /// The runner failed, but a response was provided
+    /// by `fallback` or `on_error`
+    Fallback,
+    /// The invocation failed with the error
+    Failure(&'a anyhow::Error),
+}
@@ -0,0 +1,48 @@
+/// Error type reported for failed initializations
+const ERROR_TYPE: &str = "Runtime.InitError";
+
+/// Executes the initialization of the lambda. If it fails, the error is
+/// reported to the `/runtime/init/error` endpoint of the Runtime API,
+/// so it shows up as initialization error instead of a crash.
+pub async fn report<T>(
+    init: impl std::future::Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    let err = match init.await {
+        Ok(res) => return Ok(res),
+        Err(err) => err,
+    };
+    log::error!("Lambda initialization failed: {:?}", err);
+    if std::env::var_os("AWS_LAMBDA_RUNTIME_API").is_some() {
+        if let Err(report_err) = send(&err).await {
+            log::warn!(
+                "Unable to report initialization error to the Runtime API: {:?}",
+                report_err
+            );
+        }
+    }
+    Err(err)
+}
+
+async fn send(err: &anyhow::Error) -> anyhow::Result<()> {
+    use anyhow::{anyhow, Context};
+
+    let body = serde_json::json!({
+        "errorMessage": format!("{:#}", err),
+        "errorType": ERROR_TYPE,
+        "stackTrace": [],
+    });
+    let req = lambda_runtime_api_client::build_request()
+        .method(http::Method::POST)
+        .uri("/2018-06-01/runtime/init/error")
+        .header("Lambda-Runtime-Function-Error-Type", ERROR_TYPE)
+        .body(hyper::Body::from(body.to_string()))
+        .context("Unable to build init error request")?;
+    let client = lambda_runtime_api_client::Client::builder()
+        .build()
+        .map_err(|err| anyhow!(err))?;
+    let res = client.call(req).await.map_err(|err| anyhow!(err))?;
+    if !res.status().is_success() {
+        anyhow::bail!("Runtime API responded with status {}", res.status());
+    }
+    Ok(())
+}
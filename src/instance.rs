@@ -0,0 +1,249 @@
+use crate::{LambdaEvent, Runner, TimeoutBehavior};
+
+/// Defines a runner instance which is executed every time a
+/// lambda is invoced.
+///
+/// Unlike [`Runner`], the runner is a value constructed during
+/// initialization, which allows injecting dependencies (e.g. mocks
+/// in unit tests). Use it with [`crate::exec_instance`].
+///
+/// Types:
+/// * `Shared`: Type which is shared between lambda
+///   invocations. See [`Runner`]
+/// * `Event`: The expected Event which is being send
+///   to the lambda by AWS.
+/// * `Return`: Type which is the result of the lamba
+///   invocation being returned to AWS
+#[async_trait::async_trait]
+pub trait InstanceRunner<Shared, Event, Return>: Send + Sync
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize,
+{
+    /// Invoked for every lambda invocation. See [`Runner::run`]
+    async fn run<'a>(
+        &'a self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<Return>
+    where
+        Event: 'a;
+
+    /// Behavior when an invocation is about to reach its deadline.
+    /// See [`Runner::TIMEOUT`]
+    fn timeout(&self) -> TimeoutBehavior {
+        TimeoutBehavior::Fail
+    }
+
+    /// Whether events containing fields which are not part of `Event`
+    /// are rejected. See [`Runner::DENY_UNKNOWN_FIELDS`]
+    fn deny_unknown_fields(&self) -> bool {
+        false
+    }
+
+    /// Fraction of the time budget after which [`InstanceRunner::on_early_warning`]
+    /// is called. See [`Runner::EARLY_WARNING`]
+    fn early_warning(&self) -> Option<f32> {
+        None
+    }
+
+    /// Interval in which [`InstanceRunner::on_heartbeat`] is called.
+    /// See [`Runner::HEARTBEAT`]
+    fn heartbeat(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    /// Validates the event before it is passed to [`InstanceRunner::run`].
+    /// See [`Runner::validate`]
+    fn validate(&self, _event: &Event) -> Result<(), crate::ValidationError> {
+        Ok(())
+    }
+
+    /// Checks whether the event is a warmup event. See [`Runner::warmup`]
+    fn warmup(&self, _event: &serde_json::value::RawValue) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Invoked if [`InstanceRunner::run`] failed or timed out. See [`Runner::fallback`]
+    fn fallback(&self, _shared: &Shared, _error: &anyhow::Error) -> Option<Return> {
+        None
+    }
+
+    /// Invoked if an invocation failed, before the error is returned
+    /// to lambda. See [`Runner::on_error`]
+    async fn on_error(
+        &self,
+        _shared: &Shared,
+        error: anyhow::Error,
+        _ctx: &crate::Context,
+    ) -> anyhow::Result<Return> {
+        Err(error)
+    }
+
+    /// Invoked when the timeout handler fails an invocation.
+    /// See [`Runner::on_timeout`]
+    async fn on_timeout(&self, _shared: &Shared, _ctx: &crate::Context) {}
+
+    /// Invoked once the early warning fraction of the time budget has
+    /// passed. See [`Runner::on_early_warning`]
+    async fn on_early_warning(&self, _shared: &Shared, _ctx: &crate::Context) {}
+
+    /// Invoked periodically while the runner is running. See [`Runner::on_heartbeat`]
+    async fn on_heartbeat(&self, _shared: &Shared, _ctx: &crate::Context) {}
+
+    /// Invoked at the start of every invocation. See [`Runner::before_invoke`]
+    async fn before_invoke(&self, _shared: &Shared, _ctx: &crate::Context) {}
+
+    /// Invoked after the response of an invocation was produced.
+    /// See [`Runner::after_invoke`]
+    async fn after_invoke(&self, _shared: &Shared, _info: &crate::InvocationInfo<'_>) {}
+
+    /// Converts the error of a failed invocation into the shape which
+    /// is reported to lambda. See [`Runner::classify`]
+    fn classify(&self, error: &anyhow::Error) -> crate::ErrorShape {
+        crate::ErrorShape::from_error(error)
+    }
+
+    /// Invoked once when lambda shuts down the execution environment.
+    /// See [`Runner::on_shutdown`]
+    async fn on_shutdown(&self, _shared: &Shared) {}
+
+    /// Wraps the runner with the given middleware. See [`crate::middleware`]
+    fn layer<M>(self, middleware: M) -> crate::middleware::Layered<Self, M>
+    where
+        Self: Sized,
+        M: crate::middleware::Middleware<Shared, Event, Return>,
+    {
+        crate::middleware::Layered::new(self, middleware)
+    }
+}
+
+/// Adapter which executes a static [`Runner`] as [`InstanceRunner`]
+pub struct StaticRunner<Run>(std::marker::PhantomData<fn() -> Run>);
+
+impl<Run> Default for StaticRunner<Run> {
+    fn default() -> Self {
+        Self(std::marker::PhantomData)
+    }
+}
+
+#[async_trait::async_trait]
+impl<Shared, Event, Return, Run> InstanceRunner<Shared, Event, Return> for StaticRunner<Run>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize,
+    Run: for<'a> Runner<'a, Shared, Event, Return>,
+{
+    async fn run<'a>(
+        &'a self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<Return>
+    where
+        Event: 'a,
+    {
+        Run::run(shared, event).await
+    }
+
+    fn timeout(&self) -> TimeoutBehavior {
+        <Run as Runner<'_, Shared, Event, Return>>::TIMEOUT
+    }
+
+    fn deny_unknown_fields(&self) -> bool {
+        <Run as Runner<'_, Shared, Event, Return>>::DENY_UNKNOWN_FIELDS
+    }
+
+    fn early_warning(&self) -> Option<f32> {
+        <Run as Runner<'_, Shared, Event, Return>>::EARLY_WARNING
+    }
+
+    fn heartbeat(&self) -> Option<std::time::Duration> {
+        <Run as Runner<'_, Shared, Event, Return>>::HEARTBEAT
+    }
+
+    fn validate(&self, event: &Event) -> Result<(), crate::ValidationError> {
+        <Run as Runner<'_, Shared, Event, Return>>::validate(event)
+    }
+
+    fn warmup(&self, event: &serde_json::value::RawValue) -> Option<serde_json::Value> {
+        <Run as Runner<'_, Shared, Event, Return>>::warmup(event)
+    }
+
+    fn fallback(&self, shared: &Shared, error: &anyhow::Error) -> Option<Return> {
+        Run::fallback(shared, error)
+    }
+
+    async fn on_error(
+        &self,
+        shared: &Shared,
+        error: anyhow::Error,
+        ctx: &crate::Context,
+    ) -> anyhow::Result<Return> {
+        Run::on_error(shared, error, ctx).await
+    }
+
+    async fn on_timeout(&self, shared: &Shared, ctx: &crate::Context) {
+        Run::on_timeout(shared, ctx).await
+    }
+
+    async fn on_early_warning(&self, shared: &Shared, ctx: &crate::Context) {
+        Run::on_early_warning(shared, ctx).await
+    }
+
+    async fn on_heartbeat(&self, shared: &Shared, ctx: &crate::Context) {
+        Run::on_heartbeat(shared, ctx).await
+    }
+
+    async fn before_invoke(&self, shared: &Shared, ctx: &crate::Context) {
+        Run::before_invoke(shared, ctx).await
+    }
+
+    async fn after_invoke(&self, shared: &Shared, info: &crate::InvocationInfo<'_>) {
+        Run::after_invoke(shared, info).await
+    }
+
+    fn classify(&self, error: &anyhow::Error) -> crate::ErrorShape {
+        <Run as Runner<'_, Shared, Event, Return>>::classify(error)
+    }
+
+    async fn on_shutdown(&self, shared: &Shared) {
+        Run::on_shutdown(shared).await
+    }
+}
+
+/// Adapter which executes a closure as [`InstanceRunner`]. The
+/// closure receives `Shared` directly, so the runner itself uses `()`.
+pub struct Func<Shared: 'static, Handler> {
+    shared: &'static Shared,
+    handler: Handler,
+}
+
+impl<Shared, Handler> Func<Shared, Handler> {
+    pub const fn new(shared: &'static Shared, handler: Handler) -> Self {
+        Self { shared, handler }
+    }
+}
+
+#[async_trait::async_trait]
+impl<Shared, Event, Return, Handler, Fut> InstanceRunner<(), Event, Return>
+    for Func<Shared, Handler>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize,
+    Handler: Fn(&'static Shared, Event, crate::Context) -> Fut + Send + Sync,
+    Fut: std::future::Future<Output = anyhow::Result<Return>> + Send,
+{
+    async fn run<'a>(
+        &'a self,
+        _shared: &'a (),
+        event: LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<Return>
+    where
+        Event: 'a,
+    {
+        (self.handler)(self.shared, event.event, event.ctx).await
+    }
+}
@@ -0,0 +1,240 @@
+use crate::ErrorShape;
+use serde_json::value::RawValue;
+
+/// Reports `error` and exits the process if an invocation did not complete
+/// `margin / 4` before its deadline, i.e. after the timeout handler and
+/// [`crate::Runner::on_timeout`] should have finished. See [`crate::Builder::hard_deadline`]
+pub struct HardDeadline {
+    pub margin: std::time::Duration,
+    pub error: ErrorShape,
+    pub clock: &'static dyn crate::Clock,
+}
+
+/// Invocation which was fetched from the Runtime API, but not yet executed
+pub struct Invocation {
+    ctx: lambda_runtime::Context,
+    body: hyper::body::Bytes,
+}
+
+/// Executes `setup` while the first invocation is fetched from the Runtime API,
+/// so the initialization overlaps with waiting for the event.
+///
+/// Once the first invocation is requested, lambda considers the initialization
+/// complete. Errors of `setup` are therefore reported as error of the first
+/// invocation instead of an initialization error.
+pub async fn prefetch<T>(
+    setup: impl std::future::Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<(T, Invocation)> {
+    use anyhow::anyhow;
+
+    let config = lambda_runtime::Config::from_env().map_err(|err| anyhow!(err))?;
+    let client = lambda_runtime_api_client::Client::builder()
+        .build()
+        .map_err(|err| anyhow!(err))?;
+    let (shared, invocation) = futures::future::join(setup, next(&client, &config)).await;
+    let invocation = invocation?;
+    match shared {
+        Ok(shared) => Ok((shared, invocation)),
+        Err(err) => {
+            log::error!("Lambda initialization failed: {:?}", err);
+            let shape = ErrorShape::new("Runtime.InitError", format!("{:#}", err));
+            if let Err(report_err) = send(&client, &invocation.ctx.request_id, Err(shape)).await {
+                log::warn!(
+                    "Unable to report initialization error to the Runtime API: {:?}",
+                    report_err
+                );
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Fetches invocations from the Runtime API and executes `handler` for each
+/// of them, until fetching an invocation fails. If `first` is set, it is
+/// executed before the next invocation is fetched.
+///
+/// Unlike `lambda_runtime::run`, errors are reported with the [`ErrorShape`]
+/// returned by the handler instead of the type name of the error.
+pub async fn run<F, Fut>(
+    mut first: Option<Invocation>,
+    hard_deadline: Option<HardDeadline>,
+    handler: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(lambda_runtime::LambdaEvent<Box<RawValue>>) -> Fut,
+    Fut: std::future::Future<Output = Result<Box<RawValue>, ErrorShape>>,
+{
+    use anyhow::anyhow;
+    use futures::FutureExt;
+
+    let config = lambda_runtime::Config::from_env().map_err(|err| anyhow!(err))?;
+    let client = lambda_runtime_api_client::Client::builder()
+        .build()
+        .map_err(|err| anyhow!(err))?;
+    loop {
+        let Invocation { ctx, body } = match first.take() {
+            Some(invocation) => invocation,
+            None => next(&client, &config).await?,
+        };
+        match &ctx.xray_trace_id {
+            Some(trace_id) => std::env::set_var("_X_AMZN_TRACE_ID", trace_id),
+            None => std::env::remove_var("_X_AMZN_TRACE_ID"),
+        }
+        let request_id = ctx.request_id.clone();
+        let watchdog = hard_deadline
+            .as_ref()
+            .map(|hard_deadline| watchdog(hard_deadline, ctx.deadline, &request_id));
+        let res = match serde_json::from_slice(&body) {
+            Ok(payload) => std::panic::AssertUnwindSafe(handler(lambda_runtime::LambdaEvent::new(
+                payload, ctx,
+            )))
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|payload| {
+                Err(ErrorShape::new(
+                    "Panic",
+                    format!("Lambda panicked: {}", crate::panic::message(&*payload)),
+                ))
+            }),
+            Err(err) => Err(ErrorShape::new("InvalidEventDataError", err.to_string())),
+        };
+        if watchdog.is_some_and(|watchdog| !watchdog.complete()) {
+            // The watchdog already reports the timeout and exits the process
+            return std::future::pending().await;
+        }
+        if let Err(err) = send(&client, &request_id, res).await {
+            log::error!("Unable to send result to the Runtime API: {:?}", err);
+        }
+    }
+}
+
+/// Fetches the next invocation from the Runtime API
+async fn next(
+    client: &lambda_runtime_api_client::Client,
+    config: &lambda_runtime::Config,
+) -> anyhow::Result<Invocation> {
+    use anyhow::{anyhow, Context};
+
+    loop {
+        let req = lambda_runtime_api_client::build_request()
+            .method(http::Method::GET)
+            .uri("/2018-06-01/runtime/invocation/next")
+            .body(hyper::Body::empty())
+            .context("Unable to build next invocation request")?;
+        let res = client.call(req).await.map_err(|err| anyhow!(err))?;
+        let (parts, body) = res.into_parts();
+        if parts.status == http::StatusCode::NO_CONTENT {
+            continue;
+        }
+        if !parts.status.is_success() {
+            anyhow::bail!("Runtime API responded with status {}", parts.status);
+        }
+        let ctx = lambda_runtime::Context::try_from(parts.headers)
+            .map_err(|err| anyhow!(err))
+            .context("Unable to read invocation context")?
+            .with_config(config);
+        let body = hyper::body::to_bytes(body)
+            .await
+            .context("Unable to read event")?;
+        return Ok(Invocation { ctx, body });
+    }
+}
+
+/// Watchdog of a single invocation, see [`watchdog`]
+struct Watchdog {
+    completed: std::sync::mpsc::Sender<()>,
+    reported: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl Watchdog {
+    /// Stops the watchdog. Returns `false` if it already started to report
+    /// the timeout, in which case the result of the invocation must not be sent
+    fn complete(self) -> bool {
+        use std::sync::atomic::Ordering;
+
+        drop(self.completed);
+        !self.reported.swap(true, Ordering::SeqCst)
+    }
+}
+
+/// Starts a thread which reports the timeout and exits the process, unless the
+/// returned watchdog is completed before. Only one of both reports the result
+/// of the invocation, so lambda never receives it twice.
+fn watchdog(hard_deadline: &HardDeadline, deadline_in_ms: u64, request_id: &str) -> Watchdog {
+    use std::sync::atomic::Ordering;
+    use std::sync::mpsc::RecvTimeoutError;
+
+    let (completed, wait_completed) = std::sync::mpsc::channel();
+    let reported = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let wait = hard_deadline
+        .clock
+        .until_deadline_ms(deadline_in_ms)
+        .saturating_sub(hard_deadline.margin / 4);
+    let error = hard_deadline.error.clone();
+    let request_id = request_id.to_owned();
+    let watchdog_reported = std::sync::Arc::clone(&reported);
+    std::thread::spawn(move || {
+        if wait_completed.recv_timeout(wait) != Err(RecvTimeoutError::Timeout)
+            || watchdog_reported.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+        log::error!(
+            "Invocation {} did not complete before its deadline. Exiting the execution environment",
+            request_id
+        );
+        let res = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(anyhow::Error::from)
+            .and_then(|runtime| {
+                runtime.block_on(async {
+                    let client = lambda_runtime_api_client::Client::builder()
+                        .build()
+                        .map_err(|err| anyhow::anyhow!(err))?;
+                    send(&client, &request_id, Err(error)).await
+                })
+            });
+        if let Err(err) = res {
+            log::error!("Unable to send timeout to the Runtime API: {:?}", err);
+        }
+        std::process::exit(1);
+    });
+    Watchdog {
+        completed,
+        reported,
+    }
+}
+
+async fn send(
+    client: &lambda_runtime_api_client::Client,
+    request_id: &str,
+    res: Result<Box<RawValue>, ErrorShape>,
+) -> anyhow::Result<()> {
+    use anyhow::{anyhow, Context};
+
+    let req = match res {
+        Ok(res) => lambda_runtime_api_client::build_request()
+            .method(http::Method::POST)
+            .uri(format!(
+                "/2018-06-01/runtime/invocation/{}/response",
+                request_id
+            ))
+            .body(hyper::Body::from(res.get().to_owned())),
+        Err(shape) => lambda_runtime_api_client::build_request()
+            .method(http::Method::POST)
+            .uri(format!(
+                "/2018-06-01/runtime/invocation/{}/error",
+                request_id
+            ))
+            .body(hyper::Body::from(
+                serde_json::to_vec(&shape).context("Unable to serialize error")?,
+            )),
+    }
+    .context("Unable to build invocation result request")?;
+    let res = client.call(req).await.map_err(|err| anyhow!(err))?;
+    if !res.status().is_success() {
+        anyhow::bail!("Runtime API responded with status {}", res.status());
+    }
+    Ok(())
+}
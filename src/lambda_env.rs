@@ -0,0 +1,48 @@
+/// Information about the lambda function which lambda provides
+/// through env variables.
+///
+/// The variables are parsed once and handed to runners with
+/// [`crate::LambdaEvent::env`]. During setup, use [`LambdaEnv::current`].
+/// Variables which are missing, e.g. in tests, are left empty.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LambdaEnv {
+    /// Name of the function (`AWS_LAMBDA_FUNCTION_NAME`)
+    pub function_name: String,
+    /// Version of the function (`AWS_LAMBDA_FUNCTION_VERSION`)
+    pub function_version: String,
+    /// Memory available to the function in MB (`AWS_LAMBDA_FUNCTION_MEMORY_SIZE`)
+    pub memory_size: Option<u32>,
+    /// CloudWatch log group of the function (`AWS_LAMBDA_LOG_GROUP_NAME`)
+    pub log_group_name: String,
+    /// CloudWatch log stream of the execution environment (`AWS_LAMBDA_LOG_STREAM_NAME`)
+    pub log_stream_name: String,
+    /// Runtime identifier, e.g. `AWS_Lambda_provided.al2023` (`AWS_EXECUTION_ENV`)
+    pub execution_env: Option<String>,
+    /// How the execution environment was initialized, e.g. `on-demand` or
+    /// `provisioned-concurrency` (`AWS_LAMBDA_INITIALIZATION_TYPE`)
+    pub initialization_type: Option<String>,
+}
+
+impl LambdaEnv {
+    /// Reads the env variables. Prefer [`LambdaEnv::current`],
+    /// which only reads them once
+    pub fn from_env() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self {
+            function_name: var("AWS_LAMBDA_FUNCTION_NAME").unwrap_or_default(),
+            function_version: var("AWS_LAMBDA_FUNCTION_VERSION").unwrap_or_default(),
+            memory_size: var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE").and_then(|size| size.parse().ok()),
+            log_group_name: var("AWS_LAMBDA_LOG_GROUP_NAME").unwrap_or_default(),
+            log_stream_name: var("AWS_LAMBDA_LOG_STREAM_NAME").unwrap_or_default(),
+            execution_env: var("AWS_EXECUTION_ENV"),
+            initialization_type: var("AWS_LAMBDA_INITIALIZATION_TYPE"),
+        }
+    }
+
+    /// Env of the running lambda. The env variables are read on first use
+    pub fn current() -> &'static Self {
+        static CURRENT: std::sync::OnceLock<LambdaEnv> = std::sync::OnceLock::new();
+        CURRENT.get_or_init(Self::from_env)
+    }
+}
@@ -0,0 +1,89 @@
+//! Provides lazily built state for `Shared`.
+//!
+//! [`crate::Runner::setup`] runs during the init phase of the execution
+//! environment, which delays the first invocation and, with provisioned
+//! concurrency, is billed for every prepared environment. A [`Lazy`] defers
+//! expensive construction until the first invocation uses it, so `setup` stays
+//! short. Unlike [`crate::shared_cell::SharedCell`], the value is built only
+//! once and borrowed directly.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # struct Client;
+//! # impl Client {
+//! #     async fn connect() -> anyhow::Result<Self> { Ok(Self) }
+//! #     async fn query(&self) -> anyhow::Result<u64> { Ok(0) }
+//! # }
+//! use lambda_runtime_types::lazy::Lazy;
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Lazy<Client>, (), u64> for Runner {
+//!     async fn run(shared: &'a Lazy<Client>, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<u64> {
+//!         shared.get().await?.query().await
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Lazy<Client>> {
+//!         Ok(Lazy::new(Client::connect))
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use futures::future::BoxFuture;
+
+type Init<T> = Box<dyn Fn() -> BoxFuture<'static, anyhow::Result<T>> + Send + Sync>;
+
+/// Value which is built on first access
+pub struct Lazy<T> {
+    init: Init<T>,
+    value: tokio::sync::OnceCell<T>,
+}
+
+impl<T> std::fmt::Debug for Lazy<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lazy")
+            .field("initialized", &self.value.initialized())
+            .finish()
+    }
+}
+
+impl<T: Send + Sync> Lazy<T> {
+    /// Creates a new lazy value, which is built with `init` on first access
+    pub fn new<I, Fut>(init: I) -> Self
+    where
+        I: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        Self {
+            init: Box::new(move || Box::pin(init())),
+            value: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// Returns the value or builds it on first access. If building fails,
+    /// the error is returned and the next access tries again
+    pub async fn get(&self) -> anyhow::Result<&T> {
+        self.value
+            .get_or_try_init(|| {
+                log::info!("Building lazy shared value");
+                (self.init)()
+            })
+            .await
+    }
+
+    /// Returns the value, if it was already built
+    pub fn get_if_initialized(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    /// Whether the value was already built
+    pub fn is_initialized(&self) -> bool {
+        self.value.initialized()
+    }
+}
@@ -29,14 +29,93 @@
 //! }
 //! ```
 //!
+//! # Instance based runners
+//!
+//! Instead of a type with associated functions, a runner can also be a value implementing
+//! [`InstanceRunner`]. The runner is constructed during initialization and passed to
+//! [`exec_instance`], which allows injecting dependencies and using mocks in unit tests:
+//!
+//! ```no_run
+//! struct Runner {
+//!     greeting: String,
+//! }
+//!
+//! #[async_trait::async_trait]
+//! impl lambda_runtime_types::InstanceRunner<(), String, String> for Runner {
+//!     async fn run<'a>(
+//!         &'a self,
+//!         shared: &'a (),
+//!         event: lambda_runtime_types::LambdaEvent<'a, String>,
+//!     ) -> anyhow::Result<String> {
+//!         Ok(format!("{} {}", self.greeting, event.event))
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! pub async fn main() -> anyhow::Result<()> {
+//!     let runner = Runner { greeting: "Hello".into() };
+//!     lambda_runtime_types::exec_instance(runner, ()).await
+//! }
+//! ```
+//!
+//! # Closure based lambdas
+//!
+//! Lambdas which do not need a trait implementation can use [`exec_fn`] or [`exec_tokio_fn`]
+//! with a setup and a handler closure instead:
+//!
+//! ```no_run
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio_fn(
+//!         |_region| async { Ok(()) },
+//!         |_shared: &'static (), event: String, _ctx| async move { Ok(format!("Hello {}", event)) },
+//!     )
+//! }
+//! ```
+//!
+//! With the feature `macros`, `lambda_runner` turns an async function into a [`Runner`]
+//! and a `main` function.
+//!
+//! # Runners with `!Send` futures
+//!
+//! Runners whose futures are not `Send`, e.g. because they hold an [`std::rc::Rc`] or a
+//! client bound to a thread across an await point, can implement [`LocalRunner`] and use
+//! [`exec_local`]. The runner is then executed on a current-thread runtime.
+//!
 //! # Available lambda types
 //!
 //! There are various modules which predefined Event and Return types and Runner traits
 //! specialised for differnet lambda usages. Check out the modules for examples or their
 //! usage.
 //!
+//! * [`destination`]
+//! * [`multi`]
 //! * [`rotate`]
 //!
+//! # Utilities
+//!
+//! Besides the lambda types, there are modules with utilities which are commonly needed
+//! when writing lambdas:
+//!
+//! * `assume_role`: Credentials of an assumed role which are refreshed before they expire
+//!   (requires feature `assume_role_aws_sdk`)
+//! * [`checkpoint`]: Resume long running operations after hitting the timeout
+//! * [`circuit_breaker`]: Circuit breaker to protect flaky dependencies
+//! * [`codec`]: Decode events and encode responses which are not plain JSON
+//! * [`dedup`]: Skip duplicate deliveries of the same event
+//! * [`env_config`]: Load typed configuration from env variables during setup
+//! * [`expiring`]: Refresh tokens or cached configuration in `Shared` once they expired
+//! * [`lazy`]: Defer building expensive state in `Shared` until the first invocation
+//! * [`middleware`]: Wrap invocations for metrics, authentication or payload logging
+//! * [`rate_limit`]: Token-bucket rate limiter for calls to rate limited apis
+//! * [`reload`]: Reload configuration between invocations when it changed
+//! * [`retry`]: Retry operations or whole invocations with jittered exponential backoff
+//! * [`secret_cache`]: Cache secrets and reload them after authentication failures
+//! * [`shared_cell`]: Rebuild state in `Shared`, like connections, after it broke
+//! * [`snapshot`]: Keep caches in `Shared` across restarts of the runtime within one environment
+//! * [`state`]: Share counters or deduplication windows between execution environments
+//! * [`swap`]: Replace shared state, like configuration, as a whole without holding a lock
+//! * [`warmup`]: Detect warmup events to answer them without invoking the runner
+//!
 //! # Custom Event and Return types
 //!
 //! If the predefined types are not enough, custom types can be used as long as types for
@@ -83,6 +162,40 @@
 //! }
 //! ```
 //!
+//! # Raw events
+//!
+//! Lambdas which receive events of different shapes can use [`RawEvent`] as event type. The
+//! event is then passed to the runner without being deserialized and can be parsed on demand
+//! with [`LambdaEvent::parse`]:
+//!
+//! ```no_run
+//! #[derive(serde::Deserialize)]
+//! struct Order {
+//!     order_id: String,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, (), lambda_runtime_types::RawEvent, String> for Runner {
+//!     async fn run(shared: &'a (), event: lambda_runtime_types::LambdaEvent<'a, lambda_runtime_types::RawEvent>) -> anyhow::Result<String> {
+//!         if event.event.get().contains("order_id") {
+//!             let order: Order = event.parse()?;
+//!             return Ok(order.order_id);
+//!         }
+//!         Ok("unknown".into())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+//!
 //! # Shared Data
 //!
 //! With AWS Lambda, its possible to share data between invocations, as long as both
@@ -121,30 +234,133 @@
 //! as it will never block other invocations. Instead it is even recommended to do so, to
 //! make sure that there are no unnessary things slowing down lambda execution time.
 //!
+//! Basic statistics of the execution environment, like the amount of invocations and errors,
+//! the uptime and the previous request id, are tracked by the runtime and available as
+//! [`LambdaEvent::stats`] without a counter in the shared data.
+//!
+//! Data which must not outlive an invocation, like request-scoped caches, is kept in
+//! [`LambdaEvent::scoped`] instead. It is created fresh for every invocation and dropped afterwards.
+//!
 //! # Timeout handling
 //!
 //! This crate implements a timeout handling logic. Normally, if a lambda runs into a timeout,
 //! it will not create an error, which then does not get propagated by `on_error` destinations.
 //!
-//! To fix that, a timeout handler is setup, which will "fail" 100 miliseconds before the lambda
-//! would run into a timeout, creating an error which then is propagated. There is, however, no
-//! gurantee that this handler will fail in time. It will only work, when there are multiple
-//! tokio threads or when the main lambda code is currently awaiting, giving tokio the chance
-//! to switch tasks (or run them in parallel) and fail the execution.
+//! To fix that, a timeout handler is setup, which will "fail" 100 miliseconds (configurable with
+//! [`Builder::timeout_margin`]) before the lambda would run into a timeout, creating an error
+//! which then is propagated. There is, however, no gurantee that this handler will fail in time.
+//! It will only work, when there are multiple tokio threads or when the main lambda code is
+//! currently awaiting, giving tokio the chance to switch tasks (or run them in parallel) and fail
+//! the execution.
+//!
+//! With [`Builder::hard_deadline`], a watchdog thread delivers the error even if the runner
+//! blocks the thread. It fires a quarter of the timeout margin before the deadline, after
+//! [`Runner::on_timeout`] had its time. As a blocked runner can not be stopped, the execution
+//! environment is exited afterwards and lambda starts a new one for the next invocation.
+//!
+//! The behavior can be changed per runner with [`Runner::TIMEOUT`]. Runners which are meant to
+//! run until lambda stops them can either only log a warning or disable the handler completely.
+//! To disable the handler for all runners, e.g. when relying on an own watchdog, use
+//! [`Builder::timeout_handler`] or set the env variable `LAMBDA_RUNTIME_TYPES_TIMEOUT_HANDLER`
+//! to `false`.
+//! Before the error is returned, [`Runner::on_timeout`] is called to checkpoint progress,
+//! release locks or emit a metric. It is cancelled after half of the timeout margin.
+//! If the runner registered a result with [`Partial::set`], it is returned as response instead
+//! of the error, so callers learn how far processing got.
+//! Runners processing batches can check [`LambdaEvent::remaining_time`] to stop before the
+//! timeout handler fires.
+//! Long running runners can also set [`Runner::EARLY_WARNING`] to get notified through
+//! [`Runner::on_early_warning`] once a fraction of the time budget has passed.
+//! Runners which need to send Step Functions task heartbeats or refresh a lease while running
+//! can set [`Runner::HEARTBEAT`] to get [`Runner::on_heartbeat`] called periodically.
+//!
+//! # Shutdown handling
+//!
+//! Before lambda shuts down an execution environment, it sends a SIGTERM signal to the runtime,
+//! as long as at least one extension is registered. The signal is handled by calling
+//! [`Runner::on_shutdown`], which can be used to flush buffers or close connections. When testing
+//! with `exec_test`, `on_shutdown` is called for every environment after all invocations ran.
+//!
+//! # Initialization errors
+//!
+//! If [`Runner::setup`] fails, the error is reported to the Runtime API as initialization error
+//! before the process exits. The error then shows up as such in CloudWatch and the console,
+//! instead of as a crash of the runtime.
+//!
+//! With [`Builder::concurrent_setup`], the setup is executed while the first invocation is
+//! fetched, which shortens cold starts with slow setups. Errors of the setup are then reported
+//! as error of the first invocation, as lambda considers the initialization complete once
+//! an invocation was fetched.
+//!
+//! # Error reporting
+//!
+//! Failed invocations are reported to lambda with an `errorType` and an `errorMessage`. By
+//! default, the type is the type name of the error. [`Runner::classify`] can return a custom
+//! [`ErrorShape`] instead, so Step Functions `Catch` and `Retry` clauses can match on specific
+//! error types. Events which are rejected by [`Runner::validate`] are reported as
+//! `ValidationError`, which separates malformed input from failures of the runner.
+//!
+//! An [`ErrorShape`] can also carry additional fields, e.g. an error code, which are reported
+//! next to `errorType` and `errorMessage` and passed on to `on_failure` destinations. Errors
+//! which implement `Serialize` can be converted with [`ErrorShape::from_serialize`].
+//!
+//! # Business failures
+//!
+//! Expected failures, e.g. a declined payment, often should not fail the invocation, as that
+//! triggers retries and `on_failure` destinations. By returning an [`Outcome`], they are sent
+//! as successful response with a `success` flag, while errors still fail the invocation.
+//!
+//! # Panic handling
+//!
+//! Panics are normally printed to stderr, which makes them hard to find in CloudWatch. By
+//! calling [`install_panic_hook`] in `setup` (after the logger has been initialized), panics
+//! are logged through the configured logger instead. The log record contains the panic message,
+//! its location, a backtrace and the request id of the invocation which was running.
+//!
+//! Panics inside [`Runner::run`] fail the invocation with an error instead of stopping the
+//! runtime, so the execution environment keeps serving further invocations. Data in `Shared`
+//! may be left in an inconsistent state by the panic.
 //!
 //! # Memory exhaustion
 //!
-//! Another thing to consider when running lambdas is memory exhaustion. Unfortunatly it is not
-//! possible in rust to check the current memory usage. Therefore it is also not possible to
-//! fail before running into OOF. When running lambdas, it may be necessary to setup checks to
-//! verify that a lambda completed successfully, and did not run into OOF, as these errors also
-//! do not get propagated to `on_error` destinations.
+//! Another thing to consider when running lambdas is memory exhaustion. When a lambda runs out
+//! of memory, lambda stops the execution environment without reporting an error, and the error
+//! does not get propagated to `on_error` destinations. With [`Builder::memory_watchdog`], the
+//! memory usage of the process is checked during invocations (on linux), and the invocation is
+//! failed with a descriptive error once it exceeds the configured share of the lambda's memory.
+//! Without it, it may be necessary to setup checks to verify that a lambda completed successfully,
+//! and did not run into OOM.
 //!
 
 #![deny(clippy::all, clippy::nursery)]
 #![deny(nonstandard_style, rust_2018_idioms, unused_crate_dependencies)]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+#[cfg(all(feature = "rotate_elasticache", not(feature = "_rotate")))]
+compile_error!("Feature rotate_elasticache requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_http_api_key", not(feature = "_rotate")))]
+compile_error!("Feature rotate_http_api_key requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_postgres", not(feature = "_rotate")))]
+compile_error!("Feature rotate_postgres requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_msk", not(feature = "_rotate")))]
+compile_error!("Feature rotate_msk requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_rabbitmq", not(feature = "_rotate")))]
+compile_error!("Feature rotate_rabbitmq requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_redis", not(feature = "_rotate")))]
+compile_error!("Feature rotate_redis requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_snowflake", not(feature = "_rotate")))]
+compile_error!("Feature rotate_snowflake requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_sns", not(feature = "_rotate")))]
+compile_error!("Feature rotate_sns requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_ssh", not(feature = "_rotate")))]
+compile_error!("Feature rotate_ssh requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_ldap", not(feature = "_rotate")))]
+compile_error!("Feature rotate_ldap requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_local_password", not(feature = "_rotate")))]
+compile_error!("Feature rotate_local_password requires feature rotate_rusoto or rotate_aws_sdk");
+#[cfg(all(feature = "rotate_tls", not(feature = "_rotate")))]
+compile_error!("Feature rotate_tls requires feature rotate_rusoto or rotate_aws_sdk");
+
 #[cfg(feature = "_rotate")]
 #[cfg_attr(
     docsrs,
@@ -152,16 +368,110 @@
 )]
 pub mod rotate;
 
-#[cfg(test)]
-use native_tls as _;
-#[cfg(test)]
-use postgres_native_tls as _;
+#[cfg(feature = "assume_role_aws_sdk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "assume_role_aws_sdk")))]
+pub mod assume_role;
+mod builder;
+pub mod checkpoint;
+pub mod circuit_breaker;
+mod clock;
+pub mod codec;
+mod cost;
+pub mod dedup;
+pub mod destination;
+pub mod env_config;
+mod error_shape;
+pub mod expiring;
+mod handler;
+mod hooks;
+mod init_error;
+mod instance;
+mod invocation_loop;
+mod lambda_env;
+pub mod lazy;
+mod local;
+mod memory;
+pub mod middleware;
+pub mod multi;
+mod outbox;
+mod outcome;
+mod panic;
+mod partial;
+pub mod rate_limit;
+mod region;
+pub mod reload;
+pub mod retry;
+mod scoped;
+pub mod secret_cache;
+pub mod shared_cell;
+pub mod snapshot;
+mod spawner;
+pub mod state;
+mod stats;
+mod summary;
+pub mod swap;
+mod validation;
+pub mod warmup;
+
 #[cfg(test)]
 use simple_logger as _;
-#[cfg(test)]
-use tokio_postgres as _;
+// The credentials of rusoto do not need the types of the aws sdk
+#[cfg(all(feature = "_signed_requests", not(feature = "rotate_aws_sdk")))]
+use aws_types as _;
 
+pub use builder::{Builder, Flavor, TIMEOUT_HANDLER_ENV};
+pub use clock::{Clock, SystemClock};
+pub use error_shape::ErrorShape;
+pub use hooks::{InvocationInfo, InvocationOutcome};
+pub use instance::{InstanceRunner, StaticRunner};
+pub use lambda_env::LambdaEnv;
 pub use lambda_runtime::{Config, Context};
+/// The function must take the event as `LambdaEvent<'_, Event>` and optionally the
+/// shared data as `&Shared` as first parameter, and return `anyhow::Result<Return>`.
+///
+/// Arguments:
+/// * `runner` (optional): Name of the generated runner type. Defaults to `Runner`
+/// * `setup` (optional): Async function `(region: &str) -> anyhow::Result<Shared>`
+///   used as [`Runner::setup`]. Defaults to `Shared::default()`
+/// * `main` (optional): Whether a `main` function calling [`exec_tokio`] is
+///   generated. Defaults to `true`
+///
+/// ```no_run
+/// #[derive(Default)]
+/// struct Shared {
+///     invocations: std::sync::atomic::AtomicU64,
+/// }
+///
+/// #[lambda_runtime_types::lambda_runner]
+/// async fn handler(
+///     shared: &Shared,
+///     event: lambda_runtime_types::LambdaEvent<'_, String>,
+/// ) -> anyhow::Result<String> {
+///     shared
+///         .invocations
+///         .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+///     Ok(format!("Hello {}", event.event))
+/// }
+/// ```
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use lambda_runtime_types_macros::lambda_runner;
+pub use local::LocalRunner;
+pub use outbox::Outbox;
+pub use outcome::Outcome;
+pub use panic::install_panic_hook;
+pub use partial::Partial;
+pub use region::Region;
+pub use scoped::Scoped;
+pub use spawner::Spawner;
+pub use stats::Stats;
+pub use validation::ValidationError;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use async_trait::async_trait;
+}
 
 /// Types which contains all the Information relevant for
 /// the current invocation
@@ -171,10 +481,131 @@ pub struct LambdaEvent<'a, Event> {
     /// The expected Event which is being send
     /// to the lambda by AWS.
     pub event: Event,
-    /// Region the lambda is running in
+    /// Region the lambda is running in. It is validated at
+    /// startup and can be converted with [`Region::new`]. Empty
+    /// if the region is not required and missing. See [`Builder::region_required`].
+    /// `exec_test` passes the region of the test data as is
     pub region: &'a str,
-    /// Lambda Invocation Context
+    /// Lambda Invocation Context. Contains all information of
+    /// the invocation, like the client context and identity
     pub ctx: Context,
+    /// Side effects which are executed after the
+    /// invocation succeeded
+    pub outbox: Outbox<'a>,
+    /// Spawns background tasks which are awaited
+    /// before the invocation completes
+    pub spawner: Spawner,
+    /// Partial result which is returned if the
+    /// invocation runs into a timeout
+    pub partial: Partial,
+    /// State which only lives for this invocation,
+    /// like request-scoped caches
+    pub scoped: Scoped,
+    /// Whether this is the first invocation of the
+    /// execution environment
+    pub is_cold_start: bool,
+    /// Statistics of the execution environment, like the
+    /// amount of previous invocations and errors
+    pub stats: Stats,
+    /// Information about the lambda function, like
+    /// its name, version and log stream
+    pub env: &'static LambdaEnv,
+    /// Time before the deadline at which the timeout handler
+    /// fails the invocation. See [`Builder::timeout_margin`]
+    pub(crate) timeout_margin: std::time::Duration,
+    /// Clock which is used to compute the time left
+    /// until the deadline. See [`Builder::clock`]
+    pub(crate) clock: &'static dyn Clock,
+}
+
+impl<'a, Event> LambdaEvent<'a, Event> {
+    /// Creates a new event, e.g. to call runners in unit tests
+    pub fn new(event: Event, region: &'a str, ctx: Context) -> Self {
+        Self {
+            event,
+            region,
+            ctx,
+            outbox: Outbox::default(),
+            spawner: Spawner::default(),
+            partial: Partial::default(),
+            scoped: Scoped::default(),
+            is_cold_start: false,
+            stats: Stats::default(),
+            env: LambdaEnv::current(),
+            timeout_margin: builder::Settings::default().timeout_margin,
+            clock: &SystemClock,
+        }
+    }
+
+    /// Request id of the invocation
+    pub fn request_id(&self) -> &str {
+        &self.ctx.request_id
+    }
+
+    /// Arn of the invoked lambda function
+    pub fn invoked_function_arn(&self) -> &str {
+        &self.ctx.invoked_function_arn
+    }
+
+    /// Time at which lambda stops the invocation
+    pub fn deadline(&self) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_millis(self.ctx.deadline)
+    }
+
+    /// Time left until the timeout handler fails the invocation, i.e. the
+    /// deadline minus the timeout margin. Like `getRemainingTimeInMillis` of
+    /// other runtimes, it can be used to decide whether to start another
+    /// batch item. Returns [`std::time::Duration::MAX`] if the invocation has
+    /// no deadline, e.g. with `exec_test`
+    pub fn remaining_time(&self) -> std::time::Duration {
+        if self.ctx.deadline == 0 {
+            return std::time::Duration::MAX;
+        }
+        self.clock
+            .until(self.deadline())
+            .saturating_sub(self.timeout_margin)
+    }
+
+    /// Amount of memory available to the lambda in MB
+    pub const fn memory_limit(&self) -> i32 {
+        self.ctx.env_config.memory
+    }
+
+    /// Replaces the event, keeping all other information of the
+    /// invocation. Returns the previous event
+    pub(crate) fn replace<T>(self, event: T) -> (Event, LambdaEvent<'a, T>) {
+        let lambda_event = LambdaEvent {
+            event,
+            region: self.region,
+            ctx: self.ctx,
+            outbox: self.outbox,
+            spawner: self.spawner,
+            partial: self.partial,
+            scoped: self.scoped,
+            is_cold_start: self.is_cold_start,
+            stats: self.stats,
+            env: self.env,
+            timeout_margin: self.timeout_margin,
+            clock: self.clock,
+        };
+        (self.event, lambda_event)
+    }
+}
+
+/// Event which is not deserialized. Use it as `Event` to
+/// deserialize events of different shapes in the runner.
+pub type RawEvent = Box<serde_json::value::RawValue>;
+
+impl<'a> LambdaEvent<'a, RawEvent> {
+    /// Deserializes the raw event into `T`
+    pub fn parse<T>(&self) -> anyhow::Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        use anyhow::Context;
+
+        serde_json::from_str(self.event.get()).context("Unable to deserialize event")
+    }
 }
 
 /// Defines a type which is executed every time a lambda
@@ -182,17 +613,17 @@ pub struct LambdaEvent<'a, Event> {
 ///
 /// Types:
 /// * `Shared`: Type which is shared between lambda
-///             invocations. Note that lambda will
-///             create multiple environments for
-///             simulations invokations and environments
-///             are only kept alive for a certain time.
-///             It is thus not guaranteed that data
-///             can be reused, but with this types
-///             its possible.
-/// * `Event`:  The expected Event which is being send
-///             to the lambda by AWS.
+///   invocations. Note that lambda will
+///   create multiple environments for
+///   simulations invokations and environments
+///   are only kept alive for a certain time.
+///   It is thus not guaranteed that data
+///   can be reused, but with this types
+///   its possible.
+/// * `Event`: The expected Event which is being send
+///   to the lambda by AWS.
 /// * `Return`: Type which is the result of the lamba
-///             invocation being returned to AWS
+///   invocation being returned to AWS
 #[async_trait::async_trait]
 pub trait Runner<'a, Shared, Event, Return>
 where
@@ -210,6 +641,108 @@ where
     ///
     /// More Info: <https://docs.aws.amazon.com/lambda/latest/dg/runtimes-context.html>
     async fn run(shared: &'a Shared, event: LambdaEvent<'a, Event>) -> anyhow::Result<Return>;
+
+    /// Behavior when an invocation is about to reach its deadline.
+    /// Defaults to [`TimeoutBehavior::Fail`]
+    const TIMEOUT: TimeoutBehavior = TimeoutBehavior::Fail;
+
+    /// Whether events containing fields which are not part of `Event`
+    /// are rejected. Otherwise ignored fields are only logged.
+    const DENY_UNKNOWN_FIELDS: bool = false;
+
+    /// Fraction of the time budget, between 0 and 1, after which
+    /// [`Runner::on_early_warning`] is called. The budget is the time from the start
+    /// of the invocation until the timeout handler fails it. Defaults to `None`,
+    /// which disables the early warning
+    const EARLY_WARNING: Option<f32> = None;
+
+    /// Interval in which [`Runner::on_heartbeat`] is called while [`Runner::run`]
+    /// is running. Defaults to `None`, which disables the heartbeat
+    const HEARTBEAT: Option<std::time::Duration> = None;
+
+    /// Validates the event before [`Runner::run`] is invoked. Invalid events fail
+    /// the invocation with the error type `ValidationError`, without calling
+    /// [`Runner::fallback`]
+    fn validate(_event: &Event) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Checks whether the event is a warmup event, e.g. of `serverless-plugin-warmup`.
+    /// If a response is returned, the invocation is answered with it without
+    /// deserializing the event or calling [`Runner::run`]. See [`crate::warmup`]
+    fn warmup(_event: &serde_json::value::RawValue) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Invoked if [`Runner::run`] failed or timed out. If a response is returned,
+    /// it is send instead of the error. The error is still logged and reported
+    /// in the invocation summary.
+    fn fallback(_shared: &'a Shared, _error: &anyhow::Error) -> Option<Return> {
+        None
+    }
+
+    /// Invoked if an invocation failed and [`Runner::fallback`] did not provide a
+    /// response, before the error is returned to lambda. Can be used to report the
+    /// error, to add context to it or to swallow it by returning a response instead.
+    async fn on_error(
+        _shared: &'a Shared,
+        error: anyhow::Error,
+        _ctx: &'a Context,
+    ) -> anyhow::Result<Return> {
+        Err(error)
+    }
+
+    /// Invoked when the timeout handler fails an invocation, before the error is
+    /// returned. Can be used to checkpoint progress, release locks or emit a metric.
+    /// It has half of [`Builder::timeout_margin`] to complete, otherwise it is cancelled
+    async fn on_timeout(_shared: &'a Shared, _ctx: &'a Context) {}
+
+    /// Invoked while [`Runner::run`] is still running, once [`Runner::EARLY_WARNING`]
+    /// of the time budget has passed. Can be used to signal the runner through
+    /// `shared` to switch to a fast path, stop accepting work or start flushing results
+    async fn on_early_warning(_shared: &'a Shared, _ctx: &'a Context) {}
+
+    /// Invoked every [`Runner::HEARTBEAT`] while [`Runner::run`] is running. Can be
+    /// used to send Step Functions task heartbeats or to refresh a lease. Stops once
+    /// the invocation completes, even if a heartbeat is still running
+    async fn on_heartbeat(_shared: &'a Shared, _ctx: &'a Context) {}
+
+    /// Invoked at the start of every invocation, before the event is deserialized.
+    /// Can be used to refresh caches or to setup the log context
+    async fn before_invoke(_shared: &'a Shared, _ctx: &'a Context) {}
+
+    /// Invoked after the response of an invocation was produced, with its
+    /// duration and outcome. Can be used to flush metrics
+    async fn after_invoke(_shared: &'a Shared, _info: &'a InvocationInfo<'a>) {}
+
+    /// Converts the error of a failed invocation into the shape which is reported
+    /// to lambda. Allows Step Functions to match on specific error types.
+    /// Defaults to [`ErrorShape::from_error`]
+    fn classify(error: &anyhow::Error) -> ErrorShape {
+        ErrorShape::from_error(error)
+    }
+
+    /// Invoked once when lambda shuts down the execution environment. Can be
+    /// used to flush buffers, close connections or emit final metrics.
+    ///
+    /// Lambda only sends the required SIGTERM signal if at least one extension
+    /// is registered. Otherwise this function is never called.
+    async fn on_shutdown(_shared: &'a Shared) {}
+}
+
+/// Defines what happens when an invocation is about to reach
+/// its deadline. See [`Runner::TIMEOUT`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutBehavior {
+    /// Fail the invocation with a timeout error shortly
+    /// before the deadline
+    Fail,
+    /// Log a warning shortly before the deadline and keep
+    /// running until the invocation finishes or is stopped
+    /// by lambda
+    Warn,
+    /// Do not watch the deadline at all
+    Disabled,
 }
 
 /// Lambda entrypoint. This function sets up a lambda
@@ -217,21 +750,23 @@ where
 /// already have your own runtime, use the [`exec`]
 /// function.
 ///
+/// To configure the runtime, use [`Builder`].
+///
 /// Types:
 /// * `Shared`: Type which is shared between lambda
-///             invocations. Note that lambda will
-///             create multiple environments for
-///             simulations invokations and environments
-///             are only kept alive for a certain time.
-///             It is thus not guaranteed that data
-///             can be reused, but with this types
-///             its possible.
-/// * `Event`:  The expected Event which is being send
-///             to the lambda by AWS.
-/// * `Run`:    Runner which is execued for each lambda
-///             invocation.
+///   invocations. Note that lambda will
+///   create multiple environments for
+///   simulations invokations and environments
+///   are only kept alive for a certain time.
+///   It is thus not guaranteed that data
+///   can be reused, but with this types
+///   its possible.
+/// * `Event`: The expected Event which is being send
+///   to the lambda by AWS.
+/// * `Run`: Runner which is execued for each lambda
+///   invocation.
 /// * `Return`: Type which is the result of the lamba
-///             invocation being returned to AWS
+///   invocation being returned to AWS
 pub fn exec_tokio<Shared, Event, Run, Return>() -> anyhow::Result<()>
 where
     Shared: Send + Sync,
@@ -239,14 +774,26 @@ where
     Run: for<'a> Runner<'a, Shared, Event, Return>,
     Return: serde::Serialize,
 {
-    use anyhow::Context;
-    use tokio::runtime::Builder;
+    Builder::new().exec::<Shared, Event, Run, Return>()
+}
 
-    Builder::new_multi_thread()
-        .enable_all()
-        .build()
-        .context("Unable to build tokio runtime")?
-        .block_on(exec::<Shared, Event, Run, Return>())
+/// Lambda entrypoint. This function executes [`exec`] on
+/// an existing tokio runtime, which is useful when the
+/// runtime is owned by a larger application.
+///
+/// The current thread is blocked until the lambda runtime
+/// stops. It must therefore not be called from within an
+/// asynchronous context.
+///
+/// See [`exec`] for the types.
+pub fn exec_on<Shared, Event, Run, Return>(handle: &tokio::runtime::Handle) -> anyhow::Result<()>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Run: for<'a> Runner<'a, Shared, Event, Return>,
+    Return: serde::Serialize,
+{
+    handle.block_on(exec::<Shared, Event, Run, Return>())
 }
 
 /// Lambda entrypoint. This function requires a
@@ -255,19 +802,19 @@ where
 ///
 /// Types:
 /// * `Shared`: Type which is shared between lambda
-///             invocations. Note that lambda will
-///             create multiple environments for
-///             simulations invokations and environments
-///             are only kept alive for a certain time.
-///             It is thus not guaranteed that data
-///             can be reused, but with this types
-///             its possible.
-/// * `Event`:  The expected Event which is being send
-///             to the lambda by AWS.
-/// * `Run`:    Runner which is execued for each lambda
-///             invocation.
+///   invocations. Note that lambda will
+///   create multiple environments for
+///   simulations invokations and environments
+///   are only kept alive for a certain time.
+///   It is thus not guaranteed that data
+///   can be reused, but with this types
+///   its possible.
+/// * `Event`: The expected Event which is being send
+///   to the lambda by AWS.
+/// * `Run`: Runner which is execued for each lambda
+///   invocation.
 /// * `Return`: Type which is the result of the lamba
-///             invocation being returned to AWS
+///   invocation being returned to AWS
 pub async fn exec<Shared, Event, Run, Return>() -> anyhow::Result<()>
 where
     Shared: Send + Sync,
@@ -275,116 +822,724 @@ where
     Run: for<'a> Runner<'a, Shared, Event, Return>,
     Return: serde::Serialize,
 {
-    use anyhow::{anyhow, Context};
-    use lambda_runtime::{service_fn, LambdaEvent};
-    use std::env;
+    log::info!("Starting lambda runtime");
+    let (region, shared) = init_error::report(async {
+        let region = region(true)?;
+        let shared = Run::setup(&region).await?;
+        Ok((region, shared))
+    })
+    .await?;
+    exec_runtime(
+        &StaticRunner::<Run>::default(),
+        &shared,
+        &region,
+        &builder::Settings::default(),
+        None,
+    )
+    .await
+}
 
+/// Lambda entrypoint for [`InstanceRunner`]. This function
+/// requires a running tokio runtime.
+///
+/// Types:
+/// * `Shared`: Type which is shared between lambda
+///   invocations. See [`exec`]
+/// * `Event`: The expected Event which is being send
+///   to the lambda by AWS.
+/// * `Run`: Runner which is execued for each lambda
+///   invocation.
+/// * `Return`: Type which is the result of the lamba
+///   invocation being returned to AWS
+pub async fn exec_instance<Shared, Event, Run, Return>(
+    runner: Run,
+    shared: Shared,
+) -> anyhow::Result<()>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Run: InstanceRunner<Shared, Event, Return>,
+    Return: serde::Serialize,
+{
     log::info!("Starting lambda runtime");
-    let region = env::var("AWS_REGION").context("Missing AWS_REGION env variable")?;
-    let region_ref = &region;
-    let shared = Run::setup(region_ref).await?;
-    let shared_ref = &shared;
-    lambda_runtime::run(service_fn(move |data: LambdaEvent<Event>| {
-        log::info!("Received lambda invocation with event: {:?}", data.payload);
-        let deadline: u64 = data.context.deadline;
-        run::<_, Event, Run, Return>(shared_ref, data, Some(deadline), region_ref)
-    }))
+    let region = init_error::report(async { region(true) }).await?;
+    exec_runtime(
+        &runner,
+        &shared,
+        &region,
+        &builder::Settings::default(),
+        None,
+    )
     .await
-    .map_err(|e| anyhow!(e))
 }
 
-#[allow(clippy::unit_arg)]
-async fn run<'a, Shared, Event, Run, Return>(
-    shared: &'a Shared,
-    event: lambda_runtime::LambdaEvent<Event>,
-    deadline_in_ms: Option<u64>,
-    region: &'a str,
-) -> anyhow::Result<Return>
+/// Lambda entrypoint for closures. This function sets up a
+/// lambda multi-thread runtimes and executes [`exec_fn`].
+///
+/// See [`exec_fn`] for the parameters.
+pub fn exec_tokio_fn<Shared, Event, Return, Setup, SetupFut, Handler, Fut>(
+    setup: Setup,
+    handler: Handler,
+) -> anyhow::Result<()>
+where
+    Shared: Send + Sync + 'static,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize,
+    Setup: FnOnce(&str) -> SetupFut,
+    SetupFut: std::future::Future<Output = anyhow::Result<Shared>>,
+    Handler: Fn(&'static Shared, Event, Context) -> Fut + Send + Sync,
+    Fut: std::future::Future<Output = anyhow::Result<Return>> + Send,
+{
+    use anyhow::Context;
+    use tokio::runtime::Builder;
+
+    Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("Unable to build tokio runtime")?
+        .block_on(exec_fn(setup, handler))
+}
+
+/// Lambda entrypoint for closures. This function
+/// requires a running tokio runtime.
+///
+/// Parameters:
+/// * `setup`: Invoked only once before lambda runtime start.
+///   See [`Runner::setup`]
+/// * `handler`: Invoked for every lambda invocation with the
+///   shared data, the event and the invocation context.
+///   See [`Runner::run`]
+///
+/// `Shared` lives as long as the lambda runtime and is therefore
+/// passed to `handler` as `'static` reference.
+pub async fn exec_fn<Shared, Event, Return, Setup, SetupFut, Handler, Fut>(
+    setup: Setup,
+    handler: Handler,
+) -> anyhow::Result<()>
+where
+    Shared: Send + Sync + 'static,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize,
+    Setup: FnOnce(&str) -> SetupFut,
+    SetupFut: std::future::Future<Output = anyhow::Result<Shared>>,
+    Handler: Fn(&'static Shared, Event, Context) -> Fut + Send + Sync,
+    Fut: std::future::Future<Output = anyhow::Result<Return>> + Send,
+{
+    log::info!("Starting lambda runtime");
+    let (region, shared) = init_error::report(async {
+        let region = region(true)?;
+        let shared = setup(&region).await?;
+        Ok((region, shared))
+    })
+    .await?;
+    let shared: &'static Shared = Box::leak(Box::new(shared));
+    exec_runtime(
+        &instance::Func::new(shared, handler),
+        &(),
+        &region,
+        &builder::Settings::default(),
+        None,
+    )
+    .await
+}
+
+/// Lambda entrypoint for [`LocalRunner`]. This function sets up
+/// a current-thread runtime and executes the runner on a
+/// [`tokio::task::LocalSet`].
+///
+/// As the futures of the runner do not need to be `Send`,
+/// `Shared` may contain types like [`std::rc::Rc`] or clients
+/// which are bound to a thread. Since everything is executed
+/// on a single thread, the timeout handler only fails the
+/// invocation while the runner is awaiting.
+///
+/// See [`exec`] for the types.
+pub fn exec_local<Shared, Event, Run, Return>() -> anyhow::Result<()>
+where
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug,
+    Run: for<'a> LocalRunner<'a, Shared, Event, Return>,
+    Return: serde::Serialize,
+{
+    use anyhow::Context;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Unable to build tokio runtime")?;
+    tokio::task::LocalSet::new().block_on(&runtime, async {
+        log::info!("Starting lambda runtime");
+        let (region, shared) = init_error::report(async {
+            let region = region(true)?;
+            let shared = Run::setup(&region).await?;
+            Ok((region, shared))
+        })
+        .await?;
+        exec_handler::<_, Event, _, Return>(
+            &handler::Local::<Run>::default(),
+            &shared,
+            &region,
+            &builder::Settings::default(),
+            None,
+        )
+        .await
+    })
+}
+
+/// Reads and validates the region from the `AWS_REGION` env variable. If
+/// the region is not `required`, an empty region is returned when it is missing.
+fn region(required: bool) -> anyhow::Result<String> {
+    match Region::from_env()? {
+        Some(region) => Ok(region.into()),
+        None if !required => {
+            log::warn!("Missing AWS_REGION env variable. Using an empty region");
+            Ok(String::new())
+        }
+        None => anyhow::bail!("Missing AWS_REGION env variable"),
+    }
+}
+
+async fn exec_runtime<Shared, Event, Run, Return>(
+    runner: &Run,
+    shared: &Shared,
+    region: &str,
+    settings: &builder::Settings,
+    first: Option<invocation_loop::Invocation>,
+) -> anyhow::Result<()>
 where
     Shared: Send + Sync,
     Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
-    Run: Runner<'a, Shared, Event, Return>,
+    Run: InstanceRunner<Shared, Event, Return>,
+    Return: serde::Serialize,
+{
+    exec_handler::<_, Event, _, Return>(&handler::Instance(runner), shared, region, settings, first)
+        .await
+}
+
+#[allow(clippy::future_not_send)]
+async fn exec_handler<Shared, Event, Run, Return>(
+    runner: &Run,
+    shared: &Shared,
+    region: &str,
+    settings: &builder::Settings,
+    first: Option<invocation_loop::Invocation>,
+) -> anyhow::Result<()>
+where
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug,
+    Run: handler::Handler<Shared, Event, Return>,
     Return: serde::Serialize,
 {
-    use anyhow::anyhow;
     use futures::FutureExt;
 
-    let mut runner = Run::run(
-        shared,
-        LambdaEvent {
-            event: event.payload,
-            region,
-            ctx: event.context,
+    let stats = &stats::Tracker::default();
+    let hard_deadline = (settings.timeout_handler
+        && settings.hard_deadline
+        && runner.timeout() == TimeoutBehavior::Fail)
+        .then(|| invocation_loop::HardDeadline {
+            margin: settings.timeout_margin,
+            clock: settings.clock,
+            error: runner.classify(&timeout_error()),
+        });
+    let mut shutdown = Box::pin(shutdown_signal()?.fuse());
+    let mut runtime = Box::pin(
+        invocation_loop::run(first, hard_deadline, move |data| {
+            let deadline: u64 = data.context.deadline;
+            async move {
+                run::<_, Event, _, Return>(
+                    runner,
+                    shared,
+                    data,
+                    Some(deadline),
+                    region,
+                    settings,
+                    stats,
+                )
+                .await
+                .map_err(|err| runner.classify(&err))
+            }
+        })
+        .fuse(),
+    );
+    futures::select! {
+        res = runtime => res,
+        _ = shutdown => {
+            log::info!("Received SIGTERM. Shutting down lambda runtime");
+            runner.on_shutdown(shared).await;
+            Ok(())
         },
+    }
+}
+
+#[cfg(unix)]
+fn shutdown_signal() -> anyhow::Result<impl std::future::Future<Output = ()>> {
+    use anyhow::Context;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut signal = signal(SignalKind::terminate()).context("Unable to listen for SIGTERM")?;
+    Ok(async move {
+        signal.recv().await;
+    })
+}
+
+#[cfg(not(unix))]
+fn shutdown_signal() -> anyhow::Result<impl std::future::Future<Output = ()>> {
+    Ok(futures::future::pending())
+}
+
+#[allow(clippy::future_not_send)]
+async fn run<'a, Shared, Event, Run, Return>(
+    runner: &'a Run,
+    shared: &'a Shared,
+    event: lambda_runtime::LambdaEvent<Box<serde_json::value::RawValue>>,
+    deadline_in_ms: Option<u64>,
+    region: &'a str,
+    settings: &'a builder::Settings,
+    stats: &'a stats::Tracker,
+) -> anyhow::Result<Box<serde_json::value::RawValue>>
+where
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug,
+    Run: handler::Handler<Shared, Event, Return>,
+    Return: serde::Serialize,
+{
+    let started = std::time::Instant::now();
+    let request_id = event.context.request_id.clone();
+    let memory = event.context.env_config.memory;
+    let request_bytes = event.payload.get().len();
+    let snapshot = stats.snapshot();
+    let is_cold_start = snapshot.invocations == 0;
+    panic::set_request_id(Some(&request_id));
+    let (res, retries) = summary::track_retries(invoke::<_, Event, Run, Return>(
+        runner,
+        shared,
+        event,
+        deadline_in_ms,
+        region,
+        settings,
+        snapshot,
+    ))
+    .await;
+    summary::Summary::new(
+        &request_id,
+        memory,
+        started,
+        request_bytes,
+        res.as_ref()
+            .map(|(res, fallback)| (res.get().len(), *fallback))
+            .map_err(|(class, _)| *class),
+        retries,
+        is_cold_start,
     )
-    .fuse();
-    let res = if let Some(deadline_in_ms) = deadline_in_ms {
-        let mut timeout = Box::pin(timeout_handler(deadline_in_ms).fuse());
-        futures::select! {
-            res = runner => res,
-            _ = timeout => Err(anyhow!("Lambda failed by running into a timeout")),
-        }
-    } else {
-        runner.await
-    };
-    log::info!("Completed lambda invocation");
+    .log();
+    stats.record(&request_id, !matches!(res, Ok((_, None))));
+    panic::set_request_id(None);
     match res {
-        Ok(res) => Ok(res),
-        Err(err) => {
+        Ok((res, _)) => Ok(res),
+        Err((_, err)) => {
             log::error!("{:?}", err);
             Err(err)
         }
     }
 }
 
-async fn timeout_handler(deadline_in_ms: u64) {
-    use std::time::{Duration, SystemTime, UNIX_EPOCH};
-    use tokio::time::Instant;
+#[allow(clippy::future_not_send)]
+async fn invoke<'a, Shared, Event, Run, Return>(
+    runner: &'a Run,
+    shared: &'a Shared,
+    event: lambda_runtime::LambdaEvent<Box<serde_json::value::RawValue>>,
+    deadline_in_ms: Option<u64>,
+    region: &'a str,
+    settings: &'a builder::Settings,
+    stats: Stats,
+) -> Result<
+    (
+        Box<serde_json::value::RawValue>,
+        Option<summary::ErrorClass>,
+    ),
+    (summary::ErrorClass, anyhow::Error),
+>
+where
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug,
+    Run: handler::Handler<Shared, Event, Return>,
+    Return: serde::Serialize,
+{
+    if let Some(res) = runner.warmup(&event.payload) {
+        log::info!("Answering warmup event");
+        return Ok((serialize_response(&res)?, None));
+    }
+    let ctx = event.context.clone();
+    let started = std::time::Instant::now();
+    runner.before_invoke(shared, &ctx).await;
+    let res = handle::<_, Event, Run, Return>(
+        runner,
+        shared,
+        event,
+        deadline_in_ms,
+        region,
+        settings,
+        stats.clone(),
+    )
+    .await;
+    let res = match res {
+        Ok(res) => Ok(res),
+        Err((class, err)) => match runner.on_error(shared, err, &ctx).await {
+            Ok(res) => {
+                log::warn!("Error was handled by on_error. Returning its response");
+                serialize_response(&res).map(|res| (res, Some(class)))
+            }
+            Err(err) => Err((class, err)),
+        },
+    };
+    let info = InvocationInfo {
+        ctx: &ctx,
+        duration: started.elapsed(),
+        outcome: match &res {
+            Ok((_, None)) => InvocationOutcome::Success,
+            Ok((_, Some(_))) => InvocationOutcome::Fallback,
+            Err((_, err)) => InvocationOutcome::Failure(err),
+        },
+        stats: &stats,
+    };
+    runner.after_invoke(shared, &info).await;
+    res
+}
 
-    let epoch = UNIX_EPOCH;
-    let now = SystemTime::now();
-    let now_instant = Instant::now();
+fn serialize_response<Return>(
+    res: &Return,
+) -> Result<Box<serde_json::value::RawValue>, (summary::ErrorClass, anyhow::Error)>
+where
+    Return: serde::Serialize,
+{
+    use anyhow::Context;
 
-    let duration_from_now = now.duration_since(epoch).expect("Time went backwards");
-    let duration_from_epoch = Duration::from_millis(deadline_in_ms);
-    let duration_deadline = duration_from_epoch - duration_from_now - Duration::from_millis(100);
+    serde_json::value::to_raw_value(res)
+        .context("Unable to serialize response")
+        .map_err(|err| (summary::ErrorClass::Serialization, err))
+}
 
-    let deadline = now_instant + duration_deadline;
-    log::info!("Setting deadline to: {:?}", deadline);
-    tokio::time::sleep_until(deadline).await;
+#[allow(clippy::future_not_send, clippy::unit_arg)]
+async fn handle<'a, Shared, Event, Run, Return>(
+    runner: &'a Run,
+    shared: &'a Shared,
+    event: lambda_runtime::LambdaEvent<Box<serde_json::value::RawValue>>,
+    deadline_in_ms: Option<u64>,
+    region: &'a str,
+    settings: &'a builder::Settings,
+    stats: Stats,
+) -> Result<
+    (
+        Box<serde_json::value::RawValue>,
+        Option<summary::ErrorClass>,
+    ),
+    (summary::ErrorClass, anyhow::Error),
+>
+where
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug,
+    Run: handler::Handler<Shared, Event, Return>,
+    Return: serde::Serialize,
+{
+    use anyhow::anyhow;
+    use futures::FutureExt;
+    use summary::ErrorClass;
+
+    let payload: Event = deserialize_event(event.payload.get(), runner.deny_unknown_fields())
+        .map_err(|err| (ErrorClass::Deserialization, err))?;
+    log::debug!("Received lambda invocation with event: {:?}", payload);
+    runner.validate(&payload).map_err(|err| {
+        log::warn!("Rejected invalid event: {}", err);
+        (ErrorClass::Validation, anyhow::Error::new(err))
+    })?;
+    let outbox = Outbox::default();
+    let spawner = Spawner::default();
+    let partial = Partial::default();
+    let timeout = runner.timeout();
+    let ctx = event.context.clone();
+    let memory_limit = event.context.env_config.memory;
+    let early_warning =
+        deadline_in_ms
+            .zip(runner.early_warning())
+            .map(|(deadline_in_ms, fraction)| {
+                let ctx = &ctx;
+                async move {
+                    early_warning_handler(settings, deadline_in_ms, fraction).await;
+                    log::info!("Lambda used {}% of its time budget", fraction * 100.0);
+                    runner.on_early_warning(shared, ctx).await;
+                }
+            });
+    let heartbeat = runner.heartbeat().map(|interval| {
+        let ctx = &ctx;
+        heartbeat(interval, move || runner.on_heartbeat(shared, ctx))
+    });
+    let running = alongside(
+        runner.run(
+            shared,
+            LambdaEvent {
+                event: payload,
+                region,
+                ctx: event.context,
+                outbox: outbox.clone(),
+                spawner: spawner.clone(),
+                partial: partial.clone(),
+                scoped: Scoped::default(),
+                is_cold_start: stats.invocations == 0,
+                stats,
+                env: LambdaEnv::current(),
+                timeout_margin: settings.timeout_margin,
+                clock: settings.clock,
+            },
+        ),
+        futures::future::join(
+            futures::future::OptionFuture::from(early_warning),
+            futures::future::OptionFuture::from(heartbeat),
+        ),
+    );
+    let running = std::panic::AssertUnwindSafe(Box::pin(running))
+        .catch_unwind()
+        .map(|res| match res {
+            Ok(res) => res.map_err(|err| (ErrorClass::Handler, err)),
+            Err(payload) => Err((
+                ErrorClass::Panic,
+                anyhow!("Lambda panicked: {}", panic::message(&*payload)),
+            )),
+        });
+    let memory_watchdog = async {
+        let err = memory::watchdog(settings.memory_watchdog, memory_limit).await;
+        Err((ErrorClass::Memory, err))
+    };
+    let mut running = futures::future::select(Box::pin(running), Box::pin(memory_watchdog))
+        .map(|res| res.factor_first().0)
+        .fuse();
+    let res = match deadline_in_ms {
+        Some(deadline_in_ms)
+            if settings.timeout_handler && timeout != TimeoutBehavior::Disabled =>
+        {
+            let mut timeout_handler = Box::pin(timeout_handler(settings, deadline_in_ms).fuse());
+            futures::select! {
+                res = running => res,
+                _ = timeout_handler => if timeout == TimeoutBehavior::Warn {
+                    log::warn!("Lambda is about to run into a timeout");
+                    running.await
+                } else {
+                    let budget = settings.timeout_margin / 2;
+                    if tokio::time::timeout(budget, runner.on_timeout(shared, &ctx))
+                        .await
+                        .is_err()
+                    {
+                        log::warn!("on_timeout did not complete within {:?}", budget);
+                    }
+                    Err((ErrorClass::Timeout, timeout_error()))
+                },
+            }
+        }
+        _ => running.await,
+    };
+    if matches!(res, Err((ErrorClass::Timeout, _))) {
+        spawner.abort();
+    } else {
+        spawner
+            .join(
+                settings.clock,
+                deadline_in_ms,
+                settings.timeout_margin + std::time::Duration::from_millis(100),
+            )
+            .await;
+    }
+    let (res, fallback) = match res {
+        Ok(res) => {
+            let deadline = deadline_in_ms
+                .filter(|_| settings.timeout_handler && timeout == TimeoutBehavior::Fail)
+                .map(|deadline_in_ms| {
+                    let remaining = settings
+                        .clock
+                        .until_deadline_ms(deadline_in_ms)
+                        .saturating_sub(settings.timeout_margin);
+                    tokio::time::Instant::now() + remaining
+                });
+            outbox.flush(deadline).await.map_err(|err| {
+                if err.is::<tokio::time::error::Elapsed>() {
+                    (ErrorClass::Timeout, err)
+                } else {
+                    (ErrorClass::SideEffect, err)
+                }
+            })?;
+            (res, None)
+        }
+        Err((class, err)) => {
+            outbox.discard();
+            if let (ErrorClass::Timeout, Some(res)) = (class, partial.take()) {
+                log::error!("Returning partial result after error: {:?}", err);
+                return Ok((res, Some(class)));
+            }
+            match runner.fallback(shared, &err) {
+                Some(res) => {
+                    log::error!("Returning fallback response after error: {:?}", err);
+                    (res, Some(class))
+                }
+                None => return Err((class, err)),
+            }
+        }
+    };
+    Ok((serialize_response(&res)?, fallback))
+}
+
+fn deserialize_event<Event>(payload: &str, deny_unknown_fields: bool) -> anyhow::Result<Event>
+where
+    Event: for<'de> serde::Deserialize<'de>,
+{
+    use anyhow::Context;
+
+    let mut ignored = Vec::new();
+    let event =
+        serde_ignored::deserialize(&mut serde_json::Deserializer::from_str(payload), |path| {
+            ignored.push(path.to_string())
+        })
+        .context("Unable to deserialize event")?;
+    if ignored.is_empty() {
+        return Ok(event);
+    }
+    let ignored = ignored.join(", ");
+    if deny_unknown_fields {
+        anyhow::bail!("Unable to deserialize event. Unknown fields: {}", ignored);
+    }
+    log::warn!("Ignored unknown fields in event: {}", ignored);
+    Ok(event)
+}
+
+/// Polls `background` alongside `running` until `running` completes
+async fn alongside<T>(
+    running: impl std::future::Future<Output = T>,
+    background: impl std::future::Future,
+) -> T {
+    use futures::future::Either;
+
+    match futures::future::select(Box::pin(running), Box::pin(background)).await {
+        Either::Left((res, _)) => res,
+        Either::Right((_, running)) => running.await,
+    }
+}
+
+async fn heartbeat<Fut>(interval: std::time::Duration, beat: impl Fn() -> Fut)
+where
+    Fut: std::future::Future<Output = ()>,
+{
+    let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticks.tick().await;
+        beat().await;
+    }
+}
+
+async fn early_warning_handler(settings: &builder::Settings, deadline_in_ms: u64, fraction: f32) {
+    let budget = settings
+        .clock
+        .until_deadline_ms(deadline_in_ms)
+        .saturating_sub(settings.timeout_margin);
+    let delay =
+        std::time::Duration::try_from_secs_f32(budget.as_secs_f32() * fraction.clamp(0.0, 1.0))
+            .unwrap_or_default();
+    tokio::time::sleep(delay).await;
+}
+
+fn timeout_error() -> anyhow::Error {
+    anyhow::anyhow!("Lambda failed by running into a timeout")
+}
+
+async fn timeout_handler(settings: &builder::Settings, deadline_in_ms: u64) {
+    let remaining = settings
+        .clock
+        .until_deadline_ms(deadline_in_ms)
+        .saturating_sub(settings.timeout_margin);
+    log::debug!("Setting deadline to {:?} from now", remaining);
+    tokio::time::sleep(remaining).await;
 }
 
 /// TestData which can be used to test lambda invocations
 /// locally in combination with [`exec_test`].
+///
+/// Fields:
+/// * `region`: Region passed to the lambda
+/// * `invocations`: Events which are send to the lambda
+/// * `environments` (optional): Amount of simulated execution
+///   environments, each with its own `Shared`. Invocations are
+///   distributed round-robin. Defaults to 1.
+/// * `concurrent` (optional): Whether environments execute their
+///   invocations concurrently. Defaults to false.
 #[derive(serde::Deserialize, Clone, Debug)]
 #[cfg(feature = "test")]
 #[cfg_attr(docsrs, doc(cfg(feature = "test")))]
 pub struct TestData<Event> {
     region: String,
+    /// Amount of simulated execution environments. Each environment
+    /// has its own `Shared` and invocations are distributed round-robin.
+    #[serde(default = "default_environments")]
+    environments: std::num::NonZeroUsize,
+    /// Whether environments execute their invocations concurrently
+    /// instead of interleaved one after another
+    #[serde(default)]
+    concurrent: bool,
     invocations: Vec<Event>,
 }
 
+#[cfg(feature = "test")]
+const fn default_environments() -> std::num::NonZeroUsize {
+    std::num::NonZeroUsize::MIN
+}
+
 /// Lambda entrypoint. This function can be used to
 /// test one or multiple lambda invocations locally.
 ///
 /// Types:
 /// * `Shared`: Type which is shared between lambda
-///             invocations. Note that lambda will
-///             create multiple environments for
-///             simulations invokations and environments
-///             are only kept alive for a certain time.
-///             It is thus not guaranteed that data
-///             can be reused, but with this types
-///             its possible.
-/// * `Event`:  The expected Event which is being send
-///             to the lambda by AWS.
-/// * `Run`:    Runner which is execued for each lambda
-///             invocation.
+///   invocations. Note that lambda will
+///   create multiple environments for
+///   simulations invokations and environments
+///   are only kept alive for a certain time.
+///   It is thus not guaranteed that data
+///   can be reused, but with this types
+///   its possible.
+/// * `Event`: The expected Event which is being send
+///   to the lambda by AWS.
+/// * `Run`: Runner which is execued for each lambda
+///   invocation.
 /// * `Return`: Type which is the result of the lamba
-///             invocation being returned to AWS
+///   invocation being returned to AWS
 #[cfg(feature = "test")]
 #[cfg_attr(docsrs, doc(cfg(feature = "test")))]
 pub fn exec_test<Shared, Event, Run, Return>(test_data: &str) -> anyhow::Result<()>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Run: for<'a> Runner<'a, Shared, Event, Return>,
+    Return: serde::Serialize + std::fmt::Debug,
+{
+    use anyhow::Context;
+
+    let test_data: TestData<serde_json::Value> =
+        serde_json::from_str(test_data).context("Unable to deserialize test_data")?;
+    exec_test_data::<_, Event, Run, _>(test_data)
+}
+
+/// Lambda entrypoint. Same as [`exec_test`], but reads
+/// the test data from YAML instead of JSON.
+#[cfg(feature = "test_yaml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test_yaml")))]
+pub fn exec_test_yaml<Shared, Event, Run, Return>(test_data: &str) -> anyhow::Result<()>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Run: for<'a> Runner<'a, Shared, Event, Return>,
+    Return: serde::Serialize + std::fmt::Debug,
+{
+    use anyhow::Context;
+
+    let test_data: TestData<serde_json::Value> =
+        serde_yaml::from_str(test_data).context("Unable to deserialize test_data")?;
+    exec_test_data::<_, Event, Run, _>(test_data)
+}
+
+#[cfg(feature = "test")]
+fn exec_test_data<Shared, Event, Run, Return>(
+    test_data: TestData<serde_json::Value>,
+) -> anyhow::Result<()>
 where
     Shared: Send + Sync,
     Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
@@ -400,26 +1555,86 @@ where
         .context("Unable to build tokio runtime")?
         .block_on(async {
             log::info!("Starting lambda test runtime");
-            let test_data: TestData<Event> =
-                serde_json::from_str(test_data).context("Unable to deserialize test_data")?;
-            let region_ref = &test_data.region;
-            let shared = Run::setup(region_ref).await?;
-            let shared_ref = &shared;
-
-            for (i, data) in test_data.invocations.into_iter().enumerate() {
-                log::info!("Starting lambda invocation: {}", i);
-                let res = run::<_, Event, Run, Return>(
-                    shared_ref,
-                    lambda_runtime::LambdaEvent {
-                        payload: data,
-                        context: crate::Context::default(),
+            let region_ref = test_data.region.as_str();
+            let environments = test_data.environments.get();
+            let mut shared = Vec::with_capacity(environments);
+            for _ in 0..environments {
+                shared.push((Run::setup(region_ref).await?, stats::Tracker::default()));
+            }
+
+            let invocations = test_data.invocations.into_iter().enumerate();
+            if test_data.concurrent {
+                let mut queues: Vec<Vec<_>> = (0..environments).map(|_| Vec::new()).collect();
+                for (i, data) in invocations {
+                    queues[i % environments].push((i, data));
+                }
+                futures::future::try_join_all(shared.iter().zip(queues).enumerate().map(
+                    |(env, ((shared, stats), queue))| async move {
+                        for (i, data) in queue {
+                            exec_test_invocation::<_, Event, Run, _>(
+                                shared, data, i, env, region_ref, stats,
+                            )
+                            .await?;
+                        }
+                        Run::on_shutdown(shared).await;
+                        Ok::<_, anyhow::Error>(())
                     },
-                    None,
-                    region_ref,
-                )
+                ))
                 .await?;
-                log::info!("{:?}", res);
+            } else {
+                for (i, data) in invocations {
+                    let env = i % environments;
+                    let (shared, stats) = &shared[env];
+                    exec_test_invocation::<_, Event, Run, _>(
+                        shared, data, i, env, region_ref, stats,
+                    )
+                    .await?;
+                }
+                for (shared, _) in &shared {
+                    Run::on_shutdown(shared).await;
+                }
             }
             Ok(())
         })
 }
+
+#[cfg(feature = "test")]
+async fn exec_test_invocation<'a, Shared, Event, Run, Return>(
+    shared: &'a Shared,
+    data: serde_json::Value,
+    invocation: usize,
+    environment: usize,
+    region: &'a str,
+    stats: &'a stats::Tracker,
+) -> anyhow::Result<()>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Run: for<'b> Runner<'b, Shared, Event, Return>,
+    Return: serde::Serialize + std::fmt::Debug,
+{
+    use anyhow::Context;
+
+    log::info!(
+        "Starting lambda invocation: {} (environment {})",
+        invocation,
+        environment
+    );
+    let payload =
+        serde_json::value::to_raw_value(&data).context("Unable to serialize test event")?;
+    let res = run::<_, Event, _, Return>(
+        &handler::Instance(&StaticRunner::<Run>::default()),
+        shared,
+        lambda_runtime::LambdaEvent {
+            payload,
+            context: crate::Context::default(),
+        },
+        None,
+        region,
+        &builder::Settings::default(),
+        stats,
+    )
+    .await?;
+    log::info!("{}", res);
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use crate::{Context, ErrorShape, InvocationInfo, LambdaEvent, TimeoutBehavior, ValidationError};
+
+/// Defines a type which is executed every time a lambda
+/// is invoced, but whose futures are not `Send`.
+///
+/// Used with [`crate::exec_local`], which executes the runner
+/// on a single thread. This allows using clients which are
+/// bound to a thread. Otherwise it is the same as [`crate::Runner`].
+///
+/// ```no_run
+/// struct Runner;
+///
+/// #[async_trait::async_trait(?Send)]
+/// impl<'a> lambda_runtime_types::LocalRunner<'a, std::rc::Rc<String>, (), String> for Runner {
+///     async fn run(
+///         shared: &'a std::rc::Rc<String>,
+///         event: lambda_runtime_types::LambdaEvent<'a, ()>,
+///     ) -> anyhow::Result<String> {
+///         let greeting = std::rc::Rc::clone(shared);
+///         tokio::task::yield_now().await;
+///         Ok(greeting.to_string())
+///     }
+///
+///     async fn setup(_region: &'a str) -> anyhow::Result<std::rc::Rc<String>> {
+///         Ok(std::rc::Rc::new("Hello".into()))
+///     }
+/// }
+///
+/// pub fn main() -> anyhow::Result<()> {
+///     lambda_runtime_types::exec_local::<_, _, Runner, _>()
+/// }
+/// ```
+#[async_trait::async_trait(?Send)]
+pub trait LocalRunner<'a, Shared, Event, Return>
+where
+    Shared: 'a,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug,
+    Return: serde::Serialize,
+{
+    /// See [`crate::Runner::setup`]
+    async fn setup(region: &'a str) -> anyhow::Result<Shared>;
+
+    /// See [`crate::Runner::run`]
+    async fn run(shared: &'a Shared, event: LambdaEvent<'a, Event>) -> anyhow::Result<Return>;
+
+    /// See [`crate::Runner::TIMEOUT`]
+    const TIMEOUT: TimeoutBehavior = TimeoutBehavior::Fail;
+
+    /// See [`crate::Runner::DENY_UNKNOWN_FIELDS`]
+    const DENY_UNKNOWN_FIELDS: bool = false;
+
+    /// See [`crate::Runner::EARLY_WARNING`]
+    const EARLY_WARNING: Option<f32> = None;
+
+    /// See [`crate::Runner::HEARTBEAT`]
+    const HEARTBEAT: Option<std::time::Duration> = None;
+
+    /// See [`crate::Runner::validate`]
+    fn validate(_event: &Event) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// See [`crate::Runner::on_timeout`]
+    async fn on_timeout(_shared: &'a Shared, _ctx: &'a Context) {}
+
+    /// See [`crate::Runner::on_early_warning`]
+    async fn on_early_warning(_shared: &'a Shared, _ctx: &'a Context) {}
+
+    /// See [`crate::Runner::on_heartbeat`]
+    async fn on_heartbeat(_shared: &'a Shared, _ctx: &'a Context) {}
+
+    /// See [`crate::Runner::before_invoke`]
+    async fn before_invoke(_shared: &'a Shared, _ctx: &'a Context) {}
+
+    /// See [`crate::Runner::after_invoke`]
+    async fn after_invoke(_shared: &'a Shared, _info: &'a InvocationInfo<'a>) {}
+
+    /// See [`crate::Runner::classify`]
+    fn classify(error: &anyhow::Error) -> ErrorShape {
+        ErrorShape::from_error(error)
+    }
+
+    /// See [`crate::Runner::on_shutdown`]
+    async fn on_shutdown(_shared: &'a Shared) {}
+}
@@ -0,0 +1,53 @@
+/// Interval in which the memory usage is checked
+const INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Returns the resident memory of the process in bytes. Only
+/// available on linux, where it is read from `/proc/self/status`
+pub fn resident() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Completes with an error once the resident memory exceeds `threshold` of
+/// `limit_mb`. Never completes if no threshold is set or the memory usage is
+/// not available. See [`crate::Builder::memory_watchdog`]
+pub async fn watchdog(threshold: Option<f32>, limit_mb: i32) -> anyhow::Error {
+    let (Some(threshold), Ok(limit_mb @ 1..)) = (threshold, u64::try_from(limit_mb)) else {
+        return futures::future::pending().await;
+    };
+    if resident().is_none() {
+        log::warn!("Memory usage is not available. Memory watchdog is disabled");
+        return futures::future::pending().await;
+    }
+    let limit = limit_mb * 1024 * 1024;
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    let max = (limit as f64 * f64::from(threshold.clamp(0.0, 1.0))) as u64;
+    let mut ticks = tokio::time::interval(INTERVAL);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticks.tick().await;
+        match resident() {
+            Some(used) if used >= max => {
+                return anyhow::anyhow!(
+                    "Lambda failed by using {} MB of its {} MB memory, which exceeds the threshold of {}%",
+                    used / 1024 / 1024,
+                    limit_mb,
+                    threshold * 100.0
+                );
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Provides middleware which wraps invocations of an [`InstanceRunner`].
+//!
+//! Middleware is used for cross-cutting concerns like authentication, metrics
+//! or payload logging, which would otherwise need to be implemented in every
+//! runner. Each middleware receives the event and decides whether and how the
+//! wrapped runner is called through [`Next`]. Middleware is added with
+//! [`InstanceRunner::layer`]. The last added middleware is executed first.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::middleware::{Middleware, Next};
+//! use lambda_runtime_types::{InstanceRunner, LambdaEvent, StaticRunner};
+//!
+//! struct LogDuration;
+//!
+//! #[async_trait::async_trait]
+//! impl Middleware<(), String, String> for LogDuration {
+//!     async fn call<'a>(
+//!         &'a self,
+//!         shared: &'a (),
+//!         event: LambdaEvent<'a, String>,
+//!         next: Next<'a, (), String, String>,
+//!     ) -> anyhow::Result<String> {
+//!         let started = std::time::Instant::now();
+//!         let res = next.run(shared, event).await;
+//!         log::info!("Invocation took {:?}", started.elapsed());
+//!         res
+//!     }
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, (), String, String> for Runner {
+//!     async fn run(shared: &'a (), event: LambdaEvent<'a, String>) -> anyhow::Result<String> {
+//!         Ok(event.event)
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! pub async fn main() -> anyhow::Result<()> {
+//!     let runner = StaticRunner::<Runner>::default().layer(LogDuration);
+//!     lambda_runtime_types::exec_instance(runner, ()).await
+//! }
+//! ```
+
+use crate::{InstanceRunner, LambdaEvent, TimeoutBehavior};
+
+/// Wraps invocations of a runner
+///
+/// Types:
+/// * `Shared`: Type which is shared between lambda
+///   invocations. See [`crate::Runner`]
+/// * `Event`: The expected Event which is being send
+///   to the lambda by AWS.
+/// * `Return`: Type which is the result of the lamba
+///   invocation being returned to AWS
+#[async_trait::async_trait]
+pub trait Middleware<Shared, Event, Return>: Send + Sync
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize,
+{
+    /// Invoked for every lambda invocation. Call [`Next::run`] to
+    /// continue with the wrapped runner
+    async fn call<'a>(
+        &'a self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+        next: Next<'a, Shared, Event, Return>,
+    ) -> anyhow::Result<Return>;
+}
+
+/// Remaining chain of middleware and the runner
+pub struct Next<'a, Shared, Event, Return> {
+    runner: &'a (dyn InstanceRunner<Shared, Event, Return> + 'a),
+}
+
+impl<Shared, Event, Return> Clone for Next<'_, Shared, Event, Return> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Shared, Event, Return> Copy for Next<'_, Shared, Event, Return> {}
+
+impl<'a, Shared, Event, Return> Next<'a, Shared, Event, Return>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize,
+{
+    /// Runs the remaining chain with the given event
+    pub async fn run(
+        self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<Return> {
+        self.runner.run(shared, event).await
+    }
+}
+
+/// Runner wrapped by a middleware. Created with [`InstanceRunner::layer`]
+pub struct Layered<Run, M> {
+    runner: Run,
+    middleware: M,
+}
+
+impl<Run, M> Layered<Run, M> {
+    pub(crate) const fn new(runner: Run, middleware: M) -> Self {
+        Self { runner, middleware }
+    }
+}
+
+#[async_trait::async_trait]
+impl<Shared, Event, Return, Run, M> InstanceRunner<Shared, Event, Return> for Layered<Run, M>
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Return: serde::Serialize + 'static,
+    Run: InstanceRunner<Shared, Event, Return>,
+    M: Middleware<Shared, Event, Return>,
+{
+    async fn run<'a>(
+        &'a self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<Return>
+    where
+        Event: 'a,
+    {
+        let next = Next {
+            runner: &self.runner,
+        };
+        self.middleware.call(shared, event, next).await
+    }
+
+    fn timeout(&self) -> TimeoutBehavior {
+        self.runner.timeout()
+    }
+
+    fn deny_unknown_fields(&self) -> bool {
+        self.runner.deny_unknown_fields()
+    }
+
+    fn early_warning(&self) -> Option<f32> {
+        self.runner.early_warning()
+    }
+
+    fn heartbeat(&self) -> Option<std::time::Duration> {
+        self.runner.heartbeat()
+    }
+
+    fn validate(&self, event: &Event) -> Result<(), crate::ValidationError> {
+        self.runner.validate(event)
+    }
+
+    fn warmup(&self, event: &serde_json::value::RawValue) -> Option<serde_json::Value> {
+        self.runner.warmup(event)
+    }
+
+    fn fallback(&self, shared: &Shared, error: &anyhow::Error) -> Option<Return> {
+        self.runner.fallback(shared, error)
+    }
+
+    async fn on_error(
+        &self,
+        shared: &Shared,
+        error: anyhow::Error,
+        ctx: &crate::Context,
+    ) -> anyhow::Result<Return> {
+        self.runner.on_error(shared, error, ctx).await
+    }
+
+    async fn on_timeout(&self, shared: &Shared, ctx: &crate::Context) {
+        self.runner.on_timeout(shared, ctx).await
+    }
+
+    async fn on_early_warning(&self, shared: &Shared, ctx: &crate::Context) {
+        self.runner.on_early_warning(shared, ctx).await
+    }
+
+    async fn on_heartbeat(&self, shared: &Shared, ctx: &crate::Context) {
+        self.runner.on_heartbeat(shared, ctx).await
+    }
+
+    async fn before_invoke(&self, shared: &Shared, ctx: &crate::Context) {
+        self.runner.before_invoke(shared, ctx).await
+    }
+
+    async fn after_invoke(&self, shared: &Shared, info: &crate::InvocationInfo<'_>) {
+        self.runner.after_invoke(shared, info).await
+    }
+
+    fn classify(&self, error: &anyhow::Error) -> crate::ErrorShape {
+        self.runner.classify(error)
+    }
+
+    async fn on_shutdown(&self, shared: &Shared) {
+        self.runner.on_shutdown(shared).await
+    }
+}
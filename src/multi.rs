@@ -0,0 +1,327 @@
+//! Provides types for lambdas which are invoked by multiple event sources.
+//!
+//! The source of an [`AnyEvent`] is detected by its shape. SQS, SNS and S3
+//! events are detected by the `eventSource` of their records, EventBridge
+//! events by their `source`, `detail-type` and `detail` fields. All other
+//! events are treated as direct invocations.
+//!
+//! A [`MultiRunner`] has one method per source. Sources which are not
+//! implemented fail the invocation.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::multi::{EventBridgeEvent, MultiRunner, SqsEvent};
+//! use lambda_runtime_types::LambdaEvent;
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> MultiRunner<'a, (), ()> for Runner {
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//!
+//!     async fn sqs(_shared: &'a (), event: LambdaEvent<'a, SqsEvent>) -> anyhow::Result<()> {
+//!         for message in event.event.records {
+//!             log::info!("Received message: {}", message.body);
+//!         }
+//!         Ok(())
+//!     }
+//!
+//!     async fn event_bridge(
+//!         _shared: &'a (),
+//!         event: LambdaEvent<'a, EventBridgeEvent>,
+//!     ) -> anyhow::Result<()> {
+//!         log::info!("Received {} from {}", event.event.detail_type, event.event.source);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+//!
+//! For further usage like `Shared` Data, refer to the main [documentation](`crate`)
+
+use crate::LambdaEvent;
+use std::collections::HashMap;
+
+/// Event of any supported source
+#[derive(Debug, Clone)]
+pub enum AnyEvent {
+    /// Messages received from SQS
+    Sqs(SqsEvent),
+    /// Notifications received from SNS
+    Sns(SnsEvent),
+    /// Notifications about changed S3 objects
+    S3(S3Event),
+    /// Event received from EventBridge
+    EventBridge(EventBridgeEvent),
+    /// Any other event, e.g. of a direct invocation
+    Direct(serde_json::Value),
+}
+
+impl<'de> serde::Deserialize<'de> for AnyEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let record_source = value
+            .pointer("/Records/0/eventSource")
+            .or_else(|| value.pointer("/Records/0/EventSource"))
+            .and_then(serde_json::Value::as_str);
+        let is_event_bridge = ["source", "detail-type", "detail"]
+            .iter()
+            .all(|field| value.get(field).is_some());
+        let event = match record_source {
+            Some("aws:sqs") => serde_json::from_value(value).map(Self::Sqs),
+            Some("aws:sns") => serde_json::from_value(value).map(Self::Sns),
+            Some("aws:s3") => serde_json::from_value(value).map(Self::S3),
+            _ if is_event_bridge => serde_json::from_value(value).map(Self::EventBridge),
+            _ => Ok(Self::Direct(value)),
+        };
+        event.map_err(D::Error::custom)
+    }
+}
+
+/// Messages received from SQS
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SqsEvent {
+    /// Received messages
+    #[serde(rename = "Records")]
+    pub records: Vec<SqsMessage>,
+}
+
+/// Single message received from SQS
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SqsMessage {
+    /// Id of the message
+    #[serde(rename = "messageId")]
+    pub message_id: String,
+    /// Handle which is used to delete the message
+    #[serde(rename = "receiptHandle")]
+    pub receipt_handle: String,
+    /// Body of the message
+    #[serde(rename = "body")]
+    pub body: String,
+    /// System attributes of the message, e.g. `ApproximateReceiveCount`
+    #[serde(rename = "attributes", default)]
+    pub attributes: HashMap<String, String>,
+    /// Custom attributes of the message
+    #[serde(rename = "messageAttributes", default)]
+    pub message_attributes: HashMap<String, serde_json::Value>,
+    /// Arn of the queue
+    #[serde(rename = "eventSourceARN")]
+    pub event_source_arn: String,
+    /// Region of the queue
+    #[serde(rename = "awsRegion")]
+    pub aws_region: String,
+}
+
+/// Notifications received from SNS
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SnsEvent {
+    /// Received notifications
+    #[serde(rename = "Records")]
+    pub records: Vec<SnsRecord>,
+}
+
+/// Single notification received from SNS
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SnsRecord {
+    /// Arn of the subscription
+    #[serde(rename = "EventSubscriptionArn")]
+    pub event_subscription_arn: String,
+    /// The notification
+    #[serde(rename = "Sns")]
+    pub sns: SnsMessage,
+}
+
+/// Notification send by SNS
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SnsMessage {
+    /// Id of the notification
+    #[serde(rename = "MessageId")]
+    pub message_id: String,
+    /// Arn of the topic
+    #[serde(rename = "TopicArn")]
+    pub topic_arn: String,
+    /// Subject of the notification, if set
+    #[serde(rename = "Subject")]
+    pub subject: Option<String>,
+    /// Message of the notification
+    #[serde(rename = "Message")]
+    pub message: String,
+    /// Time the notification was published
+    #[serde(rename = "Timestamp")]
+    pub timestamp: String,
+    /// Custom attributes of the notification
+    #[serde(rename = "MessageAttributes", default)]
+    pub message_attributes: HashMap<String, serde_json::Value>,
+}
+
+/// Notifications about changed S3 objects
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct S3Event {
+    /// Received notifications
+    #[serde(rename = "Records")]
+    pub records: Vec<S3Record>,
+}
+
+/// Single notification about a changed S3 object
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct S3Record {
+    /// Name of the event, e.g. `ObjectCreated:Put`
+    #[serde(rename = "eventName")]
+    pub event_name: String,
+    /// Time of the event
+    #[serde(rename = "eventTime")]
+    pub event_time: String,
+    /// Region of the bucket
+    #[serde(rename = "awsRegion")]
+    pub aws_region: String,
+    /// Bucket and object of the event
+    #[serde(rename = "s3")]
+    pub s3: S3Entity,
+}
+
+/// Bucket and object of a [`S3Record`]
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct S3Entity {
+    /// Bucket containing the object
+    #[serde(rename = "bucket")]
+    pub bucket: S3Bucket,
+    /// The changed object
+    #[serde(rename = "object")]
+    pub object: S3Object,
+}
+
+/// Bucket of a [`S3Record`]
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct S3Bucket {
+    /// Name of the bucket
+    #[serde(rename = "name")]
+    pub name: String,
+    /// Arn of the bucket
+    #[serde(rename = "arn")]
+    pub arn: String,
+}
+
+/// Object of a [`S3Record`]
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct S3Object {
+    /// Url encoded key of the object
+    #[serde(rename = "key")]
+    pub key: String,
+    /// Size of the object. Not available for deletions
+    #[serde(rename = "size")]
+    pub size: Option<u64>,
+    /// ETag of the object. Not available for deletions
+    #[serde(rename = "eTag")]
+    pub e_tag: Option<String>,
+}
+
+/// Event received from EventBridge
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct EventBridgeEvent {
+    /// Id of the event
+    #[serde(rename = "id")]
+    pub id: String,
+    /// Type of the event
+    #[serde(rename = "detail-type")]
+    pub detail_type: String,
+    /// Source of the event, e.g. `aws.ec2`
+    #[serde(rename = "source")]
+    pub source: String,
+    /// Account which emitted the event
+    #[serde(rename = "account")]
+    pub account: String,
+    /// Time of the event
+    #[serde(rename = "time")]
+    pub time: String,
+    /// Region of the event
+    #[serde(rename = "region")]
+    pub region: String,
+    /// Resources affected by the event
+    #[serde(rename = "resources", default)]
+    pub resources: Vec<String>,
+    /// Source specific content of the event
+    #[serde(rename = "detail")]
+    pub detail: serde_json::Value,
+}
+
+/// Defines a type which is executed every time a lambda
+/// is invoced by one of multiple event sources.
+///
+/// Every method except `setup` defaults to failing the
+/// invocation, so only used sources need to be implemented.
+#[async_trait::async_trait]
+pub trait MultiRunner<'a, Shared, Return>
+where
+    Shared: Send + Sync + 'a,
+    Return: 'static + Send,
+{
+    /// See documentation of [`super::Runner::setup`]
+    async fn setup(region: &'a str) -> anyhow::Result<Shared>;
+
+    /// Invoked for events received from SQS
+    async fn sqs(_shared: &'a Shared, _event: LambdaEvent<'a, SqsEvent>) -> anyhow::Result<Return> {
+        anyhow::bail!("Events from SQS are not supported")
+    }
+
+    /// Invoked for events received from SNS
+    async fn sns(_shared: &'a Shared, _event: LambdaEvent<'a, SnsEvent>) -> anyhow::Result<Return> {
+        anyhow::bail!("Events from SNS are not supported")
+    }
+
+    /// Invoked for events received from S3
+    async fn s3(_shared: &'a Shared, _event: LambdaEvent<'a, S3Event>) -> anyhow::Result<Return> {
+        anyhow::bail!("Events from S3 are not supported")
+    }
+
+    /// Invoked for events received from EventBridge
+    async fn event_bridge(
+        _shared: &'a Shared,
+        _event: LambdaEvent<'a, EventBridgeEvent>,
+    ) -> anyhow::Result<Return> {
+        anyhow::bail!("Events from EventBridge are not supported")
+    }
+
+    /// Invoked for all other events, e.g. direct invocations
+    async fn direct(
+        _shared: &'a Shared,
+        _event: LambdaEvent<'a, serde_json::Value>,
+    ) -> anyhow::Result<Return> {
+        anyhow::bail!("Direct invocations are not supported")
+    }
+}
+
+#[async_trait::async_trait]
+impl<'a, Type, Shared, Return> super::Runner<'a, Shared, AnyEvent, Return> for Type
+where
+    Shared: Send + Sync + 'a,
+    Return: 'static + Send + serde::Serialize,
+    Type: 'static + MultiRunner<'a, Shared, Return>,
+{
+    async fn setup(region: &'a str) -> anyhow::Result<Shared> {
+        Self::setup(region).await
+    }
+
+    async fn run(shared: &'a Shared, event: LambdaEvent<'a, AnyEvent>) -> anyhow::Result<Return> {
+        let (any, event) = event.replace(());
+        match any {
+            AnyEvent::Sqs(sqs) => Self::sqs(shared, event.replace(sqs).1).await,
+            AnyEvent::Sns(sns) => Self::sns(shared, event.replace(sns).1).await,
+            AnyEvent::S3(s3) => Self::s3(shared, event.replace(s3).1).await,
+            AnyEvent::EventBridge(event_bridge) => {
+                Self::event_bridge(shared, event.replace(event_bridge).1).await
+            }
+            AnyEvent::Direct(direct) => Self::direct(shared, event.replace(direct).1).await,
+        }
+    }
+}
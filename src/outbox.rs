@@ -0,0 +1,108 @@
+/// Future of a side effect which is stored in the [`Outbox`]
+type SideEffect<'a> = futures::future::BoxFuture<'a, anyhow::Result<()>>;
+
+/// Buffer for side effects of an invocation (e.g. SNS publishes, EventBridge
+/// entries or SQS messages).
+///
+/// Side effects are only executed after [`crate::Runner::run`] returned
+/// successfully, so failed invocations do not emit any side effects. They
+/// are executed in the order they were added. If a side effect fails, the
+/// remaining ones are not executed and the invocation fails.
+///
+/// Side effects count towards the time budget of the invocation. If they did not
+/// complete before the timeout handler fires (see [`crate::Builder::timeout_margin`]),
+/// the running one is cancelled and the invocation fails with a timeout. In that case
+/// all side effects before it were already executed, the cancelled one may have been
+/// partially executed and the remaining ones were not executed.
+///
+/// ```no_run
+/// # async fn publish(message: String) -> anyhow::Result<()> { Ok(()) }
+/// struct Runner;
+///
+/// #[async_trait::async_trait]
+/// impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+///     async fn run(shared: &'a (), event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+///         event.outbox.push("publish notification", async {
+///             publish("done".into()).await
+///         });
+///         Ok(())
+///     }
+///
+///     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Outbox<'a> {
+    effects: std::sync::Arc<std::sync::Mutex<Vec<(String, SideEffect<'a>)>>>,
+}
+
+impl std::fmt::Debug for Outbox<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Outbox")
+            .field("effects", &self.len())
+            .finish()
+    }
+}
+
+impl<'a> Outbox<'a> {
+    /// Adds a side effect, which is executed after the invocation succeeded
+    pub fn push<F>(&self, name: impl Into<String>, effect: F)
+    where
+        F: std::future::Future<Output = anyhow::Result<()>> + Send + 'a,
+    {
+        self.lock().push((name.into(), Box::pin(effect)));
+    }
+
+    /// Amount of side effects currently buffered
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether there are no side effects buffered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Executes all buffered side effects in order. Fails with
+    /// [`tokio::time::error::Elapsed`] if they did not complete before `deadline`
+    pub(crate) async fn flush(&self, deadline: Option<tokio::time::Instant>) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let effects = std::mem::take(&mut *self.lock());
+        let total = effects.len();
+        for (executed, (name, effect)) in effects.into_iter().enumerate() {
+            log::info!("Executing side effect: {}", name);
+            let res = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, effect)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Side effect did not complete before the timeout: {}. {} side effects were executed before, {} were not executed",
+                            name,
+                            executed,
+                            total - executed - 1
+                        )
+                    })?,
+                None => effect.await,
+            };
+            res.with_context(|| format!("Side effect failed: {}", name))?;
+        }
+        Ok(())
+    }
+
+    /// Drops all buffered side effects without executing them
+    pub(crate) fn discard(&self) {
+        let effects = std::mem::take(&mut *self.lock());
+        if !effects.is_empty() {
+            log::info!("Discarding {} side effects", effects.len());
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(String, SideEffect<'a>)>> {
+        self.effects
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
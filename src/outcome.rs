@@ -0,0 +1,97 @@
+/// Return type which distinguishes business failures from errors.
+///
+/// Both variants are returned to lambda as successful response. A
+/// [`Outcome::Success`] is serialized as `{"success": true, "result": ...}`
+/// and a [`Outcome::Failure`] as `{"success": false, "failure": ...}`. This
+/// allows Step Functions `Choice` states or API Gateway integrations to react
+/// to expected failures, e.g. a declined payment, while errors returned as
+/// [`anyhow::Error`] still fail the invocation and trigger retries.
+///
+/// ```no_run
+/// #[derive(serde::Serialize)]
+/// struct Declined {
+///     reason: String,
+/// }
+///
+/// struct Runner;
+///
+/// #[async_trait::async_trait]
+/// impl<'a> lambda_runtime_types::Runner<'a, (), u64, lambda_runtime_types::Outcome<String, Declined>> for Runner {
+///     async fn run(
+///         _shared: &'a (),
+///         event: lambda_runtime_types::LambdaEvent<'a, u64>,
+///     ) -> anyhow::Result<lambda_runtime_types::Outcome<String, Declined>> {
+///         if event.event > 1000 {
+///             return Ok(lambda_runtime_types::Outcome::Failure(Declined {
+///                 reason: "Amount exceeds limit".into(),
+///             }));
+///         }
+///         Ok(lambda_runtime_types::Outcome::Success("Payment accepted".into()))
+///     }
+///
+///     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// pub fn main() -> anyhow::Result<()> {
+///     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome<T, F> {
+    /// The invocation succeeded
+    Success(T),
+    /// The invocation failed for a business reason
+    Failure(F),
+}
+
+impl<T, F> Outcome<T, F> {
+    /// Whether the outcome is a [`Outcome::Success`]
+    pub const fn is_success(&self) -> bool {
+        matches!(self, Self::Success(_))
+    }
+
+    /// Converts the outcome into a [`Result`]
+    pub fn into_result(self) -> Result<T, F> {
+        match self {
+            Self::Success(res) => Ok(res),
+            Self::Failure(failure) => Err(failure),
+        }
+    }
+}
+
+impl<T, F> From<Result<T, F>> for Outcome<T, F> {
+    fn from(res: Result<T, F>) -> Self {
+        match res {
+            Ok(res) => Self::Success(res),
+            Err(failure) => Self::Failure(failure),
+        }
+    }
+}
+
+impl<T, F> serde::Serialize for Outcome<T, F>
+where
+    T: serde::Serialize,
+    F: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Outcome", 2)?;
+        match self {
+            Self::Success(res) => {
+                state.serialize_field("success", &true)?;
+                state.serialize_field("result", res)?;
+            }
+            Self::Failure(failure) => {
+                state.serialize_field("success", &false)?;
+                state.serialize_field("failure", failure)?;
+            }
+        }
+        state.end()
+    }
+}
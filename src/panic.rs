@@ -0,0 +1,55 @@
+/// Request id of the invocation which is currently running.
+/// Used to correlate panics with the invocation they happened in.
+static REQUEST_ID: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+/// Stores the request id of the currently running invocation,
+/// or removes it if `None` is given
+pub fn set_request_id(request_id: Option<&str>) {
+    if let Ok(mut current) = REQUEST_ID.lock() {
+        *current = request_id.map(ToOwned::to_owned);
+    }
+}
+
+/// Returns the message of a panic payload
+pub fn message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>")
+}
+
+/// Installs a panic hook which logs panics through the configured logger.
+///
+/// Instead of printing panics to stderr, the record is logged as a
+/// single json object on `error` level and contains the panic message,
+/// its location, a backtrace and the request id of the invocation
+/// which was running when the panic happened.
+///
+/// Should be called in [`crate::Runner::setup`] after the logger was
+/// initialized. If no logger is enabled for the `error` level, the
+/// previously installed panic hook is used instead.
+pub fn install_panic_hook() {
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !log::log_enabled!(log::Level::Error) {
+            previous_hook(info);
+            return;
+        }
+        let message = message(info.payload());
+        // `try_lock` as the panic may have happened while the lock was held
+        let request_id = REQUEST_ID
+            .try_lock()
+            .ok()
+            .and_then(|request_id| request_id.clone());
+        let record = serde_json::json!({
+            "type": "panic",
+            "request_id": request_id,
+            "message": message,
+            "location": info.location().map(ToString::to_string),
+            "thread": std::thread::current().name(),
+            "backtrace": std::backtrace::Backtrace::force_capture().to_string(),
+        });
+        log::error!("{}", record);
+    }));
+}
@@ -0,0 +1,69 @@
+use serde_json::value::RawValue;
+
+/// Slot for the partial result of an invocation.
+///
+/// If the invocation is failed by the timeout handler, the last partial result
+/// is returned as response instead of the timeout error, so callers learn how far
+/// processing got before the deadline. The partial result has to be of the same
+/// shape as the response of the runner.
+///
+/// ```no_run
+/// # async fn process(item: &str) -> anyhow::Result<()> { Ok(()) }
+/// #[derive(serde::Serialize)]
+/// struct Progress {
+///     processed: usize,
+///     done: bool,
+/// }
+///
+/// struct Runner;
+///
+/// #[async_trait::async_trait]
+/// impl<'a> lambda_runtime_types::Runner<'a, (), Vec<String>, Progress> for Runner {
+///     async fn run(shared: &'a (), event: lambda_runtime_types::LambdaEvent<'a, Vec<String>>) -> anyhow::Result<Progress> {
+///         for (processed, item) in event.event.iter().enumerate() {
+///             event.partial.set(&Progress { processed, done: false })?;
+///             process(item).await?;
+///         }
+///         Ok(Progress { processed: event.event.len(), done: true })
+///     }
+///
+///     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Partial {
+    result: std::sync::Arc<std::sync::Mutex<Option<Box<RawValue>>>>,
+}
+
+impl Partial {
+    /// Replaces the partial result. It is serialized immediately,
+    /// so it can be returned even if the runner blocks afterwards
+    pub fn set<T>(&self, result: &T) -> anyhow::Result<()>
+    where
+        T: serde::Serialize,
+    {
+        use anyhow::Context;
+
+        let result = serde_json::value::to_raw_value(result)
+            .context("Unable to serialize partial result")?;
+        *self.lock() = Some(result);
+        Ok(())
+    }
+
+    /// Removes the partial result
+    pub fn clear(&self) {
+        *self.lock() = None;
+    }
+
+    pub(crate) fn take(&self) -> Option<Box<RawValue>> {
+        self.lock().take()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Box<RawValue>>> {
+        self.result
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,134 @@
+//! Provides a token-bucket rate limiter which can be stored in `Shared`.
+//!
+//! Tokens are refilled based on wall-clock time. As lambda environments may be
+//! frozen between invocations, the bucket is refilled with the time passed during
+//! the freeze when the next invocation arrives, but never above its capacity.
+//! If the clock moves backwards, no tokens are refilled until it caught up again.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! struct Shared {
+//!     limiter: lambda_runtime_types::rate_limit::RateLimiter,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), ()> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+//!         for _ in 0..100 {
+//!             // Waits until a token is available
+//!             shared.limiter.acquire(1).await?;
+//!             // Call rate limited api
+//!         }
+//!         Ok(())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             // Allow bursts of 10 requests and 5 requests per second afterwards
+//!             limiter: lambda_runtime_types::rate_limit::RateLimiter::new(10, 5.0),
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use std::time::{Duration, SystemTime};
+
+/// Token-bucket rate limiter
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_second: f64,
+    state: std::sync::Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    last_refill: SystemTime,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter which holds up to `capacity` tokens and refills
+    /// `refill_per_second` tokens every second. The bucket starts full.
+    pub fn new(capacity: u32, refill_per_second: f64) -> Self {
+        let capacity = f64::from(capacity);
+        Self {
+            capacity,
+            refill_per_second,
+            state: std::sync::Mutex::new(State {
+                tokens: capacity,
+                last_refill: SystemTime::now(),
+            }),
+        }
+    }
+
+    /// Returns the amount of tokens currently available
+    pub fn available(&self) -> f64 {
+        let mut state = self.lock();
+        self.refill(&mut state);
+        state.tokens
+    }
+
+    /// Takes `tokens` from the bucket if enough are available
+    pub fn try_acquire(&self, tokens: u32) -> bool {
+        self.take(tokens).is_ok()
+    }
+
+    /// Takes `tokens` from the bucket, waiting until enough are available.
+    /// Fails if more tokens are requested than the bucket can hold.
+    pub async fn acquire(&self, tokens: u32) -> anyhow::Result<()> {
+        if f64::from(tokens) > self.capacity {
+            anyhow::bail!(
+                "Unable to acquire {} tokens from rate limiter with capacity of {}",
+                tokens,
+                self.capacity
+            );
+        }
+        loop {
+            match self.take(tokens) {
+                Ok(()) => return Ok(()),
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    /// Takes `tokens` or returns the time to wait until enough are available
+    fn take(&self, tokens: u32) -> Result<(), Duration> {
+        let tokens = f64::from(tokens);
+        let mut state = self.lock();
+        self.refill(&mut state);
+        if state.tokens >= tokens {
+            state.tokens -= tokens;
+            return Ok(());
+        }
+        let missing = tokens - state.tokens;
+        drop(state);
+        Err(Duration::from_secs_f64(
+            (missing / self.refill_per_second).clamp(0.001, 60.0),
+        ))
+    }
+
+    fn refill(&self, state: &mut State) {
+        let now = SystemTime::now();
+        if let Ok(elapsed) = now.duration_since(state.last_refill) {
+            state.tokens = elapsed
+                .as_secs_f64()
+                .mul_add(self.refill_per_second, state.tokens)
+                .min(self.capacity);
+            state.last_refill = now;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,93 @@
+/// Validated AWS region, e.g. `eu-central-1`
+///
+/// The region of the lambda is validated at startup, so runners can
+/// rely on a well-formed region. It dereferences to `str`, which is
+/// how it is passed to runners.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Region(String);
+
+impl Region {
+    /// Creates a new region, failing if `region` is not a valid region name
+    pub fn new(region: impl Into<String>) -> anyhow::Result<Self> {
+        let region = region.into();
+        let parts: Vec<_> = region.split('-').collect();
+        let valid = match parts.as_slice() {
+            [names @ .., number] if names.len() >= 2 => {
+                names
+                    .iter()
+                    .all(|name| !name.is_empty() && name.bytes().all(|b| b.is_ascii_lowercase()))
+                    && !number.is_empty()
+                    && number.bytes().all(|b| b.is_ascii_digit())
+            }
+            _ => false,
+        };
+        if !valid {
+            anyhow::bail!("Invalid AWS region: {:?}", region);
+        }
+        Ok(Self(region))
+    }
+
+    /// Reads the region from the `AWS_REGION` env variable.
+    /// Returns `None` if the variable is not set
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        use anyhow::Context;
+
+        match std::env::var("AWS_REGION") {
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            res => {
+                let region = res.context("Invalid AWS_REGION env variable")?;
+                Self::new(region).map(Some)
+            }
+        }
+    }
+
+    /// Name of the region
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Partition of the region, which is used in ARNs
+    pub fn partition(&self) -> &'static str {
+        if self.0.starts_with("cn-") {
+            "aws-cn"
+        } else if self.0.starts_with("us-gov-") {
+            "aws-us-gov"
+        } else {
+            "aws"
+        }
+    }
+}
+
+impl std::ops::Deref for Region {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Region {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Region> for String {
+    fn from(region: Region) -> Self {
+        region.0
+    }
+}
+
+impl std::str::FromStr for Region {
+    type Err = anyhow::Error;
+
+    fn from_str(region: &str) -> anyhow::Result<Self> {
+        Self::new(region)
+    }
+}
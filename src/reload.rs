@@ -0,0 +1,168 @@
+//! Provides configuration which is reloaded between invocations when it changed.
+//!
+//! A [`Source`] (e.g. SSM parameters or AppConfig) is loaded once during setup and
+//! the resulting snapshot is kept in `Shared`. At the start of an invocation,
+//! [`Reloadable::get`] compares the version of the source (e.g. an etag or version
+//! number) with the snapshot and reloads it if it changed. This allows changing the
+//! configuration (and everything built from it) without a new deployment or cold
+//! start. If checking or reloading fails, the previous snapshot is kept.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # async fn fetch_parameter() -> anyhow::Result<(String, i64)> { Ok((String::new(), 0)) }
+//! # async fn fetch_parameter_version() -> anyhow::Result<i64> { Ok(0) }
+//! use lambda_runtime_types::reload::{Reloadable, Source};
+//!
+//! struct Config {
+//!     endpoint: String,
+//! }
+//!
+//! struct Parameter;
+//!
+//! #[async_trait::async_trait]
+//! impl Source for Parameter {
+//!     type Value = Config;
+//!
+//!     async fn version(&self) -> anyhow::Result<String> {
+//!         Ok(fetch_parameter_version().await?.to_string())
+//!     }
+//!
+//!     async fn load(&self) -> anyhow::Result<(Config, String)> {
+//!         let (endpoint, version) = fetch_parameter().await?;
+//!         Ok((Config { endpoint }, version.to_string()))
+//!     }
+//! }
+//!
+//! struct Shared {
+//!     config: Reloadable<Parameter>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), ()> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+//!         let config = shared.config.get().await;
+//!         log::info!("Using endpoint {}", config.endpoint);
+//!         Ok(())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             config: Reloadable::new(Parameter).await?,
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Source of a [`Reloadable`] value
+#[async_trait::async_trait]
+pub trait Source: Send + Sync {
+    /// Value loaded from the source. May contain everything built
+    /// from the configuration, like clients
+    type Value: Send + Sync;
+
+    /// Returns the current version of the source (e.g. an etag or
+    /// version number). Should be cheaper than loading the value
+    async fn version(&self) -> anyhow::Result<String>;
+
+    /// Loads the value together with its version
+    async fn load(&self) -> anyhow::Result<(Self::Value, String)>;
+}
+
+/// Snapshot of a [`Source`] which is reloaded when its version changed
+pub struct Reloadable<S: Source> {
+    source: S,
+    check_interval: Duration,
+    snapshot: tokio::sync::Mutex<Snapshot<S::Value>>,
+}
+
+struct Snapshot<V> {
+    value: Arc<V>,
+    version: String,
+    checked_at: SystemTime,
+}
+
+impl<S: Source> std::fmt::Debug for Reloadable<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reloadable")
+            .field("check_interval", &self.check_interval)
+            .finish()
+    }
+}
+
+impl<S: Source> Reloadable<S> {
+    /// Loads the initial snapshot of `source`
+    pub async fn new(source: S) -> anyhow::Result<Self> {
+        let (value, version) = source.load().await?;
+        log::info!("Loaded configuration with version: {}", version);
+        Ok(Self {
+            source,
+            check_interval: Duration::ZERO,
+            snapshot: tokio::sync::Mutex::new(Snapshot {
+                value: Arc::new(value),
+                version,
+                checked_at: SystemTime::now(),
+            }),
+        })
+    }
+
+    /// Sets the minimum time between two version checks. Defaults to
+    /// zero, which checks the version on every access.
+    pub const fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    /// Returns the current snapshot, reloading it if the version of the
+    /// source changed
+    pub async fn get(&self) -> Arc<S::Value> {
+        let mut snapshot = self.snapshot.lock().await;
+        let check = SystemTime::now()
+            .duration_since(snapshot.checked_at)
+            .map_or(true, |elapsed| elapsed >= self.check_interval);
+        if check {
+            snapshot.checked_at = SystemTime::now();
+            if let Err(err) = self.reload(&mut snapshot).await {
+                log::warn!("Unable to reload configuration: {:?}", err);
+            }
+        }
+        let value = Arc::clone(&snapshot.value);
+        drop(snapshot);
+        value
+    }
+
+    /// Returns the source of the snapshots
+    pub const fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Returns the current snapshot without checking the source
+    pub async fn snapshot(&self) -> Arc<S::Value> {
+        Arc::clone(&self.snapshot.lock().await.value)
+    }
+
+    async fn reload(&self, snapshot: &mut Snapshot<S::Value>) -> anyhow::Result<()> {
+        let version = self.source.version().await?;
+        if version == snapshot.version {
+            return Ok(());
+        }
+        let (value, version) = self.source.load().await?;
+        log::info!(
+            "Reloaded configuration. Version changed from {} to {}",
+            snapshot.version,
+            version
+        );
+        snapshot.value = Arc::new(value);
+        snapshot.version = version;
+        Ok(())
+    }
+}
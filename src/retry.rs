@@ -0,0 +1,310 @@
+//! Provides a retry utility with jittered exponential backoff.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # async fn call_downstream() -> anyhow::Result<()> { Ok(()) }
+//! # async fn example(ctx: lambda_runtime_types::Context) -> anyhow::Result<()> {
+//! use lambda_runtime_types::retry;
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let policy = retry::Policy::exponential(5)
+//!     .with_base_delay(Duration::from_millis(50))
+//!     .with_deadline(UNIX_EPOCH + Duration::from_millis(ctx.deadline));
+//! retry::retry_if(
+//!     &policy,
+//!     |err: &anyhow::Error| err.to_string().contains("Throttling"),
+//!     || call_downstream(),
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To retry whole invocations, the runner can be wrapped with [`Retry`]:
+//!
+//! ```no_run
+//! use lambda_runtime_types::{retry::Retry, InstanceRunner, StaticRunner};
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, (), String, String> for Runner {
+//!     async fn run(
+//!         _shared: &'a (),
+//!         event: lambda_runtime_types::LambdaEvent<'a, String>,
+//!     ) -> anyhow::Result<String> {
+//!         Ok(event.event)
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! pub async fn main() -> anyhow::Result<()> {
+//!     let retry = Retry::exponential(3).retry_if(|err| err.to_string().contains("Throttling"));
+//!     let runner = StaticRunner::<Runner>::default().layer(retry);
+//!     lambda_runtime_types::exec_instance(runner, ()).await
+//! }
+//! ```
+
+use crate::middleware::{Middleware, Next};
+use crate::LambdaEvent;
+use std::time::{Duration, SystemTime};
+
+/// Describes how often and with which delay an operation is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    deadline: Option<SystemTime>,
+}
+
+impl Policy {
+    /// Creates a policy which retries up to `max_retries` times. The delay
+    /// starts at 100 ms, is doubled on every retry and capped at 10 seconds.
+    pub const fn exponential(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            deadline: None,
+        }
+    }
+
+    /// Creates a policy which never retries
+    pub const fn none() -> Self {
+        Self::exponential(0)
+    }
+
+    /// Sets the delay before the first retry
+    pub const fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the maximum delay between two retries
+    pub const fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Disables the random jitter which is added to each delay
+    pub const fn without_jitter(mut self) -> Self {
+        self.jitter = false;
+        self
+    }
+
+    /// Sets a deadline after which no retry is started anymore. A retry is
+    /// also skipped if its delay would end after the deadline.
+    pub const fn with_deadline(mut self, deadline: SystemTime) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Maximum amount of retries
+    pub const fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Returns the delay before the given retry (starting at 1) or `None`
+    /// if no further retry should be done.
+    pub fn delay(&self, retry: u32) -> Option<Duration> {
+        if retry == 0 || retry > self.max_retries {
+            return None;
+        }
+        let factor = 2u32.saturating_pow(retry - 1);
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        let delay = if self.jitter {
+            // Equal jitter: keep half of the delay and randomize the other half
+            delay / 2 + delay.mul_f64(random_fraction() / 2.0)
+        } else {
+            delay
+        };
+        let remaining = self.deadline.map(|deadline| {
+            deadline
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO)
+        });
+        remaining
+            .is_none_or(|remaining| delay < remaining)
+            .then_some(delay)
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self::exponential(3)
+    }
+}
+
+/// Runs `operation` and retries it on every error as defined by `policy`
+pub async fn retry<T, E, Op, Fut>(policy: &Policy, operation: Op) -> Result<T, E>
+where
+    Op: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+{
+    retry_if(policy, |_: &E| true, operation).await
+}
+
+/// Runs `operation` and retries it as defined by `policy` as long as
+/// `predicate` returns true for the returned error.
+pub async fn retry_if<T, E, Op, Fut, P>(
+    policy: &Policy,
+    mut predicate: P,
+    mut operation: Op,
+) -> Result<T, E>
+where
+    Op: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+    P: FnMut(&E) -> bool,
+{
+    let mut retry = 0;
+    loop {
+        let err = match operation().await {
+            Ok(res) => return Ok(res),
+            Err(err) => err,
+        };
+        retry += 1;
+        let delay = match policy.delay(retry) {
+            Some(delay) if predicate(&err) => delay,
+            _ => return Err(err),
+        };
+        log::info!("Retrying operation in {:?} (retry {})", delay, retry);
+        crate::summary::record_retry();
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Middleware which runs the wrapped runner again within the same invocation
+/// if it fails with a retryable error.
+///
+/// A retry is only started if its delay and the duration of the failed attempt
+/// fit into the remaining time of the invocation. Side effects pushed to the
+/// outbox by failed attempts are discarded. Requires `Event` to be [`Clone`].
+pub struct Retry {
+    policy: Policy,
+    predicate: Box<dyn Fn(&anyhow::Error) -> bool + Send + Sync>,
+}
+
+impl std::fmt::Debug for Retry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Retry")
+            .field("policy", &self.policy)
+            .field("predicate", &"[...]")
+            .finish()
+    }
+}
+
+impl Retry {
+    /// Retries failed invocations up to `max_retries` times.
+    /// See [`Policy::exponential`]
+    pub fn exponential(max_retries: u32) -> Self {
+        Self::new(Policy::exponential(max_retries))
+    }
+
+    /// Retries failed invocations as defined by `policy`. The deadline
+    /// of the policy is replaced by the deadline of the invocation.
+    pub fn new(policy: Policy) -> Self {
+        Self {
+            policy,
+            predicate: Box::new(|_| true),
+        }
+    }
+
+    /// Only retries errors for which `predicate` returns true.
+    /// Defaults to retrying all errors
+    #[must_use]
+    pub fn retry_if(
+        mut self,
+        predicate: impl Fn(&anyhow::Error) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicate = Box::new(predicate);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<Shared, Event, Return> Middleware<Shared, Event, Return> for Retry
+where
+    Shared: Send + Sync,
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Clone + Send + 'static,
+    Return: serde::Serialize,
+{
+    async fn call<'a>(
+        &'a self,
+        shared: &'a Shared,
+        event: LambdaEvent<'a, Event>,
+        next: Next<'a, Shared, Event, Return>,
+    ) -> anyhow::Result<Return> {
+        // Tests and manual invocations do not have a deadline
+        let deadline = (event.ctx.deadline != 0).then(|| event.deadline());
+        let mut retry = 0;
+        loop {
+            let started = std::time::Instant::now();
+            let attempt = LambdaEvent {
+                event: event.event.clone(),
+                region: event.region,
+                ctx: event.ctx.clone(),
+                outbox: event.outbox.clone(),
+                spawner: event.spawner.clone(),
+                partial: event.partial.clone(),
+                scoped: event.scoped.clone(),
+                is_cold_start: event.is_cold_start,
+                env: event.env,
+                stats: event.stats.clone(),
+                timeout_margin: event.timeout_margin,
+                clock: event.clock,
+            };
+            let err = match next.run(shared, attempt).await {
+                Ok(res) => return Ok(res),
+                Err(err) => err,
+            };
+            retry += 1;
+            let policy = deadline.map_or(self.policy, |deadline| {
+                self.policy.with_deadline(
+                    deadline
+                        .checked_sub(started.elapsed())
+                        .unwrap_or(SystemTime::UNIX_EPOCH),
+                )
+            });
+            let delay = match policy.delay(retry) {
+                Some(delay) if (self.predicate)(&err) => delay,
+                _ => return Err(err),
+            };
+            log::warn!(
+                "Invocation failed. Retrying in {:?} (retry {}): {:?}",
+                delay,
+                retry,
+                err
+            );
+            event.outbox.discard();
+            crate::summary::record_retry();
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Returns a random value between 0 and 1. Uses the randomly seeded std
+/// hasher to not require an additional dependency.
+fn random_fraction() -> f64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -1,15 +1,25 @@
 #[derive(Clone)]
 pub struct SmcClient {
     client: aws_sdk_secretsmanager::Client,
+    #[cfg(feature = "_signed_requests")]
+    config: aws_config::SdkConfig,
 }
 
 impl SmcClient {
-    pub async fn new() -> Self {
-        let config = aws_config::load_from_env().await;
+    pub async fn new(region: &str) -> Self {
+        let config = aws_config::from_env()
+            .region(aws_sdk_secretsmanager::Region::new(region.to_owned()))
+            .load()
+            .await;
         let client = aws_sdk_secretsmanager::Client::new(&config);
-        Self { client }
+        Self {
+            client,
+            #[cfg(feature = "_signed_requests")]
+            config,
+        }
     }
 
+    #[cfg(not(feature = "rotate_local_password"))]
     pub async fn generate_new_password(
         &self,
         puncutation: bool,
@@ -53,15 +63,18 @@ impl SmcClient {
                 secret_id
             )
         })?;
+        let binary = secret_value.secret_string.is_none() && secret_value.secret_binary.is_some();
         let inner = match (secret_value.secret_string, secret_value.secret_binary) {
-            (Some(string), _) => serde_json::from_str(&string),
-            (_, Some(bytes)) => serde_json::from_slice(bytes.as_ref()),
+            (Some(string), _) => crate::rotate::SecretContainer::from_secret_value(string.as_bytes()),
+            (_, Some(bytes)) => crate::rotate::SecretContainer::from_secret_value(bytes.as_ref()),
             _ => anyhow::bail!("Neither secret_string nor secret_binary is set for id: {}", secret_id),
         }
         .with_context(|| format!("Unable to parse secret value. Value does not confirm to required structure. Id: {}", secret_id))?;
         Ok(crate::rotate::smc::Secret {
             arn,
             version_id,
+            version_stages: secret_value.version_stages.unwrap_or_default(),
+            binary,
             inner,
         })
     }
@@ -70,22 +83,176 @@ impl SmcClient {
         &self,
         secret_id: &str,
         request_token: Option<&str>,
-        secret_str: &str,
+        secret_value: &[u8],
+        binary: bool,
+        version_stages: &[String],
     ) -> anyhow::Result<()> {
         use anyhow::Context;
 
-        self.client
+        let request = self
+            .client
             .put_secret_value()
             .set_client_request_token(request_token.map(|v| v.to_string()))
             .secret_id(secret_id)
-            .secret_string(secret_str)
-            .version_stages("AWSPENDING")
+            .set_version_stages(Some(version_stages.to_vec()));
+        let request = if binary {
+            request.secret_binary(aws_sdk_secretsmanager::types::Blob::new(secret_value))
+        } else {
+            request.secret_string(
+                std::str::from_utf8(secret_value).context("SecretString must be valid utf-8")?,
+            )
+        };
+        request.send().await.with_context(|| {
+            format!(
+                "Unable to push new SecretValue to {} for id: {}",
+                version_stages.join(", "),
+                secret_id
+            )
+        })?;
+        Ok(())
+    }
+
+    pub async fn describe_secret(
+        &self,
+        secret_id: &str,
+    ) -> anyhow::Result<crate::rotate::smc::SecretDescription> {
+        use anyhow::Context;
+
+        let secret = self
+            .client
+            .describe_secret()
+            .secret_id(secret_id)
+            .send()
+            .await
+            .with_context(|| format!("Unable to describe secret with id: {}", secret_id))?;
+        Ok(crate::rotate::smc::SecretDescription {
+            name: secret.name.with_context(|| {
+                format!("Name is unavailable for secret with id: {}", secret_id)
+            })?,
+            rotation_enabled: secret.rotation_enabled.unwrap_or_default(),
+            rotation_lambda_arn: secret.rotation_lambda_arn,
+            version_ids_to_stages: secret.version_ids_to_stages.unwrap_or_default(),
+            replicas: secret
+                .replication_status
+                .unwrap_or_default()
+                .into_iter()
+                .map(|replica| crate::rotate::smc::ReplicaStatus {
+                    region: replica.region.unwrap_or_default(),
+                    status: replica
+                        .status
+                        .map(|status| status.as_str().to_owned())
+                        .unwrap_or_default(),
+                    message: replica.status_message,
+                })
+                .collect(),
+            tags: secret
+                .tags
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|tag| Some((tag.key?, tag.value.unwrap_or_default())))
+                .collect(),
+            kms_key_id: secret.kms_key_id,
+        })
+    }
+
+    pub async fn tag_resource(&self, secret_id: &str, tags: &[(&str, &str)]) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.client
+            .tag_resource()
+            .secret_id(secret_id)
+            .set_tags(Some(
+                tags.iter()
+                    .map(|(key, value)| {
+                        aws_sdk_secretsmanager::model::Tag::builder()
+                            .key(*key)
+                            .value(*value)
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .send()
+            .await
+            .with_context(|| format!("Unable to tag secret with id: {}", secret_id))?;
+        Ok(())
+    }
+
+    pub async fn untag_resource(&self, secret_id: &str, keys: &[&str]) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.client
+            .untag_resource()
+            .secret_id(secret_id)
+            .set_tag_keys(Some(keys.iter().map(|key| (*key).to_owned()).collect()))
+            .send()
+            .await
+            .with_context(|| format!("Unable to untag secret with id: {}", secret_id))?;
+        Ok(())
+    }
+
+    pub async fn update_kms_key(&self, secret_id: &str, kms_key_id: &str) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.client
+            .update_secret()
+            .secret_id(secret_id)
+            .kms_key_id(kms_key_id)
+            .send()
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to change KMS key to {} for secret with id: {}",
+                    kms_key_id, secret_id
+                )
+            })?;
+        Ok(())
+    }
+
+    pub async fn update_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+        remove_from_version_id: Option<String>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.client
+            .update_secret_version_stage()
+            .move_to_version_id(version_id)
+            .set_remove_from_version_id(remove_from_version_id)
+            .secret_id(secret_id)
+            .version_stage(stage)
+            .send()
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to move stage {} to version {} for id: {}",
+                    stage, version_id, secret_id
+                )
+            })?;
+        Ok(())
+    }
+
+    pub async fn remove_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.client
+            .update_secret_version_stage()
+            .remove_from_version_id(version_id)
+            .secret_id(secret_id)
+            .version_stage(stage)
             .send()
             .await
             .with_context(|| {
                 format!(
-                    "Unable to push new SecretValue to AWSPENDING for id: {}",
-                    secret_id
+                    "Unable to remove stage {} from version {} for id: {}",
+                    stage, version_id, secret_id
                 )
             })?;
         Ok(())
@@ -96,6 +263,7 @@ impl SmcClient {
         secret_arn: String,
         secret_current_version_id: String,
         secret_pending_version_id: String,
+        stage: &str,
     ) -> anyhow::Result<()> {
         use anyhow::Context;
 
@@ -104,15 +272,35 @@ impl SmcClient {
             .move_to_version_id(secret_pending_version_id)
             .remove_from_version_id(secret_current_version_id)
             .secret_id(&secret_arn)
-            .version_stage("AWSCURRENT")
+            .version_stage(stage)
             .send()
             .await
             .with_context(|| {
                 format!(
-                    "Unable to push new SecretValue to AWSPENDING for arn: {}",
-                    secret_arn
+                    "Unable to move stage {} to pending SecretValue for arn: {}",
+                    stage, secret_arn
                 )
             })?;
         Ok(())
     }
+
+    /// Credentials of the config, which sign requests of [`super::signed::SignedClient`]
+    #[cfg(feature = "_signed_requests")]
+    pub async fn credentials(&self) -> anyhow::Result<super::signed::Credentials> {
+        use anyhow::Context;
+        use aws_types::credentials::ProvideCredentials;
+
+        let credentials = self
+            .config
+            .credentials_provider()
+            .context("No credentials provider available")?
+            .provide_credentials()
+            .await
+            .context("Unable to load credentials")?;
+        Ok(super::signed::Credentials {
+            access_key_id: credentials.access_key_id().to_owned(),
+            secret_access_key: credentials.secret_access_key().to_owned(),
+            session_token: credentials.session_token().map(ToOwned::to_owned),
+        })
+    }
 }
@@ -0,0 +1,285 @@
+//! Rotation of secrets which contain multiple credentials, e.g. of an application
+//! user and a readonly user of the same database.
+//!
+//! A [`CredentialRotator`] rotates a single credential, [`CompositeRotation`] runs it
+//! for every credential of the secret. The secret stores the credentials by name in
+//! the field `credentials`:
+//!
+//! ```json
+//! { "credentials": { "app": { ... }, "readonly": { ... } } }
+//! ```
+//!
+//! All credentials are created at once and stored in the same pending version. The
+//! other steps continue with the remaining credentials, if one credential fails, and
+//! fail with an error which names every failed credential. As `set` skips credentials
+//! for which [`CredentialRotator::test`] succeeds already, a re-delivered `set` only
+//! retries the credentials which failed.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::rotate::composite::{CompositeRotation, CredentialRotator};
+//! use lambda_runtime_types::rotate::{RotationContext, Smc};
+//!
+//! #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+//! struct User {
+//!     user: String,
+//!     password: String,
+//! }
+//!
+//! struct Database;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> CredentialRotator<'a, (), User> for Database {
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//!
+//!     async fn create(
+//!         _shared: &'a (),
+//!         _name: &str,
+//!         mut credential_cur: User,
+//!         smc: &Smc,
+//!         _ctx: &RotationContext<'_>,
+//!     ) -> anyhow::Result<User> {
+//!         credential_cur.password = smc.generate_new_password(false, None).await?;
+//!         Ok(credential_cur)
+//!     }
+//!
+//!     async fn set(
+//!         _shared: &'a (),
+//!         _name: &str,
+//!         _credential_cur: User,
+//!         _credential_new: User,
+//!         _smc: &Smc,
+//!         _ctx: &RotationContext<'_>,
+//!     ) -> anyhow::Result<()> {
+//!         // Change the password of the user
+//!         Ok(())
+//!     }
+//!
+//!     async fn test(
+//!         _shared: &'a (),
+//!         _name: &str,
+//!         _credential_new: User,
+//!         _smc: &Smc,
+//!         _ctx: &RotationContext<'_>,
+//!     ) -> anyhow::Result<()> {
+//!         // Connect with the new password
+//!         Ok(())
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, CompositeRotation<Database>, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc};
+use std::collections::BTreeMap;
+
+/// Secret containing multiple credentials by name
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CompositeSecret<C> {
+    /// Credentials by name
+    pub credentials: BTreeMap<String, C>,
+}
+
+/// Rotates a single credential of a [`CompositeSecret`]. Like the steps of
+/// [`super::RotateRunner`], but called once for every credential with its name
+#[async_trait::async_trait]
+pub trait CredentialRotator<'a, Shared, C>: Send + Sync + 'static
+where
+    Shared: Send + Sync + 'a,
+    C: 'static + Send,
+{
+    /// See documentation of [`super::RotateRunner::setup`]
+    async fn setup(region: &'a str) -> anyhow::Result<Shared>;
+
+    /// Create a new credential without setting it yet
+    async fn create(
+        shared: &'a Shared,
+        name: &str,
+        credential_cur: C,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<C>;
+
+    /// Set the credential in the service
+    async fn set(
+        shared: &'a Shared,
+        name: &str,
+        credential_cur: C,
+        credential_new: C,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()>;
+
+    /// Test whether a connection with the given credential works
+    async fn test(
+        shared: &'a Shared,
+        name: &str,
+        credential_new: C,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()>;
+
+    /// Perform any work which may be necessary to complete rotation of the credential
+    async fn finish(
+        _shared: &'a Shared,
+        _name: &str,
+        _credential_cur: C,
+        _credential_new: C,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Rotates every credential of a [`CompositeSecret`] with `T`
+pub struct CompositeRotation<T>(std::marker::PhantomData<T>);
+
+impl<T> std::fmt::Debug for CompositeRotation<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompositeRotation").finish()
+    }
+}
+
+/// Combines the errors of failed credentials into one error, which names them
+fn combine(errors: Vec<(String, anyhow::Error)>) -> anyhow::Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<_> = errors.iter().map(|(name, _)| name.as_str()).collect();
+    let details: Vec<_> = errors
+        .iter()
+        .map(|(name, err)| format!("{}: {:#}", name, err))
+        .collect();
+    Err(anyhow::anyhow!(
+        "Rotation of credentials {} failed: {}",
+        names.join(", "),
+        details.join("; ")
+    ))
+}
+
+/// Returns the current credential `name`
+fn current<C: Clone>(secret_cur: &CompositeSecret<C>, name: &str) -> anyhow::Result<C> {
+    use anyhow::Context;
+
+    secret_cur
+        .credentials
+        .get(name)
+        .cloned()
+        .with_context(|| format!("Credential {} is missing in current secret", name))
+}
+
+#[async_trait::async_trait]
+impl<'a, T, Shared, C> super::RotateRunner<'a, Shared, CompositeSecret<C>> for CompositeRotation<T>
+where
+    T: CredentialRotator<'a, Shared, C>,
+    Shared: Send + Sync + 'a,
+    C: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    async fn setup(region: &'a str) -> anyhow::Result<Shared> {
+        T::setup(region).await
+    }
+
+    async fn create(
+        shared: &'a Shared,
+        mut secret_cur: SecretContainer<CompositeSecret<C>>,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<CompositeSecret<C>>> {
+        use anyhow::Context;
+
+        for (name, credential) in &mut secret_cur.credentials {
+            *credential = T::create(shared, name, credential.clone(), smc, ctx)
+                .await
+                .with_context(|| format!("Unable to create credential {}", name))?;
+        }
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        shared: &'a Shared,
+        secret_cur: SecretContainer<CompositeSecret<C>>,
+        secret_new: SecretContainer<CompositeSecret<C>>,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+        for (name, credential_new) in &secret_new.credentials {
+            if T::test(shared, name, credential_new.clone(), smc, ctx)
+                .await
+                .is_ok()
+            {
+                log::info!("Credential {} already set in remote system.", name);
+                continue;
+            }
+            let res = match current(&secret_cur, name) {
+                Ok(credential_cur) => {
+                    T::set(
+                        shared,
+                        name,
+                        credential_cur,
+                        credential_new.clone(),
+                        smc,
+                        ctx,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            };
+            if let Err(err) = res {
+                errors.push((name.clone(), err));
+            }
+        }
+        combine(errors)
+    }
+
+    async fn test(
+        shared: &'a Shared,
+        secret_new: SecretContainer<CompositeSecret<C>>,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+        for (name, credential_new) in &secret_new.credentials {
+            if let Err(err) = T::test(shared, name, credential_new.clone(), smc, ctx).await {
+                errors.push((name.clone(), err));
+            }
+        }
+        combine(errors)
+    }
+
+    async fn finish(
+        shared: &'a Shared,
+        secret_cur: SecretContainer<CompositeSecret<C>>,
+        secret_new: SecretContainer<CompositeSecret<C>>,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+        for (name, credential_new) in &secret_new.credentials {
+            let res = match current(&secret_cur, name) {
+                Ok(credential_cur) => {
+                    T::finish(
+                        shared,
+                        name,
+                        credential_cur,
+                        credential_new.clone(),
+                        smc,
+                        ctx,
+                    )
+                    .await
+                }
+                Err(err) => Err(err),
+            };
+            if let Err(err) = res {
+                errors.push((name.clone(), err));
+            }
+        }
+        combine(errors)
+    }
+}
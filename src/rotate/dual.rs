@@ -0,0 +1,116 @@
+/// Slot of a [`DualCredential`]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Slot {
+    /// First credential, stored in the field `blue`
+    Blue,
+    /// Second credential, stored in the field `green`
+    Green,
+}
+
+impl Slot {
+    /// Returns the other slot
+    #[must_use]
+    pub const fn other(self) -> Self {
+        match self {
+            Self::Blue => Self::Green,
+            Self::Green => Self::Blue,
+        }
+    }
+}
+
+/// Secret which holds two credentials, of which only the `active` one is in use.
+///
+/// Rotation refreshes the standby credential and flips the `active` pointer to it,
+/// so the previously active credential stays valid until the next rotation and
+/// clients which still use it are not interrupted. As both happen in the same new
+/// secret version, readers never see a refreshed standby without the flipped pointer.
+///
+/// The secret is stored as
+///
+/// ```json
+/// { "active": "blue", "blue": { ... }, "green": { ... } }
+/// ```
+///
+/// Use it as `Secret` of a [`super::RotateRunner`]:
+///
+/// ```no_run
+/// # #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+/// # struct Credential { user: String, password: String }
+/// use lambda_runtime_types::rotate::{DualCredential, RotationContext, SecretContainer, Smc};
+///
+/// async fn create(
+///     mut secret_cur: SecretContainer<DualCredential<Credential>>,
+///     smc: &Smc,
+///     _ctx: &RotationContext<'_>,
+/// ) -> anyhow::Result<SecretContainer<DualCredential<Credential>>> {
+///     let mut standby = secret_cur.standby().clone();
+///     standby.password = smc.generate_new_password(false, None).await?;
+///     secret_cur.rotate(standby);
+///     // `set` and `test` apply the now active credential in the service
+///     Ok(secret_cur)
+/// }
+/// ```
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct DualCredential<C> {
+    /// Slot of the credential in use
+    pub active: Slot,
+    /// First credential
+    pub blue: C,
+    /// Second credential
+    pub green: C,
+}
+
+impl<C> DualCredential<C> {
+    /// Credential of the given slot
+    pub const fn get(&self, slot: Slot) -> &C {
+        match slot {
+            Slot::Blue => &self.blue,
+            Slot::Green => &self.green,
+        }
+    }
+
+    /// Mutable credential of the given slot
+    pub const fn get_mut(&mut self, slot: Slot) -> &mut C {
+        match slot {
+            Slot::Blue => &mut self.blue,
+            Slot::Green => &mut self.green,
+        }
+    }
+
+    /// Credential in use
+    pub const fn active(&self) -> &C {
+        self.get(self.active)
+    }
+
+    /// Credential which is not in use
+    pub const fn standby(&self) -> &C {
+        self.get(self.active.other())
+    }
+
+    /// Mutable credential which is not in use
+    pub const fn standby_mut(&mut self) -> &mut C {
+        self.get_mut(self.active.other())
+    }
+
+    /// Flips the `active` pointer to the standby credential
+    pub const fn flip(&mut self) {
+        self.active = self.active.other();
+    }
+
+    /// Replaces the standby credential with `standby` and flips the `active`
+    /// pointer to it. Returns the replaced credential
+    pub const fn rotate(&mut self, standby: C) -> C {
+        let replaced = std::mem::replace(self.standby_mut(), standby);
+        self.flip();
+        replaced
+    }
+}
@@ -0,0 +1,297 @@
+//! Provides a [`super::RotateRunner`] for API keys of third-party REST APIs.
+//!
+//! Most APIs allow to create a new key, authenticated with an existing one, and to
+//! revoke keys by their id. An [`ApiKeyTemplate`] describes these requests, so only
+//! urls and field names have to be filled in:
+//! * `create`: Creates a new key at the remote API. The key (and its id) are read
+//!   from the json response and stored as pending secret
+//! * `set`: Nothing to do, as the key is already active after creation
+//! * `test`: Sends the test request with the new key
+//! * `finish`: Revokes the previous key with the new key, if a revoke request is set
+//!
+//! The secret contains the key in the field `api_key` and its id in `key_id`. Besides
+//! the feature `rotate_http_api_key`, one of the features `rotate_rusoto` or
+//! `rotate_aws_sdk` has to be enabled.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::rotate::http_api_key::{ApiKeyTemplate, HttpApiKeyRotation, Request};
+//!
+//! struct ExampleApi;
+//!
+//! impl ApiKeyTemplate for ExampleApi {
+//!     const KEY_POINTER: &'static str = "/data/secret";
+//!
+//!     fn create() -> Request {
+//!         Request::new(http::Method::POST, "https://api.example.com/v1/keys")
+//!             .body(r#"{"name": "rotated"}"#)
+//!     }
+//!
+//!     fn test() -> Request {
+//!         Request::new(http::Method::GET, "https://api.example.com/v1/me")
+//!     }
+//!
+//!     fn revoke() -> Option<Request> {
+//!         Some(Request::new(http::Method::DELETE, "https://api.example.com/v1/keys/{key_id}"))
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, HttpApiKeyRotation<ExampleApi>, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc};
+
+/// Placeholder in urls and bodies which is replaced with the id of the key
+pub const KEY_ID_PLACEHOLDER: &str = "{key_id}";
+
+/// Secret containing an API key
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct ApiKeySecret {
+    /// The API key
+    pub api_key: String,
+    /// Id of the API key, which is used to revoke it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+}
+
+impl std::fmt::Debug for ApiKeySecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeySecret")
+            .field("api_key", &"[...]")
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+/// Request sent to the remote API.
+///
+/// Occurrences of [`KEY_ID_PLACEHOLDER`] in the url are replaced with the id of the
+/// key the request is authenticated with. For the revoke request, the url and body
+/// contain the id of the previous key instead
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Http method of the request
+    pub method: http::Method,
+    /// Url of the request
+    pub url: String,
+    /// Json body of the request
+    pub body: Option<String>,
+}
+
+impl Request {
+    /// Creates a new request without body
+    pub fn new(method: http::Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            body: None,
+        }
+    }
+
+    /// Sets the json body of the request
+    #[must_use]
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+}
+
+/// Describes how API keys of a remote API are created, tested and revoked
+pub trait ApiKeyTemplate: Send + Sync + 'static {
+    /// Header which contains the key. Defaults to `Authorization`
+    const AUTH_HEADER: &'static str = "Authorization";
+
+    /// Prefix of the key in [`ApiKeyTemplate::AUTH_HEADER`]. Defaults to `Bearer `
+    const AUTH_PREFIX: &'static str = "Bearer ";
+
+    /// Json pointer to the new key in the response of [`ApiKeyTemplate::create`].
+    /// Defaults to `/key`
+    const KEY_POINTER: &'static str = "/key";
+
+    /// Json pointer to the id of the new key in the response of
+    /// [`ApiKeyTemplate::create`]. Defaults to `/id`
+    const KEY_ID_POINTER: Option<&'static str> = Some("/id");
+
+    /// Request which creates a new key. Authenticated with the current key
+    fn create() -> Request;
+
+    /// Request which succeeds if the key is valid
+    fn test() -> Request;
+
+    /// Request which revokes the previous key. Authenticated with the
+    /// new key. Defaults to `None`, which keeps the previous key
+    fn revoke() -> Option<Request> {
+        None
+    }
+}
+
+/// Rotates API keys as described by `T`
+pub struct HttpApiKeyRotation<T>(std::marker::PhantomData<T>);
+
+impl<T> std::fmt::Debug for HttpApiKeyRotation<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpApiKeyRotation").finish()
+    }
+}
+
+impl<T> HttpApiKeyRotation<T>
+where
+    T: ApiKeyTemplate,
+{
+    /// Creates a new key using the current one
+    pub async fn create_key(current: &ApiKeySecret) -> anyhow::Result<ApiKeySecret> {
+        use anyhow::Context;
+
+        let response = send::<T>(T::create(), current).await?;
+        let api_key = response
+            .pointer(T::KEY_POINTER)
+            .and_then(serde_json::Value::as_str)
+            .with_context(|| format!("Response contains no key at {}", T::KEY_POINTER))?
+            .to_owned();
+        let key_id = match T::KEY_ID_POINTER {
+            Some(pointer) => Some(
+                match response
+                    .pointer(pointer)
+                    .with_context(|| format!("Response contains no key id at {}", pointer))?
+                {
+                    serde_json::Value::String(id) => id.clone(),
+                    id => id.to_string(),
+                },
+            ),
+            None => None,
+        };
+        Ok(ApiKeySecret { api_key, key_id })
+    }
+
+    /// Tests whether the key is valid
+    pub async fn test_key(key: &ApiKeySecret) -> anyhow::Result<()> {
+        send::<T>(T::test(), key).await?;
+        Ok(())
+    }
+
+    /// Revokes the previous key using the new one
+    pub async fn revoke_key(previous: &ApiKeySecret, new: &ApiKeySecret) -> anyhow::Result<()> {
+        let Some(mut request) = T::revoke() else {
+            return Ok(());
+        };
+        let Some(key_id) = &previous.key_id else {
+            log::warn!("Previous key has no id. Unable to revoke it");
+            return Ok(());
+        };
+        request.url = request.url.replace(KEY_ID_PLACEHOLDER, key_id);
+        request.body = request
+            .body
+            .map(|body| body.replace(KEY_ID_PLACEHOLDER, key_id));
+        send::<T>(request, new).await?;
+        Ok(())
+    }
+}
+
+/// Sends the request authenticated with `key` and returns the json response.
+/// Empty responses are returned as `null`
+async fn send<T>(mut request: Request, key: &ApiKeySecret) -> anyhow::Result<serde_json::Value>
+where
+    T: ApiKeyTemplate,
+{
+    use anyhow::{anyhow, Context};
+
+    if let Some(key_id) = &key.key_id {
+        request.url = request.url.replace(KEY_ID_PLACEHOLDER, key_id);
+    }
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    let client = hyper::Client::builder().build::<_, hyper::Body>(connector);
+    let req = http::Request::builder()
+        .method(request.method.clone())
+        .uri(&request.url)
+        .header(T::AUTH_HEADER, format!("{}{}", T::AUTH_PREFIX, key.api_key))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(
+            request
+                .body
+                .map_or_else(hyper::Body::empty, hyper::Body::from),
+        )
+        .with_context(|| format!("Unable to build request to {}", request.url))?;
+    let res = client
+        .request(req)
+        .await
+        .with_context(|| format!("Request {} {} failed", request.method, request.url))?;
+    let status = res.status();
+    let body = hyper::body::to_bytes(res.into_body())
+        .await
+        .with_context(|| format!("Unable to read response of {}", request.url))?;
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Request {} {} responded with status {}: {}",
+            request.method,
+            request.url,
+            status,
+            String::from_utf8_lossy(&body)
+        ));
+    }
+    if body.is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+    serde_json::from_slice(&body)
+        .with_context(|| format!("Response of {} is not valid json", request.url))
+}
+
+#[async_trait::async_trait]
+impl<'a, T> super::RotateRunner<'a, (), ApiKeySecret> for HttpApiKeyRotation<T>
+where
+    T: ApiKeyTemplate,
+{
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn create(
+        _shared: &'a (),
+        mut secret_cur: SecretContainer<ApiKeySecret>,
+        _smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<ApiKeySecret>> {
+        if ctx.dry_run {
+            log::info!("Dry run: Would create new API key.");
+            return Ok(secret_cur);
+        }
+        secret_cur.data = Self::create_key(&secret_cur).await?;
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        _shared: &'a (),
+        _secret_cur: SecretContainer<ApiKeySecret>,
+        _secret_new: SecretContainer<ApiKeySecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        // The key is active since it was created
+        Ok(())
+    }
+
+    async fn test(
+        _shared: &'a (),
+        secret_new: SecretContainer<ApiKeySecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        Self::test_key(&secret_new).await
+    }
+
+    async fn finish(
+        _shared: &'a (),
+        secret_cur: SecretContainer<ApiKeySecret>,
+        secret_new: SecretContainer<ApiKeySecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        Self::revoke_key(&secret_cur, &secret_new).await
+    }
+}
@@ -0,0 +1,399 @@
+//! Provides a ready [`super::RotateRunner`] for LDAP and Active Directory users.
+//!
+//! The user changes its own password, so no administrative account is required:
+//! `set` binds with the current password and changes it, `test` binds with the new
+//! one. OpenLDAP and other directories which implement the password modify extended
+//! operation (RFC 3062) are supported as well as Active Directory, which requires
+//! `active_directory` to be set to `true` in the secret. Active Directory only allows
+//! password changes over LDAPS. Connections use LDAPS unless `tls` is set to `false`
+//! in the secret. Besides the feature `rotate_ldap`, one of the features
+//! `rotate_rusoto` or `rotate_aws_sdk` has to be enabled.
+//!
+//! Password policies of the directory, e.g. a minimum password age, apply to the
+//! rotation as well.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, lambda_runtime_types::rotate::ldap::LdapRotation, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Object identifier of the password modify extended operation (RFC 3062)
+const PASSWORD_MODIFY_OID: &str = "1.3.6.1.4.1.4203.1.11.1";
+
+/// Upper bound of the length of a response. Responses to bind, modify and
+/// extended operations are small, so larger lengths are rejected instead of allocated
+const MAX_MESSAGE_LENGTH: usize = 1024 * 1024;
+
+/// Secret of an LDAP or Active Directory user
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct LdapSecret {
+    /// Host of the directory server
+    pub host: String,
+    /// Port of the directory server. Defaults to 636
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Distinguished name of the user whose password is rotated
+    #[serde(rename = "bind_dn", alias = "username", alias = "user")]
+    pub bind_dn: String,
+    /// Password of the user
+    pub password: String,
+    /// Whether the connection uses LDAPS. Defaults to `true`
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+    /// Whether the directory is an Active Directory, which changes passwords by
+    /// modifying the attribute `unicodePwd`. Defaults to `false`
+    #[serde(default)]
+    pub active_directory: bool,
+}
+
+impl std::fmt::Debug for LdapSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LdapSecret")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("bind_dn", &self.bind_dn)
+            .field("password", &"[...]")
+            .field("tls", &self.tls)
+            .field("active_directory", &self.active_directory)
+            .finish()
+    }
+}
+
+const fn default_port() -> u16 {
+    636
+}
+
+const fn default_tls() -> bool {
+    true
+}
+
+impl LdapSecret {
+    /// Connects to the directory server and binds as the user of the secret
+    pub async fn connect(&self) -> anyhow::Result<LdapClient> {
+        use anyhow::Context;
+
+        let tcp = tokio::net::TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .with_context(|| format!("Unable to connect to ldap at {}", self.host))?;
+        let stream: Box<dyn Stream> = if self.tls {
+            let connector = native_tls::TlsConnector::new()
+                .context("Unable to prepare TLS Connection for LDAP")?;
+            let stream = tokio_native_tls::TlsConnector::from(connector)
+                .connect(&self.host, tcp)
+                .await
+                .with_context(|| format!("Unable to establish TLS with ldap at {}", self.host))?;
+            Box::new(stream)
+        } else {
+            Box::new(tcp)
+        };
+        let mut client = LdapClient {
+            stream,
+            message_id: 0,
+        };
+        client
+            .bind(&self.bind_dn, &self.password)
+            .await
+            .with_context(|| format!("Unable to bind as {}", self.bind_dn))?;
+        Ok(client)
+    }
+
+    /// Changes the password of the user from the one in this secret to `password`
+    pub async fn change_password(&self, password: &str) -> anyhow::Result<()> {
+        let mut client = self.connect().await?;
+        if self.active_directory {
+            client
+                .modify_unicode_pwd(&self.bind_dn, &self.password, password)
+                .await?;
+        } else {
+            client
+                .password_modify(&self.bind_dn, &self.password, password)
+                .await?;
+        }
+        client.unbind().await
+    }
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Stream for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// Minimal LDAPv3 client, which executes one operation after another
+pub struct LdapClient {
+    stream: Box<dyn Stream>,
+    message_id: i32,
+}
+
+impl std::fmt::Debug for LdapClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LdapClient")
+            .field("message_id", &self.message_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LdapClient {
+    /// Authenticates as `dn` with a simple bind
+    pub async fn bind(&mut self, dn: &str, password: &str) -> anyhow::Result<()> {
+        let mut request = integer(0x02, 3);
+        request.extend(tlv(0x04, dn.as_bytes()));
+        request.extend(tlv(0x80, password.as_bytes()));
+        self.request(0x60, &request, 0x61).await
+    }
+
+    /// Changes the password of `user` with the password modify extended operation
+    pub async fn password_modify(
+        &mut self,
+        user: &str,
+        old_password: &str,
+        new_password: &str,
+    ) -> anyhow::Result<()> {
+        let mut value = tlv(0x80, user.as_bytes());
+        value.extend(tlv(0x81, old_password.as_bytes()));
+        value.extend(tlv(0x82, new_password.as_bytes()));
+        let mut request = tlv(0x80, PASSWORD_MODIFY_OID.as_bytes());
+        request.extend(tlv(0x81, &tlv(0x30, &value)));
+        self.request(0x77, &request, 0x78).await
+    }
+
+    /// Changes the password of the Active Directory user `dn` by deleting the old and
+    /// adding the new value of `unicodePwd`, which users may do with their own password
+    pub async fn modify_unicode_pwd(
+        &mut self,
+        dn: &str,
+        old_password: &str,
+        new_password: &str,
+    ) -> anyhow::Result<()> {
+        // Operations delete (1) and add (0) of the attribute unicodePwd
+        let change = |operation: i32, password: &str| {
+            let value: Vec<u8> = format!("\"{}\"", password)
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect();
+            let mut attribute = tlv(0x04, b"unicodePwd");
+            attribute.extend(tlv(0x31, &tlv(0x04, &value)));
+            let mut change = integer(0x0a, operation);
+            change.extend(tlv(0x30, &attribute));
+            tlv(0x30, &change)
+        };
+        let mut changes = change(1, old_password);
+        changes.extend(change(0, new_password));
+        let mut request = tlv(0x04, dn.as_bytes());
+        request.extend(tlv(0x30, &changes));
+        self.request(0x66, &request, 0x67).await
+    }
+
+    /// Closes the connection
+    pub async fn unbind(mut self) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.message_id += 1;
+        let mut message = integer(0x02, self.message_id);
+        message.extend(tlv(0x42, &[]));
+        self.stream
+            .write_all(&tlv(0x30, &message))
+            .await
+            .context("Unable to send unbind to ldap")?;
+        self.stream
+            .shutdown()
+            .await
+            .context("Unable to close connection to ldap")
+    }
+
+    /// Sends the operation `op` and checks the result code of the response `response_op`
+    async fn request(&mut self, op: u8, content: &[u8], response_op: u8) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.message_id += 1;
+        let mut message = integer(0x02, self.message_id);
+        message.extend(tlv(op, content));
+        self.stream
+            .write_all(&tlv(0x30, &message))
+            .await
+            .context("Unable to send request to ldap")?;
+        self.stream
+            .flush()
+            .await
+            .context("Unable to send request to ldap")?;
+
+        let (tag, message) = read_tlv(&mut self.stream).await?;
+        anyhow::ensure!(tag == 0x30, "Invalid ldap message");
+        let (_, message_id, rest) = parse_tlv(&message)?;
+        let message_id = message_id
+            .iter()
+            .fold(0_i64, |id, byte| (id << 8) | i64::from(*byte));
+        let (tag, response, _) = parse_tlv(rest)?;
+        let (_, code, rest) = parse_tlv(response)?;
+        let (_, _matched_dn, rest) = parse_tlv(rest)?;
+        let (_, diagnostic, _) = parse_tlv(rest)?;
+        let code = code
+            .iter()
+            .fold(0_u32, |code, byte| (code << 8) | u32::from(*byte));
+        // Also covers unsolicited notifications, e.g. of a disconnect
+        if code != 0 {
+            anyhow::bail!(
+                "LDAP responded with error {} ({}): {}",
+                code,
+                result_name(code),
+                String::from_utf8_lossy(diagnostic)
+            );
+        }
+        anyhow::ensure!(
+            message_id == i64::from(self.message_id) && tag == response_op,
+            "Unexpected ldap response {:#04x} to message {}",
+            tag,
+            message_id
+        );
+        Ok(())
+    }
+}
+
+/// Name of common LDAP result codes
+const fn result_name(code: u32) -> &'static str {
+    match code {
+        1 => "operationsError",
+        2 => "protocolError",
+        19 => "constraintViolation",
+        32 => "noSuchObject",
+        49 => "invalidCredentials",
+        50 => "insufficientAccessRights",
+        51 => "busy",
+        52 => "unavailable",
+        53 => "unwillingToPerform",
+        80 => "other",
+        _ => "unknown",
+    }
+}
+
+/// Encodes a BER element with a definite length
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut element = vec![tag];
+    if content.len() < 0x80 {
+        element.push(content.len() as u8);
+    } else {
+        let length = content.len().to_be_bytes();
+        let skip = length.iter().take_while(|byte| **byte == 0).count();
+        element.push(0x80 | (length.len() - skip) as u8);
+        element.extend_from_slice(&length[skip..]);
+    }
+    element.extend_from_slice(content);
+    element
+}
+
+/// Encodes a non-negative BER integer or enumeration
+fn integer(tag: u8, value: i32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes
+        .windows(2)
+        .take_while(|pair| pair[0] == 0 && pair[1] < 0x80)
+        .count();
+    tlv(tag, &bytes[skip..])
+}
+
+/// Splits the first BER element of `data` into tag, content and remaining data
+fn parse_tlv(data: &[u8]) -> anyhow::Result<(u8, &[u8], &[u8])> {
+    use anyhow::Context;
+
+    let (&tag, data) = data.split_first().context("Truncated ldap message")?;
+    let (&first, data) = data.split_first().context("Truncated ldap message")?;
+    let (length, data) = if first < 0x80 {
+        (usize::from(first), data)
+    } else {
+        let count = usize::from(first & 0x7f);
+        anyhow::ensure!(
+            count <= 4 && data.len() >= count,
+            "Invalid length in ldap message"
+        );
+        let length = data[..count]
+            .iter()
+            .fold(0, |length, byte| (length << 8) | usize::from(*byte));
+        (length, &data[count..])
+    };
+    anyhow::ensure!(data.len() >= length, "Truncated ldap message");
+    Ok((tag, &data[..length], &data[length..]))
+}
+
+/// Reads a single BER element from the stream
+async fn read_tlv(stream: &mut Box<dyn Stream>) -> anyhow::Result<(u8, Vec<u8>)> {
+    use anyhow::Context;
+
+    let mut header = [0; 2];
+    stream
+        .read_exact(&mut header)
+        .await
+        .context("Unable to read response from ldap")?;
+    let length = if header[1] < 0x80 {
+        usize::from(header[1])
+    } else {
+        let count = usize::from(header[1] & 0x7f);
+        anyhow::ensure!(count <= 4, "Invalid length in ldap message");
+        let mut length = [0; 4];
+        stream
+            .read_exact(&mut length[4 - count..])
+            .await
+            .context("Unable to read response from ldap")?;
+        usize::try_from(u32::from_be_bytes(length))?
+    };
+    anyhow::ensure!(
+        length <= MAX_MESSAGE_LENGTH,
+        "Ldap message exceeds {} bytes: {}",
+        MAX_MESSAGE_LENGTH,
+        length
+    );
+    let mut content = vec![0; length];
+    stream
+        .read_exact(&mut content)
+        .await
+        .context("Unable to read response from ldap")?;
+    Ok((header[0], content))
+}
+
+/// Rotates the password of the LDAP or Active Directory user in the secret
+#[derive(Debug)]
+pub struct LdapRotation;
+
+#[async_trait::async_trait]
+impl<'a> super::RotateRunner<'a, (), LdapSecret> for LdapRotation {
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn create(
+        _shared: &'a (),
+        mut secret_cur: SecretContainer<LdapSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<LdapSecret>> {
+        secret_cur.password = smc.generate_new_password(false, None).await?;
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        _shared: &'a (),
+        secret_cur: SecretContainer<LdapSecret>,
+        secret_new: SecretContainer<LdapSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        secret_cur
+            .change_password(&secret_new.password)
+            .await
+            .with_context(|| format!("Unable to change password of {}", secret_cur.bind_dn))
+    }
+
+    async fn test(
+        _shared: &'a (),
+        secret_new: SecretContainer<LdapSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        secret_new.connect().await?.unbind().await
+    }
+}
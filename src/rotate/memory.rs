@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// In-memory replacement of the `SecretManager`, which is used by
+/// [`super::simulation::Simulation`]. Clones share the same secrets
+#[derive(Clone, Default)]
+pub struct SmcClient {
+    secrets: Arc<Mutex<HashMap<String, MemorySecret>>>,
+    counter: Arc<std::sync::atomic::AtomicU64>,
+    published: Arc<Mutex<Vec<super::simulation::PublishedMessage>>>,
+    /// Arns of associated SCRAM secrets by cluster arn
+    #[cfg(feature = "rotate_msk")]
+    scram_secrets: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    /// Valid AUTH tokens by replication group id
+    #[cfg(feature = "rotate_elasticache")]
+    auth_tokens: Arc<Mutex<HashMap<String, Vec<String>>>>,
+}
+
+#[derive(Clone)]
+struct MemorySecret {
+    arn: String,
+    name: String,
+    /// Values by version id. Versions without value are created by [`SmcClient::start_rotation`]
+    values: HashMap<String, Option<(Vec<u8>, bool)>>,
+    stages: HashMap<String, Vec<String>>,
+    tags: HashMap<String, String>,
+    kms_key_id: Option<String>,
+}
+
+impl MemorySecret {
+    fn version_with(&self, stage: &str) -> Option<&str> {
+        self.stages
+            .iter()
+            .find(|(_, stages)| stages.iter().any(|s| s == stage))
+            .map(|(version, _)| version.as_str())
+    }
+
+    fn attach(&mut self, stage: &str, version_id: &str) {
+        for stages in self.stages.values_mut() {
+            stages.retain(|s| s != stage);
+        }
+        self.stages
+            .entry(version_id.to_owned())
+            .or_default()
+            .push(stage.to_owned());
+    }
+}
+
+impl SmcClient {
+    /// Returns a unique id, e.g. for versions and request tokens
+    pub fn next_id(&self, prefix: &str) -> String {
+        let id = self
+            .counter
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        format!("{}-{:08}", prefix, id)
+    }
+
+    /// Creates or replaces the secret `name` with `value` as current value
+    pub fn create_secret(&self, name: &str, value: &str) {
+        self.insert_secret(name, value.as_bytes(), false);
+    }
+
+    /// Creates or replaces the secret `name` with `value` as current value,
+    /// which is stored as `SecretBinary`
+    pub fn create_binary_secret(&self, name: &str, value: &[u8]) {
+        self.insert_secret(name, value, true);
+    }
+
+    fn insert_secret(&self, name: &str, value: &[u8], binary: bool) {
+        let version_id = self.next_id("version");
+        let mut secret = MemorySecret {
+            arn: format!(
+                "arn:aws:secretsmanager:us-east-1:000000000000:secret:{}",
+                name
+            ),
+            name: name.to_owned(),
+            values: HashMap::new(),
+            stages: HashMap::new(),
+            tags: HashMap::new(),
+            kms_key_id: None,
+        };
+        secret
+            .values
+            .insert(version_id.clone(), Some((value.to_owned(), binary)));
+        secret.attach("AWSCURRENT", &version_id);
+        self.lock().insert(name.to_owned(), secret);
+    }
+
+    /// Creates a version without value, labeled with `stage`,
+    /// like `RotateSecret` does before the rotation lambda is invoked.
+    /// Fails like `RotateSecret`, if `stage` is attached to a version
+    /// of a previous rotation, which is not labeled with `current`
+    pub fn start_rotation(
+        &self,
+        secret_id: &str,
+        version_id: &str,
+        stage: &str,
+        current: &str,
+    ) -> anyhow::Result<()> {
+        self.with_secret(secret_id, |secret| {
+            if let Some(previous) = secret.version_with(stage) {
+                anyhow::ensure!(
+                    secret.version_with(current) == Some(previous),
+                    "A previous rotation isn't complete. Stage {} is attached to version {} of secret {}",
+                    stage,
+                    previous,
+                    secret_id
+                );
+            }
+            secret.values.entry(version_id.to_owned()).or_insert(None);
+            secret.attach(stage, version_id);
+            Ok(())
+        })
+    }
+
+    /// Removes `stage` from the secret, like the `SecretManager`
+    /// does with `AWSPENDING` once a rotation finished
+    pub fn end_rotation(&self, secret_id: &str, stage: &str) -> anyhow::Result<()> {
+        self.with_secret(secret_id, |secret| {
+            for stages in secret.stages.values_mut() {
+                stages.retain(|s| s != stage);
+            }
+            Ok(())
+        })
+    }
+
+    /// Value of the version which is labeled with `stage` and
+    /// whether it is stored as `SecretBinary`
+    pub fn value(&self, secret_id: &str, stage: &str) -> Option<(Vec<u8>, bool)> {
+        self.with_secret(secret_id, |secret| {
+            let version_id = secret.version_with(stage).unwrap_or_default().to_owned();
+            Ok(secret.values.get(&version_id).cloned().flatten())
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// Messages which were published to SNS topics
+    pub fn published(&self) -> Vec<super::simulation::PublishedMessage> {
+        self.published
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    #[cfg(not(feature = "rotate_local_password"))]
+    pub async fn generate_new_password(
+        &self,
+        _puncutation: bool,
+        length: Option<i64>,
+    ) -> anyhow::Result<String> {
+        let length = usize::try_from(length.unwrap_or(32))?;
+        Ok(self
+            .next_id("password")
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .cycle()
+            .take(length)
+            .collect())
+    }
+
+    pub async fn get_secret_value<S: serde::de::DeserializeOwned>(
+        &self,
+        secret_id: &str,
+        version_stage: &str,
+    ) -> anyhow::Result<crate::rotate::smc::Secret<S>> {
+        use anyhow::Context;
+
+        let (arn, version_id, version_stages, value) = self.with_secret(secret_id, |secret| {
+            let version_id = secret.version_with(version_stage).with_context(|| {
+                format!(
+                    "Unable to fetch SecretValue with id: {}. No version with stage {}",
+                    secret_id, version_stage
+                )
+            })?;
+            let value = secret
+                .values
+                .get(version_id)
+                .cloned()
+                .flatten()
+                .with_context(|| {
+                    format!(
+                        "Unable to fetch SecretValue with id: {}. Version {} has no value",
+                        secret_id, version_id
+                    )
+                })?;
+            Ok((
+                secret.arn.clone(),
+                version_id.to_owned(),
+                secret.stages[version_id].clone(),
+                value,
+            ))
+        })?;
+        let (value, binary) = value;
+        let inner = crate::rotate::SecretContainer::from_secret_value(&value)
+            .with_context(|| format!("Unable to parse secret value. Value does not confirm to required structure. Id: {}", secret_id))?;
+        Ok(crate::rotate::smc::Secret {
+            arn,
+            version_id,
+            version_stages,
+            binary,
+            inner,
+        })
+    }
+
+    pub async fn put_secret_value_pending(
+        &self,
+        secret_id: &str,
+        request_token: Option<&str>,
+        secret_value: &[u8],
+        binary: bool,
+        version_stages: &[String],
+    ) -> anyhow::Result<()> {
+        let version_id = request_token.map_or_else(|| self.next_id("version"), str::to_owned);
+        self.with_secret(secret_id, |secret| {
+            let value = (secret_value.to_owned(), binary);
+            match secret.values.get(&version_id) {
+                Some(Some(existing)) if *existing != value => anyhow::bail!(
+                    "Unable to push new SecretValue for id: {}. Version {} exists with a different value",
+                    secret_id,
+                    version_id
+                ),
+                _ => {}
+            }
+            secret.values.insert(version_id.clone(), Some(value));
+            for stage in version_stages {
+                secret.attach(stage, &version_id);
+            }
+            Ok(())
+        })
+    }
+
+    pub async fn describe_secret(
+        &self,
+        secret_id: &str,
+    ) -> anyhow::Result<crate::rotate::smc::SecretDescription> {
+        self.with_secret(secret_id, |secret| {
+            Ok(crate::rotate::smc::SecretDescription {
+                name: secret.name.clone(),
+                rotation_enabled: true,
+                rotation_lambda_arn: None,
+                version_ids_to_stages: secret.stages.clone(),
+                replicas: Vec::new(),
+                tags: secret.tags.clone(),
+                kms_key_id: secret.kms_key_id.clone(),
+            })
+        })
+    }
+
+    pub async fn tag_resource(&self, secret_id: &str, tags: &[(&str, &str)]) -> anyhow::Result<()> {
+        self.with_secret(secret_id, |secret| {
+            for (key, value) in tags {
+                secret.tags.insert((*key).to_owned(), (*value).to_owned());
+            }
+            Ok(())
+        })
+    }
+
+    pub async fn untag_resource(&self, secret_id: &str, keys: &[&str]) -> anyhow::Result<()> {
+        self.with_secret(secret_id, |secret| {
+            for key in keys {
+                secret.tags.remove(*key);
+            }
+            Ok(())
+        })
+    }
+
+    pub async fn update_kms_key(&self, secret_id: &str, kms_key_id: &str) -> anyhow::Result<()> {
+        self.with_secret(secret_id, |secret| {
+            secret.kms_key_id = Some(kms_key_id.to_owned());
+            Ok(())
+        })
+    }
+
+    pub async fn update_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+        remove_from_version_id: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.with_secret(secret_id, |secret| {
+            if !secret.values.contains_key(version_id) {
+                anyhow::bail!(
+                    "Unable to move stage {} to version {} for id: {}. Version does not exist",
+                    stage,
+                    version_id,
+                    secret_id
+                );
+            }
+            let attached_to = secret.version_with(stage).map(str::to_owned);
+            if attached_to.as_deref() == Some(version_id) {
+                return Ok(());
+            }
+            if attached_to.is_some() && attached_to != remove_from_version_id {
+                anyhow::bail!(
+                    "Unable to move stage {} to version {} for id: {}. Stage is attached to version {}",
+                    stage,
+                    version_id,
+                    secret_id,
+                    attached_to.unwrap_or_default()
+                );
+            }
+            secret.attach(stage, version_id);
+            if let (Some(previous), "AWSCURRENT") = (attached_to, stage) {
+                secret.attach("AWSPREVIOUS", &previous);
+            }
+            Ok(())
+        })
+    }
+
+    pub async fn remove_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.with_secret(secret_id, |secret| {
+            let stages = secret.stages.get_mut(version_id).with_context(|| {
+                format!(
+                    "Unable to remove stage {} from version {} for id: {}. Version does not exist",
+                    stage, version_id, secret_id
+                )
+            })?;
+            stages.retain(|s| s != stage);
+            Ok(())
+        })
+    }
+
+    pub async fn set_pending_secret_value_to_current(
+        &self,
+        secret_arn: String,
+        secret_current_version_id: String,
+        secret_pending_version_id: String,
+        stage: &str,
+    ) -> anyhow::Result<()> {
+        self.update_version_stage(
+            &secret_arn,
+            stage,
+            &secret_pending_version_id,
+            Some(secret_current_version_id),
+        )
+        .await
+    }
+
+    #[cfg(feature = "rotate_sns")]
+    pub async fn publish(
+        &self,
+        topic_arn: &str,
+        _region: &str,
+        subject: &str,
+        message: &str,
+    ) -> anyhow::Result<()> {
+        self.published
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(super::simulation::PublishedMessage {
+                topic_arn: topic_arn.to_owned(),
+                subject: subject.to_owned(),
+                message: message.to_owned(),
+            });
+        Ok(())
+    }
+
+    #[cfg(feature = "rotate_msk")]
+    pub async fn scram_secrets(
+        &self,
+        cluster_arn: &str,
+        _region: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(self
+            .scram_secrets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(cluster_arn)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    #[cfg(feature = "rotate_msk")]
+    pub async fn associate_scram_secret(
+        &self,
+        cluster_arn: &str,
+        _region: &str,
+        secret_arn: &str,
+    ) -> anyhow::Result<()> {
+        let mut scram_secrets = self
+            .scram_secrets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let secret_arns = scram_secrets.entry(cluster_arn.to_owned()).or_default();
+        if !secret_arns.iter().any(|arn| arn == secret_arn) {
+            secret_arns.push(secret_arn.to_owned());
+        }
+        drop(scram_secrets);
+        Ok(())
+    }
+
+    /// Creates or replaces the replication group `replication_group_id` with `auth_token`
+    #[cfg(feature = "rotate_elasticache")]
+    pub fn create_replication_group(&self, replication_group_id: &str, auth_token: &str) {
+        self.auth_tokens
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(replication_group_id.to_owned(), vec![auth_token.to_owned()]);
+    }
+
+    /// Valid AUTH tokens of the replication group `replication_group_id`
+    #[cfg(feature = "rotate_elasticache")]
+    pub fn auth_tokens(&self, replication_group_id: &str) -> Vec<String> {
+        self.auth_tokens
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(replication_group_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Applies `ModifyReplicationGroup` immediately. Like ElastiCache,
+    /// at most two tokens are valid at once
+    #[cfg(feature = "rotate_elasticache")]
+    pub async fn modify_auth_token(
+        &self,
+        _region: &str,
+        replication_group_id: &str,
+        auth_token: &str,
+        strategy: super::redis::AuthTokenUpdateStrategy,
+    ) -> anyhow::Result<()> {
+        let mut auth_tokens = self
+            .auth_tokens
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let tokens = auth_tokens
+            .entry(replication_group_id.to_owned())
+            .or_default();
+        match strategy {
+            super::redis::AuthTokenUpdateStrategy::Rotate => {
+                if let Some(current) = tokens.pop() {
+                    tokens.clear();
+                    tokens.push(current);
+                }
+            }
+            super::redis::AuthTokenUpdateStrategy::Set => tokens.clear(),
+        }
+        tokens.push(auth_token.to_owned());
+        drop(auth_tokens);
+        Ok(())
+    }
+
+    /// Replication groups are modified immediately, so they are always available
+    #[cfg(feature = "rotate_elasticache")]
+    pub async fn replication_group_status(
+        &self,
+        _region: &str,
+        _replication_group_id: &str,
+    ) -> anyhow::Result<String> {
+        Ok("available".into())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, MemorySecret>> {
+        self.secrets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Calls `f` with the secret, which is referenced by its name or arn
+    fn with_secret<T>(
+        &self,
+        secret_id: &str,
+        f: impl FnOnce(&mut MemorySecret) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        self.lock()
+            .values_mut()
+            .find(|secret| secret.name == secret_id || secret.arn == secret_id)
+            .ok_or_else(|| anyhow::anyhow!("Secret with id {} does not exist", secret_id))
+            .and_then(f)
+    }
+}
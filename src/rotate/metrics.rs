@@ -0,0 +1,80 @@
+use super::Step;
+use std::time::Duration;
+
+/// Outcome of a rotation step
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOutcome {
+    /// The step completed
+    Success,
+    /// The step failed. The error is passed to [`super::RotateRunner::on_rotation_failure`]
+    Failure,
+}
+
+/// Metrics of a single rotation step, which are passed to
+/// [`super::RotateRunner::on_step_metrics`]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone, serde::Serialize)]
+#[non_exhaustive]
+pub struct StepMetrics<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// Executed step
+    pub step: Step,
+    /// Id of the rotated secret
+    pub secret_id: &'a str,
+    /// Version id of the new secret, which is the client request token
+    pub version_id: &'a str,
+    /// Time it took to execute the step
+    #[serde(rename = "duration_ms", serialize_with = "millis")]
+    pub duration: Duration,
+    /// Whether the step succeeded
+    pub outcome: StepOutcome,
+    /// Error of a failed step
+    pub error: Option<String>,
+    /// Whether the rotation is a dry run
+    pub dry_run: bool,
+}
+
+impl<'a> StepMetrics<'a> {
+    pub(crate) fn new(
+        step: Step,
+        ctx: &super::RotationContext<'a>,
+        duration: Duration,
+        result: &anyhow::Result<()>,
+    ) -> Self {
+        Self {
+            kind: "rotation_step",
+            step,
+            secret_id: ctx.secret_id,
+            version_id: ctx.client_request_token,
+            duration,
+            outcome: match result {
+                Ok(()) => StepOutcome::Success,
+                Err(_) => StepOutcome::Failure,
+            },
+            error: result.as_ref().err().map(|err| format!("{:#}", err)),
+            dry_run: ctx.dry_run,
+        }
+    }
+
+    /// Writes the metrics as a single json line, which can be
+    /// queried with CloudWatch Logs Insights or a metric filter
+    pub fn log(&self) {
+        match serde_json::to_string(self) {
+            Ok(metrics) => log::info!("{}", metrics),
+            Err(err) => log::error!("Unable to serialize rotation step metrics: {:?}", err),
+        }
+    }
+}
+
+fn millis<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
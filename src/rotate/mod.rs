@@ -22,6 +22,7 @@
 //!         shared: &'a (),
 //!         secret_cur: lambda_runtime_types::rotate::SecretContainer<Secret>,
 //!         smc: &lambda_runtime_types::rotate::Smc,
+//!         ctx: &lambda_runtime_types::rotate::RotationContext<'_>,
 //!     ) -> anyhow::Result<lambda_runtime_types::rotate::SecretContainer<Secret>> {
 //!         // Create a new secret without setting it yet.
 //!         // Only called if there is no pending secret available
@@ -33,6 +34,8 @@
 //!         shared: &'a (),
 //!         secret_cur: lambda_runtime_types::rotate::SecretContainer<Secret>,
 //!         secret_new: lambda_runtime_types::rotate::SecretContainer<Secret>,
+//!         smc: &lambda_runtime_types::rotate::Smc,
+//!         ctx: &lambda_runtime_types::rotate::RotationContext<'_>,
 //!     ) -> anyhow::Result<()> {
 //!         // Set the secret in the service
 //!         // Only called if password is not already set, checked by  
@@ -46,6 +49,8 @@
 //!     async fn test(
 //!         shared: &'a (),
 //!         secret_new: lambda_runtime_types::rotate::SecretContainer<Secret>,
+//!         smc: &lambda_runtime_types::rotate::Smc,
+//!         ctx: &lambda_runtime_types::rotate::RotationContext<'_>,
 //!     ) -> anyhow::Result<()> {
 //!         // Test whether a connection with the given secret works
 //!         Ok(())
@@ -55,6 +60,8 @@
 //!         shared: &'a (),
 //!         secret_cur: lambda_runtime_types::rotate::SecretContainer<Secret>,
 //!         secret_new: lambda_runtime_types::rotate::SecretContainer<Secret>,
+//!         smc: &lambda_runtime_types::rotate::Smc,
+//!         ctx: &lambda_runtime_types::rotate::RotationContext<'_>,
 //!     ) -> anyhow::Result<()> {
 //!         // Optional: Perform any work which may be necessary to
 //!         // complete rotation
@@ -69,36 +76,164 @@
 //! ```
 //!
 //! For further usage like `Shared` Data, refer to the main [documentation](`crate`)
+//!
+//! How the steps are orchestrated, e.g. that `test` is called before `set`, can be
+//! customized by overriding [`RotateRunner::run_step`]. See [`pipeline`] for details.
+//!
+//! For Postgres users, a ready runner is available in `rotate::postgres` with the feature
+//! `rotate_postgres`, for Redis ACL users in `rotate::redis` with the feature `rotate_redis`
+//! and for AUTH tokens of ElastiCache replication groups in the same module with the
+//! feature `rotate_elasticache`,
+//! for LDAP and Active Directory users in `rotate::ldap` with the feature `rotate_ldap`,
+//! for RabbitMQ users in `rotate::rabbitmq` with the feature `rotate_rabbitmq` and for
+//! SASL/SCRAM users of Amazon MSK clusters in `rotate::msk` with the feature `rotate_msk`.
+//! Passwords and key pairs of Snowflake users are rotated in `rotate::snowflake` with the
+//! feature `rotate_snowflake`.
+//! API keys of REST APIs are rotated by filling in a template in `rotate::http_api_key`
+//! with the feature `rotate_http_api_key`, SSH key pairs in `rotate::ssh` with the feature
+//! `rotate_ssh` and TLS certificates in `rotate::tls` with the feature `rotate_tls`.
+//!
+//! Secrets which contain multiple credentials, e.g. of an application user and a readonly
+//! user, are rotated credential by credential with [`composite::CompositeRotation`].
+//!
+//! Secrets which hold an active and a standby credential, which are flipped on every
+//! rotation, can be modelled with [`DualCredential`].
+//!
+//! With the feature `rotate_local_password`, [`Smc::generate_new_password`] generates passwords
+//! locally instead of calling `GetRandomPassword`, which saves a request and an IAM permission.
 
 #[cfg(feature = "rotate_aws_sdk")]
 mod aws_sdk;
+pub mod composite;
+mod dual;
+#[cfg(feature = "rotate_http_api_key")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_http_api_key")))]
+pub mod http_api_key;
+#[cfg(feature = "rotate_ldap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_ldap")))]
+pub mod ldap;
+#[cfg(feature = "test")]
+mod memory;
+mod metrics;
+#[cfg(feature = "rotate_msk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_msk")))]
+pub mod msk;
+mod notification;
+#[cfg(feature = "rotate_local_password")]
+mod password;
+pub mod pipeline;
+#[cfg(feature = "rotate_postgres")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_postgres")))]
+pub mod postgres;
+#[cfg(feature = "rotate_rabbitmq")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_rabbitmq")))]
+pub mod rabbitmq;
+#[cfg(feature = "rotate_redis")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_redis")))]
+pub mod redis;
 #[cfg(feature = "rotate_rusoto")]
 mod rusoto;
+#[cfg(feature = "_signed_requests")]
+mod signed;
+#[cfg(feature = "test")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test")))]
+pub mod simulation;
 mod smc;
+#[cfg(feature = "rotate_snowflake")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_snowflake")))]
+pub mod snowflake;
+#[cfg(feature = "rotate_ssh")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_ssh")))]
+pub mod ssh;
+#[cfg(feature = "rotate_tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_tls")))]
+pub mod tls;
+
+pub use dual::{DualCredential, Slot};
+pub use metrics::{StepMetrics, StepOutcome};
+pub use notification::Notification;
+#[cfg(feature = "rotate_local_password")]
+pub use password::generate_password;
+pub use smc::{
+    BinarySecret, RawSecret, ReplicaStatus, SecretContainer, SecretDescription, Smc, StageLabels,
+};
+
+/// Env variable which enables the dry run mode if set to `true` or `1`.
+/// See [`RotateRunner::dry_run`]
+pub const DRY_RUN_ENV: &str = "LAMBDA_RUNTIME_TYPES_ROTATION_DRY_RUN";
+
+/// Tag which holds the lease of a running step, if [`RotateRunner::lock_lease`] is set
+pub const ROTATION_LOCK_TAG: &str = "lambda-runtime-types:rotation-lock";
 
-pub use smc::{SecretContainer, Smc};
+/// Env variable with the arn of the SNS topic, which is notified about
+/// completed and failed rotations. See [`RotateRunner::notification_topic`]
+pub const NOTIFICATION_TOPIC_ENV: &str = "LAMBDA_RUNTIME_TYPES_ROTATION_NOTIFICATION_TOPIC";
+
+/// Information about the running rotation, which is passed to
+/// every step of a [`RotateRunner`]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct RotationContext<'a> {
+    /// Id of the secret to rotate
+    pub secret_id: &'a str,
+    /// Region the lambda is executed in
+    pub lambda_region: &'a str,
+    /// Region of the secret to rotate. See [`RotateRunner::secret_region`]
+    pub secret_region: &'a str,
+    /// Request Token of the rotation, which is used as version of the new secret
+    pub client_request_token: &'a str,
+    /// Whether the rotation is a dry run. See [`RotateRunner::dry_run`]
+    pub dry_run: bool,
+    /// Arn of the invoked lambda
+    pub invoked_function_arn: &'a str,
+}
 
 /// `Event` which is send by the `SecretManager` to the rotation lambda
 #[cfg_attr(
     docsrs,
     doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
 )]
+///
+/// Besides the `PascalCase` field names sent by the `SecretManager`, `camelCase`
+/// and `snake_case` names are accepted, as events from tooling and tests often
+/// differ slightly. Unknown fields are ignored
 #[derive(Clone, serde::Deserialize)]
 pub struct Event<Secret> {
     /// Request Token used for `SecretManager` Operations
-    #[serde(rename = "ClientRequestToken")]
+    #[serde(
+        rename = "ClientRequestToken",
+        alias = "clientRequestToken",
+        alias = "client_request_token"
+    )]
     pub client_request_token: String,
     /// Id of the secret to rotate
-    #[serde(rename = "SecretId")]
+    #[serde(rename = "SecretId", alias = "secretId", alias = "secret_id")]
     pub secret_id: String,
     /// Current step of the rotation
-    #[serde(rename = "Step")]
+    #[serde(rename = "Step", alias = "step")]
     pub step: Step,
     #[doc(hidden)]
     #[serde(skip)]
     pub _m: std::marker::PhantomData<Secret>,
 }
 
+impl<Secret> Event<Secret> {
+    /// Returns the region of the secret, if `secret_id` is an ARN
+    pub fn secret_region(&self) -> Option<&str> {
+        let mut parts = self.secret_id.split(':');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some("arn"), Some(_), Some("secretsmanager"), Some(region)) if !region.is_empty() => {
+                Some(region)
+            }
+            _ => None,
+        }
+    }
+}
+
 impl<Secret> std::fmt::Debug for Event<Secret> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Event")
@@ -114,7 +249,7 @@ impl<Secret> std::fmt::Debug for Event<Secret> {
     docsrs,
     doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
 )]
-#[derive(Debug, Copy, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub enum Step {
     /// Secret creation
     #[serde(rename = "createSecret")]
@@ -128,6 +263,15 @@ pub enum Step {
     /// Secret rotation finalization
     #[serde(rename = "finishSecret")]
     Finish,
+    /// Rollback to the previous secret. Never sent by the `SecretManager`, but
+    /// may be used to invoke the lambda manually, if a finished rotation turns
+    /// out to be broken. See [`RotateRunner::rollback`]
+    #[serde(rename = "rollbackSecret")]
+    Rollback,
+    /// Any other step, e.g. one added to the `SecretManager` after this version
+    /// or sent by a test harness. See [`RotateRunner::custom_step`]
+    #[serde(untagged)]
+    Other(String),
 }
 
 /// Defines a type which is executed every time a lambda
@@ -136,17 +280,17 @@ pub enum Step {
 ///
 /// Types:
 /// * `Shared`: Type which is shared between lambda
-///             invocations. Note that lambda will
-///             create multiple environments for
-///             simulations invokations and environments
-///             are only kept alive for a certain time.
-///             It is thus not guaranteed that data
-///             can be reused, but with this types
-///             its possible.
+///   invocations. Note that lambda will
+///   create multiple environments for
+///   simulations invokations and environments
+///   are only kept alive for a certain time.
+///   It is thus not guaranteed that data
+///   can be reused, but with this types
+///   its possible.
 /// * `Secret`: The structure of the secret stored in
-///             the `SecretManager`. May contain only
-///             necessary fields, as other undefined
-///             fields are internally preserved.
+///   the `SecretManager`. May contain only
+///   necessary fields, as other undefined
+///   fields are internally preserved.
 #[cfg_attr(
     docsrs,
     doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
@@ -160,6 +304,175 @@ where
     /// See documentation of [`super::Runner::setup`]
     async fn setup(region: &'a str) -> anyhow::Result<Shared>;
 
+    /// Region of the secret to rotate, which is used to create the [`Smc`].
+    /// Defaults to the region in the secret ARN and falls back to the region
+    /// of the lambda if the secret is not referenced by its ARN.
+    fn secret_region(lambda_region: &str, event: &Event<Secret>) -> String {
+        event.secret_region().unwrap_or(lambda_region).to_owned()
+    }
+
+    /// Whether the rotation is executed as dry run. Defaults to `true`
+    /// if the env variable [`DRY_RUN_ENV`] is set to `true` or `1`.
+    ///
+    /// In a dry run, the secret is only read from the `SecretManager` and
+    /// nothing is written to it. `set` and `finish` are not called, but logged
+    /// instead. `create` and `test` are still called, so implementations of
+    /// `create` must check [`RotationContext::dry_run`] before changing a
+    /// remote system. As no pending secret is stored, the steps after
+    /// `create` only log what they would do, unless a pending secret
+    /// exists already.
+    fn dry_run() -> bool {
+        matches!(std::env::var(DRY_RUN_ENV).as_deref(), Ok("true" | "1"))
+    }
+
+    /// Revert the service to the previous secret, after a finished rotation
+    /// turned out to be broken. Called for [`Step::Rollback`] before the previous
+    /// secret is made current again with [`Smc::rollback_to_previous`].
+    /// Defaults to doing nothing, which is sufficient if the previous secret
+    /// is still valid in the service
+    async fn rollback(
+        _shared: &'a Shared,
+        _secret_cur: SecretContainer<Secret>,
+        _secret_previous: SecretContainer<Secret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Executes a step which is unknown to this crate, see [`Step::Other`].
+    /// Defaults to logging a warning and succeeding, so new steps of the
+    /// `SecretManager` do not fail the rotation
+    async fn custom_step(
+        _shared: &'a Shared,
+        step: &str,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        log::warn!("Ignoring unknown rotation step {}.", step);
+        Ok(())
+    }
+
+    /// Invoked if `step` failed, before the error is returned to lambda. Can be used
+    /// to alert, to clean up credentials which were created in the remote system or
+    /// to mark the secret. The error is returned by default, but context can be
+    /// added to it or it can be swallowed by returning `Ok(())`
+    async fn on_rotation_failure(
+        _shared: &'a Shared,
+        _step: Step,
+        error: anyhow::Error,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        Err(error)
+    }
+
+    /// Invoked after every step with its duration and outcome. Defaults to logging
+    /// the metrics as a single json line with [`StepMetrics::log`], so a fleet of
+    /// rotation lambdas can be monitored uniformly
+    async fn on_step_metrics(_shared: &'a Shared, metrics: &StepMetrics<'_>) {
+        metrics.log();
+    }
+
+    /// Time `finish` waits for replicas in other regions to carry the new secret,
+    /// after it was marked as current. Defaults to `None`, which does not wait.
+    ///
+    /// If set, the rotation only succeeds once every replica carries the new
+    /// version. See [`Smc::wait_for_replicas`]
+    fn replica_sync_timeout() -> Option<std::time::Duration> {
+        None
+    }
+
+    /// Whether completed steps are recorded as staging labels on the pending version.
+    /// Defaults to `false`.
+    ///
+    /// If enabled, [`pipeline::SET_MARKER`] and [`pipeline::TEST_MARKER`] are
+    /// attached to the pending version once `set` or `test` completed. Re-delivered
+    /// events skip the step then, instead of probing with [`RotateRunner::test`]
+    /// whether the secret is set already. Staging labels only belong to one version at a
+    /// time, so markers move to the new version in the next rotation. Requires the
+    /// permission `secretsmanager:DescribeSecret` in addition to the usual ones.
+    fn step_markers() -> bool {
+        false
+    }
+
+    /// Staging labels which are used by the rotation, e.g. to stage secrets through
+    /// additional environments. Defaults to the labels of the `SecretManager`.
+    ///
+    /// Note that the `SecretManager` only attaches `AWSPENDING` to the new version,
+    /// if it starts the rotation. With custom labels, the lambda is usually invoked
+    /// by another workflow. See [`StageLabels`]
+    fn stage_labels() -> StageLabels {
+        StageLabels::default()
+    }
+
+    /// KMS key which encrypts the new secret value. Defaults to `None`, which
+    /// keeps the key of the secret.
+    ///
+    /// If set, the secret is switched to the key before the pending value is
+    /// stored, e.g. to migrate secrets to a new customer managed key with their
+    /// next rotation. See [`Smc::reencrypt_secret`] for the required permissions
+    fn kms_key_id() -> Option<String> {
+        None
+    }
+
+    /// Whether [`pipeline::preflight`] verifies the rotation configuration of the
+    /// secret before every step. Defaults to `false`.
+    ///
+    /// If enabled, a step fails fast if rotation is disabled for the secret, if the
+    /// secret is configured to be rotated by a different lambda or if the request
+    /// token does not belong to a pending version. Steps for a version which is
+    /// current already are skipped. Requires the permission
+    /// `secretsmanager:DescribeSecret` in addition to the usual ones.
+    fn preflight_checks() -> bool {
+        false
+    }
+
+    /// Duration of the lease on the secret, which every step acquires before it is
+    /// executed. Defaults to `None`, which does not lock the secret.
+    ///
+    /// If set, a step fails while another rotation, i.e. a different request token,
+    /// holds the lease. This guards against manual invocations racing the scheduled
+    /// rotation or several lambdas configured for the same secret. The lease is
+    /// stored in the tag [`ROTATION_LOCK_TAG`] and released after the step, so it
+    /// only outlives a step if the lambda crashed. It should exceed the timeout of
+    /// the lambda. See [`Smc::acquire_lock`] for the required permissions
+    fn lock_lease() -> Option<std::time::Duration> {
+        None
+    }
+
+    /// Arn of the SNS topic, which is notified once `finish` completed or a step
+    /// failed. Defaults to the env variable [`NOTIFICATION_TOPIC_ENV`] and to no
+    /// notifications if it is unset.
+    ///
+    /// The message is a json encoded [`Notification`]. Nothing is published in a
+    /// dry run and failures to publish do not fail the rotation. Publishing requires
+    /// the feature `rotate_sns`, without it the notification is only logged. Requires the
+    /// permissions `sns:Publish` and `secretsmanager:DescribeSecret`, which is used
+    /// to determine the old version id, in addition to the usual ones.
+    fn notification_topic() -> Option<String> {
+        std::env::var(NOTIFICATION_TOPIC_ENV)
+            .ok()
+            .filter(|topic| !topic.is_empty())
+    }
+
+    /// Executes `step` of the rotation. Defaults to [`pipeline::step`].
+    ///
+    /// Override it to insert additional phases or to change the orchestration of
+    /// a step, e.g. to skip the [`RotateRunner::test`] before [`RotateRunner::set`].
+    /// The default orchestration of each step is available in [`pipeline`].
+    async fn run_step(
+        shared: &'a Shared,
+        step: Step,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()>
+    where
+        Secret: Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+    {
+        pipeline::step::<Self, _, _>(shared, step, smc, ctx).await
+    }
+
     /// Create a new secret without setting it yet.
     /// Only called if there is no pending secret available
     /// (which may happen if rotation fails at any stage)
@@ -167,28 +480,49 @@ where
         shared: &'a Shared,
         secret_cur: SecretContainer<Secret>,
         smc: &Smc,
+        ctx: &RotationContext<'_>,
     ) -> anyhow::Result<SecretContainer<Secret>>;
 
+    /// Validates the secret returned by [`RotateRunner::create`], before it is stored
+    /// as pending value. An error fails the step, so malformed secrets are never
+    /// staged. Defaults to accepting every secret
+    fn validate_secret(_secret_new: &SecretContainer<Secret>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
     /// Set the secret in the service
     /// Only called if password is not already set, checked by  
     /// calling [`test`] with new password beforehand. The reason
     /// for that it, that a failure in a later stage means all
     /// stages are called again with set failing as the old password
-    /// does not work anymore
+    /// does not work anymore.
+    ///
+    /// Like all steps, it receives the [`Smc`], which allows to read other
+    /// secrets with [`Smc::get_secret`], e.g. credentials of an admin user
     async fn set(
         shared: &'a Shared,
         secret_cur: SecretContainer<Secret>,
         secret_new: SecretContainer<Secret>,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
     ) -> anyhow::Result<()>;
 
     /// Test whether a connection with the given secret works
-    async fn test(shared: &'a Shared, secret_new: SecretContainer<Secret>) -> anyhow::Result<()>;
+    async fn test(
+        shared: &'a Shared,
+        secret_new: SecretContainer<Secret>,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()>;
 
-    /// Perform any work which may be necessary to complete rotation
+    /// Perform any work which may be necessary to complete rotation.
+    /// Not called again once the pending version is current
     async fn finish(
         _shared: &'a Shared,
         _secret_cur: SecretContainer<Secret>,
         _secret_new: SecretContainer<Secret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
     ) -> anyhow::Result<()> {
         Ok(())
     }
@@ -199,7 +533,7 @@ impl<'a, Type, Shared, Sec> super::Runner<'a, Shared, Event<Sec>, ()> for Type
 where
     Shared: Send + Sync + 'a,
     Sec: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
-    Type: 'static + RotateRunner<'a, Shared, Sec>,
+    Type: 'static + Send + Sync + RotateRunner<'a, Shared, Sec>,
 {
     async fn setup(region: &'a str) -> anyhow::Result<Shared> {
         Self::setup(region).await
@@ -209,76 +543,89 @@ where
         shared: &'a Shared,
         event: crate::LambdaEvent<'a, Event<Sec>>,
     ) -> anyhow::Result<()> {
-        let smc = Smc::new(event.region).await?;
-        log::info!("{:?}", event.event.step);
-        match event.event.step {
-            Step::Create => {
-                let secret_cur = smc
-                    .get_secret_value_current::<Sec>(&event.event.secret_id)
-                    .await?;
-                let secret_new = smc
-                    .get_secret_value_pending::<Sec>(&event.event.secret_id)
-                    .await;
-                if let Ok(secret_new) = secret_new {
-                    if secret_new.version_id != secret_cur.version_id {
-                        log::info!("Found existing pending value.");
-                        return Ok(());
-                    }
-                }
-                log::info!("Creating new secret value.");
-                let secret = Self::create(shared, secret_cur.inner, &smc).await?;
-                smc.put_secret_value_pending(
-                    &event.event.secret_id,
-                    Some(&event.event.client_request_token),
-                    &secret,
-                )
-                .await?;
-                Ok(())
-            }
-            Step::Set => {
-                log::info!("Setting secret on remote system.");
-                let secret_new = smc
-                    .get_secret_value_pending(&event.event.secret_id)
-                    .await?
-                    .inner;
-                if Self::test(shared, SecretContainer::clone(&secret_new))
-                    .await
-                    .is_err()
-                {
-                    let secret_cur = smc
-                        .get_secret_value_current(&event.event.secret_id)
-                        .await?
-                        .inner;
-                    Self::set(shared, secret_cur, secret_new).await?;
-                } else {
-                    log::info!("Password already set in remote system.");
-                }
-                Ok(())
-            }
-            Step::Test => {
-                log::info!("Testing secret on remote system.");
-                let secret = smc
-                    .get_secret_value_pending(&event.event.secret_id)
-                    .await?
-                    .inner;
-                Self::test(shared, secret).await?;
-                Ok(())
-            }
-            Step::Finish => {
-                log::info!("Finishing secret deployment.");
-                let secret_current: smc::Secret<Sec> =
-                    smc.get_secret_value_current(&event.event.secret_id).await?;
-                let secret_pending: smc::Secret<Sec> =
-                    smc.get_secret_value_pending(&event.event.secret_id).await?;
-                Self::finish(shared, secret_current.inner, secret_pending.inner).await?;
-                smc.set_pending_secret_value_to_current(
-                    secret_current.arn,
-                    secret_current.version_id,
-                    secret_pending.version_id,
-                )
-                .await?;
-                Ok(())
-            }
+        let secret_region = Self::secret_region(event.region, &event.event);
+        let ctx = RotationContext {
+            secret_id: &event.event.secret_id,
+            lambda_region: event.region,
+            secret_region: &secret_region,
+            client_request_token: &event.event.client_request_token,
+            dry_run: Self::dry_run(),
+            invoked_function_arn: event.invoked_function_arn(),
+        };
+        let smc = Smc::new(&secret_region)
+            .await?
+            .with_stage_labels(Self::stage_labels())
+            .with_kms_key_id(Self::kms_key_id());
+        let step = event.event.step.clone();
+        let run = Self::run_step(shared, step.clone(), &smc, &ctx);
+        execute::<Self, _, _>(shared, step, &smc, &ctx, run).await
+    }
+}
+
+/// Executes `step` by awaiting `run`, wrapped in the lock, pre-flight checks,
+/// metrics, notifications and failure handling of `R`
+async fn execute<'a, R, Shared, Sec>(
+    shared: &'a Shared,
+    step: Step,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+    run: impl std::future::Future<Output = anyhow::Result<()>> + Send,
+) -> anyhow::Result<()>
+where
+    Shared: Send + Sync + 'a,
+    Sec: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+    R: RotateRunner<'a, Shared, Sec> + ?Sized,
+{
+    if ctx.dry_run {
+        log::info!("{:?} (dry run)", step);
+    } else {
+        log::info!("{:?}", step);
+    }
+    let started = std::time::Instant::now();
+    let locked = match R::lock_lease().filter(|_| !ctx.dry_run) {
+        Some(lease) => smc
+            .acquire_lock(ctx.secret_id, ctx.client_request_token, lease)
+            .await
+            .map(|()| true),
+        None => Ok(false),
+    };
+    let acquired = matches!(locked, Ok(true));
+    let res = match locked {
+        Ok(_) if R::preflight_checks() => pipeline::preflight(&step, smc, ctx).await,
+        Ok(_) => Ok(true),
+        Err(err) => Err(err),
+    };
+    let (res, executed) = match res {
+        Ok(true) => (run.await, true),
+        Ok(false) => (Ok(()), false),
+        Err(err) => (Err(err), true),
+    };
+    if acquired {
+        if let Err(err) = smc
+            .release_lock(ctx.secret_id, ctx.client_request_token)
+            .await
+        {
+            log::warn!("Unable to release rotation lock: {:?}", err);
         }
     }
+    let metrics = StepMetrics::new(step.clone(), ctx, started.elapsed(), &res);
+    R::on_step_metrics(shared, &metrics).await;
+    let completed = executed && step == Step::Finish;
+    if let Some(topic) = R::notification_topic().filter(|_| completed || res.is_err()) {
+        if ctx.dry_run {
+            log::info!("Would publish rotation notification to {}.", topic);
+        } else {
+            #[cfg(feature = "rotate_sns")]
+            notification::notify(&topic, step.clone(), smc, ctx, &res).await;
+            #[cfg(not(feature = "rotate_sns"))]
+            log::warn!(
+                "Unable to publish rotation notification to {}. Requires the feature rotate_sns.",
+                topic
+            );
+        }
+    }
+    match res {
+        Ok(()) => Ok(()),
+        Err(err) => R::on_rotation_failure(shared, step, err, smc, ctx).await,
+    }
 }
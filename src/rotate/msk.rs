@@ -0,0 +1,535 @@
+//! Provides a ready [`super::RotateRunner`] for SASL/SCRAM users of Amazon MSK clusters.
+//!
+//! MSK reads the current value of secrets, which are associated with the cluster,
+//! so a pending password can't be set on the brokers ahead of time. Instead, `set`
+//! associates the secret with the cluster with `BatchAssociateScramSecret`, unless it
+//! is associated already, and `test` is skipped. Once `finish` marked the new password
+//! as current, the brokers take a moment to pick it up, so `finish` waits until a
+//! SASL/SCRAM-SHA-512 authentication with the new password succeeds on every broker.
+//!
+//! The cluster is configured by the env variables [`CLUSTER_ARN_ENV`] and
+//! [`BOOTSTRAP_BROKERS_ENV`]. As required by MSK, the name of the secret has to start
+//! with `AmazonMSK_` and it has to be encrypted with a customer managed KMS key. Besides
+//! the feature `rotate_msk`, one of the features `rotate_rusoto` or `rotate_aws_sdk` has
+//! to be enabled. The lambda requires the permissions `kafka:ListScramSecrets` and
+//! `kafka:BatchAssociateScramSecret` in addition to the usual ones.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, lambda_runtime_types::rotate::msk::MskScramRotation, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc, Step};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Env variable with the arn of the MSK cluster
+pub const CLUSTER_ARN_ENV: &str = "LAMBDA_RUNTIME_TYPES_MSK_CLUSTER_ARN";
+
+/// Env variable with the comma separated SASL/SCRAM bootstrap brokers of
+/// the MSK cluster, e.g. `b-1.example.kafka.eu-central-1.amazonaws.com:9096`
+pub const BOOTSTRAP_BROKERS_ENV: &str = "LAMBDA_RUNTIME_TYPES_MSK_BOOTSTRAP_BROKERS";
+
+/// Retry policy used while waiting for the brokers to accept a new password
+const PROPAGATION_POLICY: crate::retry::Policy = crate::retry::Policy::exponential(8)
+    .with_base_delay(std::time::Duration::from_secs(2))
+    .with_max_delay(std::time::Duration::from_secs(30));
+
+/// Mechanism of the SASL authentication
+const MECHANISM: &str = "SCRAM-SHA-512";
+
+/// Client id which is sent with every request to the brokers
+const CLIENT_ID: &str = "lambda-runtime-types";
+
+/// Upper bound of the size of a response. The responses to the SASL requests are
+/// small, so larger sizes are rejected instead of allocated
+const MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+/// Upper bound of the PBKDF2 iterations requested by a broker. Kafka uses 4096
+/// by default, so larger counts are rejected instead of blocking the invocation
+const MAX_ITERATIONS: u32 = 100_000;
+
+const SASL_HANDSHAKE: i16 = 17;
+const SASL_AUTHENTICATE: i16 = 36;
+
+/// Secret of a SASL/SCRAM user, in the format which is required by MSK
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct MskScramSecret {
+    /// Name of the user
+    pub username: String,
+    /// Password of the user
+    pub password: String,
+}
+
+impl std::fmt::Debug for MskScramSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MskScramSecret")
+            .field("username", &self.username)
+            .field("password", &"[...]")
+            .finish()
+    }
+}
+
+/// MSK cluster whose SCRAM secrets are rotated
+#[derive(Debug, Clone)]
+pub struct MskCluster {
+    /// Arn of the cluster
+    pub cluster_arn: String,
+    /// SASL/SCRAM bootstrap brokers as `host:port`
+    pub bootstrap_brokers: Vec<String>,
+    /// Whether connections to the brokers use TLS. MSK always requires TLS for SASL/SCRAM
+    pub tls: bool,
+}
+
+impl MskCluster {
+    /// Reads the cluster from the env variables [`CLUSTER_ARN_ENV`] and [`BOOTSTRAP_BROKERS_ENV`]
+    pub fn from_env() -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let cluster_arn = std::env::var(CLUSTER_ARN_ENV)
+            .with_context(|| format!("Env variable {} is missing", CLUSTER_ARN_ENV))?;
+        let bootstrap_brokers: Vec<_> = std::env::var(BOOTSTRAP_BROKERS_ENV)
+            .with_context(|| format!("Env variable {} is missing", BOOTSTRAP_BROKERS_ENV))?
+            .split(',')
+            .map(str::trim)
+            .filter(|broker| !broker.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+        anyhow::ensure!(
+            !bootstrap_brokers.is_empty(),
+            "Env variable {} contains no brokers",
+            BOOTSTRAP_BROKERS_ENV
+        );
+        Ok(Self {
+            cluster_arn,
+            bootstrap_brokers,
+            tls: true,
+        })
+    }
+
+    /// Authenticates with the credentials of `secret` on every bootstrap broker
+    pub async fn check_credentials(&self, secret: &MskScramSecret) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        for broker in &self.bootstrap_brokers {
+            let mut client = KafkaClient::connect(broker, self.tls).await?;
+            client
+                .authenticate(&secret.username, &secret.password)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Unable to authenticate as {} on broker {}",
+                        secret.username, broker
+                    )
+                })?;
+        }
+        Ok(())
+    }
+}
+
+/// Page of the response of `ListScramSecrets`
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ScramSecretsPage {
+    #[serde(default)]
+    pub(crate) secret_arn_list: Vec<String>,
+    pub(crate) next_token: Option<String>,
+}
+
+/// Response of `BatchAssociateScramSecret`
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AssociateResponse {
+    #[serde(default)]
+    unprocessed_scram_secrets: Vec<UnprocessedScramSecret>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnprocessedScramSecret {
+    secret_arn: Option<String>,
+    error_code: Option<String>,
+    error_message: Option<String>,
+}
+
+impl AssociateResponse {
+    /// Fails if a secret could not be associated with the cluster
+    pub(crate) fn check(&self, cluster_arn: &str) -> anyhow::Result<()> {
+        if self.unprocessed_scram_secrets.is_empty() {
+            return Ok(());
+        }
+        let errors: Vec<_> = self
+            .unprocessed_scram_secrets
+            .iter()
+            .map(|secret| {
+                format!(
+                    "{}: {} ({})",
+                    secret.secret_arn.as_deref().unwrap_or_default(),
+                    secret.error_message.as_deref().unwrap_or_default(),
+                    secret.error_code.as_deref().unwrap_or_default()
+                )
+            })
+            .collect();
+        anyhow::bail!(
+            "Unable to associate secrets with cluster {}: {}",
+            cluster_arn,
+            errors.join("; ")
+        )
+    }
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Stream for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// Minimal Kafka client, which only supports the SASL requests
+struct KafkaClient {
+    stream: Box<dyn Stream>,
+    correlation_id: i32,
+}
+
+impl KafkaClient {
+    async fn connect(broker: &str, tls: bool) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let host = broker
+            .rsplit_once(':')
+            .map_or(broker, |(host, _)| host)
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        let tcp = tokio::net::TcpStream::connect(broker)
+            .await
+            .with_context(|| format!("Unable to connect to kafka broker {}", broker))?;
+        let stream: Box<dyn Stream> = if tls {
+            let connector = native_tls::TlsConnector::new()
+                .context("Unable to prepare TLS Connection for Kafka")?;
+            let stream = tokio_native_tls::TlsConnector::from(connector)
+                .connect(host, tcp)
+                .await
+                .with_context(|| format!("Unable to establish TLS with kafka broker {}", broker))?;
+            Box::new(stream)
+        } else {
+            Box::new(tcp)
+        };
+        Ok(Self {
+            stream,
+            correlation_id: 0,
+        })
+    }
+
+    /// Performs a SCRAM-SHA-512 authentication
+    async fn authenticate(&mut self, username: &str, password: &str) -> anyhow::Result<()> {
+        use base64::Engine;
+
+        let response = self.request(SASL_HANDSHAKE, 1, &string(MECHANISM)?).await?;
+        let mut data = response.as_slice();
+        let error_code = read_i16(&mut data)?;
+        anyhow::ensure!(
+            error_code == 0,
+            "Broker does not support {}, error code {}",
+            MECHANISM,
+            error_code
+        );
+
+        let mut nonce = [0; 18];
+        ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut nonce)
+            .map_err(|_| anyhow::anyhow!("Unable to generate nonce"))?;
+        let client_nonce = base64::engine::general_purpose::STANDARD.encode(nonce);
+        let client_first_bare = format!("n={},r={}", sasl_name(username), client_nonce);
+        let server_first = self
+            .sasl_authenticate(format!("n,,{}", client_first_bare).as_bytes())
+            .await?;
+        let server_first = String::from_utf8(server_first)?;
+        let (server_nonce, salt, iterations) = parse_server_first(&server_first)?;
+        anyhow::ensure!(
+            server_nonce.starts_with(&client_nonce),
+            "Broker responded with an invalid nonce"
+        );
+
+        let salt = base64::engine::general_purpose::STANDARD.decode(salt)?;
+        let mut salted_password = [0; 64];
+        ring::pbkdf2::derive(
+            ring::pbkdf2::PBKDF2_HMAC_SHA512,
+            iterations,
+            &salt,
+            password.as_bytes(),
+            &mut salted_password,
+        );
+        let salted_password = ring::hmac::Key::new(ring::hmac::HMAC_SHA512, &salted_password);
+        let client_key = ring::hmac::sign(&salted_password, b"Client Key");
+        let stored_key = ring::digest::digest(&ring::digest::SHA512, client_key.as_ref());
+        let client_final_without_proof = format!("c=biws,r={}", server_nonce);
+        let auth_message = format!(
+            "{},{},{}",
+            client_first_bare, server_first, client_final_without_proof
+        );
+        let client_signature = ring::hmac::sign(
+            &ring::hmac::Key::new(ring::hmac::HMAC_SHA512, stored_key.as_ref()),
+            auth_message.as_bytes(),
+        );
+        let proof: Vec<_> = client_key
+            .as_ref()
+            .iter()
+            .zip(client_signature.as_ref())
+            .map(|(key, signature)| key ^ signature)
+            .collect();
+        let client_final = format!(
+            "{},p={}",
+            client_final_without_proof,
+            base64::engine::general_purpose::STANDARD.encode(proof)
+        );
+        let server_final = self.sasl_authenticate(client_final.as_bytes()).await?;
+        let server_final = String::from_utf8(server_final)?;
+        if let Some(error) = server_final.strip_prefix("e=") {
+            anyhow::bail!("Authentication failed: {}", error);
+        }
+        let server_key = ring::hmac::sign(&salted_password, b"Server Key");
+        let server_signature = ring::hmac::sign(
+            &ring::hmac::Key::new(ring::hmac::HMAC_SHA512, server_key.as_ref()),
+            auth_message.as_bytes(),
+        );
+        anyhow::ensure!(
+            server_final.strip_prefix("v=")
+                == Some(
+                    base64::engine::general_purpose::STANDARD
+                        .encode(server_signature)
+                        .as_str()
+                ),
+            "Broker responded with an invalid server signature"
+        );
+        Ok(())
+    }
+
+    /// Sends a `SaslAuthenticate` request and returns the auth bytes of the broker
+    async fn sasl_authenticate(&mut self, auth_bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let response = self
+            .request(SASL_AUTHENTICATE, 0, &bytes(auth_bytes)?)
+            .await?;
+        let mut data = response.as_slice();
+        let error_code = read_i16(&mut data)?;
+        let error_message = read_string(&mut data)?;
+        anyhow::ensure!(
+            error_code == 0,
+            "Broker responded with error {}: {}",
+            error_code,
+            error_message.unwrap_or_default()
+        );
+        read_bytes(&mut data)
+    }
+
+    /// Sends a request and returns the body of its response
+    async fn request(
+        &mut self,
+        api_key: i16,
+        api_version: i16,
+        body: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        use anyhow::Context;
+
+        self.correlation_id += 1;
+        let mut request = api_key.to_be_bytes().to_vec();
+        request.extend_from_slice(&api_version.to_be_bytes());
+        request.extend_from_slice(&self.correlation_id.to_be_bytes());
+        request.extend(string(CLIENT_ID)?);
+        request.extend_from_slice(body);
+        let mut message = i32::try_from(request.len())?.to_be_bytes().to_vec();
+        message.extend(request);
+        self.stream
+            .write_all(&message)
+            .await
+            .context("Unable to send request to kafka broker")?;
+        self.stream
+            .flush()
+            .await
+            .context("Unable to send request to kafka broker")?;
+
+        let mut size = [0; 4];
+        self.stream
+            .read_exact(&mut size)
+            .await
+            .context("Kafka broker closed the connection")?;
+        let size = usize::try_from(i32::from_be_bytes(size))
+            .context("Kafka broker responded with a negative size")?;
+        anyhow::ensure!(
+            size <= MAX_RESPONSE_SIZE,
+            "Response of kafka broker exceeds {} bytes: {}",
+            MAX_RESPONSE_SIZE,
+            size
+        );
+        let mut response = vec![0; size];
+        self.stream
+            .read_exact(&mut response)
+            .await
+            .context("Unable to read response of kafka broker")?;
+        let mut data = response.as_slice();
+        let correlation_id = read_i32(&mut data)?;
+        anyhow::ensure!(
+            correlation_id == self.correlation_id,
+            "Kafka broker responded to request {} instead of {}",
+            correlation_id,
+            self.correlation_id
+        );
+        Ok(data.to_vec())
+    }
+}
+
+/// Escapes `,` and `=` in the username of a SCRAM message
+fn sasl_name(username: &str) -> String {
+    username.replace('=', "=3D").replace(',', "=2C")
+}
+
+/// Splits the first message of the server into nonce, salt and iterations
+fn parse_server_first(message: &str) -> anyhow::Result<(&str, &str, std::num::NonZeroU32)> {
+    use anyhow::Context;
+
+    let attribute = |name: &str| {
+        message
+            .split(',')
+            .find_map(|attribute| attribute.strip_prefix(name))
+            .with_context(|| format!("Invalid SCRAM message of broker: {}", message))
+    };
+    let nonce = attribute("r=")?;
+    let salt = attribute("s=")?;
+    let iterations: std::num::NonZeroU32 = attribute("i=")?
+        .parse()
+        .with_context(|| format!("Invalid iterations in SCRAM message: {}", message))?;
+    anyhow::ensure!(
+        iterations.get() <= MAX_ITERATIONS,
+        "Broker requested {} SCRAM iterations, which exceeds the maximum of {}",
+        iterations,
+        MAX_ITERATIONS
+    );
+    Ok((nonce, salt, iterations))
+}
+
+fn string(value: &str) -> anyhow::Result<Vec<u8>> {
+    let mut data = i16::try_from(value.len())?.to_be_bytes().to_vec();
+    data.extend_from_slice(value.as_bytes());
+    Ok(data)
+}
+
+fn bytes(value: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut data = i32::try_from(value.len())?.to_be_bytes().to_vec();
+    data.extend_from_slice(value);
+    Ok(data)
+}
+
+fn take<'b>(data: &mut &'b [u8], len: usize) -> anyhow::Result<&'b [u8]> {
+    anyhow::ensure!(data.len() >= len, "Truncated response of kafka broker");
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn read_i16(data: &mut &[u8]) -> anyhow::Result<i16> {
+    let value = take(data, 2)?;
+    Ok(i16::from_be_bytes([value[0], value[1]]))
+}
+
+fn read_i32(data: &mut &[u8]) -> anyhow::Result<i32> {
+    let value = take(data, 4)?;
+    Ok(i32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+/// Reads a nullable string
+fn read_string(data: &mut &[u8]) -> anyhow::Result<Option<String>> {
+    let len = read_i16(data)?;
+    if len < 0 {
+        return Ok(None);
+    }
+    let value = take(data, usize::try_from(len)?)?;
+    Ok(Some(String::from_utf8_lossy(value).into_owned()))
+}
+
+fn read_bytes(data: &mut &[u8]) -> anyhow::Result<Vec<u8>> {
+    let len = read_i32(data)?;
+    Ok(take(data, usize::try_from(len.max(0))?)?.to_vec())
+}
+
+/// Rotates the password of the SCRAM user in the secret
+#[derive(Debug)]
+pub struct MskScramRotation;
+
+#[async_trait::async_trait]
+impl<'a> super::RotateRunner<'a, MskCluster, MskScramSecret> for MskScramRotation {
+    async fn setup(_region: &'a str) -> anyhow::Result<MskCluster> {
+        MskCluster::from_env()
+    }
+
+    async fn run_step(
+        shared: &'a MskCluster,
+        step: Step,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        match step {
+            // The brokers only accept the pending password once it is current
+            Step::Set => super::pipeline::set_without_probe::<Self, _, _>(shared, smc, ctx).await,
+            Step::Test => {
+                log::info!("Pending password is tested once the rotation finished.");
+                Ok(())
+            }
+            Step::Finish => {
+                super::pipeline::finish::<Self, _, _>(shared, smc, ctx).await?;
+                if ctx.dry_run {
+                    log::info!("Dry run: Would wait until the brokers accept the new password.");
+                    return Ok(());
+                }
+                let secret = smc.get_secret::<MskScramSecret>(ctx.secret_id).await?;
+                crate::retry::retry(&PROPAGATION_POLICY, || {
+                    Self::test(shared, SecretContainer::clone(&secret), smc, ctx)
+                })
+                .await
+                .context("MSK did not accept the new password in time")
+            }
+            step => super::pipeline::step::<Self, _, _>(shared, step, smc, ctx).await,
+        }
+    }
+
+    async fn create(
+        _shared: &'a MskCluster,
+        mut secret_cur: SecretContainer<MskScramSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<MskScramSecret>> {
+        secret_cur.password = smc.generate_new_password(false, None).await?;
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        shared: &'a MskCluster,
+        _secret_cur: SecretContainer<MskScramSecret>,
+        _secret_new: SecretContainer<MskScramSecret>,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        let secret_arn = smc
+            .get_secret_value_current::<MskScramSecret>(ctx.secret_id)
+            .await?
+            .arn;
+        if smc
+            .scram_secrets(&shared.cluster_arn)
+            .await?
+            .contains(&secret_arn)
+        {
+            log::info!("Secret is associated with the cluster already.");
+            return Ok(());
+        }
+        log::info!("Associating secret with cluster {}.", shared.cluster_arn);
+        smc.associate_scram_secret(&shared.cluster_arn, &secret_arn)
+            .await
+    }
+
+    async fn test(
+        shared: &'a MskCluster,
+        secret_new: SecretContainer<MskScramSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        shared.check_credentials(&secret_new).await
+    }
+}
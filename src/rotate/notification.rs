@@ -0,0 +1,121 @@
+#[cfg(feature = "rotate_sns")]
+use super::{RotationContext, Smc};
+use super::{Step, StepOutcome};
+
+/// Message which is published to the topic of [`super::RotateRunner::notification_topic`]
+/// once a rotation completed or a step failed
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone, serde::Serialize)]
+#[non_exhaustive]
+pub struct Notification<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// Id of the rotated secret
+    pub secret_id: &'a str,
+    /// Step which completed the rotation or failed
+    pub step: Step,
+    /// Whether the rotation completed or failed
+    pub outcome: StepOutcome,
+    /// Version id of the secret which was current before the rotation,
+    /// if it could be determined
+    pub old_version_id: Option<String>,
+    /// Version id of the new secret, which is the client request token
+    pub new_version_id: &'a str,
+    /// Error of a failed step
+    pub error: Option<String>,
+}
+
+impl<'a> Notification<'a> {
+    /// Subject of the published message
+    pub fn subject(&self) -> String {
+        let outcome = match self.outcome {
+            StepOutcome::Success => "completed",
+            StepOutcome::Failure => "failed",
+        };
+        let subject = format!("Rotation of {} {}", self.secret_id, outcome);
+        // Subjects of SNS messages are limited to 100 characters
+        subject.chars().take(100).collect()
+    }
+}
+
+/// Publishes a [`Notification`] about `step` to `topic_arn`. Failures to
+/// publish are only logged, so they do not fail the rotation
+#[cfg(feature = "rotate_sns")]
+pub async fn notify(
+    topic_arn: &str,
+    step: Step,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+    result: &anyhow::Result<()>,
+) {
+    let outcome = match result {
+        Ok(()) => StepOutcome::Success,
+        Err(_) => StepOutcome::Failure,
+    };
+    let notification = Notification {
+        kind: "rotation_notification",
+        secret_id: ctx.secret_id,
+        step,
+        outcome,
+        old_version_id: old_version_id(smc, ctx, outcome).await,
+        new_version_id: ctx.client_request_token,
+        error: result.as_ref().err().map(|err| format!("{:#}", err)),
+    };
+    let message = match serde_json::to_string(&notification) {
+        Ok(message) => message,
+        Err(err) => {
+            log::error!("Unable to serialize rotation notification: {:?}", err);
+            return;
+        }
+    };
+    if let Err(err) = smc
+        .publish_notification(topic_arn, &notification.subject(), &message)
+        .await
+    {
+        log::error!("Unable to publish rotation notification: {:?}", err);
+    }
+}
+
+/// Version id of the secret which was current before the rotation. After a
+/// completed rotation, it carries the previous label, otherwise the current one
+#[cfg(feature = "rotate_sns")]
+async fn old_version_id(
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+    outcome: StepOutcome,
+) -> Option<String> {
+    let labels = smc.stage_labels();
+    let stage = match outcome {
+        StepOutcome::Success => &labels.previous,
+        StepOutcome::Failure => &labels.current,
+    };
+    match smc.describe_secret(ctx.secret_id).await {
+        Ok(description) => description
+            .version_ids_to_stages
+            .into_iter()
+            .find(|(_, stages)| stages.contains(stage))
+            .map(|(version_id, _)| version_id),
+        Err(err) => {
+            log::warn!(
+                "Unable to determine old version id for rotation notification: {:?}",
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Form encoded body of an SNS `Publish` request
+#[cfg(feature = "rotate_sns")]
+pub fn publish_request_body(topic_arn: &str, subject: &str, message: &str) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .append_pair("Action", "Publish")
+        .append_pair("Version", "2010-03-31")
+        .append_pair("TopicArn", topic_arn)
+        .append_pair("Subject", subject)
+        .append_pair("Message", message)
+        .finish()
+}
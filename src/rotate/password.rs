@@ -0,0 +1,52 @@
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+// Same punctuation as `GetRandomPassword`, without `"`, which is excluded by [`super::Smc`]
+const PUNCTUATION: &[u8] = b"!#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// Default length of `GetRandomPassword`
+const DEFAULT_LENGTH: usize = 32;
+
+/// Generates a password locally with the random number generator of the operating system.
+///
+/// Like `GetRandomPassword`, the password contains at least one character
+/// of every included type and defaults to a length of 32.
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_local_password")))]
+pub fn generate_password(punctuation: bool, length: Option<usize>) -> anyhow::Result<String> {
+    let length = length.unwrap_or(DEFAULT_LENGTH);
+    let mut types = vec![LOWERCASE, UPPERCASE, DIGITS];
+    if punctuation {
+        types.push(PUNCTUATION);
+    }
+    anyhow::ensure!(
+        length >= types.len(),
+        "Password length {} is too short to include every character type",
+        length
+    );
+    let charset = types.concat();
+    loop {
+        let mut password = Vec::with_capacity(length);
+        while password.len() < length {
+            password.push(charset[random_index(charset.len())?]);
+        }
+        if types
+            .iter()
+            .all(|chars| password.iter().any(|c| chars.contains(c)))
+        {
+            return Ok(String::from_utf8(password)?);
+        }
+    }
+}
+
+/// Returns an index below `len`, without the bias of a plain modulo
+fn random_index(len: usize) -> anyhow::Result<usize> {
+    let limit = 256 - 256 % len;
+    loop {
+        let mut byte = [0];
+        getrandom::getrandom(&mut byte)
+            .map_err(|err| anyhow::anyhow!("Unable to generate random data: {}", err))?;
+        if usize::from(byte[0]) < limit {
+            return Ok(usize::from(byte[0]) % len);
+        }
+    }
+}
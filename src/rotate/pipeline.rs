@@ -0,0 +1,448 @@
+//! Default orchestration of the rotation steps.
+//!
+//! [`RotateRunner::run_step`] executes [`step`] by default, which calls the
+//! function of the current step in this module. To insert additional phases or
+//! change how a step is orchestrated, override [`RotateRunner::run_step`] and
+//! call the functions of this module for the steps which should keep their
+//! default behavior:
+//!
+//! ```no_run
+//! # #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+//! # struct Secret;
+//! # async fn notify(secret_id: &str) -> anyhow::Result<()> { Ok(()) }
+//! use lambda_runtime_types::rotate::{pipeline, RotateRunner, RotationContext, SecretContainer, Smc, Step};
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> RotateRunner<'a, (), Secret> for Runner {
+//!     # async fn setup(_region: &'a str) -> anyhow::Result<()> { Ok(()) }
+//!     # async fn create(_shared: &'a (), secret_cur: SecretContainer<Secret>, _smc: &Smc, _ctx: &RotationContext<'_>) -> anyhow::Result<SecretContainer<Secret>> { Ok(secret_cur) }
+//!     # async fn set(_shared: &'a (), _secret_cur: SecretContainer<Secret>, _secret_new: SecretContainer<Secret>, _smc: &Smc, _ctx: &RotationContext<'_>) -> anyhow::Result<()> { Ok(()) }
+//!     # async fn test(_shared: &'a (), _secret_new: SecretContainer<Secret>, _smc: &Smc, _ctx: &RotationContext<'_>) -> anyhow::Result<()> { Ok(()) }
+//!     // ...
+//!
+//!     async fn run_step(
+//!         shared: &'a (),
+//!         step: Step,
+//!         smc: &Smc,
+//!         ctx: &RotationContext<'_>,
+//!     ) -> anyhow::Result<()> {
+//!         match step {
+//!             // The service does not accept the new secret before it is set,
+//!             // so probing it with `test` is not necessary
+//!             Step::Set => pipeline::set_without_probe::<Self, _, _>(shared, smc, ctx).await,
+//!             Step::Finish => {
+//!                 pipeline::finish::<Self, _, _>(shared, smc, ctx).await?;
+//!                 notify(ctx.secret_id).await
+//!             }
+//!             step => pipeline::step::<Self, _, _>(shared, step, smc, ctx).await,
+//!         }
+//!     }
+//! }
+//! ```
+
+use super::{RotateRunner, RotationContext, SecretContainer, Smc, Step};
+
+/// Staging label which marks the version, for which `set` completed.
+/// See [`RotateRunner::step_markers`]
+pub const SET_MARKER: &str = "LambdaRuntimeTypesSet";
+
+/// Staging label which marks the version, for which `test` completed.
+/// See [`RotateRunner::step_markers`]
+pub const TEST_MARKER: &str = "LambdaRuntimeTypesTested";
+
+/// Executes the default implementation of `step`
+pub async fn step<'a, R, Shared, Secret>(
+    shared: &'a Shared,
+    step: Step,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    match step {
+        Step::Create => create::<R, _, _>(shared, smc, ctx).await,
+        Step::Set => set::<R, _, _>(shared, smc, ctx).await,
+        Step::Test => test::<R, _, _>(shared, smc, ctx).await,
+        Step::Finish => finish::<R, _, _>(shared, smc, ctx).await,
+        Step::Rollback => rollback::<R, _, _>(shared, smc, ctx).await,
+        Step::Other(step) => R::custom_step(shared, &step, smc, ctx).await,
+    }
+}
+
+/// Verifies that the secret is configured to be rotated by this lambda and that
+/// the request token belongs to a pending version.
+///
+/// Returns `false` if the version is current already, so `step` has nothing to do.
+/// [`Step::Rollback`] and [`Step::Other`] are not checked, as they are not
+/// necessarily sent by the `SecretManager`. See [`RotateRunner::preflight_checks`]
+pub async fn preflight(step: &Step, smc: &Smc, ctx: &RotationContext<'_>) -> anyhow::Result<bool> {
+    if matches!(step, Step::Rollback | Step::Other(_)) {
+        return Ok(true);
+    }
+    let description = smc.describe_secret(ctx.secret_id).await?;
+    if !description.rotation_enabled {
+        anyhow::bail!("Secret {} is not enabled for rotation", ctx.secret_id);
+    }
+    if let Some(rotation_lambda_arn) = &description.rotation_lambda_arn {
+        if unqualified(rotation_lambda_arn) != unqualified(ctx.invoked_function_arn) {
+            anyhow::bail!(
+                "Secret {} is configured to be rotated by {}, but invoked lambda is {}",
+                ctx.secret_id,
+                rotation_lambda_arn,
+                ctx.invoked_function_arn
+            );
+        }
+    }
+    let stages = description
+        .version_ids_to_stages
+        .get(ctx.client_request_token)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Secret version {} has no stage for rotation of secret {}",
+                ctx.client_request_token,
+                ctx.secret_id
+            )
+        })?;
+    let labels = smc.stage_labels();
+    if stages.contains(&labels.current) {
+        log::info!(
+            "Secret version {} is already set as {} for secret {}.",
+            ctx.client_request_token,
+            labels.current,
+            ctx.secret_id
+        );
+        return Ok(false);
+    }
+    if !stages.contains(&labels.pending) {
+        anyhow::bail!(
+            "Secret version {} is not set as {} for rotation of secret {}",
+            ctx.client_request_token,
+            labels.pending,
+            ctx.secret_id
+        );
+    }
+    Ok(true)
+}
+
+/// Strips the version or alias from a lambda arn
+fn unqualified(function_arn: &str) -> &str {
+    match function_arn.match_indices(':').nth(6) {
+        Some((idx, _)) => &function_arn[..idx],
+        None => function_arn,
+    }
+}
+
+/// Creates a new secret with [`RotateRunner::create`] and stores it as pending
+/// value, unless a pending value exists already.
+///
+/// The new secret is validated with [`RotateRunner::validate_secret`] before it is stored.
+/// The pending value is stored as `SecretBinary` if the current value is stored that way.
+/// A pending value of an abandoned rotation, which belongs to a different request
+/// token, is replaced
+pub async fn create<'a, R, Shared, Secret>(
+    shared: &'a Shared,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    use anyhow::Context;
+
+    let secret_cur = smc
+        .get_secret_value_current::<Secret>(ctx.secret_id)
+        .await?;
+    let secret_new = smc.get_secret_value_pending::<Secret>(ctx.secret_id).await;
+    if let Ok(secret_new) = secret_new {
+        if secret_new.version_id == ctx.client_request_token {
+            log::info!("Found existing pending value.");
+            return Ok(());
+        }
+        if secret_new.version_id != secret_cur.version_id {
+            log::warn!(
+                "Found stale pending value of version {}, which belongs to an abandoned rotation.",
+                secret_new.version_id
+            );
+            if !ctx.dry_run {
+                smc.remove_version_stage(
+                    ctx.secret_id,
+                    &smc.stage_labels().pending,
+                    &secret_new.version_id,
+                )
+                .await
+                .context("Unable to remove stale pending value")?;
+            }
+        }
+    }
+    log::info!("Creating new secret value.");
+    let binary = secret_cur.binary;
+    let secret = R::create(shared, secret_cur.inner, smc, ctx).await?;
+    R::validate_secret(&secret).context("New secret value is invalid")?;
+    if ctx.dry_run {
+        log::info!(
+            "Dry run: Would store new secret value as pending version {}.",
+            ctx.client_request_token
+        );
+        return Ok(());
+    }
+    smc.put_secret_value_pending(
+        ctx.secret_id,
+        Some(ctx.client_request_token),
+        &secret,
+        binary,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Sets the pending secret with [`RotateRunner::set`], unless
+/// [`RotateRunner::test`] shows that it is set already
+pub async fn set<'a, R, Shared, Secret>(
+    shared: &'a Shared,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    log::info!("Setting secret on remote system.");
+    let secret_new = match pending::<Secret>(smc, ctx).await? {
+        Some(secret_new) => secret_new,
+        None => {
+            log::info!("Dry run: Would set pending secret value on remote system.");
+            return Ok(());
+        }
+    };
+    if completed::<R, _, _>(&secret_new, SET_MARKER) {
+        log::info!("Password already set in remote system, according to step marker.");
+        return Ok(());
+    }
+    if R::test(shared, SecretContainer::clone(&secret_new.inner), smc, ctx)
+        .await
+        .is_ok()
+    {
+        log::info!("Password already set in remote system.");
+        return mark::<R, _, _>(smc, ctx, &secret_new.version_id, SET_MARKER).await;
+    }
+    apply::<R, _, _>(shared, smc, ctx, secret_new).await
+}
+
+/// Sets the pending secret with [`RotateRunner::set`] without
+/// checking whether it is set already
+pub async fn set_without_probe<'a, R, Shared, Secret>(
+    shared: &'a Shared,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    log::info!("Setting secret on remote system.");
+    match pending::<Secret>(smc, ctx).await? {
+        Some(secret_new) if completed::<R, _, _>(&secret_new, SET_MARKER) => {
+            log::info!("Password already set in remote system, according to step marker.");
+            Ok(())
+        }
+        Some(secret_new) => apply::<R, _, _>(shared, smc, ctx, secret_new).await,
+        None => {
+            log::info!("Dry run: Would set pending secret value on remote system.");
+            Ok(())
+        }
+    }
+}
+
+async fn apply<'a, R, Shared, Secret>(
+    shared: &'a Shared,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+    secret_new: super::smc::Secret<Secret>,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    if ctx.dry_run {
+        log::info!("Dry run: Would set pending secret value on remote system.");
+        return Ok(());
+    }
+    let secret_cur = smc.get_secret_value_current(ctx.secret_id).await?.inner;
+    R::set(
+        shared,
+        secret_cur,
+        SecretContainer::clone(&secret_new.inner),
+        smc,
+        ctx,
+    )
+    .await?;
+    mark::<R, _, _>(smc, ctx, &secret_new.version_id, SET_MARKER).await
+}
+
+/// Tests the pending secret with [`RotateRunner::test`]
+pub async fn test<'a, R, Shared, Secret>(
+    shared: &'a Shared,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    log::info!("Testing secret on remote system.");
+    match pending::<Secret>(smc, ctx).await? {
+        Some(secret) if completed::<R, _, _>(&secret, TEST_MARKER) => {
+            log::info!("Secret already tested, according to step marker.");
+            Ok(())
+        }
+        Some(secret) => {
+            R::test(shared, SecretContainer::clone(&secret.inner), smc, ctx).await?;
+            mark::<R, _, _>(smc, ctx, &secret.version_id, TEST_MARKER).await
+        }
+        None => {
+            log::info!("Dry run: Would test pending secret value on remote system.");
+            Ok(())
+        }
+    }
+}
+
+/// Completes the rotation with [`RotateRunner::finish`] and
+/// marks the pending secret as current. Skipped if the pending version
+/// carries the current label already, e.g. for a re-delivered event
+pub async fn finish<'a, R, Shared, Secret>(
+    shared: &'a Shared,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    log::info!("Finishing secret deployment.");
+    let secret_current = smc
+        .get_secret_value_current::<Secret>(ctx.secret_id)
+        .await?;
+    let current_label = &smc.stage_labels().current;
+    let secret_pending = match pending::<Secret>(smc, ctx).await? {
+        Some(secret_pending)
+            if secret_pending
+                .version_stages
+                .iter()
+                .any(|stage| stage == current_label) =>
+        {
+            log::info!("Rotation already finished, pending secret value is current.");
+            return Ok(());
+        }
+        Some(secret_pending) if !ctx.dry_run => secret_pending,
+        Some(secret_pending) => {
+            log::info!(
+                "Dry run: Would finish rotation and mark version {} as current.",
+                secret_pending.version_id
+            );
+            return Ok(());
+        }
+        None => {
+            log::info!("Dry run: Would finish rotation and mark pending secret value as current.");
+            return Ok(());
+        }
+    };
+    R::finish(shared, secret_current.inner, secret_pending.inner, smc, ctx).await?;
+    smc.set_pending_secret_value_to_current(
+        secret_current.arn,
+        secret_current.version_id,
+        secret_pending.version_id.clone(),
+    )
+    .await?;
+    if let Some(timeout) = R::replica_sync_timeout() {
+        smc.wait_for_replicas(ctx.secret_id, &secret_pending.version_id, timeout)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Reverts the service with [`RotateRunner::rollback`] and
+/// marks the previous secret as current
+pub async fn rollback<'a, R, Shared, Secret>(
+    shared: &'a Shared,
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+{
+    log::info!("Rolling back to previous secret.");
+    let secret_current = smc
+        .get_secret_value_current::<Secret>(ctx.secret_id)
+        .await?
+        .inner;
+    let secret_previous = smc
+        .get_secret_value_previous::<Secret>(ctx.secret_id)
+        .await?;
+    if ctx.dry_run {
+        log::info!(
+            "Dry run: Would roll back remote system and mark previous secret value as current."
+        );
+        return Ok(());
+    }
+    R::rollback(shared, secret_current, secret_previous, smc, ctx).await?;
+    smc.rollback_to_previous(ctx.secret_id).await
+}
+
+/// Whether `marker` is attached to `secret`, if step markers are enabled
+fn completed<'a, R, Shared, Secret>(secret: &super::smc::Secret<Secret>, marker: &str) -> bool
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send,
+{
+    R::step_markers() && secret.version_stages.iter().any(|stage| stage == marker)
+}
+
+/// Attaches `marker` to the version `version_id`, if step markers are enabled
+async fn mark<'a, R, Shared, Secret>(
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+    version_id: &str,
+    marker: &str,
+) -> anyhow::Result<()>
+where
+    R: RotateRunner<'a, Shared, Secret> + ?Sized,
+    Shared: Send + Sync + 'a,
+    Secret: 'static + Send,
+{
+    if !R::step_markers() || ctx.dry_run {
+        return Ok(());
+    }
+    smc.move_version_stage(ctx.secret_id, marker, version_id)
+        .await
+}
+
+/// Reads the pending secret. In a dry run, a missing pending
+/// secret is expected, as `create` did not store it
+async fn pending<Secret>(
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+) -> anyhow::Result<Option<super::smc::Secret<Secret>>>
+where
+    Secret: serde::de::DeserializeOwned,
+{
+    match smc.get_secret_value_pending(ctx.secret_id).await {
+        Ok(secret) => Ok(Some(secret)),
+        Err(_) if ctx.dry_run => Ok(None),
+        Err(err) => Err(err),
+    }
+}
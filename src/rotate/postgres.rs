@@ -0,0 +1,152 @@
+//! Provides a ready [`super::RotateRunner`] for Postgres users.
+//!
+//! The secret has to contain the standard fields of database secrets in the
+//! `SecretManager`, i.e. `host`, `port`, `username`, `password` and `dbname`.
+//! Connections are established with TLS. Besides the feature `rotate_postgres`,
+//! one of the features `rotate_rusoto` or `rotate_aws_sdk` has to be enabled.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, lambda_runtime_types::rotate::postgres::PostgresRotation, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc};
+
+/// Secret of a Postgres user
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct PostgresSecret {
+    /// Host of the database
+    pub host: String,
+    /// Port of the database. Defaults to 5432
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Name of the user whose password is rotated
+    #[serde(rename = "username", alias = "user")]
+    pub user: String,
+    /// Password of the user
+    pub password: String,
+    /// Database to connect to. Defaults to `postgres`
+    #[serde(default = "default_dbname", alias = "database")]
+    pub dbname: String,
+}
+
+impl std::fmt::Debug for PostgresSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PostgresSecret")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &"[...]")
+            .field("dbname", &self.dbname)
+            .finish()
+    }
+}
+
+const fn default_port() -> u16 {
+    5432
+}
+
+fn default_dbname() -> String {
+    "postgres".into()
+}
+
+impl PostgresSecret {
+    /// Connects to the database with TLS. The connection is driven
+    /// by a background task until the client is dropped
+    pub async fn connect(&self) -> anyhow::Result<tokio_postgres::Client> {
+        use anyhow::Context;
+
+        let connector = native_tls::TlsConnector::new()
+            .context("Unable to prepare TLS Connection for Database")?;
+        let connector = postgres_native_tls::MakeTlsConnector::new(connector);
+        let (client, connection) = tokio_postgres::Config::new()
+            .host(&self.host)
+            .port(self.port)
+            .user(&self.user)
+            .password(&self.password)
+            .dbname(&self.dbname)
+            .ssl_mode(tokio_postgres::config::SslMode::Require)
+            .connect(connector)
+            .await
+            .with_context(|| format!("Unable to connect to database at {}", self.host))?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                log::error!("Connection to postgres database failed: {}", err);
+            }
+        });
+        Ok(client)
+    }
+}
+
+/// Rotates the password of the Postgres user in the secret
+#[derive(Debug)]
+pub struct PostgresRotation;
+
+#[async_trait::async_trait]
+impl<'a> super::RotateRunner<'a, (), PostgresSecret> for PostgresRotation {
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn create(
+        _shared: &'a (),
+        mut secret_cur: SecretContainer<PostgresSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<PostgresSecret>> {
+        secret_cur.password = smc.generate_new_password(false, None).await?;
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        _shared: &'a (),
+        secret_cur: SecretContainer<PostgresSecret>,
+        secret_new: SecretContainer<PostgresSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let query = format!(
+            "ALTER USER {} WITH PASSWORD {}",
+            quote_identifier(&secret_new.user),
+            quote_literal(&secret_new.password)
+        );
+        secret_cur
+            .connect()
+            .await?
+            .batch_execute(&query)
+            .await
+            .context("Unable to change user password")
+    }
+
+    async fn test(
+        _shared: &'a (),
+        secret_new: SecretContainer<PostgresSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        secret_new
+            .connect()
+            .await?
+            .batch_execute("SELECT 1;")
+            .await
+            .context("Connection to database failed")
+    }
+}
+
+/// Quotes an identifier, like a user name, for use in a query
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Quotes a string literal for use in a query. Requires
+/// `standard_conforming_strings`, which is the default of Postgres
+pub fn quote_literal(literal: &str) -> String {
+    format!("'{}'", literal.replace('\'', "''"))
+}
@@ -0,0 +1,284 @@
+//! Provides a ready [`super::RotateRunner`] for RabbitMQ users, including
+//! brokers of Amazon MQ for RabbitMQ.
+//!
+//! `set` changes the password with the management HTTP API and keeps the tags of
+//! the user. By default, the user changes its own password, which requires the tag
+//! `administrator`. Otherwise, `admin_secret_id` refers to a secret with `username`
+//! and `password` of an administrator. Clusters take a moment until every node
+//! accepts the new password, so `set` waits until the management API accepts the
+//! new password. `test` authenticates against `/api/whoami` with the new password
+//! as well, so the user needs one of the tags `management`, `policymaker`,
+//! `monitoring` or `administrator`.
+//!
+//! Besides the feature `rotate_rabbitmq`, one of the features `rotate_rusoto` or
+//! `rotate_aws_sdk` has to be enabled. Users of Amazon MQ for ActiveMQ are managed
+//! through the Amazon MQ API instead and are therefore not supported.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, lambda_runtime_types::rotate::rabbitmq::RabbitMqRotation, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc};
+
+/// Retry policy used while waiting for the broker to accept a new password
+const PROPAGATION_POLICY: crate::retry::Policy = crate::retry::Policy::exponential(6)
+    .with_base_delay(std::time::Duration::from_millis(500))
+    .with_max_delay(std::time::Duration::from_secs(8));
+
+/// Secret of a RabbitMQ user
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct RabbitMqSecret {
+    /// Host of the broker
+    pub host: String,
+    /// AMQP port of the broker, used by the applications. Defaults to 5671
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Name of the user whose password is rotated
+    #[serde(rename = "username", alias = "user")]
+    pub user: String,
+    /// Password of the user
+    pub password: String,
+    /// Virtual host, used by the applications. Defaults to `/`
+    #[serde(default = "default_vhost")]
+    pub vhost: String,
+    /// Whether AMQP connections of the applications use TLS. Defaults to `true`
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+    /// Base url of the management API. Defaults to `https://{host}`,
+    /// which is the url of the management API of Amazon MQ brokers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub management_url: Option<String>,
+    /// Id of a secret with `username` and `password` of an administrator,
+    /// which changes the password. Defaults to the user itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_secret_id: Option<String>,
+}
+
+impl std::fmt::Debug for RabbitMqSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RabbitMqSecret")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &"[...]")
+            .field("vhost", &self.vhost)
+            .field("tls", &self.tls)
+            .field("management_url", &self.management_url)
+            .field("admin_secret_id", &self.admin_secret_id)
+            .finish()
+    }
+}
+
+const fn default_port() -> u16 {
+    5671
+}
+
+fn default_vhost() -> String {
+    "/".into()
+}
+
+const fn default_tls() -> bool {
+    true
+}
+
+/// Credentials of the administrator in `admin_secret_id`
+#[derive(Clone, serde::Deserialize)]
+struct AdminSecret {
+    #[serde(rename = "username", alias = "user")]
+    user: String,
+    password: String,
+}
+
+impl RabbitMqSecret {
+    /// Authenticates against the management API with the credentials of the secret
+    pub async fn check_connection(&self) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let url = format!("{}/api/whoami", self.management_base());
+        let whoami = management(http::Method::GET, &url, &self.user, &self.password, None)
+            .await
+            .with_context(|| format!("Unable to authenticate to rabbitmq as user {}", self.user))?;
+        anyhow::ensure!(
+            whoami["name"] == self.user.as_str(),
+            "RabbitMQ authenticated user {} instead of {}",
+            whoami["name"],
+            self.user
+        );
+        Ok(())
+    }
+
+    /// Base url of the management API without trailing `/`
+    fn management_base(&self) -> String {
+        self.management_url.as_ref().map_or_else(
+            || format!("https://{}", self.host),
+            |url| url.trim_end_matches('/').to_owned(),
+        )
+    }
+
+    /// Changes the password of the user to `password` with the management API,
+    /// authenticated as `admin_user`. The tags of the user are kept
+    pub async fn change_password(
+        &self,
+        password: &str,
+        admin_user: &str,
+        admin_password: &str,
+    ) -> anyhow::Result<()> {
+        let url = format!(
+            "{}/api/users/{}",
+            self.management_base(),
+            encode_path_segment(&self.user)
+        );
+        let user = management(http::Method::GET, &url, admin_user, admin_password, None).await?;
+        // Tags are a list since RabbitMQ 3.9 and a comma separated string before
+        let tags = match &user["tags"] {
+            serde_json::Value::Array(tags) => tags
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+            serde_json::Value::String(tags) => tags.clone(),
+            _ => String::new(),
+        };
+        let body = serde_json::json!({ "password": password, "tags": tags });
+        management(
+            http::Method::PUT,
+            &url,
+            admin_user,
+            admin_password,
+            Some(body),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Sends a request to the management API and returns the json response.
+/// Empty responses are returned as `null`
+async fn management(
+    method: http::Method,
+    url: &str,
+    user: &str,
+    password: &str,
+    body: Option<serde_json::Value>,
+) -> anyhow::Result<serde_json::Value> {
+    use anyhow::Context;
+    use base64::Engine;
+
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    let client = hyper::Client::builder().build::<_, hyper::Body>(connector);
+    let credentials =
+        base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password));
+    let req = http::Request::builder()
+        .method(method.clone())
+        .uri(url)
+        .header(
+            http::header::AUTHORIZATION,
+            format!("Basic {}", credentials),
+        )
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(body.map_or_else(hyper::Body::empty, |body| {
+            hyper::Body::from(body.to_string())
+        }))
+        .with_context(|| format!("Unable to build request to {}", url))?;
+    let res = client
+        .request(req)
+        .await
+        .with_context(|| format!("Request {} {} failed", method, url))?;
+    let status = res.status();
+    let body = hyper::body::to_bytes(res.into_body())
+        .await
+        .with_context(|| format!("Unable to read response of {}", url))?;
+    anyhow::ensure!(
+        status.is_success(),
+        "Request {} {} responded with status {}: {}",
+        method,
+        url,
+        status,
+        String::from_utf8_lossy(&body)
+    );
+    if body.is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+    serde_json::from_slice(&body).with_context(|| format!("Invalid json response of {}", url))
+}
+
+/// Percent-encodes everything except unreserved characters, as user
+/// names may contain characters like `/` which must not split the path
+fn encode_path_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Rotates the password of the RabbitMQ user in the secret
+#[derive(Debug)]
+pub struct RabbitMqRotation;
+
+#[async_trait::async_trait]
+impl<'a> super::RotateRunner<'a, (), RabbitMqSecret> for RabbitMqRotation {
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn create(
+        _shared: &'a (),
+        mut secret_cur: SecretContainer<RabbitMqSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<RabbitMqSecret>> {
+        secret_cur.password = smc.generate_new_password(false, None).await?;
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        _shared: &'a (),
+        secret_cur: SecretContainer<RabbitMqSecret>,
+        secret_new: SecretContainer<RabbitMqSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let admin = match &secret_cur.admin_secret_id {
+            Some(admin_secret_id) => smc
+                .get_secret::<AdminSecret>(admin_secret_id)
+                .await?
+                .data
+                .clone(),
+            None => AdminSecret {
+                user: secret_cur.user.clone(),
+                password: secret_cur.password.clone(),
+            },
+        };
+        secret_cur
+            .change_password(&secret_new.password, &admin.user, &admin.password)
+            .await
+            .with_context(|| format!("Unable to change password of user {}", secret_cur.user))?;
+        crate::retry::retry(&PROPAGATION_POLICY, || secret_new.check_connection())
+            .await
+            .context("RabbitMQ did not accept the new password in time")
+    }
+
+    async fn test(
+        _shared: &'a (),
+        secret_new: SecretContainer<RabbitMqSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        secret_new.check_connection().await
+    }
+}
@@ -0,0 +1,533 @@
+//! Provides a ready [`super::RotateRunner`] for Redis ACL users.
+//!
+//! The password is rotated with a transition window in which both passwords are
+//! valid: `set` adds the new password to the user, so clients using the current
+//! password keep working until the secret is updated. Once `finish` marked the new
+//! password as current, all other passwords of the user are removed. Connections use
+//! TLS unless `tls` is set to `false` in the secret. Besides the feature `rotate_redis`,
+//! one of the features `rotate_rusoto` or `rotate_aws_sdk` has to be enabled.
+//!
+//! AUTH tokens of ElastiCache replication groups are rotated by `ElastiCacheRotation`
+//! with the feature `rotate_elasticache` through `ModifyReplicationGroup`. `set` adds
+//! the new token with the strategy `ROTATE`, so both tokens are valid, and once
+//! `finish` marked the new token as current, the strategy `SET` removes the old one.
+//! Both wait until the replication group is available again. Requires the permissions
+//! `elasticache:ModifyReplicationGroup` and `elasticache:DescribeReplicationGroups`.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, lambda_runtime_types::rotate::redis::RedisRotation, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc, Step};
+use futures::future::BoxFuture;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Upper bound of the length of a line, a bulk string and an array in a reply.
+/// Replies to the commands of the rotation are small, so larger lengths are
+/// rejected instead of allocated
+const MAX_REPLY_LENGTH: usize = 1024 * 1024;
+
+/// Secret of a Redis ACL user
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct RedisSecret {
+    /// Host of the Redis server
+    pub host: String,
+    /// Port of the Redis server. Defaults to 6379
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Name of the user whose password is rotated. Defaults to `default`
+    #[serde(default = "default_user", rename = "username", alias = "user")]
+    pub user: String,
+    /// Password of the user
+    pub password: String,
+    /// Whether the connection uses TLS. Defaults to `true`
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+}
+
+impl std::fmt::Debug for RedisSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisSecret")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &"[...]")
+            .field("tls", &self.tls)
+            .finish()
+    }
+}
+
+const fn default_port() -> u16 {
+    6379
+}
+
+fn default_user() -> String {
+    "default".into()
+}
+
+const fn default_tls() -> bool {
+    true
+}
+
+impl RedisSecret {
+    /// Connects to the Redis server and authenticates with the secret
+    pub async fn connect(&self) -> anyhow::Result<RedisClient> {
+        use anyhow::Context;
+
+        let mut client = RedisClient::open(&self.host, self.port, self.tls).await?;
+        client
+            .command(&["AUTH", &self.user, &self.password])
+            .await
+            .with_context(|| format!("Unable to authenticate as user {}", self.user))?;
+        Ok(client)
+    }
+}
+
+/// Secret of an ElastiCache replication group, which uses an AUTH token
+#[cfg(feature = "rotate_elasticache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_elasticache")))]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct ElastiCacheSecret {
+    /// Id of the replication group
+    pub replication_group_id: String,
+    /// Primary or configuration endpoint of the replication group
+    pub host: String,
+    /// Port of the replication group. Defaults to 6379
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// AUTH token of the replication group
+    #[serde(alias = "password")]
+    pub auth_token: String,
+    /// Region of the replication group. Defaults to the region of the lambda
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Whether the connection uses TLS, which ElastiCache requires
+    /// for AUTH tokens. Defaults to `true`
+    #[serde(default = "default_tls")]
+    pub tls: bool,
+}
+
+#[cfg(feature = "rotate_elasticache")]
+impl std::fmt::Debug for ElastiCacheSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ElastiCacheSecret")
+            .field("replication_group_id", &self.replication_group_id)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("auth_token", &"[...]")
+            .field("region", &self.region)
+            .field("tls", &self.tls)
+            .finish()
+    }
+}
+
+#[cfg(feature = "rotate_elasticache")]
+impl ElastiCacheSecret {
+    /// Connects to the replication group and authenticates with the AUTH token
+    pub async fn connect(&self) -> anyhow::Result<RedisClient> {
+        use anyhow::Context;
+
+        let mut client = RedisClient::open(&self.host, self.port, self.tls).await?;
+        client
+            .command(&["AUTH", &self.auth_token])
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to authenticate to replication group {}",
+                    self.replication_group_id
+                )
+            })?;
+        Ok(client)
+    }
+
+    /// Region of the replication group
+    fn region<'r>(&'r self, ctx: &RotationContext<'r>) -> &'r str {
+        self.region.as_deref().unwrap_or(ctx.lambda_region)
+    }
+}
+
+/// How `ModifyReplicationGroup` updates the AUTH token of a replication group
+#[cfg(feature = "rotate_elasticache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_elasticache")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthTokenUpdateStrategy {
+    /// Adds the token, while the existing one stays valid
+    Rotate,
+    /// Replaces all tokens with the token
+    Set,
+}
+
+#[cfg(feature = "rotate_elasticache")]
+impl AuthTokenUpdateStrategy {
+    /// Value of the parameter `AuthTokenUpdateStrategy`
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Rotate => "ROTATE",
+            Self::Set => "SET",
+        }
+    }
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Stream for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// Reply of the Redis server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    /// Simple string, e.g. `OK`
+    Status(String),
+    /// Integer
+    Integer(i64),
+    /// Binary safe string. `None` if the value does not exist
+    Bulk(Option<Vec<u8>>),
+    /// Array of replies. `None` if the value does not exist
+    Array(Option<Vec<Self>>),
+}
+
+/// Minimal Redis client, which executes one command after another
+pub struct RedisClient {
+    stream: BufReader<Box<dyn Stream>>,
+}
+
+impl std::fmt::Debug for RedisClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisClient").finish_non_exhaustive()
+    }
+}
+
+impl RedisClient {
+    /// Connects to the Redis server without authenticating
+    async fn open(host: &str, port: u16, tls: bool) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let tcp = tokio::net::TcpStream::connect((host, port))
+            .await
+            .with_context(|| format!("Unable to connect to redis at {}", host))?;
+        let stream: Box<dyn Stream> = if tls {
+            let connector = native_tls::TlsConnector::new()
+                .context("Unable to prepare TLS Connection for Redis")?;
+            let stream = tokio_native_tls::TlsConnector::from(connector)
+                .connect(host, tcp)
+                .await
+                .with_context(|| format!("Unable to establish TLS with redis at {}", host))?;
+            Box::new(stream)
+        } else {
+            Box::new(tcp)
+        };
+        Ok(Self {
+            stream: BufReader::new(stream),
+        })
+    }
+
+    /// Executes a command and returns its reply. Error replies
+    /// are returned as error
+    pub async fn command(&mut self, args: &[&str]) -> anyhow::Result<Reply> {
+        use anyhow::Context;
+
+        let mut request = format!("*{}\r\n", args.len()).into_bytes();
+        for arg in args {
+            request.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+            request.extend_from_slice(arg.as_bytes());
+            request.extend_from_slice(b"\r\n");
+        }
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(&request)
+            .await
+            .context("Unable to send command to redis")?;
+        stream
+            .flush()
+            .await
+            .context("Unable to send command to redis")?;
+        read_reply(&mut self.stream).await
+    }
+}
+
+fn read_reply<'s>(
+    stream: &'s mut BufReader<Box<dyn Stream>>,
+) -> BoxFuture<'s, anyhow::Result<Reply>> {
+    use anyhow::Context;
+
+    Box::pin(async move {
+        let mut line = String::new();
+        let read = (&mut *stream)
+            .take(MAX_REPLY_LENGTH as u64)
+            .read_line(&mut line)
+            .await
+            .context("Unable to read reply from redis")?;
+        anyhow::ensure!(
+            read < MAX_REPLY_LENGTH,
+            "Redis reply exceeds {} bytes",
+            MAX_REPLY_LENGTH
+        );
+        let line = line
+            .strip_suffix("\r\n")
+            .context("Connection to redis closed")?;
+        let (kind, value) = line.split_at(line.len().min(1));
+        let length = || -> anyhow::Result<Option<usize>> {
+            let length: i64 = value.parse().context("Invalid length in redis reply")?;
+            let Ok(length) = usize::try_from(length) else {
+                return Ok(None);
+            };
+            anyhow::ensure!(
+                length <= MAX_REPLY_LENGTH,
+                "Redis reply exceeds {} bytes: {}",
+                MAX_REPLY_LENGTH,
+                length
+            );
+            Ok(Some(length))
+        };
+        match kind {
+            "+" => Ok(Reply::Status(value.to_owned())),
+            "-" => anyhow::bail!("Redis responded with error: {}", value),
+            ":" => Ok(Reply::Integer(
+                value.parse().context("Invalid integer in redis reply")?,
+            )),
+            "$" => {
+                let Some(length) = length()? else {
+                    return Ok(Reply::Bulk(None));
+                };
+                let mut data = vec![0; length + 2];
+                stream
+                    .read_exact(&mut data)
+                    .await
+                    .context("Unable to read reply from redis")?;
+                data.truncate(length);
+                Ok(Reply::Bulk(Some(data)))
+            }
+            "*" => {
+                let Some(length) = length()? else {
+                    return Ok(Reply::Array(None));
+                };
+                let mut items = Vec::with_capacity(length);
+                for _ in 0..length {
+                    items.push(read_reply(stream).await?);
+                }
+                Ok(Reply::Array(Some(items)))
+            }
+            _ => anyhow::bail!("Invalid redis reply: {}", line),
+        }
+    })
+}
+
+/// Rotates the password of the Redis ACL user in the secret
+#[derive(Debug)]
+pub struct RedisRotation;
+
+#[async_trait::async_trait]
+impl<'a> super::RotateRunner<'a, (), RedisSecret> for RedisRotation {
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn run_step(
+        shared: &'a (),
+        step: Step,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        match step {
+            Step::Finish => {
+                super::pipeline::finish::<Self, _, _>(shared, smc, ctx).await?;
+                if ctx.dry_run {
+                    log::info!("Dry run: Would remove the previous passwords of the user.");
+                    return Ok(());
+                }
+                // Clients switch to the new password once it is current, so the
+                // previous one is only removed after the promotion
+                let secret = smc.get_secret::<RedisSecret>(ctx.secret_id).await?;
+                secret
+                    .connect()
+                    .await?
+                    .command(&[
+                        "ACL",
+                        "SETUSER",
+                        &secret.user,
+                        "resetpass",
+                        &format!(">{}", secret.password),
+                    ])
+                    .await
+                    .context("Unable to remove previous passwords of user")?;
+                Ok(())
+            }
+            step => super::pipeline::step::<Self, _, _>(shared, step, smc, ctx).await,
+        }
+    }
+
+    async fn create(
+        _shared: &'a (),
+        mut secret_cur: SecretContainer<RedisSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<RedisSecret>> {
+        secret_cur.password = smc.generate_new_password(false, None).await?;
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        _shared: &'a (),
+        secret_cur: SecretContainer<RedisSecret>,
+        secret_new: SecretContainer<RedisSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        // Adds the new password, the current one stays valid until finish
+        secret_cur
+            .connect()
+            .await?
+            .command(&[
+                "ACL",
+                "SETUSER",
+                &secret_new.user,
+                &format!(">{}", secret_new.password),
+            ])
+            .await
+            .context("Unable to add new password to user")?;
+        Ok(())
+    }
+
+    async fn test(
+        _shared: &'a (),
+        secret_new: SecretContainer<RedisSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        secret_new
+            .connect()
+            .await?
+            .command(&["PING"])
+            .await
+            .context("Connection to redis failed")?;
+        Ok(())
+    }
+}
+
+/// Retry policy used while waiting for a replication group to apply a modification
+#[cfg(feature = "rotate_elasticache")]
+const MODIFICATION_POLICY: crate::retry::Policy = crate::retry::Policy::exponential(10)
+    .with_base_delay(std::time::Duration::from_secs(5))
+    .with_max_delay(std::time::Duration::from_secs(60));
+
+/// Updates the AUTH token of the replication group in the secret and
+/// waits until the replication group is available again
+#[cfg(feature = "rotate_elasticache")]
+async fn modify_auth_token(
+    smc: &Smc,
+    ctx: &RotationContext<'_>,
+    secret: &ElastiCacheSecret,
+    strategy: AuthTokenUpdateStrategy,
+) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    let region = secret.region(ctx);
+    let id = &secret.replication_group_id;
+    let available = || async {
+        let status = smc.replication_group_status(region, id).await?;
+        anyhow::ensure!(
+            status == "available",
+            "Replication group {} is {}",
+            id,
+            status
+        );
+        Ok(())
+    };
+    // A modification fails while another one is applied
+    crate::retry::retry(&MODIFICATION_POLICY, available)
+        .await
+        .with_context(|| format!("Replication group {} is not available", id))?;
+    smc.modify_auth_token(region, id, &secret.auth_token, strategy)
+        .await?;
+    crate::retry::retry(&MODIFICATION_POLICY, available)
+        .await
+        .with_context(|| {
+            format!(
+                "Replication group {} did not apply the AUTH token in time",
+                id
+            )
+        })
+}
+
+/// Rotates the AUTH token of the ElastiCache replication group in the secret
+#[cfg(feature = "rotate_elasticache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rotate_elasticache")))]
+#[derive(Debug)]
+pub struct ElastiCacheRotation;
+
+#[cfg(feature = "rotate_elasticache")]
+#[async_trait::async_trait]
+impl<'a> super::RotateRunner<'a, (), ElastiCacheSecret> for ElastiCacheRotation {
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn run_step(
+        shared: &'a (),
+        step: Step,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        match step {
+            Step::Finish => {
+                super::pipeline::finish::<Self, _, _>(shared, smc, ctx).await?;
+                if ctx.dry_run {
+                    log::info!("Dry run: Would remove the previous AUTH token.");
+                    return Ok(());
+                }
+                // Clients switch to the new token once it is current, so the
+                // previous one is only removed after the promotion
+                let secret = smc.get_secret::<ElastiCacheSecret>(ctx.secret_id).await?;
+                modify_auth_token(smc, ctx, &secret, AuthTokenUpdateStrategy::Set).await
+            }
+            step => super::pipeline::step::<Self, _, _>(shared, step, smc, ctx).await,
+        }
+    }
+
+    async fn create(
+        _shared: &'a (),
+        mut secret_cur: SecretContainer<ElastiCacheSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<ElastiCacheSecret>> {
+        secret_cur.auth_token = smc.generate_new_password(false, None).await?;
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        _shared: &'a (),
+        _secret_cur: SecretContainer<ElastiCacheSecret>,
+        secret_new: SecretContainer<ElastiCacheSecret>,
+        smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        // Adds the new token, the current one stays valid until finish
+        modify_auth_token(smc, ctx, &secret_new, AuthTokenUpdateStrategy::Rotate).await
+    }
+
+    async fn test(
+        _shared: &'a (),
+        secret_new: SecretContainer<ElastiCacheSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        secret_new
+            .connect()
+            .await?
+            .command(&["PING"])
+            .await
+            .context("Connection to redis failed")?;
+        Ok(())
+    }
+}
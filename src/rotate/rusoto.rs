@@ -1,6 +1,11 @@
+/// Retry policy used for throttled requests
+const RETRY_POLICY: crate::retry::Policy = crate::retry::Policy::exponential(10);
+
 #[derive(Clone)]
 pub struct SmcClient {
     client: rusoto_secretsmanager::SecretsManagerClient,
+    #[cfg(feature = "_signed_requests")]
+    credentials: rusoto_core::credential::DefaultCredentialsProvider,
 }
 
 impl SmcClient {
@@ -11,9 +16,15 @@ impl SmcClient {
         let region =
             rusoto_core::Region::from_str(region).context("invalid region given to lambda")?;
         let client = rusoto_secretsmanager::SecretsManagerClient::new(region);
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            #[cfg(feature = "_signed_requests")]
+            credentials: rusoto_core::credential::DefaultCredentialsProvider::new()
+                .context("Unable to create credentials provider")?,
+        })
     }
 
+    #[cfg(not(feature = "rotate_local_password"))]
     pub async fn generate_new_password(
         &self,
         puncutation: bool,
@@ -22,23 +33,17 @@ impl SmcClient {
         use anyhow::Context;
         use rusoto_secretsmanager::SecretsManager;
 
-        let mut retries = 1;
-        let password = loop {
-            let res = self
-                .client
-                .get_random_password(rusoto_secretsmanager::GetRandomPasswordRequest {
-                    exclude_characters: Some("\"".to_string()),
-                    exclude_punctuation: Some(!puncutation),
-                    password_length: length,
-                    ..rusoto_secretsmanager::GetRandomPasswordRequest::default()
-                })
-                .await;
-            if Self::is_wait_and_repeat(&res, retries).await {
-                retries += 1;
-                continue;
-            }
-            break res.context("Unable to generate new password")?;
+        let request = rusoto_secretsmanager::GetRandomPasswordRequest {
+            exclude_characters: Some("\"".to_string()),
+            exclude_punctuation: Some(!puncutation),
+            password_length: length,
+            ..rusoto_secretsmanager::GetRandomPasswordRequest::default()
         };
+        let password = crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.get_random_password(request.clone())
+        })
+        .await
+        .context("Unable to generate new password")?;
         password
             .random_password
             .context("Generated password is empty")
@@ -52,23 +57,16 @@ impl SmcClient {
         use anyhow::Context;
         use rusoto_secretsmanager::SecretsManager;
 
-        let mut retries = 1;
-        let secret_value = loop {
-            let res = self
-                .client
-                .get_secret_value(rusoto_secretsmanager::GetSecretValueRequest {
-                    secret_id: secret_id.to_string(),
-                    version_id: None,
-                    version_stage: Some(version_stage.to_string()),
-                })
-                .await;
-            if Self::is_wait_and_repeat(&res, retries).await {
-                retries += 1;
-                continue;
-            }
-            break res
-                .with_context(|| format!("Unable to fetch SecretValue with id: {}", secret_id))?;
+        let request = rusoto_secretsmanager::GetSecretValueRequest {
+            secret_id: secret_id.to_string(),
+            version_id: None,
+            version_stage: Some(version_stage.to_string()),
         };
+        let secret_value = crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.get_secret_value(request.clone())
+        })
+        .await
+        .with_context(|| format!("Unable to fetch SecretValue with id: {}", secret_id))?;
         let arn = secret_value.arn.with_context(|| {
             format!("Arn is unavailable for secret value with id: {}", secret_id)
         })?;
@@ -78,15 +76,18 @@ impl SmcClient {
                 secret_id
             )
         })?;
+        let binary = secret_value.secret_string.is_none() && secret_value.secret_binary.is_some();
         let inner = match (secret_value.secret_string, secret_value.secret_binary) {
-            (Some(string), _) => serde_json::from_str(&string),
-            (_, Some(bytes)) => serde_json::from_slice(&bytes),
+            (Some(string), _) => crate::rotate::SecretContainer::from_secret_value(string.as_bytes()),
+            (_, Some(bytes)) => crate::rotate::SecretContainer::from_secret_value(&bytes),
             _ => anyhow::bail!("Neither secret_string nor secret_binary is set for id: {}", secret_id),
         }
         .with_context(|| format!("Unable to parse secret value. Value does not confirm to required structure. Id: {}", secret_id))?;
         Ok(crate::rotate::smc::Secret {
             arn,
             version_id,
+            version_stages: secret_value.version_stages.unwrap_or_default(),
+            binary,
             inner,
         })
     }
@@ -95,35 +96,200 @@ impl SmcClient {
         &self,
         secret_id: &str,
         request_token: Option<&str>,
-        secret_str: &str,
+        secret_value: &[u8],
+        binary: bool,
+        version_stages: &[String],
     ) -> anyhow::Result<()> {
         use anyhow::Context;
         use rusoto_secretsmanager::SecretsManager;
 
-        let mut retries = 1;
-        loop {
-            let res = self
-                .client
-                .put_secret_value(rusoto_secretsmanager::PutSecretValueRequest {
-                    client_request_token: request_token.map(|v| v.to_string()),
-                    secret_binary: None,
-                    secret_id: secret_id.to_string(),
-                    secret_string: Some(secret_str.into()),
-                    version_stages: Some(vec!["AWSPENDING".into()]),
+        let secret_string = if binary {
+            None
+        } else {
+            Some(
+                std::str::from_utf8(secret_value)
+                    .context("SecretString must be valid utf-8")?
+                    .to_owned(),
+            )
+        };
+        let request = rusoto_secretsmanager::PutSecretValueRequest {
+            client_request_token: request_token.map(|v| v.to_string()),
+            secret_binary: binary.then(|| secret_value.to_vec().into()),
+            secret_id: secret_id.to_string(),
+            secret_string,
+            version_stages: Some(version_stages.to_vec()),
+        };
+        let _ = crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.put_secret_value(request.clone())
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to push new SecretValue to {} for id: {}",
+                version_stages.join(", "),
+                secret_id
+            )
+        })?;
+        Ok(())
+    }
+
+    pub async fn describe_secret(
+        &self,
+        secret_id: &str,
+    ) -> anyhow::Result<crate::rotate::smc::SecretDescription> {
+        use anyhow::Context;
+        use rusoto_secretsmanager::SecretsManager;
+
+        let request = rusoto_secretsmanager::DescribeSecretRequest {
+            secret_id: secret_id.to_string(),
+        };
+        let secret = crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.describe_secret(request.clone())
+        })
+        .await
+        .with_context(|| format!("Unable to describe secret with id: {}", secret_id))?;
+        Ok(crate::rotate::smc::SecretDescription {
+            name: secret.name.with_context(|| {
+                format!("Name is unavailable for secret with id: {}", secret_id)
+            })?,
+            rotation_enabled: secret.rotation_enabled.unwrap_or_default(),
+            rotation_lambda_arn: secret.rotation_lambda_arn,
+            version_ids_to_stages: secret.version_ids_to_stages.unwrap_or_default(),
+            replicas: secret
+                .replication_status
+                .unwrap_or_default()
+                .into_iter()
+                .map(|replica| crate::rotate::smc::ReplicaStatus {
+                    region: replica.region.unwrap_or_default(),
+                    status: replica.status.unwrap_or_default(),
+                    message: replica.status_message,
                 })
-                .await;
-            if Self::is_wait_and_repeat(&res, retries).await {
-                retries += 1;
-                continue;
-            }
-            let _ = res.with_context(|| {
-                format!(
-                    "Unable to push new SecretValue to AWSPENDING for id: {}",
-                    secret_id
-                )
-            })?;
-            break Ok(());
-        }
+                .collect(),
+            tags: secret
+                .tags
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|tag| Some((tag.key?, tag.value.unwrap_or_default())))
+                .collect(),
+            kms_key_id: secret.kms_key_id,
+        })
+    }
+
+    pub async fn tag_resource(&self, secret_id: &str, tags: &[(&str, &str)]) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use rusoto_secretsmanager::SecretsManager;
+
+        let request = rusoto_secretsmanager::TagResourceRequest {
+            secret_id: secret_id.to_string(),
+            tags: tags
+                .iter()
+                .map(|(key, value)| rusoto_secretsmanager::Tag {
+                    key: Some((*key).to_owned()),
+                    value: Some((*value).to_owned()),
+                })
+                .collect(),
+        };
+        crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.tag_resource(request.clone())
+        })
+        .await
+        .with_context(|| format!("Unable to tag secret with id: {}", secret_id))?;
+        Ok(())
+    }
+
+    pub async fn untag_resource(&self, secret_id: &str, keys: &[&str]) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use rusoto_secretsmanager::SecretsManager;
+
+        let request = rusoto_secretsmanager::UntagResourceRequest {
+            secret_id: secret_id.to_string(),
+            tag_keys: keys.iter().map(|key| (*key).to_owned()).collect(),
+        };
+        crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.untag_resource(request.clone())
+        })
+        .await
+        .with_context(|| format!("Unable to untag secret with id: {}", secret_id))?;
+        Ok(())
+    }
+
+    pub async fn update_kms_key(&self, secret_id: &str, kms_key_id: &str) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use rusoto_secretsmanager::SecretsManager;
+
+        let request = rusoto_secretsmanager::UpdateSecretRequest {
+            secret_id: secret_id.to_string(),
+            kms_key_id: Some(kms_key_id.to_string()),
+            ..rusoto_secretsmanager::UpdateSecretRequest::default()
+        };
+        let _ = crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.update_secret(request.clone())
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to change KMS key to {} for secret with id: {}",
+                kms_key_id, secret_id
+            )
+        })?;
+        Ok(())
+    }
+
+    pub async fn update_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+        remove_from_version_id: Option<String>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use rusoto_secretsmanager::SecretsManager;
+
+        let request = rusoto_secretsmanager::UpdateSecretVersionStageRequest {
+            move_to_version_id: Some(version_id.to_string()),
+            remove_from_version_id,
+            secret_id: secret_id.to_string(),
+            version_stage: stage.to_string(),
+        };
+        let _ = crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.update_secret_version_stage(request.clone())
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to move stage {} to version {} for id: {}",
+                stage, version_id, secret_id
+            )
+        })?;
+        Ok(())
+    }
+
+    pub async fn remove_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use rusoto_secretsmanager::SecretsManager;
+
+        let request = rusoto_secretsmanager::UpdateSecretVersionStageRequest {
+            move_to_version_id: None,
+            remove_from_version_id: Some(version_id.to_string()),
+            secret_id: secret_id.to_string(),
+            version_stage: stage.to_string(),
+        };
+        let _ = crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.update_secret_version_stage(request.clone())
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to remove stage {} from version {} for id: {}",
+                stage, version_id, secret_id
+            )
+        })?;
+        Ok(())
     }
 
     pub async fn set_pending_secret_value_to_current(
@@ -131,71 +297,67 @@ impl SmcClient {
         secret_arn: String,
         secret_current_version_id: String,
         secret_pending_version_id: String,
+        stage: &str,
     ) -> anyhow::Result<()> {
         use anyhow::Context;
         use rusoto_secretsmanager::SecretsManager;
 
-        let mut retries = 1;
-        loop {
-            let res = self
-                .client
-                .update_secret_version_stage(
-                    rusoto_secretsmanager::UpdateSecretVersionStageRequest {
-                        move_to_version_id: Some(secret_pending_version_id.clone()),
-                        remove_from_version_id: Some(secret_current_version_id.clone()),
-                        secret_id: secret_arn.clone(),
-                        version_stage: "AWSCURRENT".into(),
-                    },
-                )
-                .await;
-            if Self::is_wait_and_repeat(&res, retries).await {
-                retries += 1;
-                continue;
-            }
-            let _ = res.with_context(|| {
-                format!(
-                    "Unable to push new SecretValue to AWSPENDING for arn: {}",
-                    secret_arn
-                )
-            })?;
-            break Ok(());
-        }
+        let request = rusoto_secretsmanager::UpdateSecretVersionStageRequest {
+            move_to_version_id: Some(secret_pending_version_id),
+            remove_from_version_id: Some(secret_current_version_id),
+            secret_id: secret_arn.clone(),
+            version_stage: stage.into(),
+        };
+        let _ = crate::retry::retry_if(&RETRY_POLICY, Self::is_throttling, || {
+            self.client.update_secret_version_stage(request.clone())
+        })
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to move stage {} to pending SecretValue for arn: {}",
+                stage, secret_arn
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Default credentials, which sign requests of [`super::signed::SignedClient`]
+    #[cfg(feature = "_signed_requests")]
+    pub async fn credentials(&self) -> anyhow::Result<super::signed::Credentials> {
+        use anyhow::Context;
+        use rusoto_core::credential::ProvideAwsCredentials;
+
+        let credentials = self
+            .credentials
+            .credentials()
+            .await
+            .context("Unable to load credentials")?;
+        Ok(super::signed::Credentials {
+            access_key_id: credentials.aws_access_key_id().to_owned(),
+            secret_access_key: credentials.aws_secret_access_key().to_owned(),
+            session_token: credentials.token().clone(),
+        })
     }
 
-    /// Checks whether the given result is a throttling error
-    /// and waits for 100 ms if it is
-    async fn is_wait_and_repeat<D: Send + Sync, E: std::fmt::Debug + Send + Sync>(
-        error: &Result<D, rusoto_core::RusotoError<E>>,
-        retries: u64,
-    ) -> bool {
-        if let Err(rusoto_core::RusotoError::Unknown(
-            rusoto_core::request::BufferedHttpResponse {
-                ref status,
-                ref body,
-                ..
-            },
-        )) = *error
+    /// Checks whether the given error is a throttling error
+    fn is_throttling<E>(error: &rusoto_core::RusotoError<E>) -> bool {
+        if let rusoto_core::RusotoError::Unknown(rusoto_core::request::BufferedHttpResponse {
+            ref status,
+            ref body,
+            ..
+        }) = *error
         {
-            let cooldown = match status.as_u16() {
-                400 => {
-                    let search = b"ThrottlingException";
-                    body.as_ref().windows(search.len()).any(|sub| sub == search)
-                }
-                429 => {
-                    let search = b"Too Many Requests";
-                    body.as_ref().windows(search.len()).any(|sub| sub == search)
-                }
-                503 => {
-                    let search = b"SlowDown";
-                    body.as_ref().windows(search.len()).any(|sub| sub == search)
-                }
-                _ => false,
+            let search: &[u8] = match status.as_u16() {
+                400 => b"ThrottlingException",
+                429 => b"Too Many Requests",
+                503 => b"SlowDown",
+                _ => return false,
             };
-            if cooldown {
-                println!("Info: Cooling down to prevent request limits");
-                tokio::time::sleep(tokio::time::Duration::from_millis((2 ^ retries) * 100)).await;
-                return true;
+            let throttled = body.as_ref().windows(search.len()).any(|sub| sub == search);
+            if throttled {
+                log::info!("Cooling down to prevent request limits");
             }
+            return throttled;
         }
         false
     }
@@ -0,0 +1,86 @@
+//! Requests to AWS services for which neither rusoto nor the aws sdk client is
+//! a dependency of this crate, e.g. SNS. They are signed with SigV4 using the
+//! credentials of the enabled sdk and sent with a client shared by all requests.
+
+/// Credentials of the enabled sdk, which sign the requests
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+type HttpsClient =
+    hyper::Client<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>, hyper::Body>;
+
+/// Sends signed requests. Cloning it shares the connection pool
+#[derive(Clone)]
+pub struct SignedClient {
+    client: HttpsClient,
+}
+
+impl SignedClient {
+    pub fn new() -> Self {
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_only()
+            .enable_http1()
+            .build();
+        Self {
+            client: hyper::Client::builder().build(connector),
+        }
+    }
+
+    /// Signs `request` for `service` in `region` and sends it.
+    /// Returns status and body of the response
+    pub async fn send(
+        &self,
+        credentials: &Credentials,
+        service: &str,
+        region: &str,
+        mut request: http::Request<String>,
+    ) -> anyhow::Result<(http::StatusCode, hyper::body::Bytes)> {
+        use anyhow::Context;
+
+        let mut params = aws_sigv4::http_request::SigningParams::builder()
+            .access_key(&credentials.access_key_id)
+            .secret_key(&credentials.secret_access_key)
+            .region(region)
+            .service_name(service)
+            .time(std::time::SystemTime::now())
+            .settings(aws_sigv4::http_request::SigningSettings::default());
+        params.set_security_token(credentials.session_token.as_deref());
+        let params = params
+            .build()
+            .map_err(|err| anyhow::anyhow!("{}", err))
+            .context("Unable to build signing parameters")?;
+        let (instructions, _) = aws_sigv4::http_request::sign(
+            aws_sigv4::http_request::SignableRequest::from(&request),
+            &params,
+        )
+        .map_err(|err| anyhow::anyhow!("{}", err))
+        .context("Unable to sign request")?
+        .into_parts();
+        instructions.apply_to_request(&mut request);
+
+        let response = self
+            .client
+            .request(request.map(hyper::Body::from))
+            .await
+            .context("Request failed")?;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .context("Unable to read response")?;
+        Ok((status, body))
+    }
+}
+
+/// Host of `service` in `region`
+pub fn endpoint(service: &str, region: &str) -> String {
+    let domain = if region.starts_with("cn-") {
+        "amazonaws.com.cn"
+    } else {
+        "amazonaws.com"
+    };
+    format!("{}.{}.{}", service, region, domain)
+}
@@ -0,0 +1,295 @@
+//! Drives a [`RotateRunner`] through the rotation steps against an in-memory
+//! `SecretManager`, so rotation lambdas can be tested with `cargo test`.
+//!
+//! Like the `SecretManager`, [`Simulation`] attaches the pending label to a new
+//! version before the first step and removes it once `finish` succeeded. Failures
+//! can be injected before or after a step to check that re-delivered events
+//! complete the rotation:
+//!
+//! ```no_run
+//! # #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+//! # struct Secret { password: String }
+//! # struct Runner;
+//! # #[async_trait::async_trait]
+//! # impl<'a> lambda_runtime_types::rotate::RotateRunner<'a, (), Secret> for Runner {
+//! #     async fn setup(_region: &'a str) -> anyhow::Result<()> { Ok(()) }
+//! #     async fn create(_shared: &'a (), secret_cur: lambda_runtime_types::rotate::SecretContainer<Secret>, _smc: &lambda_runtime_types::rotate::Smc, _ctx: &lambda_runtime_types::rotate::RotationContext<'_>) -> anyhow::Result<lambda_runtime_types::rotate::SecretContainer<Secret>> { Ok(secret_cur) }
+//! #     async fn set(_shared: &'a (), _secret_cur: lambda_runtime_types::rotate::SecretContainer<Secret>, _secret_new: lambda_runtime_types::rotate::SecretContainer<Secret>, _smc: &lambda_runtime_types::rotate::Smc, _ctx: &lambda_runtime_types::rotate::RotationContext<'_>) -> anyhow::Result<()> { Ok(()) }
+//! #     async fn test(_shared: &'a (), _secret_new: lambda_runtime_types::rotate::SecretContainer<Secret>, _smc: &lambda_runtime_types::rotate::Smc, _ctx: &lambda_runtime_types::rotate::RotationContext<'_>) -> anyhow::Result<()> { Ok(()) }
+//! # }
+//! use lambda_runtime_types::rotate::{simulation::Simulation, Step};
+//!
+//! #[tokio::test]
+//! async fn rotation_survives_failed_set() -> anyhow::Result<()> {
+//!     let mut simulation = Simulation::new("my-secret", r#"{"password":"old"}"#).await?;
+//!     simulation.fail_after(Step::Set);
+//!     assert!(simulation.rotate::<Runner, _, _>(&()).await.is_err());
+//!     // The `SecretManager` re-delivers the rotation
+//!     simulation.rotate::<Runner, _, _>(&()).await?;
+//!     let current = simulation.secret::<Secret>("AWSCURRENT")?;
+//!     assert_ne!(current.password, "old");
+//!     Ok(())
+//! }
+//! ```
+
+use super::{RotateRunner, RotationContext, SecretContainer, Smc, Step};
+
+/// Region of the simulated `SecretManager`
+const REGION: &str = "us-east-1";
+
+/// When an injected failure is raised
+#[derive(Debug, Clone, Copy)]
+enum Injection {
+    Before,
+    After,
+}
+
+/// Message which was published to an SNS topic, e.g. by
+/// [`super::RotateRunner::notification_topic`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedMessage {
+    /// Arn of the topic
+    pub topic_arn: String,
+    /// Subject of the message
+    pub subject: String,
+    /// Body of the message
+    pub message: String,
+}
+
+/// Simulated rotation of a single secret. See the [module](self) documentation
+pub struct Simulation {
+    client: super::memory::SmcClient,
+    smc: Smc,
+    secret_id: String,
+    client_request_token: Option<String>,
+    failures: Vec<(Step, Injection)>,
+}
+
+impl std::fmt::Debug for Simulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Simulation")
+            .field("secret_id", &self.secret_id)
+            .field("client_request_token", &self.client_request_token)
+            .field("failures", &self.failures)
+            .finish()
+    }
+}
+
+impl Simulation {
+    /// Creates a simulation of the secret `secret_id` with `value` as current value
+    pub async fn new(secret_id: &str, value: &str) -> anyhow::Result<Self> {
+        let client = super::memory::SmcClient::default();
+        client.create_secret(secret_id, value);
+        Self::with_client(secret_id, client).await
+    }
+
+    /// Creates a simulation of the secret `secret_id` with `value` as current value,
+    /// which is stored as `SecretBinary`
+    pub async fn new_binary(secret_id: &str, value: &[u8]) -> anyhow::Result<Self> {
+        let client = super::memory::SmcClient::default();
+        client.create_binary_secret(secret_id, value);
+        Self::with_client(secret_id, client).await
+    }
+
+    async fn with_client(
+        secret_id: &str,
+        client: super::memory::SmcClient,
+    ) -> anyhow::Result<Self> {
+        let smc = Smc::new(REGION).await?.with_memory_client(client.clone());
+        Ok(Self {
+            client,
+            smc,
+            secret_id: secret_id.to_owned(),
+            client_request_token: None,
+            failures: Vec::new(),
+        })
+    }
+
+    /// Adds another secret, e.g. an admin secret which is read with [`Smc::get_secret`]
+    pub fn add_secret(&self, secret_id: &str, value: &str) -> &Self {
+        self.client.create_secret(secret_id, value);
+        self
+    }
+
+    /// Fails the next execution of `step` before it is executed
+    pub fn fail_before(&mut self, step: Step) -> &mut Self {
+        self.failures.push((step, Injection::Before));
+        self
+    }
+
+    /// Fails the next execution of `step` after it was executed successfully,
+    /// e.g. like a lambda which timed out before it could return
+    pub fn fail_after(&mut self, step: Step) -> &mut Self {
+        self.failures.push((step, Injection::After));
+        self
+    }
+
+    /// Id of the rotated secret
+    pub fn secret_id(&self) -> &str {
+        &self.secret_id
+    }
+
+    /// Request token of the running rotation
+    pub fn client_request_token(&self) -> Option<&str> {
+        self.client_request_token.as_deref()
+    }
+
+    /// Stops the running rotation without completing it, so its pending version
+    /// is left behind. The next step starts a new rotation
+    pub const fn abandon_rotation(&mut self) -> Option<String> {
+        self.client_request_token.take()
+    }
+
+    /// Client of the in-memory `SecretManager`
+    pub const fn smc(&self) -> &Smc {
+        &self.smc
+    }
+
+    /// Messages which were published to SNS topics, in order
+    pub fn published(&self) -> Vec<PublishedMessage> {
+        self.client.published()
+    }
+
+    /// Arns of the SCRAM secrets, which are associated with the MSK cluster `cluster_arn`
+    #[cfg(feature = "rotate_msk")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rotate_msk")))]
+    pub async fn scram_secrets(&self, cluster_arn: &str) -> Vec<String> {
+        self.client
+            .scram_secrets(cluster_arn, REGION)
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Adds an ElastiCache replication group, whose AUTH token is rotated
+    #[cfg(feature = "rotate_elasticache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rotate_elasticache")))]
+    pub fn add_replication_group(&self, replication_group_id: &str, auth_token: &str) -> &Self {
+        self.client
+            .create_replication_group(replication_group_id, auth_token);
+        self
+    }
+
+    /// Valid AUTH tokens of the ElastiCache replication group `replication_group_id`
+    #[cfg(feature = "rotate_elasticache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rotate_elasticache")))]
+    pub fn auth_tokens(&self, replication_group_id: &str) -> Vec<String> {
+        self.client.auth_tokens(replication_group_id)
+    }
+
+    /// Stored value of the version of the rotated secret, which is labeled with `stage`.
+    /// Bytes which are not valid utf-8 are replaced, see [`Self::binary_value`]
+    pub fn value(&self, stage: &str) -> Option<String> {
+        self.client
+            .value(&self.secret_id, stage)
+            .map(|(value, _)| String::from_utf8_lossy(&value).into_owned())
+    }
+
+    /// Stored value of the version of the rotated secret, which is labeled with `stage`,
+    /// if it is stored as `SecretBinary`
+    pub fn binary_value(&self, stage: &str) -> Option<Vec<u8>> {
+        self.client
+            .value(&self.secret_id, stage)
+            .and_then(|(value, binary)| binary.then_some(value))
+    }
+
+    /// Parsed value of the version of the rotated secret, which is labeled with `stage`
+    pub fn secret<S: serde::de::DeserializeOwned>(
+        &self,
+        stage: &str,
+    ) -> anyhow::Result<SecretContainer<S>> {
+        use anyhow::Context;
+
+        let (value, _) = self
+            .client
+            .value(&self.secret_id, stage)
+            .with_context(|| format!("No value with stage {}", stage))?;
+        Ok(SecretContainer::from_secret_value(&value)?)
+    }
+
+    /// Executes the steps `create`, `set`, `test` and `finish` and stops at
+    /// the first failure. A rotation which failed is continued by calling
+    /// it again, like the `SecretManager` re-delivers its events
+    pub async fn rotate<'a, R, Shared, Secret>(&mut self, shared: &'a Shared) -> anyhow::Result<()>
+    where
+        R: RotateRunner<'a, Shared, Secret> + Send + Sync,
+        Shared: Send + Sync + 'a,
+        Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+    {
+        for step in [Step::Create, Step::Set, Step::Test, Step::Finish] {
+            self.step::<R, _, _>(shared, step).await?;
+        }
+        Ok(())
+    }
+
+    /// Executes a single step, e.g. to re-deliver an event. Starts a new rotation
+    /// if none is running. The rotation ends once [`Step::Finish`] succeeded
+    pub async fn step<'a, R, Shared, Secret>(
+        &mut self,
+        shared: &'a Shared,
+        step: Step,
+    ) -> anyhow::Result<()>
+    where
+        R: RotateRunner<'a, Shared, Secret> + Send + Sync,
+        Shared: Send + Sync + 'a,
+        Secret: 'static + Send + Sync + Clone + serde::de::DeserializeOwned + serde::Serialize,
+    {
+        let labels = R::stage_labels();
+        let client_request_token = match &self.client_request_token {
+            Some(client_request_token) => client_request_token.clone(),
+            None => {
+                let client_request_token = self.client.next_id("token");
+                self.client.start_rotation(
+                    &self.secret_id,
+                    &client_request_token,
+                    &labels.pending,
+                    &labels.current,
+                )?;
+                self.client_request_token = Some(client_request_token.clone());
+                client_request_token
+            }
+        };
+        let injection = self
+            .failures
+            .iter()
+            .position(|(failing, _)| *failing == step)
+            .map(|idx| self.failures.remove(idx).1);
+
+        let smc = self
+            .smc
+            .clone()
+            .with_stage_labels(labels)
+            .with_kms_key_id(R::kms_key_id());
+        let ctx = RotationContext {
+            secret_id: &self.secret_id,
+            lambda_region: REGION,
+            secret_region: REGION,
+            client_request_token: &client_request_token,
+            dry_run: R::dry_run(),
+            invoked_function_arn: "",
+        };
+        let run = {
+            let step = step.clone();
+            let smc = &smc;
+            async move {
+                match injection {
+                    Some(Injection::Before) => {
+                        anyhow::bail!("Injected failure before {:?}", step)
+                    }
+                    Some(Injection::After) => {
+                        R::run_step(shared, step.clone(), smc, &ctx).await?;
+                        anyhow::bail!("Injected failure after {:?}", step)
+                    }
+                    None => R::run_step(shared, step, smc, &ctx).await,
+                }
+            }
+        };
+        let finish = step == Step::Finish;
+        super::execute::<R, _, _>(shared, step, &smc, &ctx, run).await?;
+        if finish {
+            self.client
+                .end_rotation(&self.secret_id, &smc.stage_labels().pending)?;
+            self.client_request_token = None;
+        }
+        Ok(())
+    }
+}
@@ -9,10 +9,53 @@ pub struct Secret<S> {
     pub arn: String,
     /// Secret version_id
     pub version_id: String,
+    /// Staging labels attached to the version
+    pub version_stages: Vec<String>,
+    /// Whether the value is stored as `SecretBinary` instead of `SecretString`
+    pub binary: bool,
     /// Inner custom secret
     pub inner: SecretContainer<S>,
 }
 
+/// Description of a secret returned by Secret Manager
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone)]
+pub struct SecretDescription {
+    /// Name of the secret
+    pub name: String,
+    /// Whether rotation is enabled for the secret
+    pub rotation_enabled: bool,
+    /// Arn of the lambda which is configured to rotate the secret
+    pub rotation_lambda_arn: Option<String>,
+    /// Staging labels attached to each version
+    pub version_ids_to_stages: std::collections::HashMap<String, Vec<String>>,
+    /// Replication status of each replica region
+    pub replicas: Vec<ReplicaStatus>,
+    /// Tags of the secret
+    pub tags: std::collections::HashMap<String, String>,
+    /// Id or arn of the KMS key which encrypts the secret.
+    /// `None` if the key `aws/secretsmanager` is used
+    pub kms_key_id: Option<String>,
+}
+
+/// Replication status of a secret in a replica region
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone)]
+pub struct ReplicaStatus {
+    /// Region of the replica
+    pub region: String,
+    /// `InSync`, `InProgress` or `Failed`
+    pub status: String,
+    /// Details about the status, e.g. why replication failed
+    pub message: Option<String>,
+}
+
 /// Transparent container to inner value.
 /// Prevents accidental override of values not defined by `S`
 #[cfg_attr(
@@ -47,6 +90,115 @@ impl<S> std::ops::DerefMut for SecretContainer<S> {
     }
 }
 
+impl<S: serde::de::DeserializeOwned> SecretContainer<S> {
+    /// Parses a value as it is stored in the `SecretManager`. Values which
+    /// are not a json object, e.g. a bare token, are parsed as [`RawSecret`]
+    /// and values which are not valid utf-8 as [`BinarySecret`]
+    pub fn from_secret_value(value: &[u8]) -> serde_json::Result<Self> {
+        if let Ok(object @ serde_json::Value::Object(_)) = serde_json::from_slice(value) {
+            return serde_json::from_value(object);
+        }
+        std::str::from_utf8(value).map_or_else(
+            |_| serde_json::from_value(serde_json::json!({ BINARY_SECRET_KEY: value })),
+            |raw| serde_json::from_value(serde_json::json!({ RAW_SECRET_KEY: raw })),
+        )
+    }
+}
+
+impl<S: serde::Serialize> SecretContainer<S> {
+    /// Serializes the secret as it is stored in the `SecretManager`.
+    /// A [`RawSecret`] is stored as plain string
+    pub fn to_secret_value(&self) -> serde_json::Result<String> {
+        let value = serde_json::to_value(self)?;
+        if let serde_json::Value::Object(map) = &value {
+            if let (1, Some(serde_json::Value::String(raw))) = (map.len(), map.get(RAW_SECRET_KEY))
+            {
+                return Ok(raw.clone());
+            }
+        }
+        serde_json::to_string(self)
+    }
+
+    /// Serializes the secret as it is stored in the `SecretManager` and returns
+    /// whether it has to be stored as `SecretBinary`, which is the case for a [`BinarySecret`]
+    pub(crate) fn to_secret_bytes(&self) -> serde_json::Result<(Vec<u8>, bool)> {
+        let value = serde_json::to_value(self)?;
+        if let serde_json::Value::Object(map) = &value {
+            if let (1, Some(bytes)) = (map.len(), map.get(BINARY_SECRET_KEY)) {
+                return Ok((serde::Deserialize::deserialize(bytes)?, true));
+            }
+        }
+        Ok((self.to_secret_value()?.into_bytes(), false))
+    }
+}
+
+/// Seconds since the unix epoch
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Region of the resource `arn`
+#[cfg(any(feature = "rotate_sns", feature = "rotate_msk"))]
+fn arn_region(arn: &str) -> anyhow::Result<&str> {
+    use anyhow::Context;
+
+    arn.split(':')
+        .nth(3)
+        .filter(|region| !region.is_empty())
+        .with_context(|| format!("Missing region in arn: {}", arn))
+}
+
+/// Interval in which replicas are checked by [`Smc::wait_for_replicas`]
+const REPLICA_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+const RAW_SECRET_KEY: &str = "$raw";
+const BINARY_SECRET_KEY: &str = "$binary";
+
+/// Secret which is stored as plain string instead of a json object, e.g. a bare
+/// token. Use it as `Secret` of a [`super::RotateRunner`]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct RawSecret {
+    /// Value of the secret
+    #[serde(rename = "$raw")]
+    pub value: String,
+}
+
+impl std::fmt::Debug for RawSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawSecret")
+            .field("value", &"[...]")
+            .finish()
+    }
+}
+
+/// Secret which is stored as arbitrary bytes in `SecretBinary`, e.g. a keystore.
+/// Use it as `Secret` of a [`super::RotateRunner`]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct BinarySecret {
+    /// Value of the secret
+    #[serde(rename = "$binary")]
+    pub value: Vec<u8>,
+}
+
+impl std::fmt::Debug for BinarySecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BinarySecret")
+            .field("value", &"[...]")
+            .finish()
+    }
+}
+
 /// Secret Manager Client
 #[cfg_attr(
     docsrs,
@@ -58,6 +210,45 @@ pub struct Smc {
     aws_sdk_client: super::aws_sdk::SmcClient,
     #[cfg(feature = "rotate_rusoto")]
     rusoto_client: super::rusoto::SmcClient,
+    #[cfg(feature = "test")]
+    memory_client: Option<super::memory::SmcClient>,
+    #[cfg(feature = "_signed_requests")]
+    signed_client: super::signed::SignedClient,
+    labels: StageLabels,
+    kms_key_id: Option<String>,
+}
+
+/// Staging labels which are used by the rotation. Defaults to the
+/// labels of the `SecretManager`.
+///
+/// Custom labels allow to stage secrets through additional environments,
+/// e.g. by rotating the labels `STAGING` and `PRODUCTION` with separate
+/// lambdas. See [`super::RotateRunner::stage_labels`]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "rotate_rusoto", feature = "rotate_aws_sdk")))
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageLabels {
+    /// Label of the value in use, `AWSCURRENT` by default
+    pub current: String,
+    /// Label of the value which is rotated to, `AWSPENDING` by default
+    pub pending: String,
+    /// Label of the value which was in use before, `AWSPREVIOUS` by default
+    pub previous: String,
+    /// Additional labels, which are attached to the new value when it is stored
+    pub extra: Vec<String>,
+}
+
+impl Default for StageLabels {
+    fn default() -> Self {
+        Self {
+            current: "AWSCURRENT".into(),
+            pending: "AWSPENDING".into(),
+            previous: "AWSPREVIOUS".into(),
+            extra: Vec::new(),
+        }
+    }
 }
 
 impl std::fmt::Debug for Smc {
@@ -67,30 +258,129 @@ impl std::fmt::Debug for Smc {
 }
 
 impl Smc {
-    /// Create a new secret manager client
-    pub async fn new(_region: &str) -> anyhow::Result<Self> {
+    /// Create a new secret manager client for the given region
+    pub async fn new(region: &str) -> anyhow::Result<Self> {
         Ok(Self {
             #[cfg(feature = "rotate_aws_sdk")]
-            aws_sdk_client: super::aws_sdk::SmcClient::new().await,
+            aws_sdk_client: super::aws_sdk::SmcClient::new(region).await,
             #[cfg(feature = "rotate_rusoto")]
-            rusoto_client: super::rusoto::SmcClient::new(_region)?,
+            rusoto_client: super::rusoto::SmcClient::new(region)?,
+            #[cfg(feature = "test")]
+            memory_client: None,
+            #[cfg(feature = "_signed_requests")]
+            signed_client: super::signed::SignedClient::new(),
+            labels: StageLabels::default(),
+            kms_key_id: None,
         })
     }
 
-    /// Generate a new password
+    /// Uses the in-memory `SecretManager` instead of the real one
+    #[cfg(feature = "test")]
+    pub(crate) fn with_memory_client(mut self, client: super::memory::SmcClient) -> Self {
+        self.memory_client = Some(client);
+        self
+    }
+
+    /// Uses the given staging labels instead of the ones of the `SecretManager`
+    #[must_use]
+    pub fn with_stage_labels(mut self, labels: StageLabels) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Staging labels used by this client
+    pub const fn stage_labels(&self) -> &StageLabels {
+        &self.labels
+    }
+
+    /// Encrypts pending values with the given KMS key instead of the key of the
+    /// secret. The secret is switched to the key before a pending value is
+    /// written, see [`Smc::reencrypt_secret`]
+    #[must_use]
+    pub fn with_kms_key_id(mut self, kms_key_id: Option<String>) -> Self {
+        self.kms_key_id = kms_key_id;
+        self
+    }
+
+    /// KMS key used for pending values, if it differs from the key of the secret
+    pub fn kms_key_id(&self) -> Option<&str> {
+        self.kms_key_id.as_deref()
+    }
+
+    /// Generate a new password. With the feature `rotate_local_password`, the password
+    /// is generated locally instead of calling `GetRandomPassword`
     pub async fn generate_new_password(
         &self,
         puncutation: bool,
         length: Option<i64>,
     ) -> anyhow::Result<String> {
-        #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
-        let client = &self.aws_sdk_client;
-        #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
-        let client = &self.rusoto_client;
-        #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
-        compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+        #[cfg(feature = "rotate_local_password")]
+        {
+            use anyhow::Context;
+
+            let length = length
+                .map(usize::try_from)
+                .transpose()
+                .context("Invalid password length")?;
+            super::password::generate_password(puncutation, length)
+        }
+        #[cfg(not(feature = "rotate_local_password"))]
+        {
+            #[cfg(feature = "test")]
+            if let Some(client) = &self.memory_client {
+                return client.generate_new_password(puncutation, length).await;
+            }
+            #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
+            let client = &self.aws_sdk_client;
+            #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
+            let client = &self.rusoto_client;
+            #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
+            compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+
+            client.generate_new_password(puncutation, length).await
+        }
+    }
+
+    /// Fetches the current value of another secret, e.g. of
+    /// an admin secret which is required to set a new password
+    pub async fn get_secret<S: serde::de::DeserializeOwned>(
+        &self,
+        secret_id: &str,
+    ) -> anyhow::Result<SecretContainer<S>> {
+        Ok(self.get_secret_value_current(secret_id).await?.inner)
+    }
+
+    /// Fetches the previous value of the given secret_id, i.e. the value
+    /// which was current before the last finished rotation
+    pub async fn get_secret_value_previous<S: serde::de::DeserializeOwned>(
+        &self,
+        secret_id: &str,
+    ) -> anyhow::Result<SecretContainer<S>> {
+        Ok(self
+            .get_secret_value(secret_id, &self.labels.previous)
+            .await?
+            .inner)
+    }
+
+    /// Makes the previous value of the given secret_id current again. Secret Manager
+    /// moves `AWSPREVIOUS` to the value which was current before, so calling it
+    /// twice restores the original state. With custom [`StageLabels`], the previous
+    /// label is not moved
+    pub async fn rollback_to_previous(&self, secret_id: &str) -> anyhow::Result<()> {
+        use anyhow::Context;
 
-        client.generate_new_password(puncutation, length).await
+        let versions = self.describe_secret(secret_id).await?.version_ids_to_stages;
+        let version_with = |stage: &str| {
+            versions
+                .iter()
+                .find(|(_, stages)| stages.iter().any(|s| s == stage))
+                .map(|(version, _)| version.clone())
+                .with_context(|| format!("No version with stage {} for id: {}", stage, secret_id))
+        };
+        let current = version_with(&self.labels.current)?;
+        let previous = version_with(&self.labels.previous)?;
+        self.update_version_stage(secret_id, &self.labels.current, &previous, Some(current))
+            .await
     }
 
     /// Fetches the current secret value of the given secret_id
@@ -98,7 +388,7 @@ impl Smc {
         &self,
         secret_id: &str,
     ) -> anyhow::Result<Secret<S>> {
-        self.get_secret_value(secret_id, "AWSCURRENT").await
+        self.get_secret_value(secret_id, &self.labels.current).await
     }
 
     /// Fetches the pending secret value of the given secret_id
@@ -106,7 +396,7 @@ impl Smc {
         &self,
         secret_id: &str,
     ) -> anyhow::Result<Secret<S>> {
-        self.get_secret_value(secret_id, "AWSPENDING").await
+        self.get_secret_value(secret_id, &self.labels.pending).await
     }
 
     async fn get_secret_value<S: serde::de::DeserializeOwned>(
@@ -114,6 +404,10 @@ impl Smc {
         secret_id: &str,
         version_stage: &str,
     ) -> anyhow::Result<Secret<S>> {
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client.get_secret_value(secret_id, version_stage).await;
+        }
         #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
         let client = &self.aws_sdk_client;
         #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
@@ -129,9 +423,36 @@ impl Smc {
         secret_id: &str,
         request_token: Option<&str>,
         value: &SecretContainer<S>,
+        binary: bool,
     ) -> anyhow::Result<()> {
         use anyhow::Context;
 
+        let (secret_value, binary_only) = value
+            .to_secret_bytes()
+            .with_context(|| format!("Unable to serialize secret_value with id: {}", secret_id))?;
+        let binary = binary || binary_only;
+        let version_stages: Vec<_> = std::iter::once(&self.labels.pending)
+            .chain(&self.labels.extra)
+            .cloned()
+            .collect();
+        if let Some(kms_key_id) = &self.kms_key_id {
+            let description = self.describe_secret(secret_id).await?;
+            if description.kms_key_id.as_ref() != Some(kms_key_id) {
+                self.reencrypt_secret(secret_id, kms_key_id).await?;
+            }
+        }
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client
+                .put_secret_value_pending(
+                    secret_id,
+                    request_token,
+                    &secret_value,
+                    binary,
+                    &version_stages,
+                )
+                .await;
+        }
         #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
         let client = &self.aws_sdk_client;
         #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
@@ -139,19 +460,612 @@ impl Smc {
         #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
         compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
 
-        let secret_string: String = serde_json::to_string(value)
-            .with_context(|| format!("Unable to serialize secret_value with id: {}", secret_id))?;
         client
-            .put_secret_value_pending(secret_id, request_token, &secret_string)
+            .put_secret_value_pending(
+                secret_id,
+                request_token,
+                &secret_value,
+                binary,
+                &version_stages,
+            )
             .await
     }
 
+    /// Attaches the staging label `stage` to the version `version_id`,
+    /// removing it from the version it is currently attached to
+    pub(crate) async fn move_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+    ) -> anyhow::Result<()> {
+        let versions = self.describe_secret(secret_id).await?.version_ids_to_stages;
+        let remove_from = versions
+            .into_iter()
+            .find(|(_, stages)| stages.iter().any(|s| s == stage))
+            .map(|(version, _)| version);
+        if remove_from.as_deref() == Some(version_id) {
+            return Ok(());
+        }
+        self.update_version_stage(secret_id, stage, version_id, remove_from)
+            .await
+    }
+
+    async fn update_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+        remove_from_version_id: Option<String>,
+    ) -> anyhow::Result<()> {
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client
+                .update_version_stage(secret_id, stage, version_id, remove_from_version_id)
+                .await;
+        }
+        #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
+        let client = &self.aws_sdk_client;
+        #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
+        let client = &self.rusoto_client;
+        #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
+        compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+
+        client
+            .update_version_stage(secret_id, stage, version_id, remove_from_version_id)
+            .await
+    }
+
+    /// Detaches the staging label `stage` from the version `version_id`
+    pub(crate) async fn remove_version_stage(
+        &self,
+        secret_id: &str,
+        stage: &str,
+        version_id: &str,
+    ) -> anyhow::Result<()> {
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client
+                .remove_version_stage(secret_id, stage, version_id)
+                .await;
+        }
+        #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
+        let client = &self.aws_sdk_client;
+        #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
+        let client = &self.rusoto_client;
+        #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
+        compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+
+        client
+            .remove_version_stage(secret_id, stage, version_id)
+            .await
+    }
+
+    /// Removes the pending label from a version of an abandoned rotation, i.e. a
+    /// version which is neither current nor belongs to `client_request_token`.
+    /// Returns the id of the version, if the label was removed.
+    ///
+    /// The `SecretManager` refuses to start a new rotation as long as the pending
+    /// label is attached to such a version. Pass `None` as `client_request_token`
+    /// to clean up a secret before rotation is started again
+    pub async fn remove_stale_pending(
+        &self,
+        secret_id: &str,
+        client_request_token: Option<&str>,
+    ) -> anyhow::Result<Option<String>> {
+        let versions = self.describe_secret(secret_id).await?.version_ids_to_stages;
+        let stale = versions.into_iter().find(|(version_id, stages)| {
+            stages.contains(&self.labels.pending)
+                && !stages.contains(&self.labels.current)
+                && Some(version_id.as_str()) != client_request_token
+        });
+        match stale {
+            Some((version_id, _)) => {
+                log::warn!(
+                    "Removing stage {} from version {} of an abandoned rotation.",
+                    self.labels.pending,
+                    version_id
+                );
+                self.remove_version_stage(secret_id, &self.labels.pending, &version_id)
+                    .await?;
+                Ok(Some(version_id))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches the metadata of the given secret_id, e.g. its rotation
+    /// configuration and the staging labels of its versions
+    pub async fn describe_secret(&self, secret_id: &str) -> anyhow::Result<SecretDescription> {
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client.describe_secret(secret_id).await;
+        }
+        #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
+        let client = &self.aws_sdk_client;
+        #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
+        let client = &self.rusoto_client;
+        #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
+        compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+
+        client.describe_secret(secret_id).await
+    }
+
+    /// Switches the given secret_id to the KMS key `kms_key_id`, e.g. to migrate
+    /// secrets to a new customer managed key as part of their rotation.
+    ///
+    /// Versions which are written afterwards are encrypted with the new key and the
+    /// `SecretManager` re-encrypts the versions with the labels `AWSCURRENT`,
+    /// `AWSPENDING` and `AWSPREVIOUS`. Versions without label still require the old
+    /// key, so it must not be disabled before they are removed. Requires the
+    /// permissions `kms:Decrypt` on the old key and `kms:GenerateDataKey` and
+    /// `kms:Decrypt` on the new key in addition to `secretsmanager:UpdateSecret`
+    pub async fn reencrypt_secret(&self, secret_id: &str, kms_key_id: &str) -> anyhow::Result<()> {
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client.update_kms_key(secret_id, kms_key_id).await;
+        }
+        #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
+        let client = &self.aws_sdk_client;
+        #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
+        let client = &self.rusoto_client;
+        #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
+        compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+
+        client.update_kms_key(secret_id, kms_key_id).await
+    }
+
+    /// Fetches the tags of the given secret_id
+    pub async fn get_tags(
+        &self,
+        secret_id: &str,
+    ) -> anyhow::Result<std::collections::HashMap<String, String>> {
+        Ok(self.describe_secret(secret_id).await?.tags)
+    }
+
+    /// Adds the given tags to the given secret_id, overwriting
+    /// the values of tags which exist already
+    pub async fn tag_secret(&self, secret_id: &str, tags: &[(&str, &str)]) -> anyhow::Result<()> {
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client.tag_resource(secret_id, tags).await;
+        }
+        #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
+        let client = &self.aws_sdk_client;
+        #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
+        let client = &self.rusoto_client;
+        #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
+        compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+
+        client.tag_resource(secret_id, tags).await
+    }
+
+    /// Removes the tags with the given keys from the given secret_id
+    pub async fn untag_secret(&self, secret_id: &str, keys: &[&str]) -> anyhow::Result<()> {
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client.untag_resource(secret_id, keys).await;
+        }
+        #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
+        let client = &self.aws_sdk_client;
+        #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
+        let client = &self.rusoto_client;
+        #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
+        compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+
+        client.untag_resource(secret_id, keys).await
+    }
+
+    /// Acquires the lease on the given secret_id for `owner`, e.g. the request token
+    /// of a rotation, for the duration `lease`. Fails while another owner holds an
+    /// unexpired lease. See [`super::RotateRunner::lock_lease`].
+    ///
+    /// The lease is stored in the tag [`super::ROTATION_LOCK_TAG`]. As tags cannot be
+    /// written conditionally, the tag is read again after it was written, which
+    /// detects most, but not all, concurrent acquisitions. Requires the permissions
+    /// `secretsmanager:TagResource` and `secretsmanager:DescribeSecret`
+    pub async fn acquire_lock(
+        &self,
+        secret_id: &str,
+        owner: &str,
+        lease: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        let now = unix_time();
+        if let Some((holder, expires)) = self.lock_holder(secret_id).await? {
+            anyhow::ensure!(
+                holder == owner || expires <= now,
+                "Rotation of secret {} is locked by {} for another {}s",
+                secret_id,
+                holder,
+                expires - now
+            );
+        }
+        let value = format!("{}:{}", now + lease.as_secs(), owner);
+        self.tag_secret(secret_id, &[(super::ROTATION_LOCK_TAG, &value)])
+            .await?;
+        match self.lock_holder(secret_id).await? {
+            Some((holder, _)) if holder == owner => Ok(()),
+            holder => anyhow::bail!(
+                "Rotation of secret {} was locked concurrently by {}",
+                secret_id,
+                holder.map(|(holder, _)| holder).unwrap_or_default()
+            ),
+        }
+    }
+
+    /// Releases the lease on the given secret_id, if it is held by `owner`.
+    /// Requires the permission `secretsmanager:UntagResource` in addition to
+    /// the ones of [`Smc::acquire_lock`]
+    pub async fn release_lock(&self, secret_id: &str, owner: &str) -> anyhow::Result<()> {
+        match self.lock_holder(secret_id).await? {
+            Some((holder, _)) if holder == owner => {
+                self.untag_secret(secret_id, &[super::ROTATION_LOCK_TAG])
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Owner and expiry, in seconds since the unix epoch, of the lease on the secret
+    async fn lock_holder(&self, secret_id: &str) -> anyhow::Result<Option<(String, u64)>> {
+        let tags = self.get_tags(secret_id).await?;
+        Ok(tags.get(super::ROTATION_LOCK_TAG).and_then(|value| {
+            let (expires, holder) = value.split_once(':')?;
+            Some((holder.to_owned(), expires.parse().ok()?))
+        }))
+    }
+
+    /// Publishes `message` with `subject` to the SNS topic `topic_arn`,
+    /// e.g. to notify about the rotation. The region is taken from the arn
+    #[cfg(feature = "rotate_sns")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rotate_sns")))]
+    pub async fn publish_notification(
+        &self,
+        topic_arn: &str,
+        subject: &str,
+        message: &str,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let region = arn_region(topic_arn)
+            .with_context(|| format!("Invalid SNS topic arn: {}", topic_arn))?;
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client.publish(topic_arn, region, subject, message).await;
+        }
+        let request = http::Request::post(format!(
+            "https://{}/",
+            super::signed::endpoint("sns", region)
+        ))
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(super::notification::publish_request_body(
+            topic_arn, subject, message,
+        ))
+        .context("Unable to build SNS request")?;
+        let (status, body) = self
+            .send_signed("sns", region, request)
+            .await
+            .with_context(|| format!("Unable to publish to SNS topic: {}", topic_arn))?;
+        anyhow::ensure!(
+            status.is_success(),
+            "Unable to publish to SNS topic: {}. Status {}: {}",
+            topic_arn,
+            status,
+            String::from_utf8_lossy(&body)
+        );
+        Ok(())
+    }
+
+    /// Arns of the SCRAM secrets, which are associated with the MSK cluster `cluster_arn`.
+    /// Requires the permission `kafka:ListScramSecrets`
+    #[cfg(feature = "rotate_msk")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rotate_msk")))]
+    pub async fn scram_secrets(&self, cluster_arn: &str) -> anyhow::Result<Vec<String>> {
+        use anyhow::Context;
+
+        let region = arn_region(cluster_arn)?;
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client.scram_secrets(cluster_arn, region).await;
+        }
+        let mut secret_arns = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut url = format!(
+                "https://{}/v1/clusters/{}/scram-secrets",
+                super::signed::endpoint("kafka", region),
+                form_urlencoded::byte_serialize(cluster_arn.as_bytes()).collect::<String>()
+            );
+            if let Some(next_token) = &next_token {
+                url.push_str("?nextToken=");
+                url.extend(form_urlencoded::byte_serialize(next_token.as_bytes()));
+            }
+            let request = http::Request::get(url)
+                .body(String::new())
+                .context("Unable to build MSK request")?;
+            let (status, body) = self
+                .send_signed("kafka", region, request)
+                .await
+                .with_context(|| {
+                    format!("Unable to list SCRAM secrets of cluster: {}", cluster_arn)
+                })?;
+            anyhow::ensure!(
+                status.is_success(),
+                "Unable to list SCRAM secrets of cluster: {}. Status {}: {}",
+                cluster_arn,
+                status,
+                String::from_utf8_lossy(&body)
+            );
+            let page: super::msk::ScramSecretsPage = serde_json::from_slice(&body)
+                .with_context(|| format!("Invalid SCRAM secrets of cluster: {}", cluster_arn))?;
+            secret_arns.extend(page.secret_arn_list);
+            next_token = page.next_token;
+            if next_token.is_none() {
+                return Ok(secret_arns);
+            }
+        }
+    }
+
+    /// Associates the secret `secret_arn` with the MSK cluster `cluster_arn`, so its
+    /// credentials are accepted by the brokers. Requires the permission
+    /// `kafka:BatchAssociateScramSecret`
+    #[cfg(feature = "rotate_msk")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rotate_msk")))]
+    pub async fn associate_scram_secret(
+        &self,
+        cluster_arn: &str,
+        secret_arn: &str,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let region = arn_region(cluster_arn)?;
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client
+                .associate_scram_secret(cluster_arn, region, secret_arn)
+                .await;
+        }
+        let request = http::Request::post(format!(
+            "https://{}/v1/clusters/{}/scram-secrets",
+            super::signed::endpoint("kafka", region),
+            form_urlencoded::byte_serialize(cluster_arn.as_bytes()).collect::<String>()
+        ))
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::json!({ "secretArnList": [secret_arn] }).to_string())
+        .context("Unable to build MSK request")?;
+        let (status, body) = self
+            .send_signed("kafka", region, request)
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to associate secret {} with cluster: {}",
+                    secret_arn, cluster_arn
+                )
+            })?;
+        anyhow::ensure!(
+            status.is_success(),
+            "Unable to associate secret {} with cluster: {}. Status {}: {}",
+            secret_arn,
+            cluster_arn,
+            status,
+            String::from_utf8_lossy(&body)
+        );
+        let response: super::msk::AssociateResponse = serde_json::from_slice(&body)
+            .with_context(|| format!("Invalid association response of cluster: {}", cluster_arn))?;
+        response.check(cluster_arn)
+    }
+
+    /// Updates the AUTH token of the ElastiCache replication group `replication_group_id`
+    /// with `ModifyReplicationGroup`, which is applied immediately. Requires the
+    /// permission `elasticache:ModifyReplicationGroup`
+    #[cfg(feature = "rotate_elasticache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rotate_elasticache")))]
+    pub async fn modify_auth_token(
+        &self,
+        region: &str,
+        replication_group_id: &str,
+        auth_token: &str,
+        strategy: super::redis::AuthTokenUpdateStrategy,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client
+                .modify_auth_token(region, replication_group_id, auth_token, strategy)
+                .await;
+        }
+        self.elasticache(
+            region,
+            &[
+                ("Action", "ModifyReplicationGroup"),
+                ("ReplicationGroupId", replication_group_id),
+                ("AuthToken", auth_token),
+                ("AuthTokenUpdateStrategy", strategy.as_str()),
+                ("ApplyImmediately", "true"),
+            ],
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to update AUTH token of replication group: {}",
+                replication_group_id
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Status of the ElastiCache replication group `replication_group_id`, e.g.
+    /// `available` or `modifying`. Requires the permission
+    /// `elasticache:DescribeReplicationGroups`
+    #[cfg(feature = "rotate_elasticache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rotate_elasticache")))]
+    pub async fn replication_group_status(
+        &self,
+        region: &str,
+        replication_group_id: &str,
+    ) -> anyhow::Result<String> {
+        use anyhow::Context;
+
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client
+                .replication_group_status(region, replication_group_id)
+                .await;
+        }
+        let response = self
+            .elasticache(
+                region,
+                &[
+                    ("Action", "DescribeReplicationGroups"),
+                    ("ReplicationGroupId", replication_group_id),
+                ],
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to describe replication group: {}",
+                    replication_group_id
+                )
+            })?;
+        // The status of the replication group precedes the ones of its node groups
+        response
+            .split_once("<Status>")
+            .and_then(|(_, rest)| rest.split_once("</Status>"))
+            .map(|(status, _)| status.to_owned())
+            .with_context(|| {
+                format!(
+                    "Missing status of replication group: {}",
+                    replication_group_id
+                )
+            })
+    }
+
+    /// Sends a request to the query API of ElastiCache and returns the xml response
+    #[cfg(feature = "rotate_elasticache")]
+    async fn elasticache(&self, region: &str, params: &[(&str, &str)]) -> anyhow::Result<String> {
+        use anyhow::Context;
+
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(params)
+            .append_pair("Version", "2015-02-02")
+            .finish();
+        let request = http::Request::post(format!(
+            "https://{}/",
+            super::signed::endpoint("elasticache", region)
+        ))
+        .header(
+            http::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(body)
+        .context("Unable to build ElastiCache request")?;
+        let (status, body) = self.send_signed("elasticache", region, request).await?;
+        let body = String::from_utf8_lossy(&body).into_owned();
+        anyhow::ensure!(status.is_success(), "Status {}: {}", status, body);
+        Ok(body)
+    }
+
+    /// Signs `request` with the credentials of the enabled sdk and sends it
+    #[cfg(feature = "_signed_requests")]
+    async fn send_signed(
+        &self,
+        service: &str,
+        region: &str,
+        request: http::Request<String>,
+    ) -> anyhow::Result<(http::StatusCode, hyper::body::Bytes)> {
+        #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
+        let client = &self.aws_sdk_client;
+        #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
+        let client = &self.rusoto_client;
+        #[cfg(all(feature = "rotate_rusoto", feature = "rotate_aws_sdk"))]
+        compile_error("Only rotate_rusoto or rotate_aws_sdk can be enabled at once");
+
+        let credentials = client.credentials().await?;
+        self.signed_client
+            .send(&credentials, service, region, request)
+            .await
+    }
+
+    /// Waits until every replica of the given secret_id carries the version `version_id`
+    /// as current value. Fails if replication to a region failed or `timeout` elapsed
+    pub async fn wait_for_replicas(
+        &self,
+        secret_id: &str,
+        version_id: &str,
+        timeout: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        let deadline = std::time::Instant::now() + timeout;
+        let mut description = self.describe_secret(secret_id).await?;
+        let mut pending = Vec::new();
+        for replica in &description.replicas {
+            let replica_smc = Self::new(&replica.region)
+                .await?
+                .with_stage_labels(self.labels.clone());
+            pending.push((replica.region.clone(), replica_smc));
+        }
+        loop {
+            if let Some(failed) = description
+                .replicas
+                .iter()
+                .find(|replica| replica.status == "Failed")
+            {
+                anyhow::bail!(
+                    "Replication of secret {} to region {} failed: {}",
+                    secret_id,
+                    failed.region,
+                    failed.message.as_deref().unwrap_or("unknown reason")
+                );
+            }
+            let mut still_pending = Vec::new();
+            for (region, replica) in pending {
+                let current = replica
+                    .get_secret_value_current::<()>(&description.name)
+                    .await;
+                if !matches!(current, Ok(current) if current.version_id == version_id) {
+                    still_pending.push((region, replica));
+                }
+            }
+            pending = still_pending;
+            if pending.is_empty() {
+                return Ok(());
+            }
+            let regions: Vec<_> = pending.iter().map(|(region, _)| region.as_str()).collect();
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            if remaining.is_zero() {
+                anyhow::bail!(
+                    "Replicas of secret {} in {} did not receive version {} in time",
+                    secret_id,
+                    regions.join(", "),
+                    version_id
+                );
+            }
+            log::info!("Waiting for replicas in {}.", regions.join(", "));
+            tokio::time::sleep(REPLICA_POLL_INTERVAL.min(remaining)).await;
+            description = self.describe_secret(secret_id).await?;
+        }
+    }
+
     pub(crate) async fn set_pending_secret_value_to_current(
         &self,
         secret_arn: String,
         secret_current_version_id: String,
         secret_pending_version_id: String,
     ) -> anyhow::Result<()> {
+        #[cfg(feature = "test")]
+        if let Some(client) = &self.memory_client {
+            return client
+                .set_pending_secret_value_to_current(
+                    secret_arn,
+                    secret_current_version_id,
+                    secret_pending_version_id,
+                    &self.labels.current,
+                )
+                .await;
+        }
         #[cfg(all(feature = "rotate_aws_sdk", not(feature = "rotate_rusoto")))]
         let client = &self.aws_sdk_client;
         #[cfg(all(feature = "rotate_rusoto", not(feature = "rotate_aws_sdk")))]
@@ -164,6 +1078,7 @@ impl Smc {
                 secret_arn,
                 secret_current_version_id,
                 secret_pending_version_id,
+                &self.labels.current,
             )
             .await
     }
@@ -0,0 +1,481 @@
+//! Provides a ready [`super::RotateRunner`] for Snowflake users.
+//!
+//! Depending on the secret, either the password or the key pair of the user is rotated:
+//! * With `private_key`, `create` generates a new RSA key pair. `set` stores the public
+//!   key in the other of the two key slots of the user (`RSA_PUBLIC_KEY` and
+//!   `RSA_PUBLIC_KEY_2`), so the current key keeps working until clients picked up the
+//!   new one. `test` runs `SELECT 1` with the new key.
+//! * Otherwise, `set` changes the password and `test` logs in with the new password
+//!   and runs `SELECT 1`.
+//!
+//! Statements are executed with the SQL API, which requires key pair authentication.
+//! By default, the user changes its own key, so passwords can only be rotated with an
+//! administrator in `admin_secret_id`, which refers to a secret with `username`,
+//! `private_key` and optionally `role` of a user who may alter the rotated user.
+//!
+//! Besides the feature `rotate_snowflake`, one of the features `rotate_rusoto` or
+//! `rotate_aws_sdk` has to be enabled.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, lambda_runtime_types::rotate::snowflake::SnowflakeRotation, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc};
+
+/// Number of times the status of a statement is polled, if it didn't complete right away
+const STATUS_POLLS: u32 = 30;
+
+/// Client id which is reported when logging in with a password
+const CLIENT_APP_ID: &str = "lambda-runtime-types";
+
+/// Secret of a Snowflake user
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct SnowflakeSecret {
+    /// Account identifier, e.g. `myorg-myaccount`
+    pub account: String,
+    /// Login name of the user
+    #[serde(rename = "username", alias = "user")]
+    pub user: String,
+    /// Password of the user, if the password is rotated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Private key of the user as PKCS#8 PEM, if the key pair is rotated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+    /// Slot of the public key of `private_key`, either `1` for `RSA_PUBLIC_KEY`
+    /// or `2` for `RSA_PUBLIC_KEY_2`. Defaults to `1`
+    #[serde(default = "default_key_slot")]
+    pub key_slot: u8,
+    /// Warehouse which executes the test query
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warehouse: Option<String>,
+    /// Role which executes the test query
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// Base url of the account. Defaults to `https://{account}.snowflakecomputing.com`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Id of a secret with `username`, `private_key` and optionally `role` of an
+    /// administrator, which alters the user. Defaults to the user itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_secret_id: Option<String>,
+}
+
+impl std::fmt::Debug for SnowflakeSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SnowflakeSecret")
+            .field("account", &self.account)
+            .field("user", &self.user)
+            .field("password", &self.password.as_ref().map(|_| "[...]"))
+            .field("private_key", &self.private_key.as_ref().map(|_| "[...]"))
+            .field("key_slot", &self.key_slot)
+            .field("warehouse", &self.warehouse)
+            .field("role", &self.role)
+            .field("url", &self.url)
+            .field("admin_secret_id", &self.admin_secret_id)
+            .finish()
+    }
+}
+
+const fn default_key_slot() -> u8 {
+    1
+}
+
+/// Credentials of the administrator in `admin_secret_id`
+#[derive(Clone, serde::Deserialize)]
+struct AdminSecret {
+    #[serde(rename = "username", alias = "user")]
+    user: String,
+    private_key: String,
+    #[serde(default)]
+    role: Option<String>,
+}
+
+impl SnowflakeSecret {
+    /// Base url of the account
+    pub fn base_url(&self) -> String {
+        self.url.as_ref().map_or_else(
+            || format!("https://{}.snowflakecomputing.com", self.account),
+            |url| url.trim_end_matches('/').to_owned(),
+        )
+    }
+
+    /// Executes `statement` as the user with the SQL API and returns the response.
+    /// Requires `private_key`
+    pub async fn execute(&self, statement: &str) -> anyhow::Result<serde_json::Value> {
+        use anyhow::Context;
+
+        let private_key = self
+            .private_key
+            .as_deref()
+            .with_context(|| format!("Secret of user {} contains no private_key", self.user))?;
+        execute(
+            &self.base_url(),
+            &self.account,
+            &self.user,
+            private_key,
+            self.role.as_deref(),
+            self.warehouse.as_deref(),
+            statement,
+        )
+        .await
+    }
+
+    /// Runs `SELECT 1` with the key pair or, without `private_key`, the password of the user
+    pub async fn check_connection(&self) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        if self.private_key.is_some() {
+            self.execute("SELECT 1").await?;
+            return Ok(());
+        }
+        let password = self.password.as_deref().with_context(|| {
+            format!(
+                "Secret of user {} contains neither password nor private_key",
+                self.user
+            )
+        })?;
+        let token = self.login(password).await?;
+        let url = format!(
+            "{}/queries/v1/query-request?requestId={}",
+            self.base_url(),
+            request_id()?
+        );
+        let body =
+            serde_json::json!({ "sqlText": "SELECT 1", "asyncExec": false, "sequenceId": 1 });
+        let req = http::Request::post(&url)
+            .header(
+                http::header::AUTHORIZATION,
+                format!("Snowflake Token=\"{}\"", token),
+            )
+            .header(http::header::ACCEPT, "application/snowflake")
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(hyper::Body::from(body.to_string()))
+            .context("Unable to build query request")?;
+        let response = send(req).await?;
+        session_data(&response).context("Unable to run test query")?;
+        Ok(())
+    }
+
+    /// Logs in with `password` and returns the session token
+    async fn login(&self, password: &str) -> anyhow::Result<String> {
+        use anyhow::Context;
+
+        let query = {
+            let mut query = form_urlencoded::Serializer::new(String::new());
+            if let Some(warehouse) = &self.warehouse {
+                query.append_pair("warehouse", warehouse);
+            }
+            if let Some(role) = &self.role {
+                query.append_pair("roleName", role);
+            }
+            query.finish()
+        };
+        let url = format!("{}/session/v1/login-request?{}", self.base_url(), query);
+        let body = serde_json::json!({
+            "data": {
+                "CLIENT_APP_ID": CLIENT_APP_ID,
+                "CLIENT_APP_VERSION": env!("CARGO_PKG_VERSION"),
+                "ACCOUNT_NAME": account_name(&self.account),
+                "LOGIN_NAME": self.user,
+                "PASSWORD": password,
+            }
+        });
+        let req = http::Request::post(&url)
+            .header(http::header::ACCEPT, "application/json")
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(hyper::Body::from(body.to_string()))
+            .context("Unable to build login request")?;
+        let response = send(req).await?;
+        let data = session_data(&response)
+            .with_context(|| format!("Unable to log in as user {}", self.user))?;
+        data["token"]
+            .as_str()
+            .map(ToOwned::to_owned)
+            .context("Login response contains no token")
+    }
+}
+
+/// Generates a new RSA private key for key pair authentication as PKCS#8 PEM
+pub fn generate_private_key() -> anyhow::Result<String> {
+    let key = openssl::pkey::PKey::from_rsa(openssl::rsa::Rsa::generate(2048)?)?;
+    Ok(String::from_utf8(key.private_key_to_pem_pkcs8()?)?)
+}
+
+/// Public key of `private_key` in the format of `ALTER USER ... SET RSA_PUBLIC_KEY`,
+/// i.e. the base64 encoded DER without PEM header
+pub fn public_key(private_key: &str) -> anyhow::Result<String> {
+    use base64::Engine;
+
+    let key = parse_private_key(private_key)?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(key.public_key_to_der()?))
+}
+
+fn parse_private_key(
+    private_key: &str,
+) -> anyhow::Result<openssl::pkey::PKey<openssl::pkey::Private>> {
+    use anyhow::Context;
+
+    openssl::pkey::PKey::private_key_from_pem(private_key.as_bytes())
+        .context("Unable to parse private key")
+}
+
+/// Account name as used in logins and tokens. Locators which
+/// include the region are reduced to the locator
+fn account_name(account: &str) -> String {
+    account.split('.').next().unwrap_or_default().to_uppercase()
+}
+
+/// Json web token for key pair authentication, which is valid for five minutes
+fn jwt(account: &str, user: &str, private_key: &str) -> anyhow::Result<String> {
+    use base64::Engine;
+
+    let key = parse_private_key(private_key)?;
+    let fingerprint = base64::engine::general_purpose::STANDARD
+        .encode(openssl::sha::sha256(&key.public_key_to_der()?));
+    let subject = format!("{}.{}", account_name(account), user.to_uppercase());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let header = serde_json::json!({ "alg": "RS256", "typ": "JWT" });
+    let claims = serde_json::json!({
+        "iss": format!("{}.SHA256:{}", subject, fingerprint),
+        "sub": subject,
+        "iat": now,
+        "exp": now + 300,
+    });
+    let encode = |data: &[u8]| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data);
+    let message = format!(
+        "{}.{}",
+        encode(header.to_string().as_bytes()),
+        encode(claims.to_string().as_bytes())
+    );
+    let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key)?;
+    signer.update(message.as_bytes())?;
+    Ok(format!("{}.{}", message, encode(&signer.sign_to_vec()?)))
+}
+
+/// Executes `statement` with the SQL API and waits until it completed
+async fn execute(
+    base_url: &str,
+    account: &str,
+    user: &str,
+    private_key: &str,
+    role: Option<&str>,
+    warehouse: Option<&str>,
+    statement: &str,
+) -> anyhow::Result<serde_json::Value> {
+    use anyhow::Context;
+
+    let token = jwt(account, user, private_key)?;
+    let mut body = serde_json::json!({ "statement": statement, "timeout": 60 });
+    if let Some(role) = role {
+        body["role"] = role.into();
+    }
+    if let Some(warehouse) = warehouse {
+        body["warehouse"] = warehouse.into();
+    }
+    let mut req = sql_api_request(&token)
+        .method(http::Method::POST)
+        .uri(format!("{}/api/v2/statements", base_url))
+        .body(hyper::Body::from(body.to_string()))
+        .context("Unable to build statement request")?;
+    for _ in 0..STATUS_POLLS {
+        let (status, response) = send(req).await?;
+        anyhow::ensure!(
+            status.is_success(),
+            "Statement as user {} failed with status {}: {}",
+            user,
+            status,
+            response
+                .get("message")
+                .and_then(serde_json::Value::as_str)
+                .map_or_else(|| response.to_string(), ToOwned::to_owned)
+        );
+        if status != http::StatusCode::ACCEPTED {
+            return Ok(response);
+        }
+        // The statement is still running
+        let status_url = response["statementStatusUrl"]
+            .as_str()
+            .context("Response of running statement contains no statementStatusUrl")?;
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        req = sql_api_request(&token)
+            .uri(format!("{}{}", base_url, status_url))
+            .body(hyper::Body::empty())
+            .context("Unable to build status request")?;
+    }
+    anyhow::bail!("Statement as user {} did not complete in time", user)
+}
+
+fn sql_api_request(token: &str) -> http::request::Builder {
+    http::Request::builder()
+        .header(http::header::AUTHORIZATION, format!("Bearer {}", token))
+        .header("X-Snowflake-Authorization-Token-Type", "KEYPAIR_JWT")
+        .header(http::header::ACCEPT, "application/json")
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .header(http::header::USER_AGENT, CLIENT_APP_ID)
+}
+
+/// Sends `req` and returns the status and json body of the response
+async fn send(
+    req: http::Request<hyper::Body>,
+) -> anyhow::Result<(http::StatusCode, serde_json::Value)> {
+    use anyhow::Context;
+
+    let url = req.uri().to_string();
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_webpki_roots()
+        .https_or_http()
+        .enable_http1()
+        .build();
+    let res = hyper::Client::builder()
+        .build::<_, hyper::Body>(connector)
+        .request(req)
+        .await
+        .with_context(|| format!("Request to {} failed", url))?;
+    let status = res.status();
+    let body = hyper::body::to_bytes(res.into_body())
+        .await
+        .with_context(|| format!("Unable to read response of {}", url))?;
+    if body.is_empty() {
+        return Ok((status, serde_json::Value::Null));
+    }
+    let body = serde_json::from_slice(&body)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(&body).into_owned()));
+    Ok((status, body))
+}
+
+/// Data of a response of the session endpoints, which report failures in the body
+fn session_data(
+    (status, response): &(http::StatusCode, serde_json::Value),
+) -> anyhow::Result<&serde_json::Value> {
+    anyhow::ensure!(
+        status.is_success() && response["success"].as_bool() == Some(true),
+        "Snowflake responded with status {}: {} ({})",
+        status,
+        response["message"].as_str().unwrap_or_default(),
+        response["code"].as_str().unwrap_or_default()
+    );
+    Ok(&response["data"])
+}
+
+/// Random id in the format of an uuid
+fn request_id() -> anyhow::Result<String> {
+    let mut id = [0; 16];
+    openssl::rand::rand_bytes(&mut id)?;
+    let hex: String = id.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+/// Quotes `value` as string literal
+fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Rotates the password or key pair of the Snowflake user in the secret
+#[derive(Debug)]
+pub struct SnowflakeRotation;
+
+#[async_trait::async_trait]
+impl<'a> super::RotateRunner<'a, (), SnowflakeSecret> for SnowflakeRotation {
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn create(
+        _shared: &'a (),
+        mut secret_cur: SecretContainer<SnowflakeSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<SnowflakeSecret>> {
+        if secret_cur.private_key.is_some() {
+            secret_cur.private_key = Some(generate_private_key()?);
+            secret_cur.key_slot = if secret_cur.key_slot == 2 { 1 } else { 2 };
+        } else {
+            secret_cur.password = Some(smc.generate_new_password(false, None).await?);
+        }
+        Ok(secret_cur)
+    }
+
+    async fn set(
+        _shared: &'a (),
+        secret_cur: SecretContainer<SnowflakeSecret>,
+        secret_new: SecretContainer<SnowflakeSecret>,
+        smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let admin = match &secret_cur.admin_secret_id {
+            Some(admin_secret_id) => smc
+                .get_secret::<AdminSecret>(admin_secret_id)
+                .await?
+                .data
+                .clone(),
+            None => AdminSecret {
+                user: secret_cur.user.clone(),
+                private_key: secret_cur.private_key.clone().with_context(|| {
+                    format!(
+                        "Rotation of the password of user {} requires admin_secret_id, \
+                         as the SQL API does not support passwords",
+                        secret_cur.user
+                    )
+                })?,
+                role: secret_cur.role.clone(),
+            },
+        };
+        let user = format!("IDENTIFIER({})", literal(&secret_new.user));
+        let statement = match (&secret_new.private_key, &secret_new.password) {
+            (Some(private_key), _) => {
+                let slot = if secret_new.key_slot == 2 { "_2" } else { "" };
+                format!(
+                    "ALTER USER {} SET RSA_PUBLIC_KEY{} = {}",
+                    user,
+                    slot,
+                    literal(&public_key(private_key)?)
+                )
+            }
+            (None, Some(password)) => {
+                format!("ALTER USER {} SET PASSWORD = {}", user, literal(password))
+            }
+            (None, None) => anyhow::bail!(
+                "Secret of user {} contains neither password nor private_key",
+                secret_new.user
+            ),
+        };
+        execute(
+            &secret_cur.base_url(),
+            &secret_cur.account,
+            &admin.user,
+            &admin.private_key,
+            admin.role.as_deref(),
+            None,
+            &statement,
+        )
+        .await
+        .with_context(|| format!("Unable to alter user {}", secret_new.user))?;
+        Ok(())
+    }
+
+    async fn test(
+        _shared: &'a (),
+        secret_new: SecretContainer<SnowflakeSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        secret_new.check_connection().await
+    }
+}
@@ -0,0 +1,407 @@
+//! Provides a [`super::RotateRunner`] for TLS certificates and their private keys.
+//!
+//! A new private key and certificate signing request (CSR) are generated in `create`.
+//! The CSR is passed to [`TlsTarget::issue`], which submits it to a certificate
+//! authority or, by default, signs it itself. The resulting certificate and key are
+//! stored as PEM in the secret. Where the certificate is used depends on the target
+//! (e.g. a load balancer or a service requiring client certificates), so installing
+//! and verifying it is done by a [`TlsTarget`]:
+//! * `set`: [`TlsTarget::install`] installs the new certificate
+//! * `test`: [`TlsTarget::verify`] checks that the new certificate is used
+//! * `finish`: [`TlsTarget::finish`] optionally removes the previous certificate
+//!
+//! Besides the feature `rotate_tls`, one of the features `rotate_rusoto` or
+//! `rotate_aws_sdk` has to be enabled.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # async fn upload_certificate(certificate: &str, private_key: &str) -> anyhow::Result<()> { Ok(()) }
+//! # async fn served_certificate() -> anyhow::Result<String> { Ok(String::new()) }
+//! use lambda_runtime_types::rotate::{
+//!     tls::{TlsRotation, TlsSecret, TlsTarget},
+//!     SecretContainer,
+//! };
+//!
+//! struct LoadBalancer;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> TlsTarget<'a, ()> for LoadBalancer {
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//!
+//!     async fn install(
+//!         _shared: &'a (),
+//!         _current: &SecretContainer<TlsSecret>,
+//!         new: &SecretContainer<TlsSecret>,
+//!     ) -> anyhow::Result<()> {
+//!         upload_certificate(&new.certificate, &new.private_key).await
+//!     }
+//!
+//!     async fn verify(_shared: &'a (), new: &SecretContainer<TlsSecret>) -> anyhow::Result<()> {
+//!         new.validate()?;
+//!         anyhow::ensure!(served_certificate().await? == new.certificate, "Certificate is not served yet");
+//!         Ok(())
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, TlsRotation<LoadBalancer>, _>()
+//! }
+//! ```
+
+use super::{RotationContext, SecretContainer, Smc};
+use openssl::pkey::{PKey, Private};
+
+/// Secret containing a TLS certificate and its private key
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct TlsSecret {
+    /// Certificate (chain) as PEM
+    #[serde(deserialize_with = "pem")]
+    pub certificate: String,
+    /// Private key of the certificate as PEM
+    #[serde(deserialize_with = "pem")]
+    pub private_key: String,
+}
+
+impl std::fmt::Debug for TlsSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsSecret")
+            .field("certificate", &self.certificate)
+            .field("private_key", &"[...]")
+            .finish()
+    }
+}
+
+/// Deserializes a PEM blob. Line breaks which were stored as escape
+/// sequences, e.g. when the secret was entered in the console, are restored
+fn pem<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let pem = <String as serde::Deserialize>::deserialize(deserializer)?;
+    Ok(pem.replace("\\r\\n", "\n").replace("\\n", "\n"))
+}
+
+impl TlsSecret {
+    /// Checks that the certificate belongs to the private
+    /// key and did not expire
+    pub fn validate(&self) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let certificate = openssl::x509::X509::from_pem(self.certificate.as_bytes())
+            .context("Unable to parse certificate")?;
+        let key = PKey::private_key_from_pem(self.private_key.as_bytes())
+            .context("Unable to parse private key")?;
+        anyhow::ensure!(
+            certificate
+                .public_key()
+                .context("Unable to read public key of certificate")?
+                .public_eq(&key),
+            "Certificate does not belong to the private key"
+        );
+        let now = openssl::asn1::Asn1Time::days_from_now(0)?;
+        anyhow::ensure!(
+            certificate.not_after() > now,
+            "Certificate expired at {}",
+            certificate.not_after()
+        );
+        Ok(())
+    }
+}
+
+/// Type of generated private keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    /// ECDSA key on the curve P-256
+    EcdsaP256,
+    /// RSA key with 2048 bits
+    Rsa2048,
+}
+
+/// Names a certificate is issued for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateNames {
+    /// Common name of the subject
+    pub common_name: String,
+    /// DNS names of the subject alternative name extension
+    pub dns_names: Vec<String>,
+}
+
+impl CertificateNames {
+    /// Reads the names of an existing certificate
+    pub fn from_certificate(pem: &str) -> anyhow::Result<Self> {
+        use anyhow::Context;
+
+        let certificate =
+            openssl::x509::X509::from_pem(pem.as_bytes()).context("Unable to parse certificate")?;
+        let common_name = certificate
+            .subject_name()
+            .entries_by_nid(openssl::nid::Nid::COMMONNAME)
+            .next()
+            .context("Certificate has no common name")?
+            .data()
+            .to_string()
+            .context("Unable to read common name")?;
+        let dns_names = certificate
+            .subject_alt_names()
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|name| name.dnsname().map(str::to_owned))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            common_name,
+            dns_names,
+        })
+    }
+}
+
+/// New private key together with a certificate signing request for it
+pub struct CertificateSigningRequest {
+    key: PKey<Private>,
+    request: openssl::x509::X509Req,
+    names: CertificateNames,
+}
+
+impl std::fmt::Debug for CertificateSigningRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CertificateSigningRequest")
+            .field("names", &self.names)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CertificateSigningRequest {
+    /// Generates a new private key and a request for the given names
+    pub fn new(key_type: KeyType, names: CertificateNames) -> anyhow::Result<Self> {
+        use anyhow::Context;
+        use openssl::x509::{extension::SubjectAlternativeName, X509ReqBuilder};
+
+        let key = generate_key(key_type).context("Unable to generate private key")?;
+        let subject = subject(&names)?;
+        let mut builder = X509ReqBuilder::new()?;
+        builder.set_version(0)?;
+        builder.set_subject_name(&subject)?;
+        builder.set_pubkey(&key)?;
+        if !names.dns_names.is_empty() {
+            let mut san = SubjectAlternativeName::new();
+            for name in &names.dns_names {
+                san.dns(name);
+            }
+            let mut extensions = openssl::stack::Stack::new()?;
+            extensions.push(san.build(&builder.x509v3_context(None))?)?;
+            builder.add_extensions(&extensions)?;
+        }
+        builder
+            .sign(&key, openssl::hash::MessageDigest::sha256())
+            .context("Unable to sign certificate signing request")?;
+        Ok(Self {
+            key,
+            request: builder.build(),
+            names,
+        })
+    }
+
+    /// Names the certificate is requested for
+    pub const fn names(&self) -> &CertificateNames {
+        &self.names
+    }
+
+    /// The certificate signing request as PEM
+    pub fn pem(&self) -> anyhow::Result<String> {
+        Ok(String::from_utf8(self.request.to_pem()?)?)
+    }
+
+    /// Issues a certificate for the request which is signed with its own key
+    pub fn self_signed(&self, validity_days: u32) -> anyhow::Result<String> {
+        use anyhow::Context;
+        use openssl::x509::{extension::SubjectAlternativeName, X509Builder};
+
+        let mut serial = [0; 16];
+        openssl::rand::rand_bytes(&mut serial)?;
+        // Serial numbers have to be positive
+        serial[0] &= 0x7f;
+        let serial = openssl::bn::BigNum::from_slice(&serial)?.to_asn1_integer()?;
+        let subject = subject(&self.names)?;
+        let mut builder = X509Builder::new()?;
+        builder.set_version(2)?;
+        builder.set_serial_number(&serial)?;
+        builder.set_subject_name(&subject)?;
+        builder.set_issuer_name(&subject)?;
+        builder.set_pubkey(&self.key)?;
+        let not_before = openssl::asn1::Asn1Time::days_from_now(0)?;
+        let not_after = openssl::asn1::Asn1Time::days_from_now(validity_days)?;
+        builder.set_not_before(&not_before)?;
+        builder.set_not_after(&not_after)?;
+        if !self.names.dns_names.is_empty() {
+            let mut san = SubjectAlternativeName::new();
+            for name in &self.names.dns_names {
+                san.dns(name);
+            }
+            let san = san.build(&builder.x509v3_context(None, None))?;
+            builder.append_extension(san)?;
+        }
+        builder
+            .sign(&self.key, openssl::hash::MessageDigest::sha256())
+            .context("Unable to sign certificate")?;
+        Ok(String::from_utf8(builder.build().to_pem()?)?)
+    }
+
+    /// The generated private key as PEM
+    pub fn private_key_pem(&self) -> anyhow::Result<String> {
+        Ok(String::from_utf8(self.key.private_key_to_pem_pkcs8()?)?)
+    }
+}
+
+fn generate_key(key_type: KeyType) -> anyhow::Result<PKey<Private>> {
+    Ok(match key_type {
+        KeyType::EcdsaP256 => {
+            let group = openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1)?;
+            PKey::from_ec_key(openssl::ec::EcKey::generate(&group)?)?
+        }
+        KeyType::Rsa2048 => PKey::from_rsa(openssl::rsa::Rsa::generate(2048)?)?,
+    })
+}
+
+fn subject(names: &CertificateNames) -> anyhow::Result<openssl::x509::X509Name> {
+    let mut subject = openssl::x509::X509NameBuilder::new()?;
+    subject.append_entry_by_nid(openssl::nid::Nid::COMMONNAME, &names.common_name)?;
+    Ok(subject.build())
+}
+
+/// Target which uses a TLS certificate
+#[async_trait::async_trait]
+pub trait TlsTarget<'a, Shared>: Send + Sync + 'static
+where
+    Shared: Send + Sync + 'a,
+{
+    /// Type of generated private keys. Defaults to [`KeyType::EcdsaP256`]
+    const KEY_TYPE: KeyType = KeyType::EcdsaP256;
+
+    /// Validity of self-signed certificates in days. Defaults to 365
+    const VALIDITY_DAYS: u32 = 365;
+
+    /// See documentation of [`crate::Runner::setup`]
+    async fn setup(region: &'a str) -> anyhow::Result<Shared>;
+
+    /// Names the new certificate is issued for. Defaults
+    /// to the names of the current certificate
+    fn names(current: &TlsSecret) -> anyhow::Result<CertificateNames> {
+        CertificateNames::from_certificate(&current.certificate)
+    }
+
+    /// Issues the certificate for the request, e.g. by submitting it to a certificate
+    /// authority. Returns the certificate (chain) as PEM. Defaults to a self-signed
+    /// certificate, which is valid for [`TlsTarget::VALIDITY_DAYS`]
+    async fn issue(
+        _shared: &'a Shared,
+        request: &CertificateSigningRequest,
+    ) -> anyhow::Result<String> {
+        request.self_signed(Self::VALIDITY_DAYS)
+    }
+
+    /// Installs the new certificate at the target
+    async fn install(
+        shared: &'a Shared,
+        current: &SecretContainer<TlsSecret>,
+        new: &SecretContainer<TlsSecret>,
+    ) -> anyhow::Result<()>;
+
+    /// Verifies that the target uses the new certificate. It is also called before
+    /// [`TlsTarget::install`] to check whether the certificate is installed already,
+    /// so it must not only validate the secret itself
+    async fn verify(shared: &'a Shared, new: &SecretContainer<TlsSecret>) -> anyhow::Result<()>;
+
+    /// Perform any work which may be necessary to complete rotation,
+    /// e.g. removing the previous certificate from the target
+    async fn finish(
+        _shared: &'a Shared,
+        _previous: &SecretContainer<TlsSecret>,
+        _new: &SecretContainer<TlsSecret>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Rotates TLS certificates of the target `T`
+pub struct TlsRotation<T>(std::marker::PhantomData<T>);
+
+impl<T> std::fmt::Debug for TlsRotation<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsRotation").finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl<'a, Shared, T> super::RotateRunner<'a, Shared, TlsSecret> for TlsRotation<T>
+where
+    Shared: Send + Sync + 'a,
+    T: TlsTarget<'a, Shared>,
+{
+    async fn setup(region: &'a str) -> anyhow::Result<Shared> {
+        T::setup(region).await
+    }
+
+    async fn create(
+        shared: &'a Shared,
+        mut secret_cur: SecretContainer<TlsSecret>,
+        _smc: &Smc,
+        ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<SecretContainer<TlsSecret>> {
+        let names = T::names(&secret_cur)?;
+        // Key generation is cpu bound and may take a while for rsa keys
+        let request =
+            tokio::task::spawn_blocking(move || CertificateSigningRequest::new(T::KEY_TYPE, names))
+                .await??;
+        if ctx.dry_run {
+            log::info!(
+                "Dry run: Would issue certificate for {:?}.",
+                request.names()
+            );
+            return Ok(secret_cur);
+        }
+        secret_cur.data = TlsSecret {
+            certificate: T::issue(shared, &request).await?,
+            private_key: request.private_key_pem()?,
+        };
+        Ok(secret_cur)
+    }
+
+    fn validate_secret(secret_new: &SecretContainer<TlsSecret>) -> anyhow::Result<()> {
+        secret_new.validate()
+    }
+
+    async fn set(
+        shared: &'a Shared,
+        secret_cur: SecretContainer<TlsSecret>,
+        secret_new: SecretContainer<TlsSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        secret_new.validate()?;
+        T::install(shared, &secret_cur, &secret_new).await
+    }
+
+    async fn test(
+        shared: &'a Shared,
+        secret_new: SecretContainer<TlsSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        T::verify(shared, &secret_new).await
+    }
+
+    async fn finish(
+        shared: &'a Shared,
+        secret_cur: SecretContainer<TlsSecret>,
+        secret_new: SecretContainer<TlsSecret>,
+        _smc: &Smc,
+        _ctx: &RotationContext<'_>,
+    ) -> anyhow::Result<()> {
+        T::finish(shared, &secret_cur, &secret_new).await
+    }
+}
@@ -0,0 +1,109 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+type Values = HashMap<TypeId, Arc<dyn Any + Send + Sync>>;
+
+/// State which is created fresh for every invocation and dropped after it.
+///
+/// In contrast to `Shared`, which lives as long as the execution environment,
+/// values in `Scoped` only live for a single invocation. It is a place for
+/// request-scoped caches, e.g. of items loaded while processing the event,
+/// which must not leak into the next invocation. Values are stored per type
+/// and created on first use, optionally from `Shared`.
+///
+/// ```no_run
+/// # struct Client;
+/// # impl Client { fn user(&self, id: &str) -> String { id.into() } }
+/// struct Shared {
+///     client: Client,
+/// }
+///
+/// #[derive(Default)]
+/// struct Users(std::sync::Mutex<std::collections::HashMap<String, String>>);
+///
+/// struct Runner;
+///
+/// #[async_trait::async_trait]
+/// impl<'a> lambda_runtime_types::Runner<'a, Shared, Vec<String>, Vec<String>> for Runner {
+///     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, Vec<String>>) -> anyhow::Result<Vec<String>> {
+///         let users = event.scoped.get_or_insert_with(Users::default);
+///         let mut users = users.0.lock().unwrap();
+///         Ok(event
+///             .event
+///             .iter()
+///             .map(|id| users.entry(id.clone()).or_insert_with(|| shared.client.user(id)).clone())
+///             .collect())
+///     }
+///
+///     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+///         Ok(Shared { client: Client })
+///     }
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Scoped {
+    values: Arc<std::sync::Mutex<Values>>,
+}
+
+impl std::fmt::Debug for Scoped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scoped")
+            .field("values", &self.lock().len())
+            .finish()
+    }
+}
+
+impl Scoped {
+    /// Returns the value of type `T`, if it was created before
+    pub fn get<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let value = Arc::clone(self.lock().get(&TypeId::of::<T>())?);
+        value.downcast().ok()
+    }
+
+    /// Returns the value of type `T`. If there is none yet, it is
+    /// created with `init`
+    pub fn get_or_insert_with<T>(&self, init: impl FnOnce() -> T) -> Arc<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        // `init` is called without holding the lock, so it may use `Scoped` as well
+        let value = Arc::new(init());
+        let stored = Arc::clone(
+            self.lock()
+                .entry(TypeId::of::<T>())
+                .or_insert_with(|| value),
+        );
+        stored.downcast().unwrap_or_else(|_| unreachable!())
+    }
+
+    /// Stores the value of type `T`. Returns the previous value
+    pub fn insert<T>(&self, value: T) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let previous = self.lock().insert(TypeId::of::<T>(), Arc::new(value))?;
+        previous.downcast().ok()
+    }
+
+    /// Removes the value of type `T` and returns it
+    pub fn remove<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let value = self.lock().remove(&TypeId::of::<T>())?;
+        value.downcast().ok()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Values> {
+        self.values
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,144 @@
+//! Provides a cache for secrets (or resources built from them, like connections)
+//! which can be stored in `Shared`.
+//!
+//! The cached value is loaded lazily and reloaded once its ttl expired. When the
+//! secret is rotated, existing credentials may stop working before the ttl expired.
+//! [`SecretCache::call`] therefore reloads the value and retries the operation once,
+//! if the operation failed with an error which indicates an authentication failure.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # struct Database;
+//! # impl Database {
+//! #     async fn connect(user: &str, password: &str) -> anyhow::Result<Self> { Ok(Self) }
+//! #     async fn query(&self) -> anyhow::Result<u64> { Ok(0) }
+//! # }
+//! # async fn fetch_secret() -> anyhow::Result<(String, String)> { Ok((String::new(), String::new())) }
+//! use lambda_runtime_types::secret_cache::SecretCache;
+//! use std::time::Duration;
+//!
+//! struct Shared {
+//!     database: SecretCache<Database>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), u64> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<u64> {
+//!         shared
+//!             .database
+//!             .call(
+//!                 |err| err.to_string().contains("password authentication failed"),
+//!                 |database| async move { database.query().await },
+//!             )
+//!             .await
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             database: SecretCache::new(Duration::from_secs(300), || async {
+//!                 let (user, password) = fetch_secret().await?;
+//!                 Database::connect(&user, &password).await
+//!             }),
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+type Loader<T> = Box<dyn Fn() -> BoxFuture<'static, anyhow::Result<T>> + Send + Sync>;
+
+/// Cache for a value loaded from a secret
+pub struct SecretCache<T> {
+    ttl: Duration,
+    loader: Loader<T>,
+    entry: tokio::sync::Mutex<Option<Entry<T>>>,
+}
+
+struct Entry<T> {
+    value: Arc<T>,
+    loaded_at: SystemTime,
+}
+
+impl<T> std::fmt::Debug for SecretCache<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretCache")
+            .field("ttl", &self.ttl)
+            .field("value", &"[...]")
+            .finish()
+    }
+}
+
+impl<T: Send + Sync> SecretCache<T> {
+    /// Creates a new cache, which loads its value with `loader` and
+    /// reloads it once `ttl` passed
+    pub fn new<L, Fut>(ttl: Duration, loader: L) -> Self
+    where
+        L: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        Self {
+            ttl,
+            loader: Box::new(move || Box::pin(loader())),
+            entry: tokio::sync::Mutex::default(),
+        }
+    }
+
+    /// Returns the cached value or loads it, if it is missing or expired
+    pub async fn get(&self) -> anyhow::Result<Arc<T>> {
+        let mut entry = self.entry.lock().await;
+        if let Some(entry) = entry.as_ref() {
+            let expired = SystemTime::now()
+                .duration_since(entry.loaded_at)
+                .map_or(true, |age| age >= self.ttl);
+            if !expired {
+                return Ok(Arc::clone(&entry.value));
+            }
+        }
+        log::info!("Loading secret");
+        let value = Arc::new((self.loader)().await?);
+        *entry = Some(Entry {
+            value: Arc::clone(&value),
+            loaded_at: SystemTime::now(),
+        });
+        drop(entry);
+        Ok(value)
+    }
+
+    /// Removes the cached value, so it is reloaded on the next access
+    pub async fn invalidate(&self) {
+        *self.entry.lock().await = None;
+    }
+
+    /// Executes `operation` with the cached value. If it fails with an error
+    /// for which `is_auth_error` returns true, the value is reloaded and the
+    /// operation is executed once more.
+    pub async fn call<R, Op, Fut, P>(
+        &self,
+        is_auth_error: P,
+        mut operation: Op,
+    ) -> anyhow::Result<R>
+    where
+        Op: FnMut(Arc<T>) -> Fut + Send,
+        Fut: std::future::Future<Output = anyhow::Result<R>> + Send,
+        P: Fn(&anyhow::Error) -> bool + Send,
+    {
+        match operation(self.get().await?).await {
+            Err(err) if is_auth_error(&err) => {
+                log::warn!("Authentication failed, reloading secret: {:?}", err);
+                self.invalidate().await;
+                operation(self.get().await?).await
+            }
+            res => res,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Provides a cell for state in `Shared` which can be invalidated and rebuilt.
+//!
+//! `Shared` is only set up once per execution environment. If state like a
+//! database connection breaks, every following invocation of the environment
+//! fails. A [`SharedCell`] builds its value lazily on first use. Once it is
+//! invalidated, the value is rebuilt by the next access, e.g. in the next
+//! invocation. [`SharedCell::call`] invalidates the value automatically, if an
+//! operation fails with an error indicating that the value is broken.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # struct Connection;
+//! # impl Connection {
+//! #     async fn connect() -> anyhow::Result<Self> { Ok(Self) }
+//! #     async fn query(&self) -> anyhow::Result<u64> { Ok(0) }
+//! # }
+//! use lambda_runtime_types::shared_cell::SharedCell;
+//!
+//! struct Shared {
+//!     connection: SharedCell<Connection>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), u64> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<u64> {
+//!         shared
+//!             .connection
+//!             .call(
+//!                 |err| err.to_string().contains("connection closed"),
+//!                 |connection| async move { connection.query().await },
+//!             )
+//!             .await
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             connection: SharedCell::new(Connection::connect),
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+type Init<T> = Box<dyn Fn() -> BoxFuture<'static, anyhow::Result<T>> + Send + Sync>;
+
+/// Cell for a value which is rebuilt after it was invalidated
+pub struct SharedCell<T> {
+    init: Init<T>,
+    value: tokio::sync::Mutex<Option<Arc<T>>>,
+}
+
+impl<T> std::fmt::Debug for SharedCell<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedCell")
+            .field("value", &"[...]")
+            .finish()
+    }
+}
+
+impl<T: Send + Sync> SharedCell<T> {
+    /// Creates a new cell, which builds its value with `init`
+    pub fn new<I, Fut>(init: I) -> Self
+    where
+        I: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        Self {
+            init: Box::new(move || Box::pin(init())),
+            value: tokio::sync::Mutex::default(),
+        }
+    }
+
+    /// Returns the value or builds it, if it is missing or was invalidated.
+    /// If building fails, the error is returned and the next access tries again
+    pub async fn get_or_init(&self) -> anyhow::Result<Arc<T>> {
+        let mut value = self.value.lock().await;
+        if let Some(value) = value.as_ref() {
+            return Ok(Arc::clone(value));
+        }
+        log::info!("Building shared value");
+        let built = Arc::new((self.init)().await?);
+        *value = Some(Arc::clone(&built));
+        drop(value);
+        Ok(built)
+    }
+
+    /// Whether the value is currently built
+    pub async fn is_initialized(&self) -> bool {
+        self.value.lock().await.is_some()
+    }
+
+    /// Removes the value, so it is rebuilt on the next access
+    pub async fn invalidate(&self) {
+        *self.value.lock().await = None;
+    }
+
+    /// Executes `operation` with the value. If it fails with an error for
+    /// which `is_broken` returns true, the value is invalidated, so the next
+    /// access rebuilds it. The error is returned in any case.
+    pub async fn call<R, Op, Fut, P>(&self, is_broken: P, operation: Op) -> anyhow::Result<R>
+    where
+        Op: FnOnce(Arc<T>) -> Fut + Send,
+        Fut: std::future::Future<Output = anyhow::Result<R>> + Send,
+        P: FnOnce(&anyhow::Error) -> bool + Send,
+    {
+        let value = self.get_or_init().await?;
+        let res = operation(Arc::clone(&value)).await;
+        if let Err(err) = &res {
+            if is_broken(err) {
+                log::warn!(
+                    "Shared value is broken, rebuilding it on next access: {:?}",
+                    err
+                );
+                let mut current = self.value.lock().await;
+                // Keep a value which was already rebuilt in the meantime
+                if current
+                    .as_ref()
+                    .is_some_and(|current| Arc::ptr_eq(current, &value))
+                {
+                    *current = None;
+                }
+            }
+        }
+        res
+    }
+}
@@ -0,0 +1,153 @@
+//! Provides state for `Shared` which survives restarts of the runtime.
+//!
+//! If the runtime process exits, e.g. after a crash or an exceeded memory limit,
+//! lambda may restart it in the same execution environment. Everything in `Shared`
+//! is lost, but files in `/tmp` are kept. A [`Snapshot`] restores its value from a
+//! file on cold start and writes it back with [`Snapshot::persist`], which is
+//! typically called in [`crate::Runner::after_invoke`]. Only values which were
+//! changed since the last snapshot are written.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::snapshot::Snapshot;
+//! use std::collections::HashMap;
+//!
+//! struct Shared {
+//!     cache: Snapshot<HashMap<String, String>>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, String, Option<String>> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, String>) -> anyhow::Result<Option<String>> {
+//!         let cached = shared.cache.read(|cache| cache.get(&event.event).cloned());
+//!         if cached.is_none() {
+//!             shared.cache.update(|cache| cache.insert(event.event.clone(), "value".into()));
+//!         }
+//!         Ok(cached)
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             cache: Snapshot::restore("/tmp/cache.json"),
+//!         })
+//!     }
+//!
+//!     async fn after_invoke(shared: &'a Shared, _info: &'a lambda_runtime_types::InvocationInfo<'a>) {
+//!         if let Err(err) = shared.cache.persist().await {
+//!             log::warn!("Unable to persist cache: {:?}", err);
+//!         }
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use std::path::PathBuf;
+
+/// Value which is persisted to a file and restored on cold start
+#[derive(Debug)]
+pub struct Snapshot<T> {
+    path: PathBuf,
+    restored: bool,
+    state: std::sync::Mutex<State<T>>,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    value: T,
+    changed: bool,
+}
+
+impl<T> Snapshot<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + Default,
+{
+    /// Restores the value from the file at `path`. If the file does
+    /// not exist or can not be read, the default value is used
+    pub fn restore(path: impl Into<PathBuf>) -> Self {
+        use anyhow::Context;
+
+        let path = path.into();
+        let restored = std::fs::read(&path)
+            .context("Unable to read snapshot")
+            .and_then(|data| serde_json::from_slice(&data).context("Unable to parse snapshot"));
+        let (value, restored) = match restored {
+            Ok(value) => {
+                log::info!("Restored snapshot from {}", path.display());
+                (value, true)
+            }
+            Err(err) => {
+                if path.exists() {
+                    log::warn!("Ignoring snapshot {}: {:?}", path.display(), err);
+                }
+                (T::default(), false)
+            }
+        };
+        Self {
+            path,
+            restored,
+            state: std::sync::Mutex::new(State {
+                value,
+                changed: false,
+            }),
+        }
+    }
+
+    /// Whether the value was restored from a previous snapshot
+    pub const fn is_restored(&self) -> bool {
+        self.restored
+    }
+
+    /// Reads the value
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.lock().value)
+    }
+
+    /// Changes the value. It is written by the next [`Snapshot::persist`]
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut state = self.lock();
+        state.changed = true;
+        f(&mut state.value)
+    }
+
+    /// Writes the value to the file, if it changed since the last snapshot.
+    /// The file is replaced atomically, so a crash while writing does not
+    /// leave a broken snapshot behind
+    pub async fn persist(&self) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        let data = {
+            let mut state = self.lock();
+            if !state.changed {
+                return Ok(());
+            }
+            let data = serde_json::to_vec(&state.value).context("Unable to serialize snapshot")?;
+            state.changed = false;
+            data
+        };
+        let path = self.path.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, data).context("Unable to write snapshot")?;
+            std::fs::rename(&tmp, &path).context("Unable to replace snapshot")
+        })
+        .await
+        .context("Unable to persist snapshot")
+        .and_then(|res| res);
+        if res.is_err() {
+            self.lock().changed = true;
+        }
+        res
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State<T>> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+/// Spawns background tasks which are bound to the invocation.
+///
+/// Tasks spawned with `tokio::spawn` keep running after the response was sent
+/// and get frozen together with the execution environment. Tasks spawned with
+/// [`Spawner::spawn`] are awaited before the response is returned. Tasks which
+/// did not finish shortly before the deadline are cancelled with a warning.
+///
+/// ```no_run
+/// # async fn send_metrics() -> anyhow::Result<()> { Ok(()) }
+/// struct Runner;
+///
+/// #[async_trait::async_trait]
+/// impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+///     async fn run(shared: &'a (), event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+///         event.spawner.spawn("send metrics", send_metrics());
+///         Ok(())
+///     }
+///
+///     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Spawner {
+    tasks: std::sync::Arc<std::sync::Mutex<Vec<Task>>>,
+}
+
+#[derive(Debug)]
+struct Task {
+    name: String,
+    handle: tokio::task::JoinHandle<anyhow::Result<()>>,
+}
+
+impl Spawner {
+    /// Spawns a task, which is awaited before the invocation completes
+    pub fn spawn<F>(&self, name: impl Into<String>, task: F)
+    where
+        F: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let handle = tokio::spawn(task);
+        self.lock().push(Task {
+            name: name.into(),
+            handle,
+        });
+    }
+
+    /// Amount of spawned tasks which were not awaited yet
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether there are no tasks which were not awaited yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Awaits all spawned tasks. Tasks still running `margin` before
+    /// `deadline_in_ms` are cancelled.
+    pub(crate) async fn join(
+        &self,
+        clock: &dyn crate::Clock,
+        deadline_in_ms: Option<u64>,
+        margin: Duration,
+    ) {
+        use futures::FutureExt;
+
+        let tasks = std::mem::take(&mut *self.lock());
+        let deadline = deadline_in_ms.map(|deadline_in_ms| {
+            let remaining = clock
+                .until_deadline_ms(deadline_in_ms)
+                .saturating_sub(margin);
+            tokio::time::Instant::now() + remaining
+        });
+        for Task { name, mut handle } in tasks {
+            let res = match deadline {
+                Some(deadline) => {
+                    let mut timeout = Box::pin(tokio::time::sleep_until(deadline).fuse());
+                    futures::select! {
+                        res = (&mut handle).fuse() => Some(res),
+                        _ = timeout => None,
+                    }
+                }
+                None => Some((&mut handle).await),
+            };
+            match res {
+                Some(Ok(Ok(()))) => {}
+                Some(Ok(Err(err))) => log::error!("Task {} failed: {:?}", name, err),
+                Some(Err(err)) => log::error!("Task {} panicked: {:?}", name, err),
+                None => {
+                    log::warn!("Cancelling task {} as the deadline is reached", name);
+                    handle.abort();
+                }
+            }
+        }
+    }
+
+    /// Cancels all spawned tasks
+    pub(crate) fn abort(&self) {
+        let tasks = std::mem::take(&mut *self.lock());
+        for Task { name, handle } in tasks {
+            if !handle.is_finished() {
+                log::warn!("Cancelling task {}", name);
+                handle.abort();
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Task>> {
+        self.tasks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
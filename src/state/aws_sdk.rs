@@ -0,0 +1,112 @@
+/// [`super::StateStore`] which persists state in a DynamoDB table.
+///
+/// The table requires a string partition key named `id`. Values are stored
+/// as json in the attribute `state` and their version in the attribute
+/// `version`. Conditional writes ensure that concurrent updates do not
+/// overwrite each other.
+#[derive(Clone, Debug)]
+pub struct DynamoDbStore {
+    client: aws_sdk_dynamodb::Client,
+    table: String,
+}
+
+impl DynamoDbStore {
+    /// Creates a new store using the given table
+    pub async fn new(table: impl Into<String>) -> Self {
+        let config = aws_config::load_from_env().await;
+        let client = aws_sdk_dynamodb::Client::new(&config);
+        Self::with_client(client, table)
+    }
+
+    /// Creates a new store using an existing client
+    pub fn with_client(client: aws_sdk_dynamodb::Client, table: impl Into<String>) -> Self {
+        Self {
+            client,
+            table: table.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl super::StateStore for DynamoDbStore {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<super::Versioned<serde_json::Value>>> {
+        use anyhow::Context;
+        use aws_sdk_dynamodb::model::AttributeValue;
+
+        let output = self
+            .client
+            .get_item()
+            .table_name(&self.table)
+            .key("id", AttributeValue::S(key.to_owned()))
+            .consistent_read(true)
+            .send()
+            .await
+            .with_context(|| format!("Unable to fetch state with key: {}", key))?;
+        let Some(item) = output.item() else {
+            return Ok(None);
+        };
+        let state = item
+            .get("state")
+            .and_then(|v| v.as_s().ok())
+            .with_context(|| format!("State with key {} has no value", key))?;
+        let version = item
+            .get("version")
+            .and_then(|v| v.as_n().ok())
+            .and_then(|v| v.parse().ok())
+            .with_context(|| format!("State with key {} has no version", key))?;
+        let value = serde_json::from_str(state)
+            .with_context(|| format!("State with key {} is invalid json", key))?;
+        Ok(Some(super::Versioned { value, version }))
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        value: &serde_json::Value,
+        expected: Option<u64>,
+    ) -> anyhow::Result<Option<u64>> {
+        use anyhow::Context;
+        use aws_sdk_dynamodb::model::AttributeValue;
+        use aws_sdk_dynamodb::types::SdkError;
+
+        let version = expected.unwrap_or(0) + 1;
+        let request = self
+            .client
+            .put_item()
+            .table_name(&self.table)
+            .item("id", AttributeValue::S(key.to_owned()))
+            .item("state", AttributeValue::S(value.to_string()))
+            .item("version", AttributeValue::N(version.to_string()));
+        let request = match expected {
+            Some(expected) => request
+                .condition_expression("version = :expected")
+                .expression_attribute_values(":expected", AttributeValue::N(expected.to_string())),
+            None => request.condition_expression("attribute_not_exists(id)"),
+        };
+        match request.send().await {
+            Ok(_) => Ok(Some(version)),
+            Err(SdkError::ServiceError(err))
+                if err.err().is_conditional_check_failed_exception() =>
+            {
+                Ok(None)
+            }
+            Err(err) => {
+                Err(err).with_context(|| format!("Unable to store state with key: {}", key))
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        use anyhow::Context;
+        use aws_sdk_dynamodb::model::AttributeValue;
+
+        self.client
+            .delete_item()
+            .table_name(&self.table)
+            .key("id", AttributeValue::S(key.to_owned()))
+            .send()
+            .await
+            .with_context(|| format!("Unable to delete state with key: {}", key))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Provides state which is shared between execution environments.
+//!
+//! `Shared` only lives within one execution environment. Counters, rate limits or
+//! deduplication windows which must hold across all environments of a function are
+//! kept in an external [`StateStore`] instead. Every value has a version, which is
+//! used for optimistic locking: [`StateStore::put`] only succeeds if the value was
+//! not changed since it was read. [`update`] retries read-modify-write cycles until
+//! they succeed. A DynamoDB store is available with the feature `state_aws_sdk`.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use lambda_runtime_types::state::{update, MemoryStore, StateStore};
+//!
+//! struct Shared {
+//!     store: Box<dyn StateStore>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), u64> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<u64> {
+//!         update(&*shared.store, "invocations", |count: Option<u64>| {
+//!             count.unwrap_or(0) + 1
+//!         })
+//!         .await
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         Ok(Shared {
+//!             store: Box::new(MemoryStore::default()),
+//!         })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+#[cfg(feature = "state_aws_sdk")]
+mod aws_sdk;
+
+#[cfg(feature = "state_aws_sdk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "state_aws_sdk")))]
+pub use aws_sdk::DynamoDbStore;
+
+use std::collections::HashMap;
+
+/// Amount of attempts of [`update`] before it gives up
+const UPDATE_ATTEMPTS: usize = 10;
+
+/// Value together with the version it was stored with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Versioned<T> {
+    /// The stored value
+    pub value: T,
+    /// Version of the value. Starts at 1 and is
+    /// incremented with every update
+    pub version: u64,
+}
+
+/// External storage for state which is shared between execution environments
+#[async_trait::async_trait]
+pub trait StateStore: Send + Sync {
+    /// Returns the value stored for the given key
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Versioned<serde_json::Value>>>;
+
+    /// Stores the value for the given key, if its current version is `expected`.
+    /// `None` expects that no value is stored yet. Returns the new version or
+    /// `None`, if the value was changed in the meantime
+    async fn put(
+        &self,
+        key: &str,
+        value: &serde_json::Value,
+        expected: Option<u64>,
+    ) -> anyhow::Result<Option<u64>>;
+
+    /// Removes the value for the given key
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+}
+
+/// Reads the value for `key`, changes it with `f` and stores it. If the value
+/// was changed by another execution environment in the meantime, the cycle is
+/// repeated. Returns the stored value
+pub async fn update<T, F>(store: &dyn StateStore, key: &str, mut f: F) -> anyhow::Result<T>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+    F: FnMut(Option<T>) -> T + Send,
+{
+    use anyhow::Context;
+
+    for _ in 0..UPDATE_ATTEMPTS {
+        let current = store.get(key).await?;
+        let expected = current.as_ref().map(|current| current.version);
+        let current = current
+            .map(|current| serde_json::from_value(current.value))
+            .transpose()
+            .with_context(|| format!("State with key {} has an invalid shape", key))?;
+        let value = f(current);
+        let json = serde_json::to_value(&value).context("Unable to serialize state")?;
+        if store.put(key, &json, expected).await?.is_some() {
+            return Ok(value);
+        }
+        log::debug!("State with key {} changed concurrently, retrying", key);
+    }
+    anyhow::bail!(
+        "Unable to update state with key {} after {} attempts",
+        key,
+        UPDATE_ATTEMPTS
+    )
+}
+
+/// [`StateStore`] which keeps values in memory. Values are not shared between
+/// execution environments, so it is mainly useful for tests
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    values: std::sync::Mutex<HashMap<String, Versioned<serde_json::Value>>>,
+}
+
+impl MemoryStore {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Versioned<serde_json::Value>>> {
+        self.values
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[async_trait::async_trait]
+impl StateStore for MemoryStore {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Versioned<serde_json::Value>>> {
+        Ok(self.lock().get(key).cloned())
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        value: &serde_json::Value,
+        expected: Option<u64>,
+    ) -> anyhow::Result<Option<u64>> {
+        let mut values = self.lock();
+        if values.get(key).map(|current| current.version) != expected {
+            return Ok(None);
+        }
+        let version = expected.unwrap_or(0) + 1;
+        values.insert(
+            key.to_owned(),
+            Versioned {
+                value: value.clone(),
+                version,
+            },
+        );
+        drop(values);
+        Ok(Some(version))
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        self.lock().remove(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+/// Statistics of the execution environment, taken at the start of an
+/// invocation. See [`crate::LambdaEvent::stats`]
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct Stats {
+    /// Completed invocations of the execution environment
+    pub invocations: u64,
+    /// Completed invocations which failed or returned a fallback response
+    pub errors: u64,
+    /// Request id of the previous invocation
+    pub last_request_id: Option<String>,
+    started: Instant,
+}
+
+impl Stats {
+    /// Time since the execution environment started
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            invocations: 0,
+            errors: 0,
+            last_request_id: None,
+            started: Instant::now(),
+        }
+    }
+}
+
+/// Tracks the statistics of an execution environment
+#[derive(Debug, Default)]
+pub struct Tracker(std::sync::Mutex<Stats>);
+
+impl Tracker {
+    /// Current statistics
+    pub fn snapshot(&self) -> Stats {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Records a completed invocation
+    pub fn record(&self, request_id: &str, failed: bool) {
+        let mut stats = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        stats.invocations += 1;
+        if failed {
+            stats.errors += 1;
+        }
+        stats.last_request_id = Some(request_id.to_owned());
+    }
+}
@@ -0,0 +1,95 @@
+use std::cell::Cell;
+use std::time::Instant;
+
+tokio::task_local! {
+    static RETRIES: Cell<u32>;
+}
+
+/// Category of the error which failed an invocation
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    Deserialization,
+    Validation,
+    Handler,
+    Panic,
+    Timeout,
+    Memory,
+    SideEffect,
+    Serialization,
+}
+
+/// Records a retry for the summary of the running invocation
+pub fn record_retry() {
+    let _ = RETRIES.try_with(|retries| retries.set(retries.get() + 1));
+}
+
+/// Executes `future` and counts the retries done while executing it
+pub async fn track_retries<F: std::future::Future>(future: F) -> (F::Output, u32) {
+    RETRIES
+        .scope(Cell::new(0), async {
+            let output = future.await;
+            (output, RETRIES.with(Cell::get))
+        })
+        .await
+}
+
+/// Single machine-parsable record describing an invocation
+#[derive(Debug, serde::Serialize)]
+pub struct Summary<'a> {
+    r#type: &'static str,
+    request_id: &'a str,
+    outcome: &'static str,
+    error_class: Option<ErrorClass>,
+    duration_ms: f64,
+    billed_duration_ms: u128,
+    memory_mb: i32,
+    gb_seconds: f64,
+    estimated_cost_usd: f64,
+    cold_start: bool,
+    retries: u32,
+    request_bytes: usize,
+    response_bytes: Option<usize>,
+}
+
+impl<'a> Summary<'a> {
+    pub fn new(
+        request_id: &'a str,
+        memory: i32,
+        started: Instant,
+        request_bytes: usize,
+        result: Result<(usize, Option<ErrorClass>), ErrorClass>,
+        retries: u32,
+        cold_start: bool,
+    ) -> Self {
+        let duration = started.elapsed();
+        let estimate = crate::cost::Estimate::new(duration, memory);
+        Self {
+            r#type: "invocation_summary",
+            request_id,
+            outcome: match result {
+                Ok((_, None)) => "success",
+                Ok((_, Some(_))) => "fallback",
+                Err(_) => "failure",
+            },
+            error_class: result.map_or_else(Some, |(_, fallback)| fallback),
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            billed_duration_ms: estimate.billed.as_millis(),
+            memory_mb: memory,
+            gb_seconds: estimate.gb_seconds,
+            estimated_cost_usd: estimate.cost,
+            cold_start,
+            retries,
+            request_bytes,
+            response_bytes: result.ok().map(|(bytes, _)| bytes),
+        }
+    }
+
+    /// Writes the summary as a single json line
+    pub fn log(&self) {
+        match serde_json::to_string(self) {
+            Ok(summary) => log::info!("{}", summary),
+            Err(err) => log::error!("Unable to serialize invocation summary: {:?}", err),
+        }
+    }
+}
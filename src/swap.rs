@@ -0,0 +1,140 @@
+//! Provides shared state which is replaced as a whole.
+//!
+//! Some state in `Shared`, like configuration and everything built from it, is
+//! never changed in place but replaced completely, e.g. after a hot-reload. A
+//! [`SharedSwap`] keeps the current value in an [`Arc`]. Invocations load the
+//! value once and keep using it, even if it is replaced in the meantime, so no lock
+//! is held across awaits. The value can be replaced by an invocation or by a
+//! background refresh started with [`SharedSwap::spawn_refresh`].
+//!
+//! # Usage
+//!
+//! ```no_run
+//! # async fn fetch_config() -> anyhow::Result<Config> { Ok(Config { endpoint: String::new() }) }
+//! use lambda_runtime_types::swap::SharedSwap;
+//!
+//! struct Config {
+//!     endpoint: String,
+//! }
+//!
+//! struct Shared {
+//!     config: SharedSwap<Config>,
+//! }
+//!
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, Shared, (), ()> for Runner {
+//!     async fn run(shared: &'a Shared, event: lambda_runtime_types::LambdaEvent<'a, ()>) -> anyhow::Result<()> {
+//!         let config = shared.config.load();
+//!         log::info!("Using endpoint {}", config.endpoint);
+//!         Ok(())
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+//!         let config = SharedSwap::new(fetch_config().await?);
+//!         config.spawn_refresh(std::time::Duration::from_secs(60), fetch_config);
+//!         Ok(Shared { config })
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use std::sync::{Arc, RwLock};
+
+/// Shared value which is atomically replaced as a whole. Clones
+/// refer to the same value
+#[derive(Debug)]
+pub struct SharedSwap<T> {
+    value: Arc<RwLock<Arc<T>>>,
+}
+
+impl<T> Clone for SharedSwap<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: Arc::clone(&self.value),
+        }
+    }
+}
+
+impl<T> SharedSwap<T> {
+    /// Creates a new swap with the initial value
+    pub fn new(value: T) -> Self {
+        Self {
+            value: Arc::new(RwLock::new(Arc::new(value))),
+        }
+    }
+
+    /// Returns the current value. It stays valid even if
+    /// the value is replaced afterwards
+    pub fn load(&self) -> Arc<T> {
+        Arc::clone(
+            &self
+                .value
+                .read()
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+        )
+    }
+
+    /// Replaces the value and returns the previous one
+    pub fn store(&self, value: T) -> Arc<T> {
+        std::mem::replace(&mut *self.write(), Arc::new(value))
+    }
+
+    /// Replaces the value with the result of `f`, which is called
+    /// with the current value. No other replacement happens in between.
+    /// Returns the new value
+    pub fn update(&self, f: impl FnOnce(&T) -> T) -> Arc<T> {
+        let mut current = self.write();
+        let value = Arc::new(f(&current));
+        *current = Arc::clone(&value);
+        drop(current);
+        value
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Arc<T>> {
+        self.value
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl<T> SharedSwap<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Spawns a task which replaces the value with the result of `load`
+    /// every `interval`. If loading fails, the previous value is kept.
+    /// The task runs as long as the returned handle is not aborted.
+    ///
+    /// As lambda freezes the execution environment between invocations,
+    /// refreshes only happen while an invocation is running.
+    pub fn spawn_refresh<F, Fut>(
+        &self,
+        interval: std::time::Duration,
+        load: F,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<T>> + Send,
+    {
+        let swap = self.clone();
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + interval;
+            let mut ticks = tokio::time::interval_at(start, interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                match load().await {
+                    Ok(value) => {
+                        swap.store(value);
+                    }
+                    Err(err) => log::warn!("Unable to refresh shared value: {:?}", err),
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,42 @@
+/// Error returned by [`crate::Runner::validate`] for events which are
+/// malformed or semantically invalid.
+///
+/// Invalid events are rejected before the runner is invoked and are reported
+/// with the error type `ValidationError`, so they can be told apart from
+/// failures of the runner in logs and destinations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Field of the event which is invalid, if any
+    pub field: Option<String>,
+    /// Why the event is invalid
+    pub message: String,
+}
+
+impl ValidationError {
+    /// Creates a new error for the whole event
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a new error for a single field of the event
+    pub fn field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: Some(field.into()),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "Invalid event field {}: {}", field, self.message),
+            None => write!(f, "Invalid event: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
@@ -0,0 +1,48 @@
+//! Provides predicates to detect warmup events, which are sent to keep
+//! execution environments warm. Use them in [`crate::Runner::warmup`].
+//!
+//! # Usage
+//!
+//! ```no_run
+//! struct Runner;
+//!
+//! #[async_trait::async_trait]
+//! impl<'a> lambda_runtime_types::Runner<'a, (), u64, u64> for Runner {
+//!     async fn run(shared: &'a (), event: lambda_runtime_types::LambdaEvent<'a, u64>) -> anyhow::Result<u64> {
+//!         Ok(event.event + 1)
+//!     }
+//!
+//!     async fn setup(_region: &'a str) -> anyhow::Result<()> {
+//!         Ok(())
+//!     }
+//!
+//!     fn warmup(event: &serde_json::value::RawValue) -> Option<serde_json::Value> {
+//!         lambda_runtime_types::warmup::is_serverless_plugin_warmup(event)
+//!             .then(|| serde_json::json!("warm"))
+//!     }
+//! }
+//!
+//! pub fn main() -> anyhow::Result<()> {
+//!     lambda_runtime_types::exec_tokio::<_, _, Runner, _>()
+//! }
+//! ```
+
+use serde_json::value::RawValue;
+
+/// Whether the event was sent by `serverless-plugin-warmup`
+pub fn is_serverless_plugin_warmup(event: &RawValue) -> bool {
+    #[derive(serde::Deserialize)]
+    struct Warmup<'a> {
+        source: Option<&'a str>,
+    }
+
+    serde_json::from_str::<Warmup<'_>>(event.get())
+        .is_ok_and(|warmup| warmup.source == Some("serverless-plugin-warmup"))
+}
+
+/// Whether the event is an object containing `field`, which is
+/// used as marker for custom keep-alive events
+pub fn has_marker(event: &RawValue, field: &str) -> bool {
+    serde_json::from_str::<std::collections::HashMap<&str, &RawValue>>(event.get())
+        .is_ok_and(|event| event.contains_key(field))
+}
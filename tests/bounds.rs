@@ -0,0 +1,39 @@
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), String, ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, String>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Only compiles if the entrypoints do not require more than the
+/// bounds below, especially not `Event: 'static`
+fn entrypoints<Event, Run>()
+where
+    Event: for<'de> serde::Deserialize<'de> + std::fmt::Debug + Send,
+    Run: for<'a> lambda_runtime_types::Runner<'a, (), Event, ()>,
+{
+    let _ = lambda_runtime_types::exec_tokio::<(), Event, Run, ()>;
+    let _ = lambda_runtime_types::exec_on::<(), Event, Run, ()>;
+    let _ = lambda_runtime_types::exec::<(), Event, Run, ()>;
+    let _ = lambda_runtime_types::exec_instance::<
+        (),
+        Event,
+        lambda_runtime_types::StaticRunner<Run>,
+        (),
+    >;
+    let _ = lambda_runtime_types::Builder::exec::<(), Event, Run, ()>;
+}
+
+#[test]
+fn test_entrypoint_bounds() {
+    entrypoints::<String, Runner>();
+}
@@ -0,0 +1,53 @@
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn setup(region: &'a str) -> anyhow::Result<()> {
+        anyhow::bail!("Setup in region '{}'", region)
+    }
+}
+
+#[test]
+fn test_builder_logger_error() {
+    let err = lambda_runtime_types::Builder::new()
+        .logger(|| anyhow::bail!("No logger"))
+        .exec::<_, _, Runner, _>()
+        .expect_err("Builder did not fail");
+    assert_eq!(err.to_string(), "Unable to setup logging");
+}
+
+#[test]
+fn test_builder_region() {
+    std::env::remove_var("AWS_REGION");
+    let err = lambda_runtime_types::Builder::new()
+        .flavor(lambda_runtime_types::Flavor::CurrentThread)
+        .exec::<_, _, Runner, _>()
+        .expect_err("Builder did not fail");
+    assert_eq!(err.to_string(), "Missing AWS_REGION env variable");
+
+    let err = lambda_runtime_types::Builder::new()
+        .region_required(false)
+        .worker_threads(1)
+        .exec::<_, _, Runner, _>()
+        .expect_err("Builder did not fail");
+    assert_eq!(err.to_string(), "Setup in region ''");
+}
+
+#[test]
+fn test_builder_worker_threads() {
+    let err = lambda_runtime_types::Builder::new()
+        .worker_threads(0)
+        .exec::<_, _, Runner, _>()
+        .expect_err("Builder did not fail");
+    assert_eq!(
+        err.to_string(),
+        "Amount of worker threads must be greater than zero"
+    );
+}
@@ -0,0 +1,56 @@
+use lambda_runtime_types::checkpoint::{Checkpointer, MemoryStore, Status};
+use std::time::{Duration, SystemTime};
+
+fn checkpointer() -> Checkpointer<u32> {
+    Checkpointer::new(MemoryStore::default())
+        .with_interval(Duration::ZERO)
+        .with_margin(Duration::ZERO)
+}
+
+#[tokio::test]
+async fn test_checkpoint_suspend_and_resume() {
+    let checkpointer = checkpointer();
+    let deadline = SystemTime::now() + Duration::from_millis(50);
+    let status = checkpointer
+        .run("job", deadline, |resumed, progress| async move {
+            assert_eq!(resumed, None);
+            progress.save(3).await?;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        })
+        .await
+        .expect("Unable to run operation");
+    assert_eq!(status, Status::Suspended);
+    assert_eq!(
+        checkpointer.load("job").await.expect("Unable to load"),
+        Some(3)
+    );
+
+    let deadline = SystemTime::now() + Duration::from_secs(10);
+    let status = checkpointer
+        .run("job", deadline, |resumed, _| async move { Ok(resumed) })
+        .await
+        .expect("Unable to run operation");
+    assert_eq!(status, Status::Completed(Some(3)));
+    assert_eq!(
+        checkpointer.load("job").await.expect("Unable to load"),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_checkpoint_persisted_on_error() {
+    let checkpointer = checkpointer().with_interval(Duration::from_secs(3600));
+    let deadline = SystemTime::now() + Duration::from_secs(10);
+    let res: anyhow::Result<Status<()>> = checkpointer
+        .run("job", deadline, |_, progress| async move {
+            progress.save(7).await?;
+            anyhow::bail!("failed")
+        })
+        .await;
+    assert!(res.is_err());
+    assert_eq!(
+        checkpointer.load("job").await.expect("Unable to load"),
+        Some(7)
+    );
+}
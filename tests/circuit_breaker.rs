@@ -0,0 +1,84 @@
+use lambda_runtime_types::circuit_breaker::{CircuitBreaker, Config, Error, State};
+use std::time::Duration;
+
+fn breaker(open_duration: Duration) -> CircuitBreaker {
+    CircuitBreaker::new(Config {
+        failure_rate: 0.5,
+        window: 4,
+        minimum_calls: 2,
+        open_duration,
+    })
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_opens() {
+    let breaker = breaker(Duration::from_secs(60));
+    for _ in 0..2 {
+        let res: Result<(), _> = breaker.call("dep", || async { Err("failed") }).await;
+        assert!(matches!(res, Err(Error::Inner("failed"))));
+    }
+    assert_eq!(breaker.state("dep"), State::Open);
+    assert_eq!(breaker.state("other"), State::Closed);
+
+    let mut called = false;
+    let res: Result<(), Error<&str>> = breaker
+        .call("dep", || {
+            called = true;
+            async { Ok(()) }
+        })
+        .await;
+    assert!(!called);
+    match res {
+        Err(Error::Open(open)) => assert_eq!(open.name, "dep"),
+        _ => panic!("Expected open circuit"),
+    }
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_recovers() {
+    let breaker = breaker(Duration::from_millis(10));
+    for _ in 0..2 {
+        let _: Result<(), _> = breaker.call("dep", || async { Err("failed") }).await;
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(breaker.state("dep"), State::HalfOpen);
+
+    let res: Result<(), Error<&str>> = breaker.call("dep", || async { Ok(()) }).await;
+    assert!(res.is_ok());
+    assert_eq!(breaker.state("dep"), State::Closed);
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_dropped_trial() {
+    let breaker = breaker(Duration::from_millis(10));
+    for _ in 0..2 {
+        let _: Result<(), _> = breaker.call("dep", || async { Err("failed") }).await;
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(breaker.state("dep"), State::HalfOpen);
+
+    // The trial call times out, so its future is dropped mid-flight
+    let trial = breaker.call("dep", || async {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok::<_, &str>(())
+    });
+    tokio::time::timeout(Duration::from_millis(5), trial)
+        .await
+        .expect_err("Trial call completed");
+    assert_eq!(breaker.state("dep"), State::Open);
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(breaker.state("dep"), State::HalfOpen);
+    let res: Result<(), Error<&str>> = breaker.call("dep", || async { Ok(()) }).await;
+    assert!(res.is_ok());
+    assert_eq!(breaker.state("dep"), State::Closed);
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_below_threshold() {
+    let breaker = breaker(Duration::from_secs(60));
+    for res in [Ok(()), Ok(()), Ok(()), Err("failed")] {
+        let _ = breaker.call("dep", || async move { res }).await;
+    }
+    assert_eq!(breaker.state("dep"), State::Closed);
+}
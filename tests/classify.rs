@@ -0,0 +1,101 @@
+mod common;
+
+#[derive(Debug)]
+struct PaymentDeclined;
+
+impl std::fmt::Display for PaymentDeclined {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Card was declined")
+    }
+}
+
+impl std::error::Error for PaymentDeclined {}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), bool, ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, bool>,
+    ) -> anyhow::Result<()> {
+        if event.event {
+            return Err(PaymentDeclined.into());
+        }
+        anyhow::bail!("Database unreachable")
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn classify(error: &anyhow::Error) -> lambda_runtime_types::ErrorShape {
+        match error.downcast_ref::<PaymentDeclined>() {
+            Some(err) => lambda_runtime_types::ErrorShape::new("PaymentDeclined", err.to_string()),
+            None => lambda_runtime_types::ErrorShape::from_error(error),
+        }
+    }
+}
+
+fn invoke(event: &'static str) -> (String, serde_json::Value) {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, event));
+
+    // The runtime fails once the api stops accepting connections
+    let _ = lambda_runtime_types::exec_tokio::<_, _, Runner, _>();
+
+    let (request_line, body) = api.join().expect("Runtime API failed");
+    let body = serde_json::from_str(&body).expect("Invalid error body");
+    (request_line, body)
+}
+
+#[test]
+fn test_classify() {
+    let (request_line, body) = invoke("true");
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "errorType": "PaymentDeclined",
+            "errorMessage": "Card was declined",
+        })
+    );
+
+    let (_, body) = invoke("false");
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "errorType": "Error",
+            "errorMessage": "Database unreachable",
+        })
+    );
+}
+
+#[test]
+fn test_classify_root_cause() {
+    use anyhow::Context;
+
+    let io = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound))
+        .context("Unable to read file")
+        .expect_err("No error");
+    let shape = lambda_runtime_types::ErrorShape::from_error(&io);
+    assert_eq!(shape.error_type, "IoError");
+    assert_eq!(shape.error_message, "Unable to read file");
+
+    let json = serde_json::from_str::<bool>("invalid")
+        .context("Unable to parse")
+        .expect_err("No error");
+    assert_eq!(
+        lambda_runtime_types::ErrorShape::from_error(&json).error_type,
+        "JsonError"
+    );
+
+    let other = anyhow::Error::from(PaymentDeclined).context("Unable to pay");
+    assert_eq!(
+        lambda_runtime_types::ErrorShape::from_error(&other).error_type,
+        "Error"
+    );
+}
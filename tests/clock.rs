@@ -0,0 +1,71 @@
+mod common;
+
+use lambda_runtime_types::Clock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug)]
+struct FixedClock(SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// Clock which runs ahead of the system time
+#[derive(Debug)]
+struct AheadClock(Duration);
+
+impl Clock for AheadClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now() + self.0
+    }
+}
+
+static AHEAD: AheadClock = AheadClock(Duration::from_millis(9_800));
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        futures::future::pending().await
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_clock_until() {
+    let clock = FixedClock(UNIX_EPOCH + Duration::from_secs(100));
+    assert_eq!(clock.until_deadline_ms(101_000), Duration::from_secs(1));
+    // Deadlines in the past, e.g. because of clock skew, do not panic
+    assert_eq!(clock.until_deadline_ms(99_000), Duration::ZERO);
+    assert_eq!(clock.until_deadline_ms(0), Duration::ZERO);
+    assert!(clock.until_deadline_ms(u64::MAX) > Duration::from_secs(1));
+}
+
+#[test]
+fn test_builder_clock() {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+
+    // The mock api sets the deadline 10 seconds ahead, so the timeout
+    // handler fires after roughly 100 milliseconds with the ahead clock
+    let started = std::time::Instant::now();
+    let _ = lambda_runtime_types::Builder::new()
+        .clock(&AHEAD)
+        .exec::<_, _, Runner, _>();
+
+    let (request_line, _) = api.join().expect("Runtime API failed");
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
@@ -0,0 +1,86 @@
+use lambda_runtime_types::codec::{Codec, Coded, Json};
+use serde_json::value::RawValue;
+
+/// JSON encoded as hex string
+struct Hex;
+
+impl Codec for Hex {
+    fn decode<T>(payload: &RawValue) -> anyhow::Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let hex: String = serde_json::from_str(payload.get())?;
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                Ok(u8::from_str_radix(
+                    hex.get(i..i + 2).unwrap_or_default(),
+                    16,
+                )?)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn encode<T>(value: &T) -> anyhow::Result<Box<RawValue>>
+    where
+        T: serde::Serialize,
+    {
+        let hex: String = serde_json::to_vec(value)?
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok(serde_json::value::to_raw_value(&hex)?)
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Eq)]
+struct Order {
+    id: u32,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), Coded<Hex, Order>, Coded<Hex, Order>> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, Coded<Hex, Order>>,
+    ) -> anyhow::Result<Coded<Hex, Order>> {
+        assert_eq!(event.event.id, 7);
+        Ok(Coded::new(Order {
+            id: event.event.into_inner().id + 1,
+        }))
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_codec() {
+    let event: Coded<Hex, Order> =
+        serde_json::from_str(r#""7b226964223a377d""#).expect("Unable to decode");
+    assert_eq!(*event, Order { id: 7 });
+    assert_eq!(
+        serde_json::to_string(&event).expect("Unable to encode"),
+        r#""7b226964223a377d""#
+    );
+
+    let err = serde_json::from_str::<Coded<Hex, Order>>(r#""7b""#).expect_err("Decoded");
+    assert!(err.to_string().contains("EOF"), "{}", err);
+
+    let json: Coded<Json, Order> = serde_json::from_str(r#"{"id":7}"#).expect("Unable to decode");
+    assert_eq!(json.into_inner(), Order { id: 7 });
+}
+
+#[test]
+fn test_codec_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": ["7b226964223a377d"],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+}
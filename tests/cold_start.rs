@@ -0,0 +1,41 @@
+static INVOCATIONS: std::sync::Mutex<Vec<(u32, bool)>> = std::sync::Mutex::new(Vec::new());
+
+#[derive(serde::Deserialize, Debug)]
+struct Event {
+    id: u32,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), Event, ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<()> {
+        INVOCATIONS
+            .lock()
+            .expect("Unable to lock invocations")
+            .push((event.event.id, event.is_cold_start));
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_cold_start() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "environments": 2,
+        "invocations": [{ "id": 0 }, { "id": 1 }, { "id": 2 }, { "id": 3 }],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    assert_eq!(
+        *INVOCATIONS.lock().expect("Unable to lock invocations"),
+        vec![(0, true), (1, true), (2, false), (3, false)]
+    );
+}
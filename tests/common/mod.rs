@@ -0,0 +1,150 @@
+//! Minimal mock of the lambda Runtime API
+#![allow(dead_code)]
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// Binds the mock Runtime API and points the lambda env variables at it
+pub fn setup() -> std::net::TcpListener {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Unable to bind");
+    let addr = listener.local_addr().expect("Unable to get address");
+    std::env::set_var("AWS_LAMBDA_RUNTIME_API", addr.to_string());
+    std::env::set_var("AWS_LAMBDA_FUNCTION_NAME", "test");
+    std::env::set_var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE", "128");
+    std::env::set_var("AWS_LAMBDA_FUNCTION_VERSION", "1");
+    std::env::set_var("AWS_LAMBDA_LOG_STREAM_NAME", "test");
+    std::env::set_var("AWS_LAMBDA_LOG_GROUP_NAME", "test");
+    std::env::set_var("AWS_REGION", "eu-central-1");
+    listener
+}
+
+/// Reads a single http request and returns its request line and body
+fn read_request(stream: &mut std::net::TcpStream) -> (String, String) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).expect("Unable to read");
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).expect("Unable to read");
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().expect("Invalid content-length");
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).expect("Unable to read");
+    (
+        request_line.trim().to_owned(),
+        String::from_utf8(body).expect("Invalid body"),
+    )
+}
+
+/// Serves a single invocation with `event` and returns the request line and
+/// body of the result the lambda sent. Afterwards the listener is closed,
+/// which stops the runtime.
+pub fn serve_invocation(listener: std::net::TcpListener, event: &str) -> (String, String) {
+    serve_invocation_with(listener, event, || ())
+}
+
+/// Same as [`serve_invocation`], but calls `on_next` once the lambda
+/// requested the next invocation
+pub fn serve_invocation_with(
+    listener: std::net::TcpListener,
+    event: &str,
+    on_next: impl FnOnce(),
+) -> (String, String) {
+    let (mut stream, _) = listener.accept().expect("Unable to accept");
+    let (request_line, _) = read_request(&mut stream);
+    assert_eq!(
+        request_line,
+        "GET /2018-06-01/runtime/invocation/next HTTP/1.1"
+    );
+    on_next();
+    let deadline = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis()
+        + 10_000;
+    write_invocation(&mut stream, deadline, event);
+    drop(stream);
+
+    let (mut stream, _) = listener.accept().expect("Unable to accept");
+    let result = read_request(&mut stream);
+    stream
+        .write_all(b"HTTP/1.1 202 Accepted\r\nconnection: close\r\ncontent-length: 0\r\n\r\n")
+        .expect("Unable to write");
+    result
+}
+
+/// Writes the response of a next invocation request
+fn write_invocation(stream: &mut std::net::TcpStream, deadline: u128, event: &str) {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\n\
+        connection: close\r\n\
+        lambda-runtime-aws-request-id: request-1\r\n\
+        lambda-runtime-deadline-ms: {}\r\n\
+        lambda-runtime-invoked-function-arn: arn:aws:lambda:eu-central-1:123456789012:function:test\r\n\
+        content-length: {}\r\n\r\n{}",
+        deadline,
+        event.len(),
+        event
+    )
+    .expect("Unable to write");
+}
+
+/// Serves a single invocation with `event`, which has `deadline` left, and returns
+/// the request lines and bodies of all results the lambda sent until `until` passed.
+/// Further requests for the next invocation are kept open, so the lambda keeps running.
+pub fn serve_invocation_for(
+    listener: std::net::TcpListener,
+    event: &str,
+    deadline: std::time::Duration,
+    until: std::time::Duration,
+) -> Vec<(String, String)> {
+    let (mut stream, _) = listener.accept().expect("Unable to accept");
+    let (request_line, _) = read_request(&mut stream);
+    assert_eq!(
+        request_line,
+        "GET /2018-06-01/runtime/invocation/next HTTP/1.1"
+    );
+    let start = std::time::Instant::now();
+    let deadline = (std::time::SystemTime::now() + deadline)
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis();
+    write_invocation(&mut stream, deadline, event);
+    drop(stream);
+
+    listener
+        .set_nonblocking(true)
+        .expect("Unable to set nonblocking");
+    let mut results = Vec::new();
+    let mut pending = Vec::new();
+    while start.elapsed() < until {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                continue;
+            }
+            Err(err) => panic!("Unable to accept: {}", err),
+        };
+        stream
+            .set_nonblocking(false)
+            .expect("Unable to set blocking");
+        let (request_line, body) = read_request(&mut stream);
+        if request_line.starts_with("GET ") {
+            pending.push(stream);
+            continue;
+        }
+        stream
+            .write_all(b"HTTP/1.1 202 Accepted\r\nconnection: close\r\ncontent-length: 0\r\n\r\n")
+            .expect("Unable to write");
+        results.push((request_line, body));
+    }
+    results
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static NEXT_REQUESTED: AtomicBool = AtomicBool::new(false);
+static SETUP_FAILS: AtomicBool = AtomicBool::new(false);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), bool> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        if SETUP_FAILS.load(Ordering::SeqCst) {
+            anyhow::bail!("Database unreachable");
+        }
+        // Only completes if the first invocation is fetched during the setup
+        for _ in 0..100 {
+            if NEXT_REQUESTED.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        anyhow::bail!("First invocation was not fetched during setup")
+    }
+}
+
+fn invoke() -> (String, String) {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || {
+        common::serve_invocation_with(listener, "null", || {
+            NEXT_REQUESTED.store(true, Ordering::SeqCst)
+        })
+    });
+
+    let _ = lambda_runtime_types::Builder::new()
+        .concurrent_setup(true)
+        .exec::<_, _, Runner, _>();
+
+    api.join().expect("Runtime API failed")
+}
+
+#[test]
+fn test_concurrent_setup() {
+    let (request_line, body) = invoke();
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/response HTTP/1.1"
+    );
+    assert_eq!(body, "true");
+
+    // Failed setups are reported as error of the first invocation
+    SETUP_FAILS.store(true, Ordering::SeqCst);
+    let (request_line, body) = invoke();
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert!(body.contains(r#""errorType":"Runtime.InitError""#));
+    assert!(body.contains(r#""errorMessage":"Database unreachable""#));
+}
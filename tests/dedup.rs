@@ -0,0 +1,60 @@
+use lambda_runtime_types::dedup::{Deduplicated, Deduplicator};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_dedup_skips_duplicates() {
+    let dedup = Deduplicator::<u64>::new(10, Duration::from_secs(60));
+    let mut calls = 0;
+    for _ in 0..3 {
+        let res = dedup
+            .run("id", || {
+                calls += 1;
+                async { Ok(42) }
+            })
+            .await
+            .expect("Unable to run operation");
+        assert_eq!(res.into_inner(), 42);
+    }
+    assert_eq!(calls, 1);
+    let res = dedup
+        .run("other", || async { Ok(1) })
+        .await
+        .expect("Unable to run operation");
+    assert_eq!(res, Deduplicated::New(1));
+}
+
+#[tokio::test]
+async fn test_dedup_retries_failures() {
+    let dedup = Deduplicator::<()>::new(10, Duration::from_secs(60));
+    let res = dedup
+        .run("id", || async { Err(anyhow::anyhow!("failed")) })
+        .await;
+    assert!(res.is_err());
+    let res = dedup
+        .run("id", || async { Ok(()) })
+        .await
+        .expect("Unable to run operation");
+    assert!(!res.is_duplicate());
+}
+
+#[tokio::test]
+async fn test_dedup_evicts_least_recently_used() {
+    let dedup = Deduplicator::<u64>::new(2, Duration::from_secs(60));
+    for (id, value) in [("a", 1), ("b", 2)] {
+        let _ = dedup.run(id, || async move { Ok(value) }).await;
+    }
+    // Touch "a" so "b" becomes the least recently used entry
+    assert_eq!(dedup.get("a").await.expect("Unable to get"), Some(1));
+    let _ = dedup.run("c", || async { Ok(3) }).await;
+    assert_eq!(dedup.get("a").await.expect("Unable to get"), Some(1));
+    assert_eq!(dedup.get("b").await.expect("Unable to get"), None);
+    assert_eq!(dedup.get("c").await.expect("Unable to get"), Some(3));
+}
+
+#[tokio::test]
+async fn test_dedup_expires_entries() {
+    let dedup = Deduplicator::<u64>::new(10, Duration::from_millis(10));
+    let _ = dedup.run("id", || async { Ok(1) }).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(dedup.get("id").await.expect("Unable to get"), None);
+}
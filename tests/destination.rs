@@ -0,0 +1,69 @@
+use lambda_runtime_types::destination::{Condition, ErrorPayload, Event};
+
+#[derive(Debug, serde::Deserialize)]
+struct Request {
+    id: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Response {
+    status: String,
+}
+
+#[test]
+fn test_destination_success_parsing() {
+    let json = serde_json::json!({
+        "version": "1.0",
+        "timestamp": "2019-11-24T23:08:25.651Z",
+        "requestContext": {
+            "requestId": "c2a6f2ae-7dbb-4d22-8782-d0485c9877e2",
+            "functionArn": "arn:aws:lambda:eu-central-1:123456789012:function:test:$LATEST",
+            "condition": "Success",
+            "approximateInvokeCount": 1
+        },
+        "requestPayload": { "id": "1" },
+        "responseContext": { "statusCode": 200, "executedVersion": "$LATEST" },
+        "responsePayload": { "status": "done" }
+    });
+    let event: Event<Request, Response> =
+        serde_json::from_value(json).expect("Unable to parse event");
+    assert_eq!(event.request_context.condition, Condition::Success);
+    assert_eq!(event.request_payload.id, "1");
+    assert_eq!(
+        event.response_payload.expect("Missing response").status,
+        "done"
+    );
+}
+
+#[test]
+fn test_destination_failure_parsing() {
+    let json = serde_json::json!({
+        "version": "1.0",
+        "timestamp": "2019-11-24T21:52:47.333Z",
+        "requestContext": {
+            "requestId": "8ea123e4-1db7-4aca-ad10-d9ca1234c1fd",
+            "functionArn": "arn:aws:lambda:eu-central-1:123456789012:function:test:$LATEST",
+            "condition": "RetriesExhausted",
+            "approximateInvokeCount": 3
+        },
+        "requestPayload": { "id": "1" },
+        "responseContext": {
+            "statusCode": 200,
+            "executedVersion": "$LATEST",
+            "functionError": "Unhandled"
+        },
+        "responsePayload": {
+            "errorMessage": "Lambda failed",
+            "errorType": "Error",
+            "stackTrace": [],
+            "code": 402
+        }
+    });
+    let event: Event<Request, ErrorPayload> =
+        serde_json::from_value(json).expect("Unable to parse event");
+    assert_eq!(event.request_context.condition, Condition::RetriesExhausted);
+    assert_eq!(event.request_context.approximate_invoke_count, 3);
+    let response = event.response_payload.expect("Missing response");
+    assert_eq!(response.to_string(), "Error: Lambda failed");
+    assert_eq!(response.fields["code"], 402);
+}
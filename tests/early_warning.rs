@@ -0,0 +1,48 @@
+mod common;
+
+static WARNED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    const EARLY_WARNING: Option<f32> = Some(0.5);
+
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        // Keeps working until the early warning asks to stop
+        while !WARNED.load(std::sync::atomic::Ordering::SeqCst) {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn on_early_warning(_shared: &'a (), _ctx: &'a lambda_runtime_types::Context) {
+        WARNED.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_early_warning() {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+
+    // The mock api sets the deadline 10 seconds ahead, so the time
+    // budget is roughly 400 milliseconds
+    let _ = lambda_runtime_types::Builder::new()
+        .timeout_margin(std::time::Duration::from_millis(9_600))
+        .exec::<_, _, Runner, _>();
+
+    let (request_line, body) = api.join().expect("Runtime API failed");
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/response HTTP/1.1"
+    );
+    assert_eq!(body, "null");
+}
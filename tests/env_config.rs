@@ -0,0 +1,59 @@
+use lambda_runtime_types::env_config;
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    Fast,
+    Safe,
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+struct Config {
+    table_name: String,
+    batch_size: u32,
+    dry_run: bool,
+    mode: Mode,
+    regions: Vec<String>,
+    endpoint: Option<String>,
+}
+
+#[test]
+fn test_env_config() {
+    std::env::set_var("TEST_CFG_TABLE_NAME", "orders");
+    std::env::set_var("TEST_CFG_BATCH_SIZE", "25");
+    std::env::set_var("TEST_CFG_DRY_RUN", "true");
+    std::env::set_var("TEST_CFG_MODE", "safe");
+    std::env::set_var("TEST_CFG_REGIONS", "eu-central-1, eu-west-1");
+    let config: Config = env_config::load_prefixed("TEST_CFG_").expect("Unable to load config");
+    assert_eq!(
+        config,
+        Config {
+            table_name: "orders".into(),
+            batch_size: 25,
+            dry_run: true,
+            mode: Mode::Safe,
+            regions: vec!["eu-central-1".into(), "eu-west-1".into()],
+            endpoint: None,
+        }
+    );
+
+    std::env::set_var("TEST_INVALID_TABLE_NAME", "orders");
+    std::env::set_var("TEST_INVALID_BATCH_SIZE", "many");
+    std::env::set_var("TEST_INVALID_DRY_RUN", "false");
+    std::env::set_var("TEST_INVALID_MODE", "fast");
+    std::env::set_var("TEST_INVALID_REGIONS", "");
+    let err = env_config::load_prefixed::<Config>("TEST_INVALID_").expect_err("Config is valid");
+    assert!(
+        err.to_string().contains(r#"invalid value "many""#),
+        "{}",
+        err
+    );
+
+    std::env::set_var("TEST_MISSING_TABLE_NAME", "orders");
+    let err = env_config::load_prefixed::<Config>("TEST_MISSING_").expect_err("Config is valid");
+    assert_eq!(
+        err.to_string(),
+        "Missing configuration env variables: TEST_MISSING_BATCH_SIZE, TEST_MISSING_DRY_RUN, \
+        TEST_MISSING_MODE, TEST_MISSING_REGIONS"
+    );
+}
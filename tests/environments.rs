@@ -0,0 +1,50 @@
+static ENVIRONMENTS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+static INVOCATIONS: std::sync::Mutex<Vec<(usize, u32)>> = std::sync::Mutex::new(Vec::new());
+
+#[derive(serde::Deserialize, Debug)]
+struct Event {
+    id: u32,
+}
+
+#[derive(Debug)]
+struct Shared {
+    environment: usize,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, Shared, Event, ()> for Runner {
+    async fn run(
+        shared: &'a Shared,
+        event: lambda_runtime_types::LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<()> {
+        INVOCATIONS
+            .lock()
+            .expect("Unable to lock invocations")
+            .push((shared.environment, event.event.id));
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+        Ok(Shared {
+            environment: ENVIRONMENTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst),
+        })
+    }
+}
+
+#[test]
+fn test_environments_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "environments": 2,
+        "invocations": [{ "id": 0 }, { "id": 1 }, { "id": 2 }],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    assert_eq!(ENVIRONMENTS.load(std::sync::atomic::Ordering::SeqCst), 2);
+    assert_eq!(
+        *INVOCATIONS.lock().expect("Unable to lock invocations"),
+        vec![(0, 0), (1, 1), (0, 2)]
+    );
+}
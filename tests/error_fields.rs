@@ -0,0 +1,81 @@
+mod common;
+
+#[derive(Debug, serde::Serialize)]
+struct PaymentDeclined {
+    code: u32,
+    retryable: bool,
+}
+
+impl std::fmt::Display for PaymentDeclined {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Card was declined with code {}", self.code)
+    }
+}
+
+impl std::error::Error for PaymentDeclined {}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), bool, ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, bool>,
+    ) -> anyhow::Result<()> {
+        if event.event {
+            return Err(PaymentDeclined {
+                code: 51,
+                retryable: false,
+            }
+            .into());
+        }
+        anyhow::bail!("Database unreachable")
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn classify(error: &anyhow::Error) -> lambda_runtime_types::ErrorShape {
+        match error.downcast_ref::<PaymentDeclined>() {
+            Some(err) => lambda_runtime_types::ErrorShape::from_serialize("PaymentDeclined", err),
+            None => lambda_runtime_types::ErrorShape::from_error(error)
+                .with_field("retryable", serde_json::Value::Bool(true))
+                .with_field("errorType", serde_json::Value::Null),
+        }
+    }
+}
+
+fn invoke(event: &'static str) -> serde_json::Value {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, event));
+
+    // The runtime fails once the api stops accepting connections
+    let _ = lambda_runtime_types::exec_tokio::<_, _, Runner, _>();
+
+    let (_, body) = api.join().expect("Runtime API failed");
+    serde_json::from_str(&body).expect("Invalid error body")
+}
+
+#[test]
+fn test_error_fields() {
+    assert_eq!(
+        invoke("true"),
+        serde_json::json!({
+            "errorType": "PaymentDeclined",
+            "errorMessage": "Card was declined with code 51",
+            "code": 51,
+            "retryable": false,
+        })
+    );
+
+    // Fields can not replace the type or message
+    assert_eq!(
+        invoke("false"),
+        serde_json::json!({
+            "errorType": "Error",
+            "errorMessage": "Database unreachable",
+            "retryable": true,
+        })
+    );
+}
@@ -0,0 +1,27 @@
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn setup(region: &'a str) -> anyhow::Result<()> {
+        anyhow::bail!("Setup in region '{}'", region)
+    }
+}
+
+#[test]
+fn test_exec_on_handle() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Unable to build tokio runtime");
+    std::env::set_var("AWS_REGION", "eu-central-1");
+    let err = lambda_runtime_types::exec_on::<_, _, Runner, _>(runtime.handle())
+        .expect_err("Lambda did not fail");
+    assert_eq!(err.to_string(), "Setup in region 'eu-central-1'");
+}
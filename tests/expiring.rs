@@ -0,0 +1,70 @@
+use lambda_runtime_types::expiring::Expiring;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+fn counter() -> (Arc<AtomicU32>, impl Fn() -> u32 + Send + Sync + 'static) {
+    let refreshes = Arc::new(AtomicU32::new(0));
+    let counter = Arc::clone(&refreshes);
+    (refreshes, move || {
+        counter.fetch_add(1, Ordering::SeqCst) + 1
+    })
+}
+
+#[tokio::test]
+async fn test_expiring_ttl() {
+    let (refreshes, next) = counter();
+    let value = Expiring::new(Duration::from_millis(50), move || {
+        let value = next();
+        async move { Ok(value) }
+    });
+    assert_eq!(value.expires_at().await, None);
+    assert_eq!(*value.get().await.expect("Unable to refresh"), 1);
+    assert_eq!(*value.get().await.expect("Unable to refresh"), 1);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    value.refresh_if_expired().await.expect("Unable to refresh");
+    assert_eq!(refreshes.load(Ordering::SeqCst), 2);
+    assert_eq!(*value.get().await.expect("Unable to refresh"), 2);
+}
+
+#[tokio::test]
+async fn test_expiring_with_expiry() {
+    let (refreshes, next) = counter();
+    let value = Expiring::with_expiry(move || {
+        let value = next();
+        async move { Ok((value, SystemTime::now() + Duration::from_secs(10))) }
+    })
+    .refresh_margin(Duration::from_secs(5));
+    assert_eq!(*value.get().await.expect("Unable to refresh"), 1);
+    assert!(value.expires_at().await.is_some());
+    assert_eq!(*value.get().await.expect("Unable to refresh"), 1);
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+
+    // Values which expire within the margin are refreshed on every access
+    let (refreshes, next) = counter();
+    let value = Expiring::with_expiry(move || {
+        let value = next();
+        async move { Ok((value, SystemTime::now() + Duration::from_secs(10))) }
+    })
+    .refresh_margin(Duration::from_secs(20));
+    assert_eq!(*value.get().await.expect("Unable to refresh"), 1);
+    assert_eq!(*value.get().await.expect("Unable to refresh"), 2);
+    assert_eq!(refreshes.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_expiring_failed_refresh() {
+    let (_, next) = counter();
+    let value = Expiring::new(Duration::from_secs(60), move || {
+        let attempt = next();
+        async move {
+            if attempt == 1 {
+                anyhow::bail!("Token endpoint unreachable");
+            }
+            Ok(attempt)
+        }
+    });
+    value.get().await.expect_err("Refresh succeeded");
+    assert_eq!(*value.get().await.expect("Unable to refresh"), 2);
+}
@@ -0,0 +1,35 @@
+#[derive(serde::Serialize, Debug)]
+struct Return {
+    degraded: bool,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), Return> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<Return> {
+        anyhow::bail!("Downstream unavailable")
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn fallback(_shared: &'a (), error: &anyhow::Error) -> Option<Return> {
+        assert_eq!(error.to_string(), "Downstream unavailable");
+        Some(Return { degraded: true })
+    }
+}
+
+#[test]
+fn test_fallback_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [null],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+}
@@ -0,0 +1,121 @@
+mod common;
+
+const CHILD_ENV: &str = "HARD_DEADLINE_CHILD";
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        // Blocks the only thread, so the timeout handler never runs
+        std::thread::sleep(std::time::Duration::from_secs(60));
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+struct SlowTimeoutRunner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for SlowTimeoutRunner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn on_timeout(_shared: &'a (), _ctx: &'a lambda_runtime_types::Context) {
+        // Uses the whole budget, so the error is returned right before the deadline
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+    }
+}
+
+#[test]
+fn test_hard_deadline() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        let _ = lambda_runtime_types::Builder::new()
+            .flavor(lambda_runtime_types::Flavor::CurrentThread)
+            .timeout_margin(std::time::Duration::from_millis(9_900))
+            .hard_deadline(true)
+            .exec::<_, _, Runner, _>();
+        return;
+    }
+
+    // The watchdog exits the process, so the lambda runs in a child process
+    let listener = common::setup();
+    let addr = listener.local_addr().expect("Unable to get address");
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+    let status = std::process::Command::new(std::env::current_exe().expect("Unable to get exe"))
+        .args(["--exact", "test_hard_deadline", "--nocapture"])
+        .env(CHILD_ENV, "1")
+        .env("AWS_LAMBDA_RUNTIME_API", addr.to_string())
+        .status()
+        .expect("Unable to run child");
+
+    let (request_line, body) = api.join().expect("Runtime API failed");
+    assert!(!status.success());
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert!(body.contains("timeout"), "{}", body);
+}
+
+#[test]
+fn test_hard_deadline_after_on_timeout() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        let _ = lambda_runtime_types::Builder::new()
+            .flavor(lambda_runtime_types::Flavor::CurrentThread)
+            .timeout_margin(std::time::Duration::from_millis(1_600))
+            .hard_deadline(true)
+            .exec::<_, _, SlowTimeoutRunner, _>();
+        return;
+    }
+
+    // The timeout handler fires after 400ms, on_timeout is cancelled after 1200ms
+    // and the watchdog would fire after 1600ms
+    let listener = common::setup();
+    let addr = listener.local_addr().expect("Unable to get address");
+    let api = std::thread::spawn(move || {
+        common::serve_invocation_for(
+            listener,
+            "null",
+            std::time::Duration::from_millis(2_000),
+            std::time::Duration::from_millis(3_000),
+        )
+    });
+    let mut child = std::process::Command::new(std::env::current_exe().expect("Unable to get exe"))
+        .args([
+            "--exact",
+            "test_hard_deadline_after_on_timeout",
+            "--nocapture",
+        ])
+        .env(CHILD_ENV, "1")
+        .env("AWS_LAMBDA_RUNTIME_API", addr.to_string())
+        .spawn()
+        .expect("Unable to run child");
+
+    let results = api.join().expect("Runtime API failed");
+    let exited = child.try_wait().expect("Unable to check child");
+    let _ = child.kill();
+    let _ = child.wait();
+    assert_eq!(exited, None, "The watchdog exited the process");
+    assert_eq!(results.len(), 1, "{:?}", results);
+    assert_eq!(
+        results[0].0,
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert!(results[0].1.contains("timeout"), "{}", results[0].1);
+}
@@ -0,0 +1,43 @@
+static HEARTBEATS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    const HEARTBEAT: Option<std::time::Duration> = Some(std::time::Duration::from_millis(50));
+
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        tokio::time::sleep(std::time::Duration::from_millis(260)).await;
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn on_heartbeat(_shared: &'a (), _ctx: &'a lambda_runtime_types::Context) {
+        HEARTBEATS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_heartbeat() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [null],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    let heartbeats = HEARTBEATS.load(std::sync::atomic::Ordering::SeqCst);
+    assert!((4..=6).contains(&heartbeats), "{}", heartbeats);
+
+    // Heartbeats stop with the invocation
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert_eq!(
+        HEARTBEATS.load(std::sync::atomic::Ordering::SeqCst),
+        heartbeats
+    );
+}
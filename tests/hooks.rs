@@ -0,0 +1,66 @@
+static CALLS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+fn record(call: String) {
+    CALLS.lock().expect("Unable to lock calls").push(call);
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), String, String> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, String>,
+    ) -> anyhow::Result<String> {
+        record(format!("run {}", event.event));
+        match event.event.as_str() {
+            "ok" => Ok(event.event),
+            _ => anyhow::bail!("{} failed", event.event),
+        }
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn fallback(_shared: &'a (), error: &anyhow::Error) -> Option<String> {
+        (error.to_string() == "fallback failed").then(|| "fallback".into())
+    }
+
+    async fn before_invoke(_shared: &'a (), _ctx: &'a lambda_runtime_types::Context) {
+        record("before".into());
+    }
+
+    async fn after_invoke(_shared: &'a (), info: &'a lambda_runtime_types::InvocationInfo<'a>) {
+        let outcome = match info.outcome {
+            lambda_runtime_types::InvocationOutcome::Success => "success".into(),
+            lambda_runtime_types::InvocationOutcome::Fallback => "fallback".into(),
+            lambda_runtime_types::InvocationOutcome::Failure(err) => format!("failure: {}", err),
+        };
+        record(format!("after {}", outcome));
+    }
+}
+
+#[test]
+fn test_hooks() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": ["ok", "fallback", "fatal"],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect_err("Lambda did not fail");
+    assert_eq!(
+        *CALLS.lock().expect("Unable to lock calls"),
+        vec![
+            "before",
+            "run ok",
+            "after success",
+            "before",
+            "run fallback",
+            "after fallback",
+            "before",
+            "run fatal",
+            "after failure: fatal failed",
+        ]
+    );
+}
@@ -0,0 +1,57 @@
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        anyhow::bail!("Database unreachable")
+    }
+}
+
+/// Accepts a single request and returns it
+async fn runtime_api(listener: tokio::net::TcpListener) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (mut stream, _) = listener.accept().await.expect("Unable to accept");
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !String::from_utf8_lossy(&request).contains("stackTrace") {
+        let read = stream.read(&mut buf).await.expect("Unable to read");
+        assert_ne!(read, 0, "Connection closed");
+        request.extend_from_slice(&buf[..read]);
+    }
+    stream
+        .write_all(b"HTTP/1.1 202 Accepted\r\ncontent-length: 0\r\n\r\n")
+        .await
+        .expect("Unable to write");
+    String::from_utf8(request).expect("Invalid request")
+}
+
+#[tokio::test]
+async fn test_init_error_reported() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let addr = listener.local_addr().expect("Unable to get address");
+    std::env::set_var("AWS_LAMBDA_RUNTIME_API", addr.to_string());
+    std::env::set_var("AWS_REGION", "eu-central-1");
+    let api = tokio::spawn(runtime_api(listener));
+
+    let err = lambda_runtime_types::exec::<_, _, Runner, _>()
+        .await
+        .expect_err("Lambda did not fail");
+    assert_eq!(err.to_string(), "Database unreachable");
+
+    let request = api.await.expect("Runtime API failed");
+    assert!(request.starts_with("POST /2018-06-01/runtime/init/error HTTP/1.1"));
+    assert!(request
+        .to_lowercase()
+        .contains("lambda-runtime-function-error-type: runtime.initerror"));
+    assert!(request.contains(r#""errorMessage":"Database unreachable""#));
+}
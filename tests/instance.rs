@@ -0,0 +1,57 @@
+use lambda_runtime_types::InstanceRunner;
+
+trait Greeter: Send + Sync {
+    fn greet(&self, name: &str) -> String;
+}
+
+struct MockGreeter;
+
+impl Greeter for MockGreeter {
+    fn greet(&self, name: &str) -> String {
+        format!("Hello {}", name)
+    }
+}
+
+struct Runner {
+    greeter: Box<dyn Greeter>,
+}
+
+#[async_trait::async_trait]
+impl InstanceRunner<(), String, String> for Runner {
+    async fn run<'a>(
+        &'a self,
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, String>,
+    ) -> anyhow::Result<String> {
+        Ok(self.greeter.greet(&event.event))
+    }
+}
+
+#[tokio::test]
+async fn test_instance_runner() {
+    let runner = Runner {
+        greeter: Box::new(MockGreeter),
+    };
+    let event = lambda_runtime_types::LambdaEvent::new(
+        "World".to_owned(),
+        "eu-central-1",
+        lambda_runtime_types::Context::default(),
+    );
+    let res = runner.run(&(), event).await.expect("Unable to run");
+    assert_eq!(res, "Hello World");
+}
+
+#[test]
+fn test_lambda_event_context() {
+    let mut ctx = lambda_runtime_types::Context::default();
+    ctx.request_id = "request".into();
+    ctx.deadline = 1_000;
+    ctx.env_config.memory = 128;
+    let event = lambda_runtime_types::LambdaEvent::new((), "eu-central-1", ctx);
+    assert_eq!(event.request_id(), "request");
+    assert_eq!(
+        event.deadline(),
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(1)
+    );
+    assert_eq!(event.memory_limit(), 128);
+}
@@ -0,0 +1,42 @@
+#[test]
+fn test_lambda_env() {
+    std::env::set_var("AWS_LAMBDA_FUNCTION_NAME", "orders");
+    std::env::set_var("AWS_LAMBDA_FUNCTION_VERSION", "$LATEST");
+    std::env::set_var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE", "512");
+    std::env::set_var("AWS_LAMBDA_LOG_GROUP_NAME", "/aws/lambda/orders");
+    std::env::set_var("AWS_LAMBDA_LOG_STREAM_NAME", "2024/01/01/[$LATEST]abc");
+    std::env::set_var("AWS_EXECUTION_ENV", "AWS_Lambda_provided.al2023");
+    std::env::remove_var("AWS_LAMBDA_INITIALIZATION_TYPE");
+
+    let env = lambda_runtime_types::LambdaEnv::current();
+    assert_eq!(env.function_name, "orders");
+    assert_eq!(env.function_version, "$LATEST");
+    assert_eq!(env.memory_size, Some(512));
+    assert_eq!(env.log_group_name, "/aws/lambda/orders");
+    assert_eq!(env.log_stream_name, "2024/01/01/[$LATEST]abc");
+    assert_eq!(
+        env.execution_env.as_deref(),
+        Some("AWS_Lambda_provided.al2023")
+    );
+    assert_eq!(env.initialization_type, None);
+
+    // Parsed only once
+    std::env::set_var("AWS_LAMBDA_FUNCTION_NAME", "changed");
+    assert_eq!(
+        lambda_runtime_types::LambdaEnv::current().function_name,
+        "orders"
+    );
+
+    let event = lambda_runtime_types::LambdaEvent::new(
+        (),
+        "eu-central-1",
+        lambda_runtime_types::Context::default(),
+    );
+    assert_eq!(event.env, env);
+
+    std::env::set_var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE", "invalid");
+    assert_eq!(
+        lambda_runtime_types::LambdaEnv::from_env().memory_size,
+        None
+    );
+}
@@ -0,0 +1,63 @@
+use lambda_runtime_types::lazy::Lazy;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static BUILDS: AtomicU32 = AtomicU32::new(0);
+static INVOCATIONS: std::sync::Mutex<Vec<(bool, u32)>> = std::sync::Mutex::new(Vec::new());
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, Lazy<u32>, (), ()> for Runner {
+    async fn run(
+        shared: &'a Lazy<u32>,
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        let initialized = shared.is_initialized();
+        let value = *shared.get().await?;
+        INVOCATIONS
+            .lock()
+            .expect("Unable to lock invocations")
+            .push((initialized, value));
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<Lazy<u32>> {
+        Ok(Lazy::new(|| async {
+            Ok(BUILDS.fetch_add(1, Ordering::SeqCst) + 1)
+        }))
+    }
+}
+
+#[test]
+fn test_lazy() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [null, null, null],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        *INVOCATIONS.lock().expect("Unable to lock invocations"),
+        vec![(false, 1), (true, 1), (true, 1)]
+    );
+}
+
+#[tokio::test]
+async fn test_lazy_failed_init() {
+    let attempts = std::sync::Arc::new(AtomicU32::new(0));
+    let counter = std::sync::Arc::clone(&attempts);
+    let lazy = Lazy::new(move || {
+        let attempt = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            if attempt == 0 {
+                anyhow::bail!("Database unreachable");
+            }
+            Ok(attempt)
+        }
+    });
+    lazy.get().await.expect_err("Build succeeded");
+    assert!(!lazy.is_initialized());
+    assert_eq!(*lazy.get().await.expect("Unable to build"), 1);
+    assert_eq!(lazy.get_if_initialized(), Some(&1));
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use std::rc::Rc;
+
+struct Runner;
+
+#[async_trait::async_trait(?Send)]
+impl<'a> lambda_runtime_types::LocalRunner<'a, Rc<String>, String, String> for Runner {
+    async fn run(
+        shared: &'a Rc<String>,
+        event: lambda_runtime_types::LambdaEvent<'a, String>,
+    ) -> anyhow::Result<String> {
+        // Rc held across an await point makes the future !Send
+        let greeting = Rc::clone(shared);
+        tokio::task::yield_now().await;
+        Ok(format!("{} {}", greeting, event.event))
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<Rc<String>> {
+        Ok(Rc::new("Hello".into()))
+    }
+}
+
+#[test]
+fn test_exec_local() {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, r#""World""#));
+
+    // The runtime fails once the api stops accepting connections
+    let _ = lambda_runtime_types::exec_local::<_, String, Runner, String>();
+
+    let (request_line, response) = api.join().expect("Runtime API failed");
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/response HTTP/1.1"
+    );
+    assert_eq!(response, r#""Hello World""#);
+}
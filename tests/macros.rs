@@ -0,0 +1,48 @@
+#[derive(Default)]
+struct Shared {
+    invocations: std::sync::atomic::AtomicU64,
+}
+
+async fn setup(region: &str) -> anyhow::Result<Shared> {
+    assert_eq!(region, "eu-central-1");
+    Ok(Shared::default())
+}
+
+#[lambda_runtime_types::lambda_runner(setup = setup)]
+async fn handler(
+    shared: &Shared,
+    event: lambda_runtime_types::LambdaEvent<'_, u64>,
+) -> anyhow::Result<u64> {
+    let invocations = shared
+        .invocations
+        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    assert_eq!(event.event, invocations + 1);
+    Ok(invocations + 1)
+}
+
+mod without_shared {
+    #[lambda_runtime_types::lambda_runner(runner = Echo, main = false)]
+    async fn echo(event: lambda_runtime_types::LambdaEvent<'_, String>) -> anyhow::Result<String> {
+        Ok(event.event)
+    }
+
+    #[test]
+    fn test_macro_without_shared() {
+        let test_data = serde_json::json!({
+            "region": "eu-central-1",
+            "invocations": ["hello"],
+        });
+        lambda_runtime_types::exec_test::<_, _, Echo, _>(&test_data.to_string())
+            .expect("Unable to execute lambda");
+    }
+}
+
+#[test]
+fn test_macro_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [1, 2, 3],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+}
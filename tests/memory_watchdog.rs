@@ -0,0 +1,59 @@
+mod common;
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+fn invoke(builder: lambda_runtime_types::Builder) -> (String, String) {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+    let _ = builder.exec::<_, _, Runner, _>();
+    api.join().expect("Runtime API failed")
+}
+
+#[test]
+fn test_memory_watchdog() {
+    // The mock api configures 128 MB, of which the test process
+    // uses more than one percent
+    let (request_line, body) = invoke(lambda_runtime_types::Builder::new().memory_watchdog(0.01));
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert!(body.contains("of its 128 MB memory"), "{}", body);
+
+    let (request_line, _) = invoke(lambda_runtime_types::Builder::new().memory_watchdog(1.0));
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/response HTTP/1.1"
+    );
+}
+
+#[test]
+fn test_memory_watchdog_threshold() {
+    for threshold in [0.0, -0.5, 1.5, f32::NAN] {
+        let err = lambda_runtime_types::Builder::new()
+            .memory_watchdog(threshold)
+            .exec::<_, _, Runner, _>()
+            .expect_err("Builder did not fail");
+        assert!(
+            err.to_string()
+                .starts_with("Memory watchdog threshold must be greater than 0.0 and at most 1.0"),
+            "{}",
+            err
+        );
+    }
+}
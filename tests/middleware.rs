@@ -0,0 +1,77 @@
+use lambda_runtime_types::middleware::{Middleware, Next};
+use lambda_runtime_types::{InstanceRunner, LambdaEvent, StaticRunner};
+
+struct Append(&'static str);
+
+#[async_trait::async_trait]
+impl Middleware<(), String, String> for Append {
+    async fn call<'a>(
+        &'a self,
+        shared: &'a (),
+        mut event: LambdaEvent<'a, String>,
+        next: Next<'a, (), String, String>,
+    ) -> anyhow::Result<String> {
+        event.event.push_str(self.0);
+        let res = next.run(shared, event).await?;
+        Ok(format!("{}{}", res, self.0))
+    }
+}
+
+struct Reject;
+
+#[async_trait::async_trait]
+impl Middleware<(), String, String> for Reject {
+    async fn call<'a>(
+        &'a self,
+        shared: &'a (),
+        event: LambdaEvent<'a, String>,
+        next: Next<'a, (), String, String>,
+    ) -> anyhow::Result<String> {
+        if event.event.is_empty() {
+            anyhow::bail!("Empty event");
+        }
+        next.run(shared, event).await
+    }
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), String, String> for Runner {
+    async fn run(_shared: &'a (), event: LambdaEvent<'a, String>) -> anyhow::Result<String> {
+        Ok(format!("[{}]", event.event))
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+fn event<'a>(event: &str) -> LambdaEvent<'a, String> {
+    LambdaEvent::new(
+        event.to_owned(),
+        "eu-central-1",
+        lambda_runtime_types::Context::default(),
+    )
+}
+
+#[tokio::test]
+async fn test_middleware_order() {
+    let runner = StaticRunner::<Runner>::default()
+        .layer(Append("a"))
+        .layer(Append("b"));
+    let res = runner.run(&(), event("-")).await.expect("Unable to run");
+    assert_eq!(res, "[-ba]ab");
+}
+
+#[tokio::test]
+async fn test_middleware_short_circuit() {
+    let runner = StaticRunner::<Runner>::default().layer(Reject);
+    let err = runner
+        .run(&(), event(""))
+        .await
+        .expect_err("Middleware did not reject event");
+    assert_eq!(err.to_string(), "Empty event");
+    let res = runner.run(&(), event("ok")).await.expect("Unable to run");
+    assert_eq!(res, "[ok]");
+}
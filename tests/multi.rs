@@ -0,0 +1,145 @@
+use lambda_runtime_types::multi::{EventBridgeEvent, MultiRunner, S3Event, SnsEvent, SqsEvent};
+use lambda_runtime_types::LambdaEvent;
+
+#[derive(Default)]
+struct Shared {
+    sources: std::sync::Mutex<Vec<String>>,
+}
+
+impl Shared {
+    fn push(&self, source: String) {
+        self.sources.lock().expect("Poisoned").push(source);
+    }
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> MultiRunner<'a, Shared, ()> for Runner {
+    async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+        Ok(Shared::default())
+    }
+
+    async fn sqs(shared: &'a Shared, event: LambdaEvent<'a, SqsEvent>) -> anyhow::Result<()> {
+        shared.push(format!("sqs:{}", event.event.records[0].body));
+        Ok(())
+    }
+
+    async fn sns(shared: &'a Shared, event: LambdaEvent<'a, SnsEvent>) -> anyhow::Result<()> {
+        shared.push(format!("sns:{}", event.event.records[0].sns.message));
+        Ok(())
+    }
+
+    async fn s3(shared: &'a Shared, event: LambdaEvent<'a, S3Event>) -> anyhow::Result<()> {
+        shared.push(format!("s3:{}", event.event.records[0].s3.object.key));
+        Ok(())
+    }
+
+    async fn event_bridge(
+        shared: &'a Shared,
+        event: LambdaEvent<'a, EventBridgeEvent>,
+    ) -> anyhow::Result<()> {
+        shared.push(format!("event_bridge:{}", event.event.detail_type));
+        Ok(())
+    }
+
+    async fn direct(
+        shared: &'a Shared,
+        event: LambdaEvent<'a, serde_json::Value>,
+    ) -> anyhow::Result<()> {
+        shared.push(format!("direct:{}", event.event));
+        if event.event["last"] == true {
+            let sources = shared.sources.lock().expect("Poisoned").clone();
+            assert_eq!(
+                sources,
+                [
+                    "sqs:hello",
+                    "sns:world",
+                    "s3:file.txt",
+                    "event_bridge:Scheduled Event",
+                    "direct:{\"last\":true}",
+                ]
+            );
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_multi_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [
+            {
+                "Records": [{
+                    "messageId": "1",
+                    "receiptHandle": "handle",
+                    "body": "hello",
+                    "attributes": {"ApproximateReceiveCount": "1"},
+                    "messageAttributes": {},
+                    "eventSource": "aws:sqs",
+                    "eventSourceARN": "arn:aws:sqs:eu-central-1:123456789012:queue",
+                    "awsRegion": "eu-central-1",
+                }],
+            },
+            {
+                "Records": [{
+                    "EventSource": "aws:sns",
+                    "EventSubscriptionArn": "arn:aws:sns:eu-central-1:123456789012:topic:1",
+                    "Sns": {
+                        "MessageId": "1",
+                        "TopicArn": "arn:aws:sns:eu-central-1:123456789012:topic",
+                        "Subject": null,
+                        "Message": "world",
+                        "Timestamp": "2022-01-01T00:00:00.000Z",
+                    },
+                }],
+            },
+            {
+                "Records": [{
+                    "eventSource": "aws:s3",
+                    "eventName": "ObjectCreated:Put",
+                    "eventTime": "2022-01-01T00:00:00.000Z",
+                    "awsRegion": "eu-central-1",
+                    "s3": {
+                        "bucket": {"name": "bucket", "arn": "arn:aws:s3:::bucket"},
+                        "object": {"key": "file.txt", "size": 5, "eTag": "tag"},
+                    },
+                }],
+            },
+            {
+                "id": "1",
+                "detail-type": "Scheduled Event",
+                "source": "aws.events",
+                "account": "123456789012",
+                "time": "2022-01-01T00:00:00Z",
+                "region": "eu-central-1",
+                "resources": [],
+                "detail": {},
+            },
+            {"last": true},
+        ],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+}
+
+struct SqsOnly;
+
+#[async_trait::async_trait]
+impl<'a> MultiRunner<'a, (), ()> for SqsOnly {
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_multi_unsupported_source() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [{"direct": true}],
+    });
+    let err = lambda_runtime_types::exec_test::<_, _, SqsOnly, _>(&test_data.to_string())
+        .expect_err("Lambda did not fail");
+    assert_eq!(err.to_string(), "Direct invocations are not supported");
+}
@@ -0,0 +1,41 @@
+static ERRORS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), String, String> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, String>,
+    ) -> anyhow::Result<String> {
+        anyhow::bail!("{}", event.event)
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn on_error(
+        _shared: &'a (),
+        error: anyhow::Error,
+        _ctx: &'a lambda_runtime_types::Context,
+    ) -> anyhow::Result<String> {
+        ERRORS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if error.to_string() == "ignore" {
+            return Ok("ignored".into());
+        }
+        Err(error.context("Unable to handle event"))
+    }
+}
+
+#[test]
+fn test_on_error_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": ["ignore", "fail"],
+    });
+    let err = lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect_err("Lambda did not fail");
+    assert_eq!(err.to_string(), "Unable to handle event");
+    assert_eq!(ERRORS.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+static TIMED_OUT: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        futures::future::pending().await
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn on_timeout(_shared: &'a (), ctx: &'a lambda_runtime_types::Context) {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        *TIMED_OUT.lock().expect("Unable to lock timed out") = Some(ctx.request_id.clone());
+    }
+}
+
+#[test]
+fn test_on_timeout() {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+
+    // The mock api sets the deadline 10 seconds ahead, so the
+    // timeout handler fires after roughly 200 milliseconds
+    let _ = lambda_runtime_types::Builder::new()
+        .timeout_margin(std::time::Duration::from_millis(9_800))
+        .exec::<_, _, Runner, _>();
+
+    let (request_line, body) = api.join().expect("Runtime API failed");
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert!(body.contains("timeout"), "{}", body);
+    assert_eq!(
+        TIMED_OUT
+            .lock()
+            .expect("Unable to lock timed out")
+            .as_deref(),
+        Some("request-1")
+    );
+}
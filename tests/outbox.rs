@@ -0,0 +1,55 @@
+mod common;
+
+static EXECUTED: std::sync::Mutex<Vec<&str>> = std::sync::Mutex::new(Vec::new());
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        for name in ["first", "second", "third"] {
+            event.outbox.push(name, async move {
+                if name == "second" {
+                    futures::future::pending::<()>().await;
+                }
+                EXECUTED.lock().expect("Unable to lock executed").push(name);
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_outbox_timeout() {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+
+    // The mock api sets the deadline 10 seconds ahead, so the
+    // side effects are cancelled after roughly 200 milliseconds
+    let _ = lambda_runtime_types::Builder::new()
+        .timeout_margin(std::time::Duration::from_millis(9_800))
+        .exec::<_, _, Runner, _>();
+
+    let (request_line, body) = api.join().expect("Runtime API failed");
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert!(
+        body.contains("did not complete before the timeout: second"),
+        "{}",
+        body
+    );
+    assert_eq!(
+        *EXECUTED.lock().expect("Unable to lock executed"),
+        vec!["first"]
+    );
+}
@@ -0,0 +1,34 @@
+use lambda_runtime_types::Outcome;
+
+#[derive(serde::Serialize)]
+struct Declined {
+    reason: &'static str,
+}
+
+#[test]
+fn test_outcome_serialization() {
+    let success: Outcome<u32, Declined> = Outcome::Success(42);
+    assert_eq!(
+        serde_json::to_value(&success).expect("Unable to serialize"),
+        serde_json::json!({ "success": true, "result": 42 })
+    );
+
+    let failure: Outcome<u32, Declined> = Outcome::Failure(Declined {
+        reason: "Amount exceeds limit",
+    });
+    assert_eq!(
+        serde_json::to_value(&failure).expect("Unable to serialize"),
+        serde_json::json!({ "success": false, "failure": { "reason": "Amount exceeds limit" } })
+    );
+}
+
+#[test]
+fn test_outcome_result() {
+    let outcome: Outcome<u32, &str> = Err("declined").into();
+    assert!(!outcome.is_success());
+    assert_eq!(outcome.into_result(), Err("declined"));
+
+    let outcome: Outcome<u32, &str> = Ok(1).into();
+    assert!(outcome.is_success());
+    assert_eq!(outcome.into_result(), Ok(1));
+}
@@ -0,0 +1,37 @@
+static PANICS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), bool, String> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, bool>,
+    ) -> anyhow::Result<String> {
+        if event.event {
+            panic!("Invalid event");
+        }
+        Ok("ok".into())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn fallback(_shared: &'a (), error: &anyhow::Error) -> Option<String> {
+        assert_eq!(error.to_string(), "Lambda panicked: Invalid event");
+        PANICS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Some("fallback".into())
+    }
+}
+
+#[test]
+fn test_panic_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [true, false, true],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    assert_eq!(PANICS.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
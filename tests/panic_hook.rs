@@ -0,0 +1,69 @@
+mod common;
+
+/// Collects the messages of all `error` records
+struct Logger;
+
+static RECORDS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.level() <= log::Level::Error
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if self.enabled(record.metadata()) {
+            RECORDS
+                .lock()
+                .expect("Poisoned lock")
+                .push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), bool, ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, bool>,
+    ) -> anyhow::Result<()> {
+        if event.event {
+            panic!("Invalid event");
+        }
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        log::set_logger(&Logger).expect("Unable to setup logging");
+        log::set_max_level(log::LevelFilter::Error);
+        lambda_runtime_types::install_panic_hook();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_panic_hook() {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "true"));
+
+    // The runtime fails once the api stops accepting connections
+    let _ = lambda_runtime_types::exec_tokio::<_, _, Runner, _>();
+    api.join().expect("Runtime API failed");
+
+    let records = RECORDS.lock().expect("Poisoned lock");
+    let panic: serde_json::Value = records
+        .iter()
+        .find_map(|record| serde_json::from_str(record).ok())
+        .expect("Panic was not logged as json");
+    assert_eq!(panic["type"], "panic");
+    assert_eq!(panic["request_id"], "request-1");
+    assert_eq!(panic["message"], "Invalid event");
+    assert!(panic["location"]
+        .as_str()
+        .expect("Missing location")
+        .starts_with("tests/panic_hook.rs:"));
+    assert!(panic["backtrace"].is_string());
+}
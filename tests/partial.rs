@@ -0,0 +1,42 @@
+mod common;
+
+#[derive(serde::Serialize)]
+struct Progress {
+    processed: usize,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), Progress> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<Progress> {
+        event.partial.set(&Progress { processed: 3 })?;
+        futures::future::pending().await
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_partial() {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+
+    // The mock api sets the deadline 10 seconds ahead, so the
+    // timeout handler fires after roughly 200 milliseconds
+    let _ = lambda_runtime_types::Builder::new()
+        .timeout_margin(std::time::Duration::from_millis(9_800))
+        .exec::<_, _, Runner, _>();
+
+    let (request_line, body) = api.join().expect("Runtime API failed");
+    assert_eq!(
+        request_line,
+        "POST /2018-06-01/runtime/invocation/request-1/response HTTP/1.1"
+    );
+    assert_eq!(body, r#"{"processed":3}"#);
+}
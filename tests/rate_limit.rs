@@ -0,0 +1,25 @@
+use lambda_runtime_types::rate_limit::RateLimiter;
+
+#[test]
+fn test_rate_limit_burst() {
+    let limiter = RateLimiter::new(3, 0.001);
+    assert!(limiter.try_acquire(2));
+    assert!(limiter.try_acquire(1));
+    assert!(!limiter.try_acquire(1));
+}
+
+#[tokio::test]
+async fn test_rate_limit_refill() {
+    let limiter = RateLimiter::new(1, 100.0);
+    assert!(limiter.try_acquire(1));
+    let start = std::time::Instant::now();
+    limiter.acquire(1).await.expect("Unable to acquire token");
+    assert!(start.elapsed() >= std::time::Duration::from_millis(5));
+    assert!(limiter.available() <= 1.0);
+}
+
+#[tokio::test]
+async fn test_rate_limit_above_capacity() {
+    let limiter = RateLimiter::new(2, 1.0);
+    assert!(limiter.acquire(3).await.is_err());
+}
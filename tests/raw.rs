@@ -0,0 +1,58 @@
+static ORDERS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[derive(serde::Deserialize, Debug)]
+struct Order {
+    order_id: String,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct Ping {
+    ping: bool,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), lambda_runtime_types::RawEvent, String> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, lambda_runtime_types::RawEvent>,
+    ) -> anyhow::Result<String> {
+        if let Ok(order) = event.parse::<Order>() {
+            ORDERS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            return Ok(order.order_id);
+        }
+        let ping: Ping = event.parse()?;
+        Ok(ping.ping.to_string())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_raw_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [{"order_id": "1"}, {"ping": true}, {"order_id": "2"}],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    assert_eq!(ORDERS.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_raw_event_parse() {
+    let raw = serde_json::value::to_raw_value(&serde_json::json!({"order_id": 1}))
+        .expect("Unable to serialize event");
+    let event = lambda_runtime_types::LambdaEvent::new(
+        raw,
+        "eu-central-1",
+        lambda_runtime_types::Context::default(),
+    );
+    let err = event
+        .parse::<Order>()
+        .expect_err("Event was parsed with invalid type");
+    assert_eq!(err.to_string(), "Unable to deserialize event");
+}
@@ -0,0 +1,42 @@
+use lambda_runtime_types::Region;
+
+#[test]
+fn test_region_valid() {
+    for name in [
+        "eu-central-1",
+        "us-gov-west-1",
+        "cn-north-1",
+        "us-isob-east-1",
+    ] {
+        let region = Region::new(name).expect("Region is invalid");
+        assert_eq!(region.as_str(), name);
+        assert_eq!(&*region, name);
+    }
+}
+
+#[test]
+fn test_region_invalid() {
+    for name in [
+        "",
+        "eu",
+        "eu-central",
+        "EU-CENTRAL-1",
+        "eu--1",
+        "eu-central-x",
+    ] {
+        let err = Region::new(name).expect_err("Region is valid");
+        assert_eq!(err.to_string(), format!("Invalid AWS region: {:?}", name));
+    }
+}
+
+#[test]
+fn test_region_partition() {
+    let partition = |name: &str| {
+        name.parse::<Region>()
+            .expect("Region is invalid")
+            .partition()
+    };
+    assert_eq!(partition("eu-west-1"), "aws");
+    assert_eq!(partition("cn-northwest-1"), "aws-cn");
+    assert_eq!(partition("us-gov-east-1"), "aws-us-gov");
+}
@@ -0,0 +1,38 @@
+use lambda_runtime_types::reload::{Reloadable, Source};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+#[derive(Default)]
+struct Counter {
+    version: AtomicU32,
+    loads: AtomicU32,
+}
+
+#[async_trait::async_trait]
+impl Source for Counter {
+    type Value = u32;
+
+    async fn version(&self) -> anyhow::Result<String> {
+        Ok(self.version.load(Ordering::SeqCst).to_string())
+    }
+
+    async fn load(&self) -> anyhow::Result<(u32, String)> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        let version = self.version.load(Ordering::SeqCst);
+        Ok((version * 10, version.to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_reload_on_version_change() {
+    let reloadable = Reloadable::new(Counter::default())
+        .await
+        .expect("Unable to load");
+    assert_eq!(*reloadable.get().await, 0);
+    assert_eq!(*reloadable.get().await, 0);
+
+    let source = reloadable.source();
+    source.version.store(1, Ordering::SeqCst);
+    assert_eq!(*reloadable.snapshot().await, 0);
+    assert_eq!(*reloadable.get().await, 10);
+    assert_eq!(source.loads.load(Ordering::SeqCst), 2);
+}
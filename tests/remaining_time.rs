@@ -0,0 +1,53 @@
+mod common;
+
+static REMAINING: std::sync::Mutex<Option<std::time::Duration>> = std::sync::Mutex::new(None);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        *REMAINING.lock().expect("Unable to lock remaining") = Some(event.remaining_time());
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_remaining_time() {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+
+    // The mock api sets the deadline 10 seconds ahead
+    let _ = lambda_runtime_types::Builder::new()
+        .timeout_margin(std::time::Duration::from_secs(1))
+        .exec::<_, _, Runner, _>();
+    api.join().expect("Runtime API failed");
+
+    let remaining = REMAINING
+        .lock()
+        .expect("Unable to lock remaining")
+        .expect("Runner was not invoked");
+    assert!(
+        remaining <= std::time::Duration::from_secs(9),
+        "{:?}",
+        remaining
+    );
+    assert!(
+        remaining > std::time::Duration::from_secs(8),
+        "{:?}",
+        remaining
+    );
+}
+
+#[test]
+fn test_remaining_time_without_deadline() {
+    let event = lambda_runtime_types::LambdaEvent::new((), "", Default::default());
+    assert_eq!(event.remaining_time(), std::time::Duration::MAX);
+}
@@ -0,0 +1,161 @@
+use lambda_runtime_types::retry;
+use std::time::{Duration, SystemTime};
+
+fn policy(max_retries: u32) -> retry::Policy {
+    retry::Policy::exponential(max_retries).with_base_delay(Duration::from_millis(1))
+}
+
+#[tokio::test]
+async fn test_retry_until_success() {
+    let mut attempts = 0;
+    let res: Result<u32, &str> = retry::retry(&policy(3), || {
+        attempts += 1;
+        let attempt = attempts;
+        async move {
+            if attempt < 3 {
+                Err("failed")
+            } else {
+                Ok(attempt)
+            }
+        }
+    })
+    .await;
+    assert_eq!(res, Ok(3));
+}
+
+#[tokio::test]
+async fn test_retry_exhausted() {
+    let mut attempts = 0;
+    let res: Result<(), &str> = retry::retry(&policy(2), || {
+        attempts += 1;
+        async { Err("failed") }
+    })
+    .await;
+    assert_eq!(res, Err("failed"));
+    assert_eq!(attempts, 3);
+}
+
+#[tokio::test]
+async fn test_retry_if_predicate() {
+    let mut attempts = 0;
+    let res: Result<(), &str> = retry::retry_if(
+        &policy(5),
+        |err: &&str| *err == "retryable",
+        || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt == 1 {
+                    Err("retryable")
+                } else {
+                    Err("fatal")
+                }
+            }
+        },
+    )
+    .await;
+    assert_eq!(res, Err("fatal"));
+    assert_eq!(attempts, 2);
+}
+
+#[test]
+fn test_retry_delay() {
+    let policy = retry::Policy::exponential(4)
+        .with_base_delay(Duration::from_millis(100))
+        .with_max_delay(Duration::from_millis(300))
+        .without_jitter();
+    assert_eq!(policy.delay(0), None);
+    assert_eq!(policy.delay(1), Some(Duration::from_millis(100)));
+    assert_eq!(policy.delay(2), Some(Duration::from_millis(200)));
+    assert_eq!(policy.delay(3), Some(Duration::from_millis(300)));
+    assert_eq!(policy.delay(4), Some(Duration::from_millis(300)));
+    assert_eq!(policy.delay(5), None);
+
+    let jittered = retry::Policy::exponential(1).with_base_delay(Duration::from_millis(100));
+    let delay = jittered.delay(1).expect("Missing delay");
+    assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+}
+
+#[test]
+fn test_retry_deadline() {
+    let policy = retry::Policy::exponential(3)
+        .with_base_delay(Duration::from_secs(1))
+        .with_deadline(SystemTime::now() + Duration::from_millis(500));
+    assert_eq!(policy.delay(1), None);
+}
+
+struct Flaky {
+    attempts: std::sync::atomic::AtomicU32,
+    failures: u32,
+}
+
+impl Flaky {
+    fn new(failures: u32) -> Self {
+        Self {
+            attempts: std::sync::atomic::AtomicU32::new(0),
+            failures,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl lambda_runtime_types::InstanceRunner<(), String, String> for &Flaky {
+    async fn run<'a>(
+        &'a self,
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, String>,
+    ) -> anyhow::Result<String> {
+        let attempt = self
+            .attempts
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if attempt < self.failures {
+            anyhow::bail!("Throttling");
+        }
+        Ok(event.event)
+    }
+}
+
+fn event<'a>(deadline: Option<SystemTime>) -> lambda_runtime_types::LambdaEvent<'a, String> {
+    let mut ctx = lambda_runtime_types::Context::default();
+    if let Some(deadline) = deadline {
+        ctx.deadline = deadline
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_millis() as u64;
+    }
+    lambda_runtime_types::LambdaEvent::new("done".into(), "eu-central-1", ctx)
+}
+
+#[tokio::test]
+async fn test_retry_middleware() {
+    use lambda_runtime_types::InstanceRunner;
+
+    let flaky = Flaky::new(2);
+    let runner = (&flaky).layer(retry::Retry::new(policy(3)));
+    let res = runner.run(&(), event(None)).await.expect("Retry failed");
+    assert_eq!(res, "done");
+    assert_eq!(flaky.attempts.into_inner(), 3);
+
+    let flaky = Flaky::new(2);
+    let runner = (&flaky).layer(retry::Retry::new(policy(3)).retry_if(|_| false));
+    runner
+        .run(&(), event(None))
+        .await
+        .expect_err("Error was retried");
+    assert_eq!(flaky.attempts.into_inner(), 1);
+}
+
+#[tokio::test]
+async fn test_retry_middleware_deadline() {
+    use lambda_runtime_types::InstanceRunner;
+
+    let flaky = Flaky::new(2);
+    let policy = retry::Policy::exponential(3).with_base_delay(Duration::from_secs(1));
+    let runner = (&flaky).layer(retry::Retry::new(policy));
+    let deadline = SystemTime::now() + Duration::from_millis(500);
+    runner
+        .run(&(), event(Some(deadline)))
+        .await
+        .expect_err("Retry exceeded deadline");
+    assert_eq!(flaky.attempts.into_inner(), 1);
+}
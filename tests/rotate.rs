@@ -54,3 +54,343 @@ fn test_rotation_secret_parsing_empty() {
         serde_json::from_value(json).expect("Unable to deserialize to structure");
     assert_eq!(secret.o.len(), 2);
 }
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_secret_region() {
+    let mut event: lambda_runtime_types::rotate::Event<()> =
+        serde_json::from_value(serde_json::json!({
+            "ClientRequestToken": "token",
+            "SecretId": "arn:aws:secretsmanager:eu-west-1:123456789012:secret:test-AbCdEf",
+            "Step": "createSecret",
+        }))
+        .expect("Unable to parse event");
+    assert_eq!(event.secret_region(), Some("eu-west-1"));
+    event.secret_id = "test".into();
+    assert_eq!(event.secret_region(), None);
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_dry_run_env() {
+    use lambda_runtime_types::rotate::{RotateRunner, RotationContext, SecretContainer, Smc};
+
+    struct Runner;
+
+    #[async_trait::async_trait]
+    impl<'a> RotateRunner<'a, (), ()> for Runner {
+        async fn setup(_region: &'a str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn create(
+            _shared: &'a (),
+            secret_cur: SecretContainer<()>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<SecretContainer<()>> {
+            Ok(secret_cur)
+        }
+
+        async fn set(
+            _shared: &'a (),
+            _secret_cur: SecretContainer<()>,
+            _secret_new: SecretContainer<()>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn test(
+            _shared: &'a (),
+            _secret_new: SecretContainer<()>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    let dry_run = <Runner as RotateRunner<'_, (), ()>>::dry_run;
+    std::env::remove_var(lambda_runtime_types::rotate::DRY_RUN_ENV);
+    assert!(!dry_run());
+    std::env::set_var(lambda_runtime_types::rotate::DRY_RUN_ENV, "true");
+    assert!(dry_run());
+    std::env::set_var(lambda_runtime_types::rotate::DRY_RUN_ENV, "0");
+    assert!(!dry_run());
+    std::env::remove_var(lambda_runtime_types::rotate::DRY_RUN_ENV);
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_rollback_event_parsing() {
+    let event: lambda_runtime_types::rotate::Event<()> =
+        serde_json::from_value(serde_json::json!({
+            "ClientRequestToken": "token",
+            "SecretId": "test",
+            "Step": "rollbackSecret",
+        }))
+        .expect("Unable to parse event");
+    assert!(matches!(
+        event.step,
+        lambda_runtime_types::rotate::Step::Rollback
+    ));
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_lenient_event_parsing() {
+    use lambda_runtime_types::rotate::{Event, Step};
+
+    for event in [
+        serde_json::json!({
+            "clientRequestToken": "token",
+            "secretId": "test",
+            "step": "setSecret",
+            "RotationToken": "ignored",
+        }),
+        serde_json::json!({
+            "client_request_token": "token",
+            "secret_id": "test",
+            "step": "setSecret",
+        }),
+    ] {
+        let event: Event<()> = serde_json::from_value(event).expect("Unable to parse event");
+        assert_eq!(event.client_request_token, "token");
+        assert_eq!(event.secret_id, "test");
+        assert_eq!(event.step, Step::Set);
+    }
+
+    let err = serde_json::from_value::<Event<()>>(serde_json::json!({
+        "ClientRequestToken": "token",
+        "Step": "setSecret",
+    }))
+    .expect_err("Event without SecretId was parsed");
+    assert!(err.to_string().contains("missing field `SecretId`"));
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_dual_credential() {
+    use lambda_runtime_types::rotate::{DualCredential, SecretContainer, Slot};
+
+    let mut secret: SecretContainer<DualCredential<String>> = SecretContainer::from_secret_value(
+        br#"{"active":"blue","blue":"old","green":"older","host":"db"}"#,
+    )
+    .expect("Unable to parse secret");
+    assert_eq!(secret.active(), "old");
+    assert_eq!(secret.standby(), "older");
+
+    assert_eq!(secret.rotate("new".into()), "older");
+    assert_eq!(secret.active, Slot::Green);
+    assert_eq!(secret.active(), "new");
+    assert_eq!(secret.standby(), "old");
+
+    let value: serde_json::Value = serde_json::from_str(
+        &secret
+            .to_secret_value()
+            .expect("Unable to serialize secret"),
+    )
+    .expect("Unable to parse serialized secret");
+    assert_eq!(
+        value,
+        serde_json::json!({"active": "green", "blue": "old", "green": "new", "host": "db"})
+    );
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_stage_labels() {
+    let labels = lambda_runtime_types::rotate::StageLabels::default();
+    assert_eq!(labels.current, "AWSCURRENT");
+    assert_eq!(labels.pending, "AWSPENDING");
+    assert_eq!(labels.previous, "AWSPREVIOUS");
+    assert!(labels.extra.is_empty());
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_unknown_step_parsing() {
+    let event: lambda_runtime_types::rotate::Event<()> =
+        serde_json::from_value(serde_json::json!({
+            "ClientRequestToken": "token",
+            "SecretId": "test",
+            "Step": "verifySecret",
+        }))
+        .expect("Unable to parse event");
+    assert_eq!(
+        event.step,
+        lambda_runtime_types::rotate::Step::Other("verifySecret".into())
+    );
+    assert_eq!(
+        serde_json::to_value(&event.step).expect("Unable to serialize step"),
+        "verifySecret"
+    );
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_failure_hook() {
+    use lambda_runtime_types::rotate::{RotateRunner, RotationContext, SecretContainer, Smc, Step};
+
+    static FAILURES: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+    static METRICS: std::sync::Mutex<Vec<serde_json::Value>> = std::sync::Mutex::new(Vec::new());
+
+    struct Runner;
+
+    #[async_trait::async_trait]
+    impl<'a> RotateRunner<'a, (), ()> for Runner {
+        async fn setup(_region: &'a str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn create(
+            _shared: &'a (),
+            secret_cur: SecretContainer<()>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<SecretContainer<()>> {
+            Ok(secret_cur)
+        }
+
+        async fn set(
+            _shared: &'a (),
+            _secret_cur: SecretContainer<()>,
+            _secret_new: SecretContainer<()>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn test(
+            _shared: &'a (),
+            _secret_new: SecretContainer<()>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn run_step(
+            _shared: &'a (),
+            step: Step,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            anyhow::bail!("{:?} failed", step)
+        }
+
+        async fn on_step_metrics(
+            _shared: &'a (),
+            metrics: &lambda_runtime_types::rotate::StepMetrics<'_>,
+        ) {
+            let metrics = serde_json::to_value(metrics).expect("Unable to serialize metrics");
+            METRICS
+                .lock()
+                .expect("Unable to lock metrics")
+                .push(metrics);
+        }
+
+        async fn on_rotation_failure(
+            _shared: &'a (),
+            step: Step,
+            error: anyhow::Error,
+            _smc: &Smc,
+            ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            FAILURES
+                .lock()
+                .expect("Unable to lock failures")
+                .push(format!("{} {:?}: {}", ctx.secret_id, step, error));
+            match step {
+                Step::Set => Ok(()),
+                _ => Err(error),
+            }
+        }
+    }
+
+    let event = |step: &str| {
+        serde_json::json!({
+            "ClientRequestToken": "token",
+            "SecretId": "test",
+            "Step": step,
+        })
+    };
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [event("setSecret"), event("testSecret")],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect_err("Rotation did not fail");
+    assert_eq!(
+        *FAILURES.lock().expect("Unable to lock failures"),
+        vec!["test Set: Set failed", "test Test: Test failed"]
+    );
+    let metrics = METRICS.lock().expect("Unable to lock metrics");
+    assert_eq!(metrics.len(), 2);
+    assert_eq!(metrics[0]["type"], "rotation_step");
+    assert_eq!(metrics[0]["step"], "setSecret");
+    assert_eq!(metrics[0]["secret_id"], "test");
+    assert_eq!(metrics[0]["version_id"], "token");
+    assert_eq!(metrics[0]["outcome"], "failure");
+    assert_eq!(metrics[0]["error"], "Set failed");
+    assert!(metrics[0]["duration_ms"].is_f64());
+    assert_eq!(metrics[1]["step"], "testSecret");
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_raw_secret() {
+    use lambda_runtime_types::rotate::{RawSecret, SecretContainer};
+
+    for value in ["token", "12345", "{broken"] {
+        let mut secret = SecretContainer::<RawSecret>::from_secret_value(value.as_bytes())
+            .expect("Unable to parse raw secret");
+        assert_eq!(secret.value, value);
+        assert_eq!(
+            secret
+                .to_secret_value()
+                .expect("Unable to serialize secret"),
+            value
+        );
+        secret.value = "new token".into();
+        assert_eq!(
+            secret
+                .to_secret_value()
+                .expect("Unable to serialize secret"),
+            "new token"
+        );
+    }
+
+    #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    struct TestData {
+        test: String,
+    }
+    let json = r#"{"test":"test_data"}"#;
+    let secret = SecretContainer::<TestData>::from_secret_value(json.as_bytes())
+        .expect("Unable to parse secret");
+    assert_eq!(secret.test, "test_data");
+    assert_eq!(
+        secret
+            .to_secret_value()
+            .expect("Unable to serialize secret"),
+        json
+    );
+    SecretContainer::<TestData>::from_secret_value(b"token")
+        .expect_err("Raw value was parsed as structure");
+}
+
+#[cfg(feature = "_rotate")]
+#[test]
+fn test_rotation_binary_secret() {
+    use lambda_runtime_types::rotate::{BinarySecret, RawSecret, SecretContainer};
+
+    let value = b"\xfe\xed\xfe\xed\x00\x00\x00\x02";
+    let secret = SecretContainer::<BinarySecret>::from_secret_value(value)
+        .expect("Unable to parse binary secret");
+    assert_eq!(secret.value, value);
+    SecretContainer::<RawSecret>::from_secret_value(value)
+        .expect_err("Binary value was parsed as raw secret");
+}
@@ -0,0 +1,134 @@
+#[cfg(feature = "_rotate")]
+mod composite {
+    use lambda_runtime_types::rotate::composite::{
+        CompositeRotation, CompositeSecret, CredentialRotator,
+    };
+    use lambda_runtime_types::rotate::simulation::Simulation;
+    use lambda_runtime_types::rotate::{RotationContext, Smc, Step};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+    struct User {
+        password: String,
+    }
+
+    /// Passwords which are set in the simulated service, the number of
+    /// `set` calls per user and users for which `set` fails once
+    #[derive(Default)]
+    struct Service {
+        passwords: Mutex<HashMap<String, String>>,
+        sets: Mutex<HashMap<String, usize>>,
+        failing: Mutex<Vec<String>>,
+    }
+
+    struct Database;
+
+    #[async_trait::async_trait]
+    impl<'a> CredentialRotator<'a, Service, User> for Database {
+        async fn setup(_region: &'a str) -> anyhow::Result<Service> {
+            Ok(Service::default())
+        }
+
+        async fn create(
+            _shared: &'a Service,
+            name: &str,
+            _credential_cur: User,
+            _smc: &Smc,
+            ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<User> {
+            Ok(User {
+                password: format!("{}-{}", name, ctx.client_request_token),
+            })
+        }
+
+        async fn set(
+            shared: &'a Service,
+            name: &str,
+            _credential_cur: User,
+            credential_new: User,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            *shared
+                .sets
+                .lock()
+                .expect("Lock is poisoned")
+                .entry(name.to_owned())
+                .or_default() += 1;
+            let mut failing = shared.failing.lock().expect("Lock is poisoned");
+            if let Some(idx) = failing.iter().position(|failing| failing == name) {
+                failing.remove(idx);
+                anyhow::bail!("User is locked");
+            }
+            shared
+                .passwords
+                .lock()
+                .expect("Lock is poisoned")
+                .insert(name.to_owned(), credential_new.password);
+            Ok(())
+        }
+
+        async fn test(
+            shared: &'a Service,
+            name: &str,
+            credential_new: User,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            let passwords = shared.passwords.lock().expect("Lock is poisoned");
+            anyhow::ensure!(
+                passwords.get(name) == Some(&credential_new.password),
+                "Wrong password"
+            );
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotation_composite_partial_failure() {
+        let mut simulation = Simulation::new(
+            "test",
+            r#"{"credentials":{"app":{"password":"a"},"readonly":{"password":"r"}},"host":"db"}"#,
+        )
+        .await
+        .expect("Unable to create simulation");
+        let service = Service::default();
+        service
+            .failing
+            .lock()
+            .expect("Lock is poisoned")
+            .push("readonly".into());
+
+        let err = simulation
+            .rotate::<CompositeRotation<Database>, _, _>(&service)
+            .await
+            .expect_err("Failing credential was ignored");
+        assert!(err
+            .to_string()
+            .starts_with("Rotation of credentials readonly failed"));
+        assert_eq!(service.passwords.lock().expect("Lock is poisoned").len(), 1);
+
+        for step in [Step::Set, Step::Test, Step::Finish] {
+            simulation
+                .step::<CompositeRotation<Database>, _, _>(&service, step)
+                .await
+                .expect("Re-delivered step failed");
+        }
+
+        let current = simulation
+            .secret::<CompositeSecret<User>>("AWSCURRENT")
+            .expect("No current secret");
+        let passwords = service.passwords.lock().expect("Lock is poisoned").clone();
+        for (name, user) in &current.credentials {
+            assert_eq!(passwords[name], user.password);
+        }
+        let sets = service.sets.lock().expect("Lock is poisoned").clone();
+        assert_eq!(sets["app"], 1);
+        assert_eq!(sets["readonly"], 2);
+        assert!(simulation
+            .value("AWSCURRENT")
+            .expect("No current value")
+            .contains(r#""host":"db""#));
+    }
+}
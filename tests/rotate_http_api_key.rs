@@ -0,0 +1,112 @@
+use lambda_runtime_types::rotate::http_api_key::{
+    ApiKeySecret, ApiKeyTemplate, HttpApiKeyRotation, Request,
+};
+use std::sync::atomic::{AtomicU16, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+static PORT: AtomicU16 = AtomicU16::new(0);
+
+struct ExampleApi;
+
+impl ApiKeyTemplate for ExampleApi {
+    const AUTH_HEADER: &'static str = "x-api-key";
+    const AUTH_PREFIX: &'static str = "";
+    const KEY_POINTER: &'static str = "/data/secret";
+    const KEY_ID_POINTER: Option<&'static str> = Some("/data/id");
+
+    fn create() -> Request {
+        Request::new(http::Method::POST, url("/keys")).body(r#"{"name":"rotated"}"#)
+    }
+
+    fn test() -> Request {
+        Request::new(http::Method::GET, url("/keys/{key_id}"))
+    }
+
+    fn revoke() -> Option<Request> {
+        Some(Request::new(http::Method::DELETE, url("/keys/{key_id}")))
+    }
+}
+
+fn url(path: &str) -> String {
+    format!("http://127.0.0.1:{}{}", PORT.load(Ordering::SeqCst), path)
+}
+
+/// Answers a single request with `status` and `body` and returns the request
+async fn api(listener: &tokio::net::TcpListener, status: u16, body: &str) -> String {
+    let (mut stream, _) = listener.accept().await.expect("Unable to accept");
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    loop {
+        let read = stream.read(&mut buf).await.expect("Unable to read");
+        request.extend_from_slice(&buf[..read]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some((head, content)) = text.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    line.to_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|l| l.trim().to_owned())
+                })
+                .map_or(0, |length| length.parse().expect("Invalid content-length"));
+            if content.len() >= length {
+                break;
+            }
+        }
+    }
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {} Status\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+        .await
+        .expect("Unable to write");
+    String::from_utf8(request).expect("Invalid request")
+}
+
+#[tokio::test]
+async fn test_http_api_key_rotation() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    PORT.store(
+        listener.local_addr().expect("Unable to get address").port(),
+        Ordering::SeqCst,
+    );
+    let current = ApiKeySecret {
+        api_key: "old-key".into(),
+        key_id: Some("1".into()),
+    };
+
+    let (new, request) = tokio::join!(
+        HttpApiKeyRotation::<ExampleApi>::create_key(&current),
+        api(&listener, 201, r#"{"data":{"id":2,"secret":"new-key"}}"#),
+    );
+    let new = new.expect("Unable to create key");
+    assert_eq!(new.api_key, "new-key");
+    assert_eq!(new.key_id.as_deref(), Some("2"));
+    assert!(request.starts_with("POST /keys HTTP/1.1"));
+    assert!(request.contains("x-api-key: old-key"));
+    assert!(request.ends_with(r#"{"name":"rotated"}"#));
+
+    let (res, request) = tokio::join!(
+        HttpApiKeyRotation::<ExampleApi>::test_key(&new),
+        api(&listener, 401, "unauthorized"),
+    );
+    let err = res.expect_err("Test succeeded");
+    assert!(err.to_string().contains("401"), "{}", err);
+    assert!(request.starts_with("GET /keys/2 HTTP/1.1"));
+
+    let (res, request) = tokio::join!(
+        HttpApiKeyRotation::<ExampleApi>::revoke_key(&current, &new),
+        api(&listener, 204, ""),
+    );
+    res.expect("Unable to revoke key");
+    assert!(request.starts_with("DELETE /keys/1 HTTP/1.1"));
+    assert!(request.contains("x-api-key: new-key"));
+}
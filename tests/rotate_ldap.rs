@@ -0,0 +1,143 @@
+use lambda_runtime_types::rotate::ldap::LdapSecret;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const BIND_OK: &[u8] = &[
+    0x30, 0x0c, 0x02, 0x01, 0x01, 0x61, 0x07, 0x0a, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00,
+];
+const EXTENDED_OK: &[u8] = &[
+    0x30, 0x0c, 0x02, 0x01, 0x02, 0x78, 0x07, 0x0a, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00,
+];
+const MODIFY_OK: &[u8] = &[
+    0x30, 0x0c, 0x02, 0x01, 0x02, 0x67, 0x07, 0x0a, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00,
+];
+const BIND_OVERSIZED: &[u8] = &[0x30, 0x84, 0x7f, 0xff, 0xff, 0xff];
+const BIND_INVALID_CREDENTIALS: &[u8] = &[
+    0x30, 0x0f, 0x02, 0x01, 0x01, 0x61, 0x0a, 0x0a, 0x01, 0x31, 0x04, 0x00, 0x04, 0x03, b'b', b'a',
+    b'd',
+];
+
+/// Answers each received request with the next reply and
+/// returns the received data once the connection is closed
+async fn ldap(listener: tokio::net::TcpListener, replies: Vec<&'static [u8]>) -> Vec<u8> {
+    let (mut stream, _) = listener.accept().await.expect("Unable to accept");
+    let mut received = Vec::new();
+    for reply in replies {
+        let mut buf = [0; 1024];
+        let read = stream.read(&mut buf).await.expect("Unable to read");
+        received.extend_from_slice(&buf[..read]);
+        stream.write_all(reply).await.expect("Unable to write");
+    }
+    stream
+        .read_to_end(&mut received)
+        .await
+        .expect("Unable to read");
+    received
+}
+
+async fn secret(listener: &tokio::net::TcpListener, active_directory: bool) -> LdapSecret {
+    let port = listener.local_addr().expect("Unable to get address").port();
+    serde_json::from_value(serde_json::json!({
+        "host": "127.0.0.1",
+        "port": port,
+        "bind_dn": "cn=app,dc=example",
+        "password": "secret",
+        "tls": false,
+        "active_directory": active_directory,
+    }))
+    .expect("Unable to deserialize secret")
+}
+
+fn contains(data: &[u8], search: &[u8]) -> bool {
+    data.windows(search.len()).any(|sub| sub == search)
+}
+
+#[tokio::test]
+async fn test_ldap_password_modify() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let secret = secret(&listener, false).await;
+    let server = tokio::spawn(ldap(listener, vec![BIND_OK, EXTENDED_OK]));
+
+    secret
+        .change_password("changed")
+        .await
+        .expect("Unable to change password");
+
+    let received = server.await.expect("LDAP failed");
+    let bind = b"\x30\x23\x02\x01\x01\x60\x1e\x02\x01\x03\x04\x11cn=app,dc=example\x80\x06secret";
+    assert!(received.starts_with(bind));
+    assert!(contains(&received, b"\x80\x171.3.6.1.4.1.4203.1.11.1"));
+    assert!(contains(
+        &received,
+        b"\x80\x11cn=app,dc=example\x81\x06secret\x82\x07changed"
+    ));
+    // Unbind
+    assert!(received.ends_with(b"\x30\x05\x02\x01\x03\x42\x00"));
+}
+
+#[tokio::test]
+async fn test_ldap_active_directory() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let secret = secret(&listener, true).await;
+    let server = tokio::spawn(ldap(listener, vec![BIND_OK, MODIFY_OK]));
+
+    secret
+        .change_password("changed")
+        .await
+        .expect("Unable to change password");
+
+    let received = server.await.expect("LDAP failed");
+    let unicode = |password: &str| -> Vec<u8> {
+        format!("\"{}\"", password)
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    };
+    let delete = [
+        b"\x30\x25\x0a\x01\x01\x30\x20\x04\x0aunicodePwd\x31\x12\x04\x10".as_slice(),
+        &unicode("secret"),
+    ]
+    .concat();
+    let add = [
+        b"\x30\x27\x0a\x01\x00\x30\x22\x04\x0aunicodePwd\x31\x14\x04\x12".as_slice(),
+        &unicode("changed"),
+    ]
+    .concat();
+    assert!(contains(&received, &[delete, add].concat()));
+}
+
+#[tokio::test]
+async fn test_ldap_bind_failed() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let secret = secret(&listener, false).await;
+    let server = tokio::spawn(ldap(listener, vec![BIND_INVALID_CREDENTIALS]));
+
+    let err = secret.connect().await.expect_err("Connected");
+    assert_eq!(
+        format!("{:#}", err),
+        "Unable to bind as cn=app,dc=example: LDAP responded with error 49 (invalidCredentials): bad"
+    );
+    drop(err);
+    server.await.expect("LDAP failed");
+}
+
+#[tokio::test]
+async fn test_ldap_oversized_response() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let secret = secret(&listener, false).await;
+    let server = tokio::spawn(ldap(listener, vec![BIND_OVERSIZED]));
+
+    let err = secret
+        .change_password("changed")
+        .await
+        .expect_err("Oversized response must be rejected");
+    assert!(format!("{:#}", err).ends_with("Ldap message exceeds 1048576 bytes: 2147483647"));
+    server.await.expect("LDAP failed");
+}
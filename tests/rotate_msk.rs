@@ -0,0 +1,264 @@
+use base64::Engine;
+use lambda_runtime_types::rotate::msk::{MskCluster, MskScramRotation, MskScramSecret};
+use lambda_runtime_types::rotate::simulation::Simulation;
+use lambda_runtime_types::rotate::Step;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const CLUSTER_ARN: &str = "arn:aws:kafka:us-east-1:000000000000:cluster/orders/4b1c";
+const SALT: &[u8] = b"0123456789abcdef";
+const ITERATIONS: u32 = 4096;
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    ring::hmac::sign(&ring::hmac::Key::new(ring::hmac::HMAC_SHA512, key), data)
+        .as_ref()
+        .to_vec()
+}
+
+fn attribute<'a>(message: &'a str, name: &str) -> &'a str {
+    message
+        .split(',')
+        .find_map(|attribute| attribute.strip_prefix(name))
+        .expect("Missing attribute")
+}
+
+/// Reads a request and returns api key, correlation id and body
+async fn read_request(stream: &mut tokio::net::TcpStream) -> Option<(i16, i32, Vec<u8>)> {
+    let mut size = [0; 4];
+    stream.read_exact(&mut size).await.ok()?;
+    let mut request = vec![0; i32::from_be_bytes(size) as usize];
+    stream.read_exact(&mut request).await.ok()?;
+    let api_key = i16::from_be_bytes([request[0], request[1]]);
+    let correlation_id = i32::from_be_bytes([request[4], request[5], request[6], request[7]]);
+    let client_id = i16::from_be_bytes([request[8], request[9]]) as usize;
+    Some((api_key, correlation_id, request.split_off(10 + client_id)))
+}
+
+async fn respond(stream: &mut tokio::net::TcpStream, correlation_id: i32, body: &[u8]) {
+    let mut response = ((body.len() + 4) as i32).to_be_bytes().to_vec();
+    response.extend_from_slice(&correlation_id.to_be_bytes());
+    response.extend_from_slice(body);
+    stream.write_all(&response).await.expect("Unable to write");
+}
+
+fn authenticate_response(error_code: i16, message: Option<&str>, auth_bytes: &[u8]) -> Vec<u8> {
+    let mut body = error_code.to_be_bytes().to_vec();
+    match message {
+        Some(message) => {
+            body.extend_from_slice(&(message.len() as i16).to_be_bytes());
+            body.extend_from_slice(message.as_bytes());
+        }
+        None => body.extend_from_slice(&(-1_i16).to_be_bytes()),
+    }
+    body.extend_from_slice(&(auth_bytes.len() as i32).to_be_bytes());
+    body.extend_from_slice(auth_bytes);
+    body
+}
+
+/// Plays a broker, which accepts `password` for SCRAM-SHA-512 authentications,
+/// on a single connection. Returns whether the client authenticated
+async fn broker(listener: tokio::net::TcpListener, password: String) -> bool {
+    broker_with_iterations(listener, password, ITERATIONS).await
+}
+
+/// Same as [`broker`], but requests `iterations` PBKDF2 iterations
+async fn broker_with_iterations(
+    listener: tokio::net::TcpListener,
+    password: String,
+    iterations: u32,
+) -> bool {
+    let (mut stream, _) = listener.accept().await.expect("Unable to accept");
+    let mut auth_message = String::new();
+    while let Some((api_key, correlation_id, body)) = read_request(&mut stream).await {
+        match api_key {
+            17 => {
+                assert_eq!(body, b"\x00\x0dSCRAM-SHA-512");
+                let mut response = vec![0, 0, 0, 0, 0, 1, 0, 13];
+                response.extend_from_slice(b"SCRAM-SHA-512");
+                respond(&mut stream, correlation_id, &response).await;
+            }
+            36 => {
+                let message = String::from_utf8(body[4..].to_vec()).expect("Invalid message");
+                if let Some(client_first_bare) = message.strip_prefix("n,,") {
+                    let nonce = format!("{}server", attribute(client_first_bare, "r="));
+                    let server_first = format!(
+                        "r={},s={},i={}",
+                        nonce,
+                        base64::engine::general_purpose::STANDARD.encode(SALT),
+                        iterations
+                    );
+                    auth_message = format!("{},{}", client_first_bare, server_first);
+                    let response = authenticate_response(0, None, server_first.as_bytes());
+                    respond(&mut stream, correlation_id, &response).await;
+                    continue;
+                }
+                let (without_proof, proof) = message.split_once(",p=").expect("Missing proof");
+                let mut salted_password = [0; 64];
+                ring::pbkdf2::derive(
+                    ring::pbkdf2::PBKDF2_HMAC_SHA512,
+                    std::num::NonZeroU32::new(iterations).expect("Invalid iterations"),
+                    SALT,
+                    password.as_bytes(),
+                    &mut salted_password,
+                );
+                let auth_message = format!("{},{}", auth_message, without_proof);
+                let client_key = hmac(&salted_password, b"Client Key");
+                let stored_key = ring::digest::digest(&ring::digest::SHA512, &client_key);
+                let expected: Vec<_> = client_key
+                    .iter()
+                    .zip(hmac(stored_key.as_ref(), auth_message.as_bytes()))
+                    .map(|(key, signature)| key ^ signature)
+                    .collect();
+                let proof = base64::engine::general_purpose::STANDARD
+                    .decode(proof)
+                    .expect("Invalid proof");
+                if proof != expected {
+                    let message = "Authentication failed during authentication due to invalid \
+                                   credentials with SASL mechanism SCRAM-SHA-512";
+                    let response = authenticate_response(58, Some(message), b"");
+                    respond(&mut stream, correlation_id, &response).await;
+                    return false;
+                }
+                let server_key = hmac(&salted_password, b"Server Key");
+                let server_final = format!(
+                    "v={}",
+                    base64::engine::general_purpose::STANDARD
+                        .encode(hmac(&server_key, auth_message.as_bytes()))
+                );
+                let response = authenticate_response(0, None, server_final.as_bytes());
+                respond(&mut stream, correlation_id, &response).await;
+                return true;
+            }
+            _ => panic!("Unexpected api key {}", api_key),
+        }
+    }
+    false
+}
+
+async fn cluster() -> (tokio::net::TcpListener, MskCluster) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let port = listener.local_addr().expect("Unable to get address").port();
+    let cluster = MskCluster {
+        cluster_arn: CLUSTER_ARN.to_owned(),
+        bootstrap_brokers: vec![format!("127.0.0.1:{}", port)],
+        tls: false,
+    };
+    (listener, cluster)
+}
+
+fn secret(password: &str) -> MskScramSecret {
+    MskScramSecret {
+        username: "app".into(),
+        password: password.into(),
+    }
+}
+
+#[tokio::test]
+async fn test_msk_check_credentials() {
+    let (listener, cluster) = cluster().await;
+    let server = tokio::spawn(broker(listener, "secret".into()));
+
+    cluster
+        .check_credentials(&secret("secret"))
+        .await
+        .expect("Unable to authenticate");
+
+    assert!(server.await.expect("Broker failed"));
+}
+
+#[tokio::test]
+async fn test_msk_check_credentials_invalid() {
+    let (listener, cluster) = cluster().await;
+    let server = tokio::spawn(broker(listener, "secret".into()));
+
+    let err = cluster
+        .check_credentials(&secret("wrong"))
+        .await
+        .expect_err("Authentication must fail");
+    assert_eq!(
+        format!("{:#}", err),
+        format!(
+            "Unable to authenticate as app on broker {}: Broker responded with error 58: \
+             Authentication failed during authentication due to invalid credentials with SASL \
+             mechanism SCRAM-SHA-512",
+            cluster.bootstrap_brokers[0]
+        )
+    );
+    assert!(!server.await.expect("Broker failed"));
+}
+
+#[tokio::test]
+async fn test_msk_check_credentials_excessive_iterations() {
+    let (listener, cluster) = cluster().await;
+    let server = tokio::spawn(broker_with_iterations(listener, "secret".into(), u32::MAX));
+
+    let err = cluster
+        .check_credentials(&secret("secret"))
+        .await
+        .expect_err("Excessive iterations must be rejected");
+    assert!(
+        format!("{:#}", err).ends_with(
+            "Broker requested 4294967295 SCRAM iterations, which exceeds the maximum of 100000"
+        ),
+        "{:#}",
+        err
+    );
+    assert!(!server.await.expect("Broker failed"));
+}
+
+#[tokio::test]
+async fn test_msk_check_credentials_oversized_response() {
+    let (listener, cluster) = cluster().await;
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.expect("Unable to accept");
+        read_request(&mut stream).await.expect("Missing handshake");
+        stream
+            .write_all(&i32::MAX.to_be_bytes())
+            .await
+            .expect("Unable to write");
+    });
+
+    let err = cluster
+        .check_credentials(&secret("secret"))
+        .await
+        .expect_err("Oversized response must be rejected");
+    assert!(format!("{:#}", err)
+        .ends_with("Response of kafka broker exceeds 1048576 bytes: 2147483647"));
+    server.await.expect("Broker failed");
+}
+
+#[tokio::test]
+async fn test_msk_rotation() {
+    let (listener, cluster) = cluster().await;
+    let mut simulation = Simulation::new("AmazonMSK_app", r#"{"username":"app","password":"old"}"#)
+        .await
+        .expect("Unable to create simulation");
+
+    for step in [Step::Create, Step::Set, Step::Set, Step::Test] {
+        simulation
+            .step::<MskScramRotation, _, _>(&cluster, step)
+            .await
+            .expect("Step failed");
+    }
+    assert_eq!(
+        simulation.scram_secrets(CLUSTER_ARN).await,
+        vec!["arn:aws:secretsmanager:us-east-1:000000000000:secret:AmazonMSK_app".to_owned()]
+    );
+
+    // The brokers only know the new password once it is current
+    let pending = simulation
+        .secret::<MskScramSecret>("AWSPENDING")
+        .expect("Missing pending secret");
+    let server = tokio::spawn(broker(listener, pending.password.clone()));
+    simulation
+        .step::<MskScramRotation, _, _>(&cluster, Step::Finish)
+        .await
+        .expect("Finish failed");
+
+    assert!(server.await.expect("Broker failed"));
+    let current = simulation
+        .secret::<MskScramSecret>("AWSCURRENT")
+        .expect("Missing current secret");
+    assert_eq!(current.password, pending.password);
+}
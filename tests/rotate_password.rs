@@ -0,0 +1,22 @@
+use lambda_runtime_types::rotate::generate_password;
+
+#[test]
+fn test_generate_password() {
+    let password = generate_password(false, None).expect("Unable to generate password");
+    assert_eq!(password.len(), 32);
+    assert!(password.chars().all(|c| c.is_ascii_alphanumeric()));
+    assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+    assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+    assert!(password.chars().any(|c| c.is_ascii_digit()));
+
+    let password = generate_password(true, Some(64)).expect("Unable to generate password");
+    assert_eq!(password.len(), 64);
+    assert!(password.chars().any(|c| c.is_ascii_punctuation()));
+    assert!(!password.contains('"'));
+
+    assert_ne!(
+        generate_password(false, None).expect("Unable to generate password"),
+        generate_password(false, None).expect("Unable to generate password")
+    );
+    generate_password(true, Some(3)).expect_err("Too short password was generated");
+}
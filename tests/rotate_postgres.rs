@@ -0,0 +1,35 @@
+use lambda_runtime_types::rotate::postgres::{quote_identifier, quote_literal, PostgresSecret};
+
+#[test]
+fn test_postgres_secret() {
+    let secret: PostgresSecret = serde_json::from_value(serde_json::json!({
+        "engine": "postgres",
+        "host": "db.example.com",
+        "username": "app",
+        "password": "secret",
+    }))
+    .expect("Unable to deserialize secret");
+    assert_eq!(secret.port, 5432);
+    assert_eq!(secret.dbname, "postgres");
+    assert!(!format!("{:?}", secret).contains("secret"));
+
+    // Field names of the previous example are accepted as well
+    let secret: PostgresSecret = serde_json::from_value(serde_json::json!({
+        "host": "db.example.com",
+        "port": 5433,
+        "user": "app",
+        "password": "secret",
+        "database": "app",
+    }))
+    .expect("Unable to deserialize secret");
+    assert_eq!(secret.user, "app");
+    assert_eq!(secret.dbname, "app");
+    let json = serde_json::to_value(&secret).expect("Unable to serialize secret");
+    assert_eq!(json["username"], "app");
+}
+
+#[test]
+fn test_postgres_quoting() {
+    assert_eq!(quote_identifier(r#"app"user"#), r#""app""user""#);
+    assert_eq!(quote_literal("it's"), "'it''s'");
+}
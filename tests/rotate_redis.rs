@@ -0,0 +1,232 @@
+use lambda_runtime_types::rotate::redis::{RedisRotation, RedisSecret, Reply};
+use lambda_runtime_types::rotate::simulation::Simulation;
+use lambda_runtime_types::rotate::Step;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Answers each received command with the next reply and
+/// returns the received data
+async fn redis(listener: tokio::net::TcpListener, replies: Vec<&'static str>) -> String {
+    redis_connection(&listener, replies).await
+}
+
+/// Like [`redis`] for the next connection of `listener`
+async fn redis_connection(
+    listener: &tokio::net::TcpListener,
+    replies: Vec<&'static str>,
+) -> String {
+    let (mut stream, _) = listener.accept().await.expect("Unable to accept");
+    let mut received = Vec::new();
+    for reply in replies {
+        let mut buf = [0; 1024];
+        let read = stream.read(&mut buf).await.expect("Unable to read");
+        received.extend_from_slice(&buf[..read]);
+        stream
+            .write_all(reply.as_bytes())
+            .await
+            .expect("Unable to write");
+    }
+    String::from_utf8(received).expect("Invalid request")
+}
+
+async fn secret(listener: &tokio::net::TcpListener) -> RedisSecret {
+    let port = listener.local_addr().expect("Unable to get address").port();
+    serde_json::from_value(serde_json::json!({
+        "host": "127.0.0.1",
+        "port": port,
+        "username": "app",
+        "password": "secret",
+        "tls": false,
+    }))
+    .expect("Unable to deserialize secret")
+}
+
+#[tokio::test]
+async fn test_redis_commands() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let secret = secret(&listener).await;
+    let server = tokio::spawn(redis(
+        listener,
+        vec![
+            "+OK\r\n",
+            "*3\r\n$3\r\none\r\n:2\r\n$-1\r\n",
+            "-ERR unknown user\r\n",
+        ],
+    ));
+
+    let mut client = secret.connect().await.expect("Unable to connect");
+    assert_eq!(
+        client
+            .command(&["LRANGE", "list", "0", "-1"])
+            .await
+            .expect("Command failed"),
+        Reply::Array(Some(vec![
+            Reply::Bulk(Some(b"one".to_vec())),
+            Reply::Integer(2),
+            Reply::Bulk(None),
+        ]))
+    );
+    let err = client
+        .command(&["ACL", "SETUSER", "other", ">password"])
+        .await
+        .expect_err("Command succeeded");
+    assert_eq!(
+        err.to_string(),
+        "Redis responded with error: ERR unknown user"
+    );
+
+    let received = server.await.expect("Redis failed");
+    assert!(received.starts_with("*3\r\n$4\r\nAUTH\r\n$3\r\napp\r\n$6\r\nsecret\r\n"));
+    assert!(received.contains("*4\r\n$6\r\nLRANGE\r\n$4\r\nlist\r\n$1\r\n0\r\n$2\r\n-1\r\n"));
+}
+
+#[tokio::test]
+async fn test_redis_auth_failed() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let secret = secret(&listener).await;
+    let server = tokio::spawn(redis(listener, vec!["-WRONGPASS invalid password\r\n"]));
+
+    let err = secret.connect().await.expect_err("Connected");
+    assert_eq!(err.to_string(), "Unable to authenticate as user app");
+    server.await.expect("Redis failed");
+}
+
+#[tokio::test]
+async fn test_redis_oversized_reply() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let secret = secret(&listener).await;
+    let server = tokio::spawn(redis(listener, vec!["+OK\r\n", "$9223372036854775807\r\n"]));
+
+    let mut client = secret.connect().await.expect("Unable to connect");
+    let err = client
+        .command(&["GET", "key"])
+        .await
+        .expect_err("Oversized reply must be rejected");
+    assert_eq!(
+        err.to_string(),
+        "Redis reply exceeds 1048576 bytes: 9223372036854775807"
+    );
+    server.await.expect("Redis failed");
+}
+
+#[tokio::test]
+async fn test_redis_finish_revokes_after_promotion() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let mut simulation = Simulation::new(
+        "redis/app",
+        &serde_json::to_string(&secret(&listener).await).expect("Unable to serialize secret"),
+    )
+    .await
+    .expect("Unable to create simulation");
+    simulation
+        .step::<RedisRotation, _, _>(&(), Step::Create)
+        .await
+        .expect("Create failed");
+    let pending = simulation
+        .secret::<RedisSecret>("AWSPENDING")
+        .expect("Missing pending secret");
+
+    let server = tokio::spawn(redis(
+        listener,
+        vec!["+OK\r\n", "-ERR injected failure\r\n"],
+    ));
+    simulation
+        .step::<RedisRotation, _, _>(&(), Step::Finish)
+        .await
+        .expect_err("Revocation must fail");
+
+    // The new password is current before the previous one is revoked
+    let current = simulation
+        .secret::<RedisSecret>("AWSCURRENT")
+        .expect("Missing current secret");
+    assert_eq!(current.password, pending.password);
+    let received = server.await.expect("Redis failed");
+    let password = &pending.password;
+    assert!(received.starts_with(&format!(
+        "*3\r\n$4\r\nAUTH\r\n$3\r\napp\r\n${}\r\n{}\r\n",
+        password.len(),
+        password
+    )));
+    assert!(received.contains(&format!(
+        "*5\r\n$3\r\nACL\r\n$7\r\nSETUSER\r\n$3\r\napp\r\n$9\r\nresetpass\r\n${}\r\n>{}\r\n",
+        password.len() + 1,
+        password
+    )));
+}
+
+#[cfg(feature = "rotate_elasticache")]
+#[tokio::test]
+async fn test_elasticache_rotation() {
+    use lambda_runtime_types::rotate::redis::{ElastiCacheRotation, ElastiCacheSecret};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let port = listener.local_addr().expect("Unable to get address").port();
+    let mut simulation = Simulation::new(
+        "elasticache/orders",
+        &serde_json::json!({
+            "replication_group_id": "orders",
+            "host": "127.0.0.1",
+            "port": port,
+            "auth_token": "old-token",
+            "tls": false,
+        })
+        .to_string(),
+    )
+    .await
+    .expect("Unable to create simulation");
+    simulation.add_replication_group("orders", "old-token");
+
+    let server = tokio::spawn(async move {
+        // The new token is probed before it is set
+        let probe = redis_connection(&listener, vec!["-WRONGPASS invalid password\r\n"]).await;
+        let test = redis_connection(&listener, vec!["+OK\r\n", "+PONG\r\n"]).await;
+        (probe, test)
+    });
+    for step in [Step::Create, Step::Set] {
+        simulation
+            .step::<ElastiCacheRotation, _, _>(&(), step)
+            .await
+            .expect("Step failed");
+    }
+    let pending = simulation
+        .secret::<ElastiCacheSecret>("AWSPENDING")
+        .expect("Missing pending secret");
+    // Both tokens are valid until the rotation finished
+    assert_eq!(
+        simulation.auth_tokens("orders"),
+        ["old-token", pending.auth_token.as_str()]
+    );
+
+    for step in [Step::Test, Step::Finish] {
+        simulation
+            .step::<ElastiCacheRotation, _, _>(&(), step)
+            .await
+            .expect("Step failed");
+    }
+    assert_eq!(
+        simulation.auth_tokens("orders"),
+        [pending.auth_token.as_str()]
+    );
+    let current = simulation
+        .secret::<ElastiCacheSecret>("AWSCURRENT")
+        .expect("Missing current secret");
+    assert_eq!(current.auth_token, pending.auth_token);
+
+    let (probe, test) = server.await.expect("Redis failed");
+    let auth = format!(
+        "*2\r\n$4\r\nAUTH\r\n${}\r\n{}\r\n",
+        pending.auth_token.len(),
+        pending.auth_token
+    );
+    assert_eq!(probe, auth);
+    assert_eq!(test, format!("{}*1\r\n$4\r\nPING\r\n", auth));
+}
@@ -0,0 +1,517 @@
+#[cfg(feature = "_rotate")]
+mod simulation {
+    use lambda_runtime_types::rotate::simulation::Simulation;
+    use lambda_runtime_types::rotate::{
+        BinarySecret, RotateRunner, RotationContext, SecretContainer, Smc, Step, ROTATION_LOCK_TAG,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+    struct Secret {
+        password: String,
+    }
+
+    /// Password which is set in the simulated service, the number
+    /// of `set` calls and the number of `finish` calls
+    #[derive(Default)]
+    struct Service(Mutex<(String, usize)>, AtomicUsize);
+
+    impl Service {
+        fn new(password: &str) -> Self {
+            Self(Mutex::new((password.to_owned(), 0)), AtomicUsize::new(0))
+        }
+
+        fn finished(&self) -> usize {
+            self.1.load(Ordering::SeqCst)
+        }
+
+        fn get(&self) -> (String, usize) {
+            self.0.lock().expect("Lock is poisoned").clone()
+        }
+    }
+
+    /// Optional settings of [`Runner`]
+    trait Options: Send + Sync + 'static {
+        fn notification_topic() -> Option<String> {
+            None
+        }
+
+        fn kms_key_id() -> Option<String> {
+            None
+        }
+
+        fn lock_lease() -> Option<std::time::Duration> {
+            None
+        }
+    }
+
+    struct Defaults;
+
+    impl Options for Defaults {}
+
+    #[cfg(feature = "rotate_sns")]
+    struct Notifying;
+
+    #[cfg(feature = "rotate_sns")]
+    impl Options for Notifying {
+        fn notification_topic() -> Option<String> {
+            Some(TOPIC.to_owned())
+        }
+    }
+
+    struct KeyMigration;
+
+    impl Options for KeyMigration {
+        fn kms_key_id() -> Option<String> {
+            Some(KMS_KEY.to_owned())
+        }
+    }
+
+    struct Locking;
+
+    impl Options for Locking {
+        fn lock_lease() -> Option<std::time::Duration> {
+            Some(std::time::Duration::from_secs(900))
+        }
+    }
+
+    struct Runner<O = Defaults>(std::marker::PhantomData<O>);
+
+    #[async_trait::async_trait]
+    impl<'a, O: Options> RotateRunner<'a, Service, Secret> for Runner<O> {
+        async fn setup(_region: &'a str) -> anyhow::Result<Service> {
+            Ok(Service::default())
+        }
+
+        async fn create(
+            _shared: &'a Service,
+            mut secret_cur: SecretContainer<Secret>,
+            smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<SecretContainer<Secret>> {
+            secret_cur.password = smc.generate_new_password(false, None).await?;
+            Ok(secret_cur)
+        }
+
+        async fn set(
+            shared: &'a Service,
+            _secret_cur: SecretContainer<Secret>,
+            secret_new: SecretContainer<Secret>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            let mut service = shared.0.lock().expect("Lock is poisoned");
+            service.0 = secret_new.password.clone();
+            service.1 += 1;
+            Ok(())
+        }
+
+        async fn test(
+            shared: &'a Service,
+            secret_new: SecretContainer<Secret>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            anyhow::ensure!(shared.get().0 == secret_new.password, "Wrong password");
+            Ok(())
+        }
+
+        async fn finish(
+            shared: &'a Service,
+            _secret_cur: SecretContainer<Secret>,
+            _secret_new: SecretContainer<Secret>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            shared.1.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn notification_topic() -> Option<String> {
+            O::notification_topic()
+        }
+
+        fn kms_key_id() -> Option<String> {
+            O::kms_key_id()
+        }
+
+        fn lock_lease() -> Option<std::time::Duration> {
+            O::lock_lease()
+        }
+    }
+
+    #[cfg(feature = "rotate_sns")]
+    const TOPIC: &str = "arn:aws:sns:us-east-1:000000000000:rotation";
+    const KMS_KEY: &str = "arn:aws:kms:us-east-1:000000000000:key/new";
+
+    async fn simulation() -> Simulation {
+        Simulation::new("test", r#"{"password":"old","user":"admin"}"#)
+            .await
+            .expect("Unable to create simulation")
+    }
+
+    /// Rotates a keystore, which is not valid utf-8, by appending a byte
+    struct BinaryRunner;
+
+    #[async_trait::async_trait]
+    impl<'a> RotateRunner<'a, (), BinarySecret> for BinaryRunner {
+        async fn setup(_region: &'a str) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn create(
+            _shared: &'a (),
+            mut secret_cur: SecretContainer<BinarySecret>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<SecretContainer<BinarySecret>> {
+            secret_cur.value.push(0xff);
+            Ok(secret_cur)
+        }
+
+        async fn set(
+            _shared: &'a (),
+            _secret_cur: SecretContainer<BinarySecret>,
+            _secret_new: SecretContainer<BinarySecret>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn test(
+            _shared: &'a (),
+            _secret_new: SecretContainer<BinarySecret>,
+            _smc: &Smc,
+            _ctx: &RotationContext<'_>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotation_binary_secret() {
+        let keystore = b"\xfe\xed\xfe\xed\x00\x00\x00\x02";
+        let mut simulation = Simulation::new_binary("keystore", keystore)
+            .await
+            .expect("Unable to create simulation");
+        simulation
+            .rotate::<BinaryRunner, _, _>(&())
+            .await
+            .expect("Rotation failed");
+
+        let mut rotated = keystore.to_vec();
+        rotated.push(0xff);
+        assert_eq!(simulation.binary_value("AWSCURRENT"), Some(rotated));
+        assert_eq!(
+            simulation.binary_value("AWSPREVIOUS"),
+            Some(keystore.to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotation_simulation() {
+        let mut simulation = simulation().await;
+        let service = Service::new("old");
+        simulation
+            .rotate::<Runner, _, _>(&service)
+            .await
+            .expect("Rotation failed");
+
+        let current = simulation
+            .secret::<Secret>("AWSCURRENT")
+            .expect("No current secret");
+        assert_ne!(current.password, "old");
+        assert_eq!(current.password.len(), 32);
+        assert_eq!(service.get(), (current.password.clone(), 1));
+        assert_eq!(
+            simulation
+                .secret::<Secret>("AWSPREVIOUS")
+                .expect("No previous secret")
+                .password,
+            "old"
+        );
+        assert!(simulation.value("AWSPENDING").is_none());
+        assert!(simulation.client_request_token().is_none());
+        assert!(simulation
+            .value("AWSCURRENT")
+            .expect("No current value")
+            .contains(r#""user":"admin""#));
+
+        simulation
+            .rotate::<Runner, _, _>(&service)
+            .await
+            .expect("Second rotation failed");
+        assert_eq!(
+            simulation
+                .secret::<Secret>("AWSPREVIOUS")
+                .expect("No previous secret")
+                .password,
+            current.password
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotation_simulation_redelivery() {
+        for step in [Step::Create, Step::Set, Step::Test, Step::Finish] {
+            for after in [false, true] {
+                let mut simulation = simulation().await;
+                let service = Service::new("old");
+                if after {
+                    simulation.fail_after(step.clone());
+                } else {
+                    simulation.fail_before(step.clone());
+                }
+                let err = simulation
+                    .rotate::<Runner, _, _>(&service)
+                    .await
+                    .expect_err("Injected failure was ignored");
+                assert!(err.to_string().starts_with("Injected failure"));
+                let token = simulation
+                    .client_request_token()
+                    .expect("Rotation was not kept running")
+                    .to_owned();
+
+                // Secret Manager re-delivers the failed step and continues
+                let remaining = [Step::Create, Step::Set, Step::Test, Step::Finish]
+                    .into_iter()
+                    .skip_while(|s| *s != step);
+                for step in remaining {
+                    simulation
+                        .step::<Runner, _, _>(&service, step)
+                        .await
+                        .expect("Re-delivered step failed");
+                }
+
+                let current = simulation
+                    .secret::<Secret>("AWSCURRENT")
+                    .expect("No current secret");
+                assert_eq!(service.get(), (current.password.clone(), 1), "{:?}", step);
+                // Without step markers, a re-delivered finish is skipped as well
+                assert_eq!(service.finished(), 1, "{:?}", step);
+                assert_eq!(
+                    simulation
+                        .secret::<Secret>("AWSPREVIOUS")
+                        .expect("No previous secret")
+                        .password,
+                    "old"
+                );
+                let versions = simulation
+                    .smc()
+                    .describe_secret(simulation.secret_id())
+                    .await
+                    .expect("Unable to describe secret")
+                    .version_ids_to_stages;
+                assert_eq!(versions[&token], ["AWSCURRENT"]);
+            }
+        }
+    }
+
+    #[cfg(feature = "rotate_sns")]
+    async fn current_version_id(simulation: &Simulation) -> String {
+        simulation
+            .smc()
+            .describe_secret(simulation.secret_id())
+            .await
+            .expect("Unable to describe secret")
+            .version_ids_to_stages
+            .into_iter()
+            .find(|(_, stages)| stages.iter().any(|s| s == "AWSCURRENT"))
+            .expect("No current version")
+            .0
+    }
+
+    #[cfg(feature = "rotate_sns")]
+    fn notification(simulation: &Simulation, idx: usize) -> serde_json::Value {
+        let published = simulation.published();
+        let message = published.get(idx).expect("Notification is missing");
+        assert_eq!(message.topic_arn, TOPIC);
+        serde_json::from_str(&message.message).expect("Notification is not json")
+    }
+
+    #[cfg(feature = "rotate_sns")]
+    #[tokio::test]
+    async fn test_rotation_notification() {
+        let mut simulation = simulation().await;
+        let service = Service::new("old");
+        let old_version_id = current_version_id(&simulation).await;
+
+        simulation.fail_before(Step::Set);
+        simulation
+            .rotate::<Runner<Notifying>, _, _>(&service)
+            .await
+            .expect_err("Injected failure was ignored");
+        let token = simulation
+            .client_request_token()
+            .expect("Rotation was not kept running")
+            .to_owned();
+        assert_eq!(simulation.published().len(), 1);
+        let failure = notification(&simulation, 0);
+        assert_eq!(failure["type"], "rotation_notification");
+        assert_eq!(failure["secret_id"], "test");
+        assert_eq!(failure["outcome"], "failure");
+        assert_eq!(failure["old_version_id"], old_version_id.as_str());
+        assert_eq!(failure["new_version_id"], token.as_str());
+        assert!(failure["error"]
+            .as_str()
+            .expect("Error is missing")
+            .starts_with("Injected failure"));
+        assert_eq!(simulation.published()[0].subject, "Rotation of test failed");
+
+        simulation
+            .rotate::<Runner<Notifying>, _, _>(&service)
+            .await
+            .expect("Rotation failed");
+        assert_eq!(simulation.published().len(), 2);
+        let success = notification(&simulation, 1);
+        assert_eq!(success["outcome"], "success");
+        assert_eq!(success["old_version_id"], old_version_id.as_str());
+        assert_eq!(success["new_version_id"], token.as_str());
+        assert!(success["error"].is_null());
+        assert_eq!(current_version_id(&simulation).await, token);
+    }
+
+    #[tokio::test]
+    async fn test_rotation_without_notification() {
+        let mut simulation = simulation().await;
+        let service = Service::new("old");
+        simulation.fail_before(Step::Test);
+        simulation
+            .rotate::<Runner, _, _>(&service)
+            .await
+            .expect_err("Injected failure was ignored");
+        simulation
+            .rotate::<Runner, _, _>(&service)
+            .await
+            .expect("Rotation failed");
+        assert!(simulation.published().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rotation_kms_key_migration() {
+        let mut simulation = simulation().await;
+        let service = Service::new("old");
+        let description = simulation
+            .smc()
+            .describe_secret(simulation.secret_id())
+            .await
+            .expect("Unable to describe secret");
+        assert_eq!(description.kms_key_id, None);
+
+        simulation
+            .rotate::<Runner<KeyMigration>, _, _>(&service)
+            .await
+            .expect("Rotation failed");
+        let description = simulation
+            .smc()
+            .describe_secret(simulation.secret_id())
+            .await
+            .expect("Unable to describe secret");
+        assert_eq!(description.kms_key_id.as_deref(), Some(KMS_KEY));
+        assert_eq!(
+            simulation
+                .secret::<Secret>("AWSCURRENT")
+                .expect("No current secret")
+                .password,
+            service.get().0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotation_stale_pending_cleanup() {
+        let mut simulation = simulation().await;
+        let service = Service::new("old");
+        simulation
+            .step::<Runner, _, _>(&service, Step::Create)
+            .await
+            .expect("Create failed");
+        let abandoned = simulation
+            .abandon_rotation()
+            .expect("Rotation was not running");
+
+        let err = simulation
+            .rotate::<Runner, _, _>(&service)
+            .await
+            .expect_err("Rotation started with stale pending value");
+        assert!(err.to_string().contains("previous rotation isn't complete"));
+        let removed = simulation
+            .smc()
+            .remove_stale_pending(simulation.secret_id(), None)
+            .await
+            .expect("Unable to remove stale pending value");
+        assert_eq!(removed.as_deref(), Some(abandoned.as_str()));
+        assert!(simulation.value("AWSPENDING").is_none());
+        assert_eq!(
+            simulation
+                .smc()
+                .remove_stale_pending(simulation.secret_id(), None)
+                .await
+                .expect("Unable to remove stale pending value"),
+            None
+        );
+
+        simulation
+            .rotate::<Runner, _, _>(&service)
+            .await
+            .expect("Rotation failed");
+        let current = simulation
+            .secret::<Secret>("AWSCURRENT")
+            .expect("No current secret");
+        assert_eq!(service.get(), (current.password.clone(), 1));
+    }
+
+    async fn lock(simulation: &Simulation) -> Option<String> {
+        simulation
+            .smc()
+            .get_tags(simulation.secret_id())
+            .await
+            .expect("Unable to fetch tags")
+            .remove(ROTATION_LOCK_TAG)
+    }
+
+    #[tokio::test]
+    async fn test_rotation_lock() {
+        let mut simulation = simulation().await;
+        let service = Service::new("old");
+        simulation
+            .rotate::<Runner<Locking>, _, _>(&service)
+            .await
+            .expect("Rotation failed");
+        assert_eq!(lock(&simulation).await, None);
+
+        // Lease of a manual invocation, which is still running
+        simulation
+            .smc()
+            .acquire_lock(
+                simulation.secret_id(),
+                "manual",
+                std::time::Duration::from_secs(60),
+            )
+            .await
+            .expect("Unable to acquire lock");
+        let err = simulation
+            .rotate::<Runner<Locking>, _, _>(&service)
+            .await
+            .expect_err("Rotation ignored the lock");
+        assert!(err.to_string().contains("is locked by manual"), "{}", err);
+        assert_eq!(service.get().1, 1);
+        assert!(lock(&simulation)
+            .await
+            .expect("Lock was released")
+            .ends_with(":manual"));
+
+        // Expired lease of a crashed invocation
+        simulation
+            .smc()
+            .tag_secret(simulation.secret_id(), &[(ROTATION_LOCK_TAG, "1:crashed")])
+            .await
+            .expect("Unable to tag secret");
+        simulation
+            .rotate::<Runner<Locking>, _, _>(&service)
+            .await
+            .expect("Rotation failed");
+        assert_eq!(service.get().1, 2);
+        assert_eq!(lock(&simulation).await, None);
+    }
+}
@@ -0,0 +1,306 @@
+use base64::Engine;
+use lambda_runtime_types::rotate::simulation::Simulation;
+use lambda_runtime_types::rotate::snowflake::{self, SnowflakeRotation, SnowflakeSecret};
+use lambda_runtime_types::rotate::Step;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Answers a single request with `status` and `body` and returns the request
+async fn api(listener: &tokio::net::TcpListener, status: u16, body: &str) -> String {
+    let (mut stream, _) = listener.accept().await.expect("Unable to accept");
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    loop {
+        let read = stream.read(&mut buf).await.expect("Unable to read");
+        request.extend_from_slice(&buf[..read]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some((head, content)) = text.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    line.to_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|l| l.trim().to_owned())
+                })
+                .map_or(0, |length| length.parse().expect("Invalid content-length"));
+            if content.len() >= length {
+                break;
+            }
+        }
+    }
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {} Status\r\nconnection: close\r\ncontent-length: {}\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )
+        .await
+        .expect("Unable to write");
+    String::from_utf8(request).expect("Invalid request")
+}
+
+async fn listener() -> (tokio::net::TcpListener, u16) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Unable to bind");
+    let port = listener.local_addr().expect("Unable to get address").port();
+    (listener, port)
+}
+
+fn body(request: &str) -> serde_json::Value {
+    let (_, body) = request.split_once("\r\n\r\n").expect("Missing body");
+    serde_json::from_str(body).expect("Invalid body")
+}
+
+/// Verifies the key pair token of `request` against `private_key` and returns its claims
+fn claims(request: &str, private_key: &str) -> serde_json::Value {
+    let token = request
+        .lines()
+        .find_map(|line| line.strip_prefix("authorization: Bearer "))
+        .expect("Missing token");
+    let (message, signature) = token.rsplit_once('.').expect("Invalid token");
+    let key = openssl::pkey::PKey::private_key_from_pem(private_key.as_bytes())
+        .expect("Invalid private key");
+    let mut verifier = openssl::sign::Verifier::new(openssl::hash::MessageDigest::sha256(), &key)
+        .expect("Unable to create verifier");
+    verifier
+        .update(message.as_bytes())
+        .expect("Unable to verify");
+    let signature = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(signature)
+        .expect("Invalid signature");
+    assert!(verifier.verify(&signature).expect("Unable to verify"));
+    let (_, claims) = message.split_once('.').expect("Invalid token");
+    serde_json::from_slice(
+        &base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(claims)
+            .expect("Invalid claims"),
+    )
+    .expect("Invalid claims")
+}
+
+fn secret(port: u16, private_key: Option<String>) -> SnowflakeSecret {
+    serde_json::from_value(serde_json::json!({
+        "account": "myorg-analytics",
+        "username": "loader",
+        "password": "secret",
+        "private_key": private_key,
+        "warehouse": "compute_wh",
+        "url": format!("http://127.0.0.1:{}", port),
+    }))
+    .expect("Unable to deserialize secret")
+}
+
+#[tokio::test]
+async fn test_snowflake_execute() {
+    let (listener, port) = listener().await;
+    let private_key = snowflake::generate_private_key().expect("Unable to generate key");
+    let secret = secret(port, Some(private_key.clone()));
+    let server = tokio::spawn(async move {
+        let running = api(
+            &listener,
+            202,
+            r#"{"statementStatusUrl":"/api/v2/statements/01b2?requestId=7"}"#,
+        )
+        .await;
+        let status = api(&listener, 200, r#"{"data":[["1"]]}"#).await;
+        (running, status)
+    });
+
+    let response = secret
+        .execute("SELECT 1")
+        .await
+        .expect("Unable to execute statement");
+    assert_eq!(response, serde_json::json!({ "data": [["1"]] }));
+
+    let (running, status) = server.await.expect("API failed");
+    assert!(running.starts_with("POST /api/v2/statements HTTP/1.1\r\n"));
+    assert!(running.contains("x-snowflake-authorization-token-type: KEYPAIR_JWT\r\n"));
+    assert_eq!(
+        body(&running),
+        serde_json::json!({ "statement": "SELECT 1", "timeout": 60, "warehouse": "compute_wh" })
+    );
+    let claims = claims(&running, &private_key);
+    let fingerprint = base64::engine::general_purpose::STANDARD.encode(openssl::sha::sha256(
+        &base64::engine::general_purpose::STANDARD
+            .decode(snowflake::public_key(&private_key).expect("Invalid private key"))
+            .expect("Invalid public key"),
+    ));
+    assert_eq!(claims["sub"], "MYORG-ANALYTICS.LOADER");
+    assert_eq!(
+        claims["iss"],
+        format!("MYORG-ANALYTICS.LOADER.SHA256:{}", fingerprint)
+    );
+    assert_eq!(
+        claims["exp"].as_u64().expect("Missing exp"),
+        claims["iat"].as_u64().expect("Missing iat") + 300
+    );
+    assert!(status.starts_with("GET /api/v2/statements/01b2?requestId=7 HTTP/1.1\r\n"));
+}
+
+#[tokio::test]
+async fn test_snowflake_execute_failure() {
+    let (listener, port) = listener().await;
+    let private_key = snowflake::generate_private_key().expect("Unable to generate key");
+    let secret = secret(port, Some(private_key));
+    let server = tokio::spawn(async move {
+        api(
+            &listener,
+            401,
+            r#"{"code":"390144","message":"JWT token is invalid."}"#,
+        )
+        .await
+    });
+
+    let err = secret
+        .execute("SELECT 1")
+        .await
+        .expect_err("Statement must fail");
+    assert_eq!(
+        format!("{:#}", err),
+        "Statement as user loader failed with status 401 Unauthorized: JWT token is invalid."
+    );
+    server.await.expect("API failed");
+}
+
+#[tokio::test]
+async fn test_snowflake_password_connection() {
+    let (listener, port) = listener().await;
+    let secret = secret(port, None);
+    let server = tokio::spawn(async move {
+        let login = api(
+            &listener,
+            200,
+            r#"{"success":true,"data":{"token":"session-token"}}"#,
+        )
+        .await;
+        let query = api(
+            &listener,
+            200,
+            r#"{"success":true,"data":{"rowset":[["1"]]}}"#,
+        )
+        .await;
+        (login, query)
+    });
+
+    secret.check_connection().await.expect("Unable to connect");
+
+    let (login, query) = server.await.expect("API failed");
+    assert!(login.starts_with("POST /session/v1/login-request?warehouse=compute_wh HTTP/1.1\r\n"));
+    let data = &body(&login)["data"];
+    assert_eq!(data["ACCOUNT_NAME"], "MYORG-ANALYTICS");
+    assert_eq!(data["LOGIN_NAME"], "loader");
+    assert_eq!(data["PASSWORD"], "secret");
+    assert!(query.starts_with("POST /queries/v1/query-request?requestId="));
+    assert!(query.contains("authorization: Snowflake Token=\"session-token\"\r\n"));
+    assert_eq!(body(&query)["sqlText"], "SELECT 1");
+}
+
+#[tokio::test]
+async fn test_snowflake_password_connection_refused() {
+    let (listener, port) = listener().await;
+    let secret = secret(port, None);
+    let server = tokio::spawn(async move {
+        api(
+            &listener,
+            200,
+            r#"{"success":false,"code":"390100","message":"Incorrect username or password was specified."}"#,
+        )
+        .await
+    });
+
+    let err = secret
+        .check_connection()
+        .await
+        .expect_err("Login must fail");
+    assert_eq!(
+        format!("{:#}", err),
+        "Unable to log in as user loader: Snowflake responded with status 200 OK: Incorrect \
+         username or password was specified. (390100)"
+    );
+    server.await.expect("API failed");
+}
+
+#[tokio::test]
+async fn test_snowflake_key_pair_rotation() {
+    let (listener, port) = listener().await;
+    let private_key = snowflake::generate_private_key().expect("Unable to generate key");
+    let mut current = secret(port, Some(private_key.clone()));
+    current.password = None;
+    let mut simulation = Simulation::new(
+        "snowflake/loader",
+        &serde_json::to_string(&current).expect("Unable to serialize secret"),
+    )
+    .await
+    .expect("Unable to create simulation");
+
+    simulation
+        .step::<SnowflakeRotation, _, _>(&(), Step::Create)
+        .await
+        .expect("Create failed");
+    let pending = simulation
+        .secret::<SnowflakeSecret>("AWSPENDING")
+        .expect("Missing pending secret");
+    assert_eq!(pending.key_slot, 2);
+    let new_key = pending.private_key.clone().expect("Missing private key");
+    assert_ne!(new_key, private_key);
+
+    let server = tokio::spawn(async move {
+        // The new key is probed before it is set
+        let probe = api(&listener, 401, r#"{"message":"JWT token is invalid."}"#).await;
+        let alter = api(
+            &listener,
+            200,
+            r#"{"data":[["Statement executed successfully."]]}"#,
+        )
+        .await;
+        let test = api(&listener, 200, r#"{"data":[["1"]]}"#).await;
+        (probe, alter, test)
+    });
+    for step in [Step::Set, Step::Test] {
+        simulation
+            .step::<SnowflakeRotation, _, _>(&(), step)
+            .await
+            .expect("Step failed");
+    }
+
+    let (probe, alter, test) = server.await.expect("API failed");
+    assert_eq!(body(&probe)["statement"], "SELECT 1");
+    // The current key alters the user
+    claims(&alter, &private_key);
+    assert_eq!(
+        body(&alter)["statement"],
+        format!(
+            "ALTER USER IDENTIFIER('loader') SET RSA_PUBLIC_KEY_2 = '{}'",
+            snowflake::public_key(&new_key).expect("Invalid private key")
+        )
+    );
+    claims(&test, &new_key);
+    assert_eq!(body(&test)["statement"], "SELECT 1");
+}
+
+#[tokio::test]
+async fn test_snowflake_password_rotation_requires_admin() {
+    // Nothing listens, so the probe of the new password fails
+    let (listener, port) = listener().await;
+    drop(listener);
+    let mut simulation = Simulation::new(
+        "snowflake/loader",
+        &serde_json::to_string(&secret(port, None)).expect("Unable to serialize secret"),
+    )
+    .await
+    .expect("Unable to create simulation");
+
+    simulation
+        .step::<SnowflakeRotation, _, _>(&(), Step::Create)
+        .await
+        .expect("Create failed");
+    let err = simulation
+        .step::<SnowflakeRotation, _, _>(&(), Step::Set)
+        .await
+        .expect_err("Set must fail");
+    assert!(format!("{:#}", err).contains("requires admin_secret_id"));
+}
@@ -0,0 +1,73 @@
+use lambda_runtime_types::Scoped;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static FRESH: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Default)]
+struct Cache(AtomicU32);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, u32, u32, ()> for Runner {
+    async fn run(
+        shared: &'a u32,
+        event: lambda_runtime_types::LambdaEvent<'a, u32>,
+    ) -> anyhow::Result<()> {
+        if event.scoped.get::<Cache>().is_none() {
+            FRESH.fetch_add(1, Ordering::SeqCst);
+        }
+        let cache = event
+            .scoped
+            .get_or_insert_with(|| Cache(AtomicU32::new(*shared)));
+        cache.0.fetch_add(event.event, Ordering::SeqCst);
+        assert_eq!(cache.0.load(Ordering::SeqCst), *shared + event.event);
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<u32> {
+        Ok(10)
+    }
+}
+
+#[test]
+fn test_scoped_per_invocation() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [1, 2, 3],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Lambda failed");
+    assert_eq!(FRESH.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_scoped_values() {
+    let scoped = Scoped::default();
+    assert!(scoped.get::<Cache>().is_none());
+    let cache = scoped.get_or_insert_with(Cache::default);
+    cache.0.store(5, Ordering::SeqCst);
+    let cache = scoped.get_or_insert_with(|| Cache(AtomicU32::new(1)));
+    assert_eq!(cache.0.load(Ordering::SeqCst), 5);
+
+    // Values are stored per type
+    assert!(scoped.insert(String::from("first")).is_none());
+    let previous = scoped
+        .insert(String::from("second"))
+        .expect("Missing value");
+    assert_eq!(*previous, "first");
+    assert_eq!(*scoped.get::<String>().expect("Missing value"), "second");
+    assert_eq!(
+        scoped
+            .get::<Cache>()
+            .expect("Missing value")
+            .0
+            .load(Ordering::SeqCst),
+        5
+    );
+
+    // Clones share the values of the invocation
+    let clone = scoped.clone();
+    clone.remove::<String>().expect("Missing value");
+    assert!(scoped.get::<String>().is_none());
+}
@@ -0,0 +1,54 @@
+use lambda_runtime_types::secret_cache::SecretCache;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn secret_cache(ttl: Duration) -> (SecretCache<u32>, Arc<AtomicU32>) {
+    let loads = Arc::new(AtomicU32::new(0));
+    let counter = Arc::clone(&loads);
+    let cache = SecretCache::new(ttl, move || {
+        let counter = Arc::clone(&counter);
+        async move { Ok(counter.fetch_add(1, Ordering::SeqCst) + 1) }
+    });
+    (cache, loads)
+}
+
+#[tokio::test]
+async fn test_secret_cache_ttl() {
+    let (cache, loads) = secret_cache(Duration::from_secs(3600));
+    assert_eq!(*cache.get().await.expect("Unable to load"), 1);
+    assert_eq!(*cache.get().await.expect("Unable to load"), 1);
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+    let (cache, _) = secret_cache(Duration::ZERO);
+    assert_eq!(*cache.get().await.expect("Unable to load"), 1);
+    assert_eq!(*cache.get().await.expect("Unable to load"), 2);
+}
+
+#[tokio::test]
+async fn test_secret_cache_reload_on_auth_error() {
+    let (cache, loads) = secret_cache(Duration::from_secs(3600));
+    let res = cache
+        .call(
+            |err| err.to_string() == "auth",
+            |secret| async move {
+                if *secret == 1 {
+                    anyhow::bail!("auth")
+                }
+                Ok(*secret)
+            },
+        )
+        .await
+        .expect("Unable to call");
+    assert_eq!(res, 2);
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+    let res: anyhow::Result<()> = cache
+        .call(
+            |err| err.to_string() == "auth",
+            |_| async { anyhow::bail!("other") },
+        )
+        .await;
+    assert!(res.is_err());
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,63 @@
+use lambda_runtime_types::shared_cell::SharedCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+fn shared_cell() -> SharedCell<u32> {
+    let builds = Arc::new(AtomicU32::new(0));
+    SharedCell::new(move || {
+        let builds = Arc::clone(&builds);
+        async move { Ok(builds.fetch_add(1, Ordering::SeqCst) + 1) }
+    })
+}
+
+#[tokio::test]
+async fn test_shared_cell_invalidate() {
+    let cell = shared_cell();
+    assert!(!cell.is_initialized().await);
+    assert_eq!(*cell.get_or_init().await.expect("Unable to build"), 1);
+    assert_eq!(*cell.get_or_init().await.expect("Unable to build"), 1);
+
+    cell.invalidate().await;
+    assert!(!cell.is_initialized().await);
+    assert_eq!(*cell.get_or_init().await.expect("Unable to build"), 2);
+}
+
+#[tokio::test]
+async fn test_shared_cell_call() {
+    let cell = shared_cell();
+    let res: anyhow::Result<()> = cell
+        .call(
+            |err| err.to_string() == "connection closed",
+            |_| async { anyhow::bail!("connection closed") },
+        )
+        .await;
+    res.expect_err("Operation succeeded");
+    assert!(!cell.is_initialized().await);
+    assert_eq!(*cell.get_or_init().await.expect("Unable to build"), 2);
+
+    let res: anyhow::Result<()> = cell
+        .call(
+            |err| err.to_string() == "connection closed",
+            |_| async { anyhow::bail!("invalid query") },
+        )
+        .await;
+    res.expect_err("Operation succeeded");
+    assert_eq!(*cell.get_or_init().await.expect("Unable to build"), 2);
+}
+
+#[tokio::test]
+async fn test_shared_cell_failed_init() {
+    let attempts = Arc::new(AtomicU32::new(0));
+    let counter = Arc::clone(&attempts);
+    let cell = SharedCell::new(move || {
+        let attempt = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            if attempt == 0 {
+                anyhow::bail!("Database unreachable");
+            }
+            Ok(attempt)
+        }
+    });
+    cell.get_or_init().await.expect_err("Build succeeded");
+    assert_eq!(*cell.get_or_init().await.expect("Unable to build"), 1);
+}
@@ -1,3 +1,6 @@
+// The conversion is a no-op while `prev_value` stores a `String`
+#![allow(clippy::useless_conversion)]
+
 #[derive(serde::Deserialize, Debug)]
 struct Event {
     #[serde(flatten)]
@@ -0,0 +1,45 @@
+static SHUTDOWNS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[derive(Default)]
+struct Shared {
+    invocations: std::sync::atomic::AtomicUsize,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, Shared, (), ()> for Runner {
+    async fn run(
+        shared: &'a Shared,
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        shared
+            .invocations
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<Shared> {
+        Ok(Shared::default())
+    }
+
+    async fn on_shutdown(shared: &'a Shared) {
+        assert_eq!(
+            shared.invocations.load(std::sync::atomic::Ordering::SeqCst),
+            2
+        );
+        SHUTDOWNS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_shutdown_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "environments": 2,
+        "invocations": [null, null, null, null],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    assert_eq!(SHUTDOWNS.load(std::sync::atomic::Ordering::SeqCst), 2);
+}
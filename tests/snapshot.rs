@@ -0,0 +1,52 @@
+use lambda_runtime_types::snapshot::Snapshot;
+use std::collections::HashMap;
+
+fn path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "lambda-runtime-types-{}-{}.json",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn test_snapshot_restore() {
+    let path = path("restore");
+    let snapshot: Snapshot<HashMap<String, u32>> = Snapshot::restore(&path);
+    assert!(!snapshot.is_restored());
+    snapshot.update(|cache| cache.insert("a".into(), 1));
+    snapshot.persist().await.expect("Unable to persist");
+
+    let restored: Snapshot<HashMap<String, u32>> = Snapshot::restore(&path);
+    assert!(restored.is_restored());
+    assert_eq!(restored.read(|cache| cache.get("a").copied()), Some(1));
+    std::fs::remove_file(&path).expect("Unable to remove snapshot");
+}
+
+#[tokio::test]
+async fn test_snapshot_unchanged() {
+    let path = path("unchanged");
+    let snapshot: Snapshot<u32> = Snapshot::restore(&path);
+    snapshot.persist().await.expect("Unable to persist");
+    assert!(!path.exists());
+
+    snapshot.update(|value| *value = 3);
+    snapshot.persist().await.expect("Unable to persist");
+    std::fs::write(&path, "4").expect("Unable to write");
+    // Unchanged values are not written again
+    snapshot.persist().await.expect("Unable to persist");
+    assert_eq!(std::fs::read_to_string(&path).expect("Unable to read"), "4");
+    std::fs::remove_file(&path).expect("Unable to remove snapshot");
+}
+
+#[test]
+fn test_snapshot_invalid() {
+    let path = path("invalid");
+    std::fs::write(&path, "not json").expect("Unable to write");
+    let snapshot: Snapshot<u32> = Snapshot::restore(&path);
+    assert!(!snapshot.is_restored());
+    assert_eq!(snapshot.read(|value| *value), 0);
+    std::fs::remove_file(&path).expect("Unable to remove snapshot");
+}
@@ -0,0 +1,63 @@
+use lambda_runtime_types::state::{update, MemoryStore, StateStore, Versioned};
+
+#[tokio::test]
+async fn test_state_versions() {
+    let store = MemoryStore::default();
+    let value = serde_json::json!(1);
+    assert_eq!(
+        store.put("key", &value, None).await.expect("Put failed"),
+        Some(1)
+    );
+    // The value exists already
+    assert_eq!(
+        store.put("key", &value, None).await.expect("Put failed"),
+        None
+    );
+    // The version is outdated
+    assert_eq!(
+        store.put("key", &value, Some(2)).await.expect("Put failed"),
+        None
+    );
+    assert_eq!(
+        store
+            .put("key", &serde_json::json!(2), Some(1))
+            .await
+            .expect("Put failed"),
+        Some(2)
+    );
+    assert_eq!(
+        store.get("key").await.expect("Get failed"),
+        Some(Versioned {
+            value: serde_json::json!(2),
+            version: 2
+        })
+    );
+    store.delete("key").await.expect("Delete failed");
+    assert_eq!(store.get("key").await.expect("Get failed"), None);
+}
+
+#[tokio::test]
+async fn test_state_update() {
+    let store = MemoryStore::default();
+    for expected in 1..=3u64 {
+        let count = update(&store, "count", |count: Option<u64>| count.unwrap_or(0) + 1)
+            .await
+            .expect("Update failed");
+        assert_eq!(count, expected);
+    }
+
+    // Updates which conflict with a concurrent change are repeated
+    let mut attempts = 0;
+    let count = update(&store, "count", |count: Option<u64>| {
+        attempts += 1;
+        if attempts == 1 {
+            futures::executor::block_on(store.put("count", &serde_json::json!(10), Some(3)))
+                .expect("Put failed");
+        }
+        count.unwrap_or(0) + 1
+    })
+    .await
+    .expect("Update failed");
+    assert_eq!(count, 11);
+    assert_eq!(attempts, 2);
+}
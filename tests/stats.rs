@@ -0,0 +1,63 @@
+static INVOCATIONS: std::sync::Mutex<Vec<(u32, u64, u64)>> = std::sync::Mutex::new(Vec::new());
+
+#[derive(serde::Deserialize, Debug)]
+struct Event {
+    id: u32,
+    fail: bool,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), Event, ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<()> {
+        INVOCATIONS
+            .lock()
+            .expect("Unable to lock invocations")
+            .push((event.event.id, event.stats.invocations, event.stats.errors));
+        if event.event.fail {
+            anyhow::bail!("Invocation {} failed", event.event.id);
+        }
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn fallback(_shared: &'a (), _error: &anyhow::Error) -> Option<()> {
+        Some(())
+    }
+}
+
+#[test]
+fn test_stats() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "environments": 2,
+        "invocations": [
+            { "id": 0, "fail": true },
+            { "id": 1, "fail": false },
+            { "id": 2, "fail": false },
+            { "id": 3, "fail": true },
+            { "id": 4, "fail": false },
+            { "id": 5, "fail": false },
+        ],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    assert_eq!(
+        *INVOCATIONS.lock().expect("Unable to lock invocations"),
+        vec![
+            (0, 0, 0),
+            (1, 0, 0),
+            (2, 1, 1),
+            (3, 1, 0),
+            (4, 2, 1),
+            (5, 2, 1)
+        ]
+    );
+}
@@ -0,0 +1,44 @@
+#[derive(serde::Deserialize, Debug)]
+struct Event {
+    test: String,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), Event, ()> for Runner {
+    const DENY_UNKNOWN_FIELDS: bool = true;
+
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<()> {
+        assert_eq!(event.event.test, "test");
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_strict_known_fields() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [{ "test": "test" }],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+}
+
+#[test]
+fn test_strict_unknown_fields() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [{ "test": "test", "other": "value" }],
+    });
+    let err = lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect_err("Lambda accepted unknown fields");
+    assert!(err.to_string().contains("other"));
+}
@@ -0,0 +1,43 @@
+use lambda_runtime_types::swap::SharedSwap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn test_swap_store() {
+    let swap = SharedSwap::new(String::from("first"));
+    let loaded = swap.load();
+    let previous = swap.store(String::from("second"));
+    assert_eq!(*previous, "first");
+    // Loaded values stay valid after they were replaced
+    assert_eq!(*loaded, "first");
+    assert_eq!(*swap.load(), "second");
+
+    let clone = swap.clone();
+    assert_eq!(*clone.update(|value| format!("{}!", value)), "second!");
+    assert_eq!(*swap.load(), "second!");
+}
+
+#[tokio::test]
+async fn test_swap_refresh() {
+    let swap = SharedSwap::new(0);
+    let loads = Arc::new(AtomicU32::new(0));
+    let counter = Arc::clone(&loads);
+    let refresh = swap.spawn_refresh(Duration::from_millis(20), move || {
+        let load = counter.fetch_add(1, Ordering::SeqCst) + 1;
+        async move {
+            if load == 1 {
+                anyhow::bail!("Parameter store unreachable");
+            }
+            Ok(load)
+        }
+    });
+    // Failed refreshes keep the previous value
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(*swap.load(), 0);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    refresh.abort();
+    assert!(*swap.load() >= 2);
+    assert_eq!(*swap.load(), loads.load(Ordering::SeqCst));
+}
@@ -0,0 +1,28 @@
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), String, String> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, String>,
+    ) -> anyhow::Result<String> {
+        assert_eq!(event.region, event.event);
+        Ok(event.event)
+    }
+
+    async fn setup(region: &'a str) -> anyhow::Result<()> {
+        assert_eq!(region, "local");
+        Ok(())
+    }
+}
+
+#[test]
+fn test_test_data_custom_region() {
+    // exec_test passes the region as is, so local setups can use any name
+    let test_data = serde_json::json!({
+        "region": "local",
+        "invocations": ["local"],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+}
@@ -0,0 +1,50 @@
+mod common;
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), (), ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, ()>,
+    ) -> anyhow::Result<()> {
+        tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+fn invoke(builder: impl FnOnce() -> lambda_runtime_types::Builder) -> String {
+    let listener = common::setup();
+    let api = std::thread::spawn(move || common::serve_invocation(listener, "null"));
+
+    // The mock api sets the deadline 10 seconds ahead, so the
+    // timeout handler would fire after roughly 200 milliseconds
+    let _ = builder()
+        .timeout_margin(std::time::Duration::from_millis(9_800))
+        .exec::<_, _, Runner, _>();
+
+    let (request_line, _) = api.join().expect("Runtime API failed");
+    request_line
+}
+
+#[test]
+fn test_timeout_handler() {
+    assert_eq!(
+        invoke(lambda_runtime_types::Builder::new),
+        "POST /2018-06-01/runtime/invocation/request-1/error HTTP/1.1"
+    );
+    assert_eq!(
+        invoke(|| lambda_runtime_types::Builder::new().timeout_handler(false)),
+        "POST /2018-06-01/runtime/invocation/request-1/response HTTP/1.1"
+    );
+
+    std::env::set_var(lambda_runtime_types::TIMEOUT_HANDLER_ENV, "false");
+    assert_eq!(
+        invoke(lambda_runtime_types::Builder::new),
+        "POST /2018-06-01/runtime/invocation/request-1/response HTTP/1.1"
+    );
+}
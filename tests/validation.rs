@@ -0,0 +1,58 @@
+#[derive(serde::Deserialize, Debug)]
+struct Payment {
+    amount: i64,
+}
+
+static RUNS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), Payment, ()> for Runner {
+    async fn run(
+        _shared: &'a (),
+        _event: lambda_runtime_types::LambdaEvent<'a, Payment>,
+    ) -> anyhow::Result<()> {
+        RUNS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn validate(event: &Payment) -> Result<(), lambda_runtime_types::ValidationError> {
+        if event.amount <= 0 {
+            return Err(lambda_runtime_types::ValidationError::field(
+                "amount",
+                "must be positive",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_validation() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [{ "amount": 10 }, { "amount": -5 }],
+    });
+    let err = lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect_err("Lambda did not fail");
+    assert_eq!(RUNS.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(
+        err.downcast_ref::<lambda_runtime_types::ValidationError>(),
+        Some(&lambda_runtime_types::ValidationError::field(
+            "amount",
+            "must be positive"
+        ))
+    );
+    assert_eq!(
+        lambda_runtime_types::ErrorShape::from_error(&err),
+        lambda_runtime_types::ErrorShape::new(
+            "ValidationError",
+            "Invalid event field amount: must be positive"
+        )
+    );
+}
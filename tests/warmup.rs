@@ -0,0 +1,58 @@
+static INVOCATIONS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), u64, u64> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, u64>,
+    ) -> anyhow::Result<u64> {
+        INVOCATIONS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(event.event + 1)
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn warmup(event: &serde_json::value::RawValue) -> Option<serde_json::Value> {
+        (lambda_runtime_types::warmup::is_serverless_plugin_warmup(event)
+            || lambda_runtime_types::warmup::has_marker(event, "keep_alive"))
+        .then(|| serde_json::json!("warm"))
+    }
+}
+
+#[test]
+fn test_warmup_lambda() {
+    let test_data = serde_json::json!({
+        "region": "eu-central-1",
+        "invocations": [
+            {"source": "serverless-plugin-warmup"},
+            1,
+            {"keep_alive": null},
+        ],
+    });
+    lambda_runtime_types::exec_test::<_, _, Runner, _>(&test_data.to_string())
+        .expect("Unable to execute lambda");
+    assert_eq!(INVOCATIONS.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_warmup_predicates() {
+    let event = |value: serde_json::Value| {
+        serde_json::value::to_raw_value(&value).expect("Unable to serialize event")
+    };
+    let plugin = event(serde_json::json!({"source": "serverless-plugin-warmup"}));
+    assert!(lambda_runtime_types::warmup::is_serverless_plugin_warmup(
+        &plugin
+    ));
+    assert!(!lambda_runtime_types::warmup::is_serverless_plugin_warmup(
+        &event(serde_json::json!({"source": "aws.events"}))
+    ));
+    assert!(!lambda_runtime_types::warmup::is_serverless_plugin_warmup(
+        &event(serde_json::json!([1]))
+    ));
+    assert!(lambda_runtime_types::warmup::has_marker(&plugin, "source"));
+    assert!(!lambda_runtime_types::warmup::has_marker(&plugin, "ping"));
+}
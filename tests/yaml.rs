@@ -0,0 +1,28 @@
+#[derive(serde::Deserialize, Debug)]
+struct Event {
+    test: Option<String>,
+}
+
+struct Runner;
+
+#[async_trait::async_trait]
+impl<'a> lambda_runtime_types::Runner<'a, (), Event, Option<String>> for Runner {
+    async fn run(
+        _shared: &'a (),
+        event: lambda_runtime_types::LambdaEvent<'a, Event>,
+    ) -> anyhow::Result<Option<String>> {
+        assert_eq!(event.region, "eu-central-1");
+        Ok(event.event.test)
+    }
+
+    async fn setup(_region: &'a str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_yaml_lambda() {
+    let test_data = include_str!("./yaml.yaml");
+    lambda_runtime_types::exec_test_yaml::<_, _, Runner, _>(test_data)
+        .expect("Unable to execute lambda");
+}